@@ -0,0 +1,80 @@
+use std::hint::black_box;
+
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use moos::CompactVec;
+use moos::SmallString;
+
+const TEXT: &str = "The quick brown fox jumps over the lazy dog, \
+                    then naps in the warm afternoon sun for a while.";
+
+/// The byte-by-byte path that `SmallString::push_str` used to take, through
+/// `Extend`, on the same buffer type that backs a `SmallString<32>`.
+fn push_bytes(buf: &mut CompactVec<u8, 32>, s: &str) {
+  buf.extend(s.bytes());
+}
+
+fn push_str(c: &mut Criterion) {
+  let mut group = c.benchmark_group("push_str");
+  // 64 bytes don't fit in the 32 inline bytes, so that case spills.
+  for len in [8, 24, 64] {
+    let input = &TEXT[..len];
+    group.bench_with_input(
+      BenchmarkId::new("SmallString<32>", len),
+      input,
+      |b, s| {
+        b.iter(|| {
+          let mut string: SmallString<32> = SmallString::new();
+          string.push_str(black_box(s));
+          string
+        })
+      },
+    );
+    group.bench_with_input(
+      BenchmarkId::new("extend(bytes)", len),
+      input,
+      |b, s| {
+        b.iter(|| {
+          let mut buf: CompactVec<u8, 32> = CompactVec::new();
+          push_bytes(&mut buf, black_box(s));
+          buf
+        })
+      },
+    );
+    group.bench_with_input(BenchmarkId::new("String", len), input, |b, s| {
+      b.iter(|| {
+        let mut string = String::new();
+        string.push_str(black_box(s));
+        string
+      })
+    });
+  }
+  group.finish();
+}
+
+/// Appends to a string that already holds 24 bytes, so that the inline
+/// capacity of 32 bytes runs out partway through the appended text.
+fn push_str_crossing(c: &mut Criterion) {
+  let mut group = c.benchmark_group("push_str_crossing");
+  let (prefix, input) = (&TEXT[..24], &TEXT[24..40]);
+  group.bench_function("SmallString<32>", |b| {
+    b.iter(|| {
+      let mut string: SmallString<32> = SmallString::from(prefix);
+      string.push_str(black_box(input));
+      string
+    })
+  });
+  group.bench_function("extend(bytes)", |b| {
+    b.iter(|| {
+      let mut buf: CompactVec<u8, 32> = CompactVec::from(prefix.as_bytes());
+      push_bytes(&mut buf, black_box(input));
+      buf
+    })
+  });
+  group.finish();
+}
+
+criterion_group!(benches, push_str, push_str_crossing);
+criterion_main!(benches);
@@ -0,0 +1,401 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::ops::Bound;
+use core::ops::Deref;
+use core::ops::RangeBounds;
+use core::slice;
+
+use crate::CompactVec;
+
+/// An immutable, reference-counted slice that is cheap to clone and to cut
+/// into smaller views, all sharing one allocation.
+///
+/// An `ArcSlice` is an `Arc<[T]>` together with the range of it that this
+/// handle can see. Cloning bumps the reference count, and
+/// [`slice`](Self::slice) and [`split_at`](Self::split_at) return narrower
+/// views without copying, so a large read-mostly buffer can be parsed into
+/// pieces and handed out to several tasks while being stored only once.
+/// For small buffers, [`to_compact_vec`](Self::to_compact_vec) copies a view
+/// out into an inline [`CompactVec`].
+///
+/// # Example
+///
+/// ```rust
+/// use moos::ArcSlice;
+///
+/// let frame = ArcSlice::from(vec![0x7e, 0x01, 0x02, 0x03, 0x04, 0x7e]);
+/// let payload = frame.slice(1..5);
+/// let (header, body) = payload.split_at(1);
+/// assert_eq!(header, [0x01]);
+/// assert_eq!(body, [0x02, 0x03, 0x04]);
+///
+/// let handle = std::thread::spawn(move || body.iter().sum::<u8>());
+/// assert_eq!(handle.join().unwrap(), 9);
+///
+/// // all views share the frame's allocation.
+/// assert_eq!(ArcSlice::strong_count(&frame), 3);
+/// ```
+pub struct ArcSlice<T> {
+  data:  Arc<[T]>,
+  start: usize,
+  len:   usize,
+}
+
+impl<T> ArcSlice<T> {
+  /// Creates an `ArcSlice` viewing all of `data`.
+  #[inline]
+  pub fn from_arc(data: Arc<[T]>) -> Self {
+    let len = data.len();
+    Self {
+      data,
+      start: 0,
+      len,
+    }
+  }
+
+  /// Returns the number of elements in the view.
+  #[inline(always)]
+  pub const fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Returns `true` if the view is empty.
+  #[inline(always)]
+  pub const fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns the elements in the view.
+  #[inline]
+  pub fn as_slice(&self) -> &[T] {
+    // SAFETY: `start..start + len` is always within `data`.
+    unsafe { self.data.get_unchecked(self.start..self.start + self.len) }
+  }
+
+  /// Returns a view of `range` within this view, sharing the same
+  /// allocation.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the range is out of bounds, or its start is after its end.
+  pub fn slice(&self, range: impl RangeBounds<usize>) -> Self {
+    let start = match range.start_bound() {
+      Bound::Included(&n) => n,
+      Bound::Excluded(&n) => n.checked_add(1).expect("range start overflows"),
+      Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+      Bound::Included(&n) => n.checked_add(1).expect("range end overflows"),
+      Bound::Excluded(&n) => n,
+      Bound::Unbounded => self.len,
+    };
+    assert!(
+      start <= end,
+      "slice index starts at {start} but ends at {end}"
+    );
+    assert!(
+      end <= self.len,
+      "range end index {end} out of range for slice of length {}",
+      self.len
+    );
+    Self {
+      data:  Arc::clone(&self.data),
+      start: self.start + start,
+      len:   end - start,
+    }
+  }
+
+  /// Splits the view in two at `mid`, sharing the same allocation.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `mid` is greater than the length of the view.
+  #[inline]
+  pub fn split_at(&self, mid: usize) -> (Self, Self) {
+    (self.slice(..mid), self.slice(mid..))
+  }
+
+  /// Returns the number of `ArcSlice`s sharing this allocation, including
+  /// narrower views of it.
+  #[inline]
+  pub fn strong_count(this: &Self) -> usize {
+    Arc::strong_count(&this.data)
+  }
+
+  /// Returns `true` if both `ArcSlice`s view the same elements of the same
+  /// allocation, rather than merely holding equal elements.
+  #[inline]
+  pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+    Arc::ptr_eq(&this.data, &other.data)
+      && this.start == other.start
+      && this.len == other.len
+  }
+}
+
+impl<T: Clone> ArcSlice<T> {
+  /// Creates an `ArcSlice` holding a copy of `slice`.
+  #[inline]
+  pub fn new(slice: &[T]) -> Self {
+    Self::from_arc(Arc::from(slice))
+  }
+
+  /// Copies the view into a [`CompactVec`], which keeps up to `N` elements
+  /// inline.
+  #[inline]
+  pub fn to_compact_vec<const N: usize>(&self) -> CompactVec<T, N> {
+    let mut vec = CompactVec::new();
+    vec.reserve_exact(self.len);
+    vec.extend(self.iter().cloned());
+    vec
+  }
+}
+
+impl<T> Clone for ArcSlice<T> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self {
+      data:  Arc::clone(&self.data),
+      start: self.start,
+      len:   self.len,
+    }
+  }
+}
+
+impl<T> Default for ArcSlice<T> {
+  #[inline]
+  fn default() -> Self {
+    Self::from_arc(Arc::from([]))
+  }
+}
+
+impl<T> Deref for ArcSlice<T> {
+  type Target = [T];
+
+  #[inline(always)]
+  fn deref(&self) -> &[T] {
+    self.as_slice()
+  }
+}
+
+impl<T> AsRef<[T]> for ArcSlice<T> {
+  #[inline(always)]
+  fn as_ref(&self) -> &[T] {
+    self
+  }
+}
+
+impl<T> Borrow<[T]> for ArcSlice<T> {
+  #[inline(always)]
+  fn borrow(&self) -> &[T] {
+    self
+  }
+}
+
+impl<T: Debug> Debug for ArcSlice<T> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Debug::fmt(self.as_slice(), f)
+  }
+}
+
+impl<T: Hash> Hash for ArcSlice<T> {
+  #[inline]
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.as_slice().hash(state);
+  }
+}
+
+impl<T: PartialEq> PartialEq for ArcSlice<T> {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    self.as_slice() == other.as_slice()
+  }
+}
+
+impl<T: Eq> Eq for ArcSlice<T> {}
+
+impl<T: PartialOrd> PartialOrd for ArcSlice<T> {
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    self.as_slice().partial_cmp(other.as_slice())
+  }
+}
+
+impl<T: Ord> Ord for ArcSlice<T> {
+  #[inline]
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.as_slice().cmp(other.as_slice())
+  }
+}
+
+impl<T: PartialEq> PartialEq<[T]> for ArcSlice<T> {
+  #[inline]
+  fn eq(&self, other: &[T]) -> bool {
+    self.as_slice() == other
+  }
+}
+
+impl<T: PartialEq, const M: usize> PartialEq<[T; M]> for ArcSlice<T> {
+  #[inline]
+  fn eq(&self, other: &[T; M]) -> bool {
+    self.as_slice() == other
+  }
+}
+
+impl<T: PartialEq> PartialEq<&[T]> for ArcSlice<T> {
+  #[inline]
+  fn eq(&self, other: &&[T]) -> bool {
+    self.as_slice() == *other
+  }
+}
+
+impl<'a, T> IntoIterator for &'a ArcSlice<T> {
+  type IntoIter = slice::Iter<'a, T>;
+  type Item = &'a T;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<T> From<Arc<[T]>> for ArcSlice<T> {
+  #[inline(always)]
+  fn from(data: Arc<[T]>) -> Self {
+    Self::from_arc(data)
+  }
+}
+
+impl<T> From<Vec<T>> for ArcSlice<T> {
+  #[inline]
+  fn from(vec: Vec<T>) -> Self {
+    Self::from_arc(Arc::from(vec))
+  }
+}
+
+impl<T> From<Box<[T]>> for ArcSlice<T> {
+  #[inline]
+  fn from(slice: Box<[T]>) -> Self {
+    Self::from_arc(Arc::from(slice))
+  }
+}
+
+impl<T, const N: usize> From<CompactVec<T, N>> for ArcSlice<T> {
+  #[inline]
+  fn from(vec: CompactVec<T, N>) -> Self {
+    Self::from_arc(Arc::from(vec.into_vec()))
+  }
+}
+
+impl<T: Clone> From<&[T]> for ArcSlice<T> {
+  #[inline(always)]
+  fn from(slice: &[T]) -> Self {
+    Self::new(slice)
+  }
+}
+
+impl<T, const M: usize> From<[T; M]> for ArcSlice<T> {
+  #[inline]
+  fn from(array: [T; M]) -> Self {
+    Self::from_arc(Arc::from(array))
+  }
+}
+
+impl<T> FromIterator<T> for ArcSlice<T> {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    Self::from_arc(iter.into_iter().collect())
+  }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+
+  use super::*;
+
+  impl<T: Serialize> Serialize for ArcSlice<T> {
+    #[inline(always)]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      self.as_slice().serialize(serializer)
+    }
+  }
+
+  impl<'de, T: Deserialize<'de>> Deserialize<'de> for ArcSlice<T> {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      Vec::<T>::deserialize(deserializer).map(Self::from)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::vec;
+
+  use super::*;
+
+  #[test]
+  fn arc_slice_views() {
+    let all = ArcSlice::from(vec![1, 2, 3, 4, 5]);
+    let mid = all.slice(1..=3);
+    assert_eq!(mid, [2, 3, 4]);
+    assert_eq!(mid.slice(1..), [3, 4]);
+    assert!(mid.slice(3..).is_empty());
+    assert_eq!(ArcSlice::strong_count(&all), 2);
+
+    let (a, b) = all.split_at(2);
+    assert_eq!((a.len(), b.len()), (2, 3));
+    assert!(ArcSlice::ptr_eq(&mid, &mid.clone()));
+    assert!(!ArcSlice::ptr_eq(&mid, &all));
+    assert_eq!(mid, ArcSlice::new(&[2, 3, 4]));
+    assert!(ArcSlice::<u8>::default().is_empty());
+  }
+
+  #[test]
+  #[should_panic]
+  fn arc_slice_out_of_bounds() {
+    let _ = ArcSlice::from([1, 2, 3]).slice(1..).slice(..3);
+  }
+
+  #[test]
+  fn arc_slice_conversions() {
+    let mut vec = CompactVec::<u8, 4>::new();
+    vec.extend([1, 2, 3]);
+    let shared = ArcSlice::from(vec);
+    let small = shared.slice(..2).to_compact_vec::<4>();
+    assert!(small.is_inline());
+    assert_eq!(small, [1, 2]);
+
+    assert_eq!(shared.slice(1..).to_vec(), [2, 3]);
+    assert_eq!(shared.to_vec(), [1, 2, 3]);
+    let collected: ArcSlice<_> = (0..3).collect();
+    assert_eq!(collected, [0, 1, 2]);
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn arc_slice_serde() {
+    let s = ArcSlice::from([1, 2, 3, 4]).slice(1..3);
+    let json = serde_json::to_string(&s).unwrap();
+    assert_eq!(json, "[2,3]");
+    assert_eq!(serde_json::from_str::<ArcSlice<i32>>(&json).unwrap(), s);
+  }
+}
@@ -0,0 +1,441 @@
+use alloc::alloc::Layout;
+use alloc::alloc::alloc;
+use alloc::alloc::dealloc;
+use alloc::alloc::handle_alloc_error;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::mem;
+use core::ops::Deref;
+use core::ptr;
+use core::ptr::NonNull;
+use core::slice;
+use core::str;
+use core::str::FromStr;
+use core::sync::atomic;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering::Acquire;
+use core::sync::atomic::Ordering::Relaxed;
+use core::sync::atomic::Ordering::Release;
+
+use crate::CowStr;
+use crate::InlineStr;
+
+/// The largest reference count an [`ArcStr`] may reach, mirroring the limit
+/// of [`Arc`](alloc::sync::Arc).
+const MAX_REFCOUNT: usize = isize::MAX as usize;
+
+/// An immutable, reference-counted string that is cheap to clone and can be
+/// shared between threads.
+///
+/// Unlike `Arc<str>`, which points at a separate count and string behind a
+/// fat pointer, an `ArcStr` is a single thin pointer to one allocation
+/// holding the reference count, the length, and the bytes. Cloning bumps an
+/// atomic counter, and the allocation is freed when the last clone is
+/// dropped.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::ArcStr;
+///
+/// let config = ArcStr::from("https://example.com/api");
+/// let handles: Vec<_> = (0..4)
+///   .map(|_| {
+///     let config = config.clone();
+///     std::thread::spawn(move || config.len())
+///   })
+///   .collect();
+/// for handle in handles {
+///   assert_eq!(handle.join().unwrap(), 23);
+/// }
+/// assert_eq!(ArcStr::strong_count(&config), 1);
+/// assert_eq!(size_of::<ArcStr>(), size_of::<usize>());
+/// ```
+pub struct ArcStr {
+  ptr: NonNull<Header>,
+}
+
+/// The start of an [`ArcStr`] allocation, which is followed by the bytes of
+/// the string.
+#[repr(C)]
+struct Header {
+  count: AtomicUsize,
+  len:   usize,
+}
+
+// SAFETY: the string is immutable and its reference count is atomic.
+unsafe impl Send for ArcStr {}
+unsafe impl Sync for ArcStr {}
+
+impl ArcStr {
+  /// Creates a new `ArcStr` holding a copy of `s`.
+  pub fn new(s: &str) -> Self {
+    let layout = Self::layout(s.len());
+    unsafe {
+      let ptr = alloc(layout).cast::<Header>();
+      let Some(ptr) = NonNull::new(ptr) else {
+        handle_alloc_error(layout);
+      };
+      ptr.as_ptr().write(Header {
+        count: AtomicUsize::new(1),
+        len:   s.len(),
+      });
+      let data = ptr.as_ptr().add(1).cast::<u8>();
+      ptr::copy_nonoverlapping(s.as_ptr(), data, s.len());
+      Self { ptr }
+    }
+  }
+
+  /// Returns the string as a string slice.
+  #[inline]
+  pub fn as_str(&self) -> &str {
+    unsafe {
+      let data = self.ptr.as_ptr().add(1).cast::<u8>();
+      str::from_utf8_unchecked(slice::from_raw_parts(data, self.len()))
+    }
+  }
+
+  /// Returns the length of the string in bytes.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.header().len
+  }
+
+  /// Returns `true` if the string is empty.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Returns the number of `ArcStr`s sharing this string.
+  #[inline]
+  pub fn strong_count(this: &Self) -> usize {
+    this.header().count.load(Acquire)
+  }
+
+  /// Returns `true` if both `ArcStr`s share the same allocation, rather than
+  /// merely holding equal strings.
+  #[inline(always)]
+  pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+    this.ptr == other.ptr
+  }
+
+  #[inline(always)]
+  fn header(&self) -> &Header {
+    unsafe { self.ptr.as_ref() }
+  }
+
+  #[inline]
+  fn layout(len: usize) -> Layout {
+    mem::size_of::<Header>()
+      .checked_add(len)
+      .and_then(|size| {
+        Layout::from_size_align(size, mem::align_of::<Header>()).ok()
+      })
+      .expect("string too long for ArcStr")
+  }
+}
+
+impl Clone for ArcStr {
+  #[inline]
+  fn clone(&self) -> Self {
+    let old = self.header().count.fetch_add(1, Relaxed);
+    if old > MAX_REFCOUNT {
+      self.header().count.fetch_sub(1, Relaxed);
+      panic!("ArcStr reference count overflow");
+    }
+    Self { ptr: self.ptr }
+  }
+}
+
+impl Drop for ArcStr {
+  fn drop(&mut self) {
+    if self.header().count.fetch_sub(1, Release) != 1 {
+      return;
+    }
+    // synchronize with the other clones' releases before freeing.
+    atomic::fence(Acquire);
+    let layout = Self::layout(self.len());
+    unsafe { dealloc(self.ptr.as_ptr().cast(), layout) };
+  }
+}
+
+impl Default for ArcStr {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::new("")
+  }
+}
+
+impl Deref for ArcStr {
+  type Target = str;
+
+  #[inline(always)]
+  fn deref(&self) -> &str {
+    self.as_str()
+  }
+}
+
+impl AsRef<str> for ArcStr {
+  #[inline(always)]
+  fn as_ref(&self) -> &str {
+    self
+  }
+}
+
+impl AsRef<[u8]> for ArcStr {
+  #[inline(always)]
+  fn as_ref(&self) -> &[u8] {
+    self.as_bytes()
+  }
+}
+
+impl Borrow<str> for ArcStr {
+  #[inline(always)]
+  fn borrow(&self) -> &str {
+    self
+  }
+}
+
+impl Debug for ArcStr {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Debug::fmt(self.as_str(), f)
+  }
+}
+
+impl Display for ArcStr {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Display::fmt(self.as_str(), f)
+  }
+}
+
+impl Hash for ArcStr {
+  #[inline]
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.as_str().hash(state);
+  }
+}
+
+impl PartialEq for ArcStr {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    Self::ptr_eq(self, other) || self.as_str() == other.as_str()
+  }
+}
+
+impl Eq for ArcStr {}
+
+impl PartialEq<str> for ArcStr {
+  #[inline]
+  fn eq(&self, other: &str) -> bool {
+    self.as_str() == other
+  }
+}
+
+impl PartialEq<&str> for ArcStr {
+  #[inline]
+  fn eq(&self, other: &&str) -> bool {
+    self.as_str() == *other
+  }
+}
+
+impl PartialEq<String> for ArcStr {
+  #[inline]
+  fn eq(&self, other: &String) -> bool {
+    self.as_str() == other
+  }
+}
+
+impl PartialEq<ArcStr> for str {
+  #[inline]
+  fn eq(&self, other: &ArcStr) -> bool {
+    self == other.as_str()
+  }
+}
+
+impl PartialEq<ArcStr> for &str {
+  #[inline]
+  fn eq(&self, other: &ArcStr) -> bool {
+    *self == other.as_str()
+  }
+}
+
+impl PartialOrd for ArcStr {
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for ArcStr {
+  #[inline]
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.as_str().cmp(other.as_str())
+  }
+}
+
+impl From<&str> for ArcStr {
+  #[inline(always)]
+  fn from(s: &str) -> Self {
+    Self::new(s)
+  }
+}
+
+impl From<String> for ArcStr {
+  #[inline(always)]
+  fn from(s: String) -> Self {
+    Self::new(&s)
+  }
+}
+
+impl From<Box<str>> for ArcStr {
+  #[inline(always)]
+  fn from(s: Box<str>) -> Self {
+    Self::new(&s)
+  }
+}
+
+impl<'i> From<Cow<'i, str>> for ArcStr {
+  #[inline(always)]
+  fn from(s: Cow<'i, str>) -> Self {
+    Self::new(&s)
+  }
+}
+
+impl<'i> From<CowStr<'i>> for ArcStr {
+  #[inline(always)]
+  fn from(s: CowStr<'i>) -> Self {
+    Self::new(&s)
+  }
+}
+
+impl<'i> From<&'i ArcStr> for CowStr<'i> {
+  /// Borrows the shared string without copying it.
+  #[inline(always)]
+  fn from(s: &'i ArcStr) -> Self {
+    CowStr::Borrowed(s.as_str())
+  }
+}
+
+impl From<ArcStr> for CowStr<'_> {
+  /// Converts to an inlined `CowStr` when the string is short enough, and an
+  /// owned copy otherwise.
+  #[inline]
+  fn from(s: ArcStr) -> Self {
+    match InlineStr::try_from(s.as_str()) {
+      Ok(inline) => CowStr::Inlined(inline),
+      Err(_) => CowStr::Owned(s.as_str().into()),
+    }
+  }
+}
+
+impl From<ArcStr> for String {
+  #[inline(always)]
+  fn from(s: ArcStr) -> Self {
+    String::from(s.as_str())
+  }
+}
+
+impl FromStr for ArcStr {
+  type Err = core::convert::Infallible;
+
+  #[inline(always)]
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Ok(Self::new(s))
+  }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+  use serde::de;
+
+  use super::*;
+
+  impl Serialize for ArcStr {
+    #[inline(always)]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      serializer.serialize_str(self.as_str())
+    }
+  }
+
+  struct ArcStrVisitor;
+
+  impl de::Visitor<'_> for ArcStrVisitor {
+    type Value = ArcStr;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+      E: de::Error,
+    {
+      Ok(ArcStr::new(v))
+    }
+  }
+
+  impl<'de> Deserialize<'de> for ArcStr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      deserializer.deserialize_str(ArcStrVisitor)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::string::ToString;
+
+  use super::*;
+
+  #[test]
+  fn arc_str_shares_allocation() {
+    let a = ArcStr::from("shared");
+    let b = a.clone();
+    assert!(ArcStr::ptr_eq(&a, &b));
+    assert_eq!(ArcStr::strong_count(&a), 2);
+    drop(b);
+    assert_eq!(ArcStr::strong_count(&a), 1);
+
+    let c = ArcStr::from("shared".to_string());
+    assert!(!ArcStr::ptr_eq(&a, &c));
+    assert_eq!(a, c);
+    assert!(ArcStr::default().is_empty());
+  }
+
+  #[test]
+  fn arc_str_cow_conversions() {
+    let long = "a string that is too long to be stored inline";
+    let arc = ArcStr::from(CowStr::Borrowed(long));
+    let borrowed = CowStr::from(&arc);
+    assert!(borrowed.is_borrowed());
+    assert_eq!(borrowed.as_ptr(), arc.as_ptr());
+    assert!(CowStr::from(arc.clone()).is_owned());
+    assert!(CowStr::from(ArcStr::from("short")).is_inlined());
+    // with the `zeroize` feature, `CowStr` has a destructor, so the borrow
+    // lasts until it is dropped.
+    drop(borrowed);
+    assert_eq!(String::from(arc), long);
+  }
+}
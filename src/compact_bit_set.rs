@@ -0,0 +1,533 @@
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::iter::FusedIterator;
+use core::ops::BitAnd;
+use core::ops::BitAndAssign;
+use core::ops::BitOr;
+use core::ops::BitOrAssign;
+use core::ops::BitXor;
+use core::ops::BitXorAssign;
+use core::ops::Sub;
+use core::ops::SubAssign;
+
+use crate::CompactVec;
+
+/// A set of small non-negative integers, stored as a bitmap with room for
+/// `N * 8` bits inline and spilling to the heap for larger universes.
+///
+/// Each integer in the set is one bit, so membership tests, insertion, and
+/// set operations are a few bitwise instructions, and iteration visits the
+/// members in ascending order. This suits feature flags and sets of small
+/// IDs in `no_std` code, where a `BTreeSet<usize>` would allocate for every
+/// member.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::CompactBitSet;
+///
+/// let mut enabled: CompactBitSet<4> = CompactBitSet::new();
+/// enabled.insert(3);
+/// enabled.insert(17);
+/// assert!(enabled.contains(17));
+/// assert!(enabled.is_inline());
+///
+/// let defaults: CompactBitSet<4> = [1, 3].into_iter().collect();
+/// let all = &enabled | &defaults;
+/// assert_eq!(all.iter().collect::<Vec<_>>(), [1, 3, 17]);
+/// assert_eq!((&enabled & &defaults).len(), 1);
+///
+/// enabled.insert(100);
+/// assert!(!enabled.is_inline());
+/// ```
+#[derive(Clone, Default)]
+pub struct CompactBitSet<const N: usize> {
+  /// The bitmap, with bit `i` stored in bit `i % 8` of block `i / 8`. Blocks
+  /// past the highest member may be zero.
+  blocks: CompactVec<u8, N>,
+}
+
+impl<const N: usize> CompactBitSet<N> {
+  /// Creates a new, empty set.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      blocks: CompactVec::new(),
+    }
+  }
+
+  /// Creates a new, empty set with room for the integers below `bits`
+  /// without reallocating.
+  #[inline]
+  pub fn with_capacity(bits: usize) -> Self {
+    let mut set = Self::new();
+    set.blocks.reserve(bits.div_ceil(8));
+    set
+  }
+
+  /// Returns the number of integers the set can hold without reallocating,
+  /// i.e. one more than the largest member it has room for.
+  #[inline(always)]
+  pub fn capacity(&self) -> usize {
+    self.blocks.capacity() * 8
+  }
+
+  /// Returns `true` if the bitmap is stored inline.
+  #[inline(always)]
+  pub fn is_inline(&self) -> bool {
+    self.blocks.is_inline()
+  }
+
+  /// Returns the number of members.
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.blocks.iter().map(|b| b.count_ones() as usize).sum()
+  }
+
+  /// Returns `true` if the set has no members.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.blocks.iter().all(|&b| b == 0)
+  }
+
+  /// Returns `true` if `bit` is a member.
+  #[inline]
+  pub fn contains(&self, bit: usize) -> bool {
+    self
+      .blocks
+      .get(bit / 8)
+      .is_some_and(|&b| b & mask(bit) != 0)
+  }
+
+  /// Adds `bit` to the set, returning `true` if it was not already a member.
+  #[inline]
+  pub fn insert(&mut self, bit: usize) -> bool {
+    let block = self.block_mut(bit / 8);
+    let added = *block & mask(bit) == 0;
+    *block |= mask(bit);
+    added
+  }
+
+  /// Removes `bit` from the set, returning `true` if it was a member.
+  #[inline]
+  pub fn remove(&mut self, bit: usize) -> bool {
+    match self.blocks.get_mut(bit / 8) {
+      Some(block) if *block & mask(bit) != 0 => {
+        *block &= !mask(bit);
+        true
+      }
+      _ => false,
+    }
+  }
+
+  /// Adds `bit` to the set if `value` is `true`, and removes it otherwise.
+  #[inline]
+  pub fn set(&mut self, bit: usize, value: bool) {
+    if value {
+      self.insert(bit);
+    } else {
+      self.remove(bit);
+    }
+  }
+
+  /// Flips the membership of `bit`, returning `true` if it is now a member.
+  #[inline]
+  pub fn toggle(&mut self, bit: usize) -> bool {
+    let block = self.block_mut(bit / 8);
+    *block ^= mask(bit);
+    *block & mask(bit) != 0
+  }
+
+  /// Removes all members, keeping the allocated capacity.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.blocks.clear();
+  }
+
+  /// Returns the smallest member, if any.
+  #[inline]
+  pub fn first(&self) -> Option<usize> {
+    self.iter().next()
+  }
+
+  /// Returns the largest member, if any.
+  #[inline]
+  pub fn last(&self) -> Option<usize> {
+    self.iter().next_back()
+  }
+
+  /// Returns an iterator over the members in ascending order.
+  #[inline]
+  pub fn iter(&self) -> Iter<'_> {
+    Iter {
+      blocks: &self.blocks,
+      front:  0,
+      back:   self.blocks.len() * 8,
+    }
+  }
+
+  /// Returns `true` if every member of `self` is also in `other`.
+  pub fn is_subset<const M: usize>(&self, other: &CompactBitSet<M>) -> bool {
+    self
+      .blocks
+      .iter()
+      .enumerate()
+      .all(|(i, &b)| b & !other.blocks.get(i).copied().unwrap_or(0) == 0)
+  }
+
+  /// Returns `true` if every member of `other` is also in `self`.
+  #[inline]
+  pub fn is_superset<const M: usize>(&self, other: &CompactBitSet<M>) -> bool {
+    other.is_subset(self)
+  }
+
+  /// Returns `true` if `self` and `other` have no members in common.
+  pub fn is_disjoint<const M: usize>(&self, other: &CompactBitSet<M>) -> bool {
+    self
+      .blocks
+      .iter()
+      .zip(other.blocks.iter())
+      .all(|(a, b)| a & b == 0)
+  }
+
+  /// Adds every member of `other` to `self`.
+  pub fn union_with<const M: usize>(&mut self, other: &CompactBitSet<M>) {
+    if other.blocks.len() > self.blocks.len() {
+      self.blocks.resize(other.blocks.len(), 0);
+    }
+    for (a, b) in self.blocks.iter_mut().zip(other.blocks.iter()) {
+      *a |= b;
+    }
+  }
+
+  /// Removes every member of `self` that is not in `other`.
+  pub fn intersect_with<const M: usize>(&mut self, other: &CompactBitSet<M>) {
+    self.blocks.truncate(other.blocks.len());
+    for (a, b) in self.blocks.iter_mut().zip(other.blocks.iter()) {
+      *a &= b;
+    }
+  }
+
+  /// Removes every member of `other` from `self`.
+  pub fn difference_with<const M: usize>(&mut self, other: &CompactBitSet<M>) {
+    for (a, b) in self.blocks.iter_mut().zip(other.blocks.iter()) {
+      *a &= !b;
+    }
+  }
+
+  /// Keeps the members that are in exactly one of `self` and `other`.
+  pub fn symmetric_difference_with<const M: usize>(
+    &mut self,
+    other: &CompactBitSet<M>,
+  ) {
+    if other.blocks.len() > self.blocks.len() {
+      self.blocks.resize(other.blocks.len(), 0);
+    }
+    for (a, b) in self.blocks.iter_mut().zip(other.blocks.iter()) {
+      *a ^= b;
+    }
+  }
+
+  /// Returns the bitmap, with trailing zero blocks trimmed so that equal sets
+  /// have equal slices.
+  #[inline]
+  fn trimmed(&self) -> &[u8] {
+    let len = self
+      .blocks
+      .iter()
+      .rposition(|&b| b != 0)
+      .map_or(0, |i| i + 1);
+    &self.blocks[..len]
+  }
+
+  #[inline]
+  fn block_mut(&mut self, index: usize) -> &mut u8 {
+    if index >= self.blocks.len() {
+      self.blocks.resize(index + 1, 0);
+    }
+    &mut self.blocks[index]
+  }
+}
+
+#[inline(always)]
+const fn mask(bit: usize) -> u8 {
+  1 << (bit % 8)
+}
+
+impl<const N: usize> Debug for CompactBitSet<N> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_set().entries(self.iter()).finish()
+  }
+}
+
+impl<const N: usize, const M: usize> PartialEq<CompactBitSet<M>>
+  for CompactBitSet<N>
+{
+  #[inline]
+  fn eq(&self, other: &CompactBitSet<M>) -> bool {
+    self.trimmed() == other.trimmed()
+  }
+}
+
+impl<const N: usize> Eq for CompactBitSet<N> {}
+
+impl<const N: usize> Hash for CompactBitSet<N> {
+  #[inline]
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.trimmed().hash(state);
+  }
+}
+
+impl<const N: usize> Extend<usize> for CompactBitSet<N> {
+  #[inline]
+  fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+    for bit in iter {
+      self.insert(bit);
+    }
+  }
+}
+
+impl<const N: usize> FromIterator<usize> for CompactBitSet<N> {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+    let mut set = Self::new();
+    set.extend(iter);
+    set
+  }
+}
+
+impl<const N: usize, const M: usize> From<[usize; M]> for CompactBitSet<N> {
+  #[inline]
+  fn from(bits: [usize; M]) -> Self {
+    Self::from_iter(bits)
+  }
+}
+
+impl<'a, const N: usize> IntoIterator for &'a CompactBitSet<N> {
+  type IntoIter = Iter<'a>;
+  type Item = usize;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+macro_rules! impl_bit_ops {
+  ($($op:ident::$method:ident, $assign:ident::$assign_method:ident => $with:ident;)*) => {$(
+    impl<const N: usize> $op<&CompactBitSet<N>> for &CompactBitSet<N> {
+      type Output = CompactBitSet<N>;
+
+      #[inline]
+      fn $method(self, rhs: &CompactBitSet<N>) -> CompactBitSet<N> {
+        let mut set = self.clone();
+        set.$with(rhs);
+        set
+      }
+    }
+
+    impl<const N: usize> $assign<&CompactBitSet<N>> for CompactBitSet<N> {
+      #[inline]
+      fn $assign_method(&mut self, rhs: &CompactBitSet<N>) {
+        self.$with(rhs);
+      }
+    }
+  )*};
+}
+
+impl_bit_ops! {
+  BitOr::bitor, BitOrAssign::bitor_assign => union_with;
+  BitAnd::bitand, BitAndAssign::bitand_assign => intersect_with;
+  BitXor::bitxor, BitXorAssign::bitxor_assign => symmetric_difference_with;
+  Sub::sub, SubAssign::sub_assign => difference_with;
+}
+
+/// An iterator over the members of a [`CompactBitSet`], in ascending order.
+#[derive(Clone)]
+pub struct Iter<'a> {
+  blocks: &'a [u8],
+  /// The next bit to examine from the front.
+  front:  usize,
+  /// One past the next bit to examine from the back.
+  back:   usize,
+}
+
+impl Iterator for Iter<'_> {
+  type Item = usize;
+
+  fn next(&mut self) -> Option<usize> {
+    while self.front < self.back {
+      let index = self.front / 8;
+      // ignore bits below `front` and at or above `back`.
+      let mut block = self.blocks[index] & (0xFF << (self.front % 8));
+      if index == (self.back - 1) / 8 {
+        block &= 0xFF >> (7 - (self.back - 1) % 8);
+      }
+      if block != 0 {
+        let bit = index * 8 + block.trailing_zeros() as usize;
+        self.front = bit + 1;
+        return Some(bit);
+      }
+      self.front = (index + 1) * 8;
+    }
+    None
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (0, Some(self.back.saturating_sub(self.front)))
+  }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+  fn next_back(&mut self) -> Option<usize> {
+    while self.front < self.back {
+      let index = (self.back - 1) / 8;
+      let mut block = self.blocks[index] & (0xFF >> (7 - (self.back - 1) % 8));
+      if index == self.front / 8 {
+        block &= 0xFF << (self.front % 8);
+      }
+      if block != 0 {
+        let bit = index * 8 + 7 - block.leading_zeros() as usize;
+        self.back = bit;
+        return Some(bit);
+      }
+      self.back = index * 8;
+    }
+    None
+  }
+}
+
+impl FusedIterator for Iter<'_> {}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+  use serde::de;
+
+  use super::*;
+
+  impl<const N: usize> Serialize for CompactBitSet<N> {
+    /// Serializes the set as a sequence of its members.
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      serializer.collect_seq(self.iter())
+    }
+  }
+
+  struct CompactBitSetVisitor<const N: usize>;
+
+  impl<'de, const N: usize> de::Visitor<'de> for CompactBitSetVisitor<N> {
+    type Value = CompactBitSet<N>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a sequence of integers")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+      A: de::SeqAccess<'de>,
+    {
+      let mut set = CompactBitSet::new();
+      while let Some(bit) = seq.next_element()? {
+        set.insert(bit);
+      }
+      Ok(set)
+    }
+  }
+
+  impl<'de, const N: usize> Deserialize<'de> for CompactBitSet<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      deserializer.deserialize_seq(CompactBitSetVisitor)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::vec::Vec;
+
+  use super::*;
+
+  #[test]
+  fn compact_bit_set_members() {
+    let mut set: CompactBitSet<2> = CompactBitSet::new();
+    assert!(set.insert(0));
+    assert!(!set.insert(0));
+    assert!(set.insert(15));
+    assert!(set.is_inline());
+    assert_eq!(set.capacity(), 16);
+    assert!(set.insert(16));
+    assert!(!set.is_inline());
+    assert_eq!(set.len(), 3);
+    assert!(!set.toggle(15));
+    assert!(set.remove(16));
+    assert!(!set.remove(1000));
+    assert_eq!(set, CompactBitSet::<8>::from([0]));
+    set.set(9, true);
+    assert_eq!((set.first(), set.last()), (Some(0), Some(9)));
+    set.clear();
+    assert!(set.is_empty());
+  }
+
+  #[test]
+  fn compact_bit_set_iter() {
+    let bits = [0, 1, 7, 8, 9, 31, 63, 64, 200];
+    let set: CompactBitSet<4> = bits.into_iter().collect();
+    assert_eq!(set.iter().collect::<Vec<_>>(), bits);
+    assert_eq!(set.iter().rev().collect::<Vec<_>>(), {
+      let mut rev = bits;
+      rev.reverse();
+      rev
+    });
+    let mut iter = set.iter();
+    assert_eq!(iter.next(), Some(0));
+    assert_eq!(iter.next_back(), Some(200));
+    assert_eq!(iter.next_back(), Some(64));
+    assert_eq!(iter.by_ref().count(), 6);
+    assert_eq!(iter.next(), None);
+  }
+
+  #[test]
+  fn compact_bit_set_ops() {
+    let a: CompactBitSet<2> = [1, 2, 3, 40].into();
+    let b: CompactBitSet<2> = [3, 4].into();
+    assert_eq!(&a | &b, CompactBitSet::<2>::from([1, 2, 3, 4, 40]));
+    assert_eq!(&a & &b, CompactBitSet::<2>::from([3]));
+    assert_eq!(&a ^ &b, CompactBitSet::<2>::from([1, 2, 4, 40]));
+    assert_eq!(&a - &b, CompactBitSet::<2>::from([1, 2, 40]));
+    assert!((&a & &b).is_subset(&b));
+    assert!(a.is_superset(&CompactBitSet::<0>::from([40])));
+    assert!((&a - &b).is_disjoint(&b));
+
+    let mut c = a.clone();
+    c -= &a;
+    assert!(c.is_empty());
+    assert_eq!(c, CompactBitSet::<2>::new());
+    assert_eq!(format!("{b:?}"), "{3, 4}");
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn compact_bit_set_serde() {
+    let set: CompactBitSet<2> = [5, 70].into();
+    let json = serde_json::to_string(&set).unwrap();
+    assert_eq!(json, "[5,70]");
+    assert_eq!(
+      serde_json::from_str::<CompactBitSet<2>>(&json).unwrap(),
+      set
+    );
+  }
+}
@@ -0,0 +1,507 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::borrow::BorrowMut;
+use core::cmp::Ordering;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::ops::Deref;
+use core::ops::DerefMut;
+
+use crate::CompactVec;
+use crate::compact_vec;
+
+/// A growable byte string that stores up to `N` bytes inline, spilling over
+/// to the heap when it grows beyond that.
+///
+/// This is the binary counterpart of [`SmallString`](crate::SmallString):
+/// it is built on `CompactVec<u8, N>` and has no UTF-8 invariant, which
+/// makes it a good fit for packet payloads, hashes, and other short binary
+/// data. It derefs to `[u8]`, and serializes as a byte string with serde.
+///
+/// The default inline capacity of 24 bytes matches the size of the `Vec<u8>`
+/// that it spills to, so it costs no more space than a `Vec` would.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::CompactBytes;
+///
+/// let mut payload = CompactBytes::<16>::from(&[0xCA, 0xFE][..]);
+/// payload.extend_from_slice(b"\x00\x01");
+/// assert_eq!(payload, [0xCA, 0xFE, 0x00, 0x01]);
+/// assert!(payload.is_inline());
+///
+/// payload.extend_from_slice(&[0xFF; 16]);
+/// assert!(payload.is_spilled());
+/// payload.truncate(2);
+/// assert_eq!(payload.as_slice(), b"\xCA\xFE");
+/// ```
+#[derive(Clone, Default)]
+pub struct CompactBytes<const N: usize = 24> {
+  buf: CompactVec<u8, N>,
+}
+
+impl<const N: usize> CompactBytes<N> {
+  /// Creates a new, empty `CompactBytes` using inline storage.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      buf: CompactVec::new(),
+    }
+  }
+
+  /// Creates a new, empty `CompactBytes` with room for at least `capacity`
+  /// bytes, allocating up front only if that exceeds `N`.
+  #[inline]
+  pub fn with_capacity(capacity: usize) -> Self {
+    let mut bytes = Self::new();
+    bytes.reserve(capacity);
+    bytes
+  }
+
+  /// Returns the length of the byte string.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.buf.len()
+  }
+
+  /// Returns `true` if the byte string is empty.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.buf.is_empty()
+  }
+
+  /// Returns the number of bytes that can be held without reallocating.
+  #[inline(always)]
+  pub fn capacity(&self) -> usize {
+    self.buf.capacity()
+  }
+
+  /// Returns `true` if the bytes are currently stored inline.
+  #[inline(always)]
+  pub fn is_inline(&self) -> bool {
+    self.buf.is_inline()
+  }
+
+  /// Returns `true` if the bytes have spilled over to the heap.
+  #[inline(always)]
+  pub fn is_spilled(&self) -> bool {
+    self.buf.is_spilled()
+  }
+
+  /// Returns the contents as a byte slice.
+  #[inline(always)]
+  pub fn as_slice(&self) -> &[u8] {
+    &self.buf
+  }
+
+  /// Returns the contents as a mutable byte slice.
+  #[inline(always)]
+  pub fn as_mut_slice(&mut self) -> &mut [u8] {
+    &mut self.buf
+  }
+
+  /// Appends a byte to the end.
+  #[inline]
+  pub fn push(&mut self, byte: u8) {
+    self.buf.push(byte);
+  }
+
+  /// Removes the last byte and returns it, or `None` if empty.
+  #[inline]
+  pub fn pop(&mut self) -> Option<u8> {
+    self.buf.pop()
+  }
+
+  /// Appends all bytes of `bytes` to the end, with a single copy.
+  #[inline]
+  pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+    self.buf.extend_from_copy_slice(bytes);
+  }
+
+  /// Shortens the byte string to `new_len` bytes. Has no effect if `new_len`
+  /// is greater than the current length.
+  #[inline]
+  pub fn truncate(&mut self, new_len: usize) {
+    self.buf.truncate(new_len);
+  }
+
+  /// Removes all bytes, releasing any heap storage.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.buf.clear();
+  }
+
+  /// Reserves capacity for at least `additional` more bytes.
+  #[inline]
+  pub fn reserve(&mut self, additional: usize) {
+    self.buf.reserve(additional);
+  }
+
+  /// Shrinks the capacity as much as possible, moving the bytes back inline
+  /// if they fit.
+  #[inline]
+  pub fn shrink_to_fit(&mut self) {
+    self.buf.shrink_to_fit();
+  }
+
+  /// Converts into the underlying `CompactVec<u8, N>`, without copying.
+  #[inline(always)]
+  pub fn into_compact_vec(self) -> CompactVec<u8, N> {
+    self.buf
+  }
+
+  /// Converts into a `Vec<u8>`, reusing the heap allocation if spilled.
+  #[inline]
+  pub fn into_vec(self) -> Vec<u8> {
+    self.buf.into_vec()
+  }
+}
+
+impl<const N: usize> Deref for CompactBytes<N> {
+  type Target = [u8];
+
+  #[inline(always)]
+  fn deref(&self) -> &[u8] {
+    &self.buf
+  }
+}
+
+impl<const N: usize> DerefMut for CompactBytes<N> {
+  #[inline(always)]
+  fn deref_mut(&mut self) -> &mut [u8] {
+    &mut self.buf
+  }
+}
+
+impl<const N: usize> AsRef<[u8]> for CompactBytes<N> {
+  #[inline(always)]
+  fn as_ref(&self) -> &[u8] {
+    self
+  }
+}
+
+impl<const N: usize> AsMut<[u8]> for CompactBytes<N> {
+  #[inline(always)]
+  fn as_mut(&mut self) -> &mut [u8] {
+    self
+  }
+}
+
+impl<const N: usize> Borrow<[u8]> for CompactBytes<N> {
+  #[inline(always)]
+  fn borrow(&self) -> &[u8] {
+    self
+  }
+}
+
+impl<const N: usize> BorrowMut<[u8]> for CompactBytes<N> {
+  #[inline(always)]
+  fn borrow_mut(&mut self) -> &mut [u8] {
+    self
+  }
+}
+
+impl<const N: usize> Debug for CompactBytes<N> {
+  /// Formats the bytes as an escaped byte string literal, like `b"\x00ab"`.
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "b\"{}\"", self.escape_ascii())
+  }
+}
+
+impl<const N: usize> Hash for CompactBytes<N> {
+  #[inline(always)]
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.as_slice().hash(state);
+  }
+}
+
+impl<const N: usize, const M: usize> PartialEq<CompactBytes<M>>
+  for CompactBytes<N>
+{
+  #[inline(always)]
+  fn eq(&self, other: &CompactBytes<M>) -> bool {
+    self.as_slice() == other.as_slice()
+  }
+}
+
+impl<const N: usize> PartialEq<[u8]> for CompactBytes<N> {
+  #[inline(always)]
+  fn eq(&self, other: &[u8]) -> bool {
+    self.as_slice() == other
+  }
+}
+
+impl<const N: usize> PartialEq<&[u8]> for CompactBytes<N> {
+  #[inline(always)]
+  fn eq(&self, other: &&[u8]) -> bool {
+    self.as_slice() == *other
+  }
+}
+
+impl<const N: usize, const M: usize> PartialEq<[u8; M]> for CompactBytes<N> {
+  #[inline(always)]
+  fn eq(&self, other: &[u8; M]) -> bool {
+    self.as_slice() == other
+  }
+}
+
+impl<const N: usize> PartialEq<Vec<u8>> for CompactBytes<N> {
+  #[inline(always)]
+  fn eq(&self, other: &Vec<u8>) -> bool {
+    self.as_slice() == other.as_slice()
+  }
+}
+
+impl<const N: usize> Eq for CompactBytes<N> {}
+
+impl<const N: usize> PartialOrd for CompactBytes<N> {
+  #[inline(always)]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<const N: usize> Ord for CompactBytes<N> {
+  #[inline(always)]
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.as_slice().cmp(other.as_slice())
+  }
+}
+
+impl<const N: usize> From<&[u8]> for CompactBytes<N> {
+  #[inline]
+  fn from(bytes: &[u8]) -> Self {
+    let mut this = Self::new();
+    this.extend_from_slice(bytes);
+    this
+  }
+}
+
+impl<const N: usize, const M: usize> From<[u8; M]> for CompactBytes<N> {
+  #[inline]
+  fn from(bytes: [u8; M]) -> Self {
+    Self::from(&bytes[..])
+  }
+}
+
+impl<const N: usize> From<&str> for CompactBytes<N> {
+  #[inline]
+  fn from(s: &str) -> Self {
+    Self::from(s.as_bytes())
+  }
+}
+
+impl<const N: usize> From<Vec<u8>> for CompactBytes<N> {
+  /// Converts a `Vec<u8>` into `CompactBytes`, adopting its heap allocation
+  /// unless the bytes fit inline.
+  #[inline]
+  fn from(vec: Vec<u8>) -> Self {
+    Self {
+      buf: CompactVec::from(vec),
+    }
+  }
+}
+
+impl<const N: usize> From<CompactVec<u8, N>> for CompactBytes<N> {
+  #[inline(always)]
+  fn from(buf: CompactVec<u8, N>) -> Self {
+    Self { buf }
+  }
+}
+
+impl<const N: usize> From<CompactBytes<N>> for Vec<u8> {
+  #[inline]
+  fn from(bytes: CompactBytes<N>) -> Self {
+    bytes.into_vec()
+  }
+}
+
+impl<const N: usize> Extend<u8> for CompactBytes<N> {
+  #[inline]
+  fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+    self.buf.extend(iter);
+  }
+}
+
+impl<'a, const N: usize> Extend<&'a u8> for CompactBytes<N> {
+  #[inline]
+  fn extend<I: IntoIterator<Item = &'a u8>>(&mut self, iter: I) {
+    self.buf.extend(iter.into_iter().copied());
+  }
+}
+
+impl<const N: usize> FromIterator<u8> for CompactBytes<N> {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+    Self {
+      buf: CompactVec::from_iter(iter),
+    }
+  }
+}
+
+impl<const N: usize> IntoIterator for CompactBytes<N> {
+  type Item = u8;
+  type IntoIter = compact_vec::IntoIter<u8, N>;
+
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    self.buf.into_iter()
+  }
+}
+
+impl<'a, const N: usize> IntoIterator for &'a CompactBytes<N> {
+  type Item = &'a u8;
+  type IntoIter = core::slice::Iter<'a, u8>;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+  use serde::de;
+
+  use super::*;
+
+  impl<const N: usize> Serialize for CompactBytes<N> {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      serializer.serialize_bytes(self)
+    }
+  }
+
+  struct CompactBytesVisitor<const N: usize>;
+
+  impl<'de, const N: usize> de::Visitor<'de> for CompactBytesVisitor<N> {
+    type Value = CompactBytes<N>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a byte string")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+      E: de::Error,
+    {
+      Ok(CompactBytes::from(v))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+      E: de::Error,
+    {
+      Ok(CompactBytes::from(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+      E: de::Error,
+    {
+      Ok(CompactBytes::from(v))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+      A: de::SeqAccess<'de>,
+    {
+      let mut bytes = CompactBytes::new();
+      bytes.reserve(seq.size_hint().unwrap_or(0).min(4096));
+      while let Some(byte) = seq.next_element()? {
+        bytes.push(byte);
+      }
+      Ok(bytes)
+    }
+  }
+
+  impl<'de, const N: usize> Deserialize<'de> for CompactBytes<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      deserializer.deserialize_byte_buf(CompactBytesVisitor)
+    }
+  }
+}
+
+#[cfg(feature = "zeroize")]
+mod zeroize_impl {
+  use zeroize::Zeroize;
+  use zeroize::ZeroizeOnDrop;
+
+  use super::*;
+
+  impl<const N: usize> Zeroize for CompactBytes<N> {
+    #[inline]
+    fn zeroize(&mut self) {
+      self.buf.zeroize();
+    }
+  }
+
+  impl<const N: usize> ZeroizeOnDrop for CompactBytes<N> {}
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn compact_bytes_default_capacity() {
+    let bytes = CompactBytes::<24>::from(&[7; 24][..]);
+    assert!(bytes.is_inline());
+    assert_eq!(size_of::<CompactBytes>(), size_of::<CompactVec<u8, 24>>());
+  }
+
+  #[test]
+  fn compact_bytes_push_extend_truncate() {
+    let mut bytes: CompactBytes<4> = CompactBytes::new();
+    bytes.push(0);
+    bytes.extend_from_slice(&[1, 2, 3]);
+    assert!(bytes.is_inline());
+    bytes.extend([4, 5]);
+    assert!(bytes.is_spilled());
+    assert_eq!(bytes, [0, 1, 2, 3, 4, 5]);
+    assert_eq!(bytes.pop(), Some(5));
+    bytes.truncate(3);
+    bytes.shrink_to_fit();
+    assert!(bytes.is_inline());
+    assert_eq!(bytes.into_vec(), [0, 1, 2]);
+  }
+
+  #[test]
+  fn compact_bytes_conversions_and_debug() {
+    let vec = vec![b'a'; 32];
+    let ptr = vec.as_ptr();
+    let bytes = CompactBytes::<8>::from(vec);
+    assert_eq!(bytes.as_ptr(), ptr);
+    let vec = Vec::from(bytes);
+    assert_eq!(vec.as_ptr(), ptr);
+
+    let bytes = CompactBytes::<8>::from("hi\n\0");
+    assert_eq!(format!("{bytes:?}"), r#"b"hi\n\x00""#);
+    let (low, high) = (CompactBytes::<2>::from([1]), CompactBytes::from([2]));
+    assert!(low < high);
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn compact_bytes_serde() {
+    let bytes = CompactBytes::<8>::from([1, 2, 3]);
+    let json = serde_json::to_string(&bytes).unwrap();
+    assert_eq!(json, "[1,2,3]");
+    let back: CompactBytes<8> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, bytes);
+  }
+}
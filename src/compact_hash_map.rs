@@ -0,0 +1,1143 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::hash::BuildHasher;
+use core::hash::BuildHasherDefault;
+use core::hash::Hash;
+use core::iter::FusedIterator;
+use core::mem;
+use core::ops::Index;
+use core::slice;
+
+use hashbrown::HashTable;
+use hashbrown::hash_table;
+
+use crate::CompactVec;
+use crate::compact_vec;
+use crate::pre_hashed::FxHasher;
+
+/// The default hasher of a [`CompactHashMap`], which hashes keys with
+/// [`FxHasher`].
+pub type DefaultHashBuilder = BuildHasherDefault<FxHasher>;
+
+/// A hash map that stores up to `N` entries inline and finds them with a
+/// linear scan, and moves them into a [`hashbrown`] hash table beyond that.
+///
+/// Most maps hold only a handful of entries, for which comparing each key in
+/// turn beats hashing it, and a `HashMap` allocation is pure overhead. A
+/// `CompactHashMap` starts out like a [`SmallMap`](crate::SmallMap), but
+/// once it outgrows its inline storage it hashes its keys like a `HashMap`,
+/// so lookups stay fast however large it gets. Either way, it behaves as a
+/// `HashMap` would: keys are looked up by [`Eq`] and [`Hash`], through
+/// [`Borrow`], and the iteration order is unspecified.
+///
+/// Once a map has moved into a hash table, it stays there until it is
+/// [cleared](Self::clear) or [shrunk](Self::shrink_to_fit).
+///
+/// The default [`DefaultHashBuilder`] is fast, but offers no protection
+/// against deliberately colliding keys; use
+/// [`with_hasher`](Self::with_hasher) to choose another hasher for keys that
+/// come from untrusted input.
+///
+/// This type is only available with the `hashbrown` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::CompactHashMap;
+///
+/// let mut stock: CompactHashMap<String, u32, 4> = CompactHashMap::new();
+/// stock.insert("apples".to_string(), 3);
+/// stock.insert("pears".to_string(), 5);
+/// *stock.entry("apples".to_string()).or_default() += 2;
+/// assert_eq!(stock["apples"], 5);
+/// assert!(stock.is_inline());
+///
+/// for i in 0..100 {
+///   stock.insert(format!("item-{i}"), i);
+/// }
+/// assert!(!stock.is_inline());
+/// assert_eq!(stock.get("item-42"), Some(&42));
+/// ```
+pub struct CompactHashMap<K, V, const N: usize, S = DefaultHashBuilder> {
+  repr:         Repr<K, V, N>,
+  hash_builder: S,
+}
+
+#[derive(Clone)]
+enum Repr<K, V, const N: usize> {
+  Inline(CompactVec<(K, V), N>),
+  Table(HashTable<(K, V)>),
+}
+
+impl<K, V, const N: usize> CompactHashMap<K, V, N> {
+  /// Creates a new, empty `CompactHashMap` using inline storage.
+  #[inline]
+  pub const fn new() -> Self {
+    Self::with_hasher(BuildHasherDefault::new())
+  }
+}
+
+impl<K, V, const N: usize, S> CompactHashMap<K, V, N, S> {
+  /// Creates a new, empty `CompactHashMap` using inline storage, which will
+  /// hash its keys with `hash_builder` once it outgrows it.
+  #[inline]
+  pub const fn with_hasher(hash_builder: S) -> Self {
+    Self {
+      repr: Repr::Inline(CompactVec::new()),
+      hash_builder,
+    }
+  }
+
+  /// Returns a reference to the map's [`BuildHasher`].
+  #[inline(always)]
+  pub fn hasher(&self) -> &S {
+    &self.hash_builder
+  }
+
+  /// Returns the number of entries in the map.
+  #[inline]
+  pub fn len(&self) -> usize {
+    match &self.repr {
+      Repr::Inline(entries) => entries.len(),
+      Repr::Table(table) => table.len(),
+    }
+  }
+
+  /// Returns `true` if the map contains no entries.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Returns the number of entries the map can hold without reallocating.
+  #[inline]
+  pub fn capacity(&self) -> usize {
+    match &self.repr {
+      Repr::Inline(_) => N,
+      Repr::Table(table) => table.capacity(),
+    }
+  }
+
+  /// Returns `true` if the entries are stored inline, rather than in a hash
+  /// table.
+  #[inline(always)]
+  pub fn is_inline(&self) -> bool {
+    matches!(self.repr, Repr::Inline(_))
+  }
+
+  /// Removes all entries, releasing any heap storage.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.repr = Repr::Inline(CompactVec::new());
+  }
+
+  /// Returns an iterator over the entries, in an unspecified order.
+  #[inline]
+  pub fn iter(&self) -> Iter<'_, K, V> {
+    let inner = match &self.repr {
+      Repr::Inline(entries) => IterInner::Inline(entries.iter()),
+      Repr::Table(table) => IterInner::Table(table.iter()),
+    };
+    Iter { inner }
+  }
+
+  /// Returns an iterator over the entries with mutable references to the
+  /// values, in an unspecified order.
+  #[inline]
+  pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+    let inner = match &mut self.repr {
+      Repr::Inline(entries) => IterMutInner::Inline(entries.iter_mut()),
+      Repr::Table(table) => IterMutInner::Table(table.iter_mut()),
+    };
+    IterMut { inner }
+  }
+
+  /// Returns an iterator over the keys, in an unspecified order.
+  #[inline]
+  pub fn keys(&self) -> Keys<'_, K, V> {
+    Keys { inner: self.iter() }
+  }
+
+  /// Returns an iterator over the values, in an unspecified order.
+  #[inline]
+  pub fn values(&self) -> Values<'_, K, V> {
+    Values { inner: self.iter() }
+  }
+
+  /// Returns an iterator over mutable references to the values, in an
+  /// unspecified order.
+  #[inline]
+  pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+    ValuesMut {
+      inner: self.iter_mut(),
+    }
+  }
+
+  /// Retains only the entries for which `f` returns `true`.
+  pub fn retain<F>(&mut self, mut f: F)
+  where
+    F: FnMut(&K, &mut V) -> bool,
+  {
+    match &mut self.repr {
+      Repr::Inline(entries) => {
+        let mut kept = 0;
+        for i in 0..entries.len() {
+          let (key, value) = &mut entries[i];
+          if f(key, value) {
+            entries.swap(kept, i);
+            kept += 1;
+          }
+        }
+        entries.truncate(kept);
+      }
+      Repr::Table(table) => table.retain(|(key, value)| f(key, value)),
+    }
+  }
+}
+
+impl<K, V, const N: usize, S> CompactHashMap<K, V, N, S>
+where
+  K: Eq + Hash,
+  S: BuildHasher,
+{
+  /// Reserves capacity for at least `additional` more entries, moving them
+  /// into a hash table if they won't fit inline.
+  pub fn reserve(&mut self, additional: usize) {
+    let hash_builder = &self.hash_builder;
+    match &mut self.repr {
+      Repr::Inline(entries) if entries.len() + additional > N => {
+        self.move_to_table(additional);
+      }
+      Repr::Inline(_) => {}
+      Repr::Table(table) => {
+        table.reserve(additional, |(key, _)| hash_builder.hash_one(key));
+      }
+    }
+  }
+
+  /// Shrinks the capacity of the map as much as possible, moving the entries
+  /// back inline if they fit.
+  pub fn shrink_to_fit(&mut self) {
+    let Repr::Table(table) = &mut self.repr else {
+      return;
+    };
+    if table.len() <= N {
+      let mut entries = CompactVec::new();
+      entries.extend(mem::take(table));
+      self.repr = Repr::Inline(entries);
+    } else {
+      let hash_builder = &self.hash_builder;
+      table.shrink_to_fit(|(key, _)| hash_builder.hash_one(key));
+    }
+  }
+
+  /// Moves the entries into a hash table with room for `additional` more.
+  #[cold]
+  fn move_to_table(&mut self, additional: usize) {
+    let Repr::Inline(entries) = &mut self.repr else {
+      return;
+    };
+    let entries = mem::take(entries);
+    let hasher = |(key, _): &(K, V)| self.hash_builder.hash_one(key);
+    let mut table = HashTable::with_capacity(entries.len() + additional);
+    for entry in entries {
+      table.insert_unique(hasher(&entry), entry, hasher);
+    }
+    self.repr = Repr::Table(table);
+  }
+
+  /// Returns the entry for `key`, if present.
+  #[inline]
+  fn find<Q>(&self, key: &Q) -> Option<&(K, V)>
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Hash + Eq,
+  {
+    match &self.repr {
+      Repr::Inline(entries) => entries.iter().find(|(k, _)| k.borrow() == key),
+      Repr::Table(table) => {
+        let hash = self.hash_builder.hash_one(key);
+        table.find(hash, |(k, _)| k.borrow() == key)
+      }
+    }
+  }
+
+  /// Returns a reference to the value for `key`, if present.
+  #[inline]
+  pub fn get<Q>(&self, key: &Q) -> Option<&V>
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Hash + Eq,
+  {
+    self.find(key).map(|(_, value)| value)
+  }
+
+  /// Returns references to the stored key and the value for `key`, if
+  /// present.
+  #[inline]
+  pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Hash + Eq,
+  {
+    self.find(key).map(|(k, v)| (k, v))
+  }
+
+  /// Returns a mutable reference to the value for `key`, if present.
+  #[inline]
+  pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Hash + Eq,
+  {
+    let entry = match &mut self.repr {
+      Repr::Inline(entries) => {
+        entries.iter_mut().find(|(k, _)| k.borrow() == key)
+      }
+      Repr::Table(table) => {
+        let hash = self.hash_builder.hash_one(key);
+        table.find_mut(hash, |(k, _)| k.borrow() == key)
+      }
+    };
+    entry.map(|(_, value)| value)
+  }
+
+  /// Returns `true` if the map contains an entry for `key`.
+  #[inline]
+  pub fn contains_key<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Hash + Eq,
+  {
+    self.find(key).is_some()
+  }
+
+  /// Inserts a key-value pair into the map.
+  ///
+  /// If the map already had an entry for the key, its value is replaced and
+  /// the old value returned, keeping the original key. Otherwise, `None` is
+  /// returned.
+  #[inline]
+  pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+    match self.entry(key) {
+      Entry::Occupied(mut entry) => Some(entry.insert(value)),
+      Entry::Vacant(entry) => {
+        entry.insert(value);
+        None
+      }
+    }
+  }
+
+  /// Removes the entry for `key`, returning its value if it was present.
+  #[inline]
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Hash + Eq,
+  {
+    self.remove_entry(key).map(|(_, value)| value)
+  }
+
+  /// Removes the entry for `key`, returning the stored key and value if it
+  /// was present.
+  pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Hash + Eq,
+  {
+    match &mut self.repr {
+      Repr::Inline(entries) => {
+        let index = entries.iter().position(|(k, _)| k.borrow() == key)?;
+        Some(swap_remove(entries, index))
+      }
+      Repr::Table(table) => {
+        let hash = self.hash_builder.hash_one(key);
+        let entry = table.find_entry(hash, |(k, _)| k.borrow() == key).ok()?;
+        Some(entry.remove().0)
+      }
+    }
+  }
+
+  /// Returns the entry for `key`, for in-place manipulation.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactHashMap;
+  ///
+  /// let mut counts: CompactHashMap<char, u32, 4> = CompactHashMap::new();
+  /// for c in "mississippi".chars() {
+  ///   *counts.entry(c).or_default() += 1;
+  /// }
+  /// assert_eq!(counts[&'s'], 4);
+  /// assert_eq!(counts.len(), 4);
+  /// ```
+  pub fn entry(&mut self, key: K) -> Entry<'_, K, V, N, S> {
+    if let Repr::Inline(entries) = &self.repr {
+      let index = entries.iter().position(|(k, _)| *k == key);
+      return match index {
+        Some(index) => {
+          let Repr::Inline(entries) = &mut self.repr else {
+            unreachable!()
+          };
+          Entry::Occupied(OccupiedEntry {
+            inner: Occupied::Inline { entries, index },
+          })
+        }
+        None => Entry::Vacant(VacantEntry {
+          key,
+          inner: Vacant::Inline(self),
+        }),
+      };
+    }
+    let Repr::Table(table) = &mut self.repr else {
+      unreachable!()
+    };
+    let hash_builder = &self.hash_builder;
+    let hash = hash_builder.hash_one(&key);
+    let entry =
+      table.entry(hash, |(k, _)| *k == key, |(k, _)| hash_builder.hash_one(k));
+    match entry {
+      hash_table::Entry::Occupied(entry) => Entry::Occupied(OccupiedEntry {
+        inner: Occupied::Table(entry),
+      }),
+      hash_table::Entry::Vacant(entry) => Entry::Vacant(VacantEntry {
+        key,
+        inner: Vacant::Table(entry),
+      }),
+    }
+  }
+
+  /// Inserts an entry whose key is known not to be in the map yet.
+  fn insert_unique(&mut self, key: K, value: V) -> &mut V {
+    if self.is_inline() && self.len() == N {
+      self.move_to_table(1);
+    }
+    match &mut self.repr {
+      Repr::Inline(entries) => {
+        entries.push((key, value));
+        &mut entries.last_mut().unwrap().1
+      }
+      Repr::Table(table) => {
+        let hash_builder = &self.hash_builder;
+        let hash = hash_builder.hash_one(&key);
+        let entry = table
+          .insert_unique(hash, (key, value), |(k, _)| hash_builder.hash_one(k));
+        &mut entry.into_mut().1
+      }
+    }
+  }
+}
+
+/// Removes the entry at `index` by moving the last entry into its place,
+/// since the order of inline entries doesn't matter.
+#[inline]
+fn swap_remove<T, const N: usize>(
+  entries: &mut CompactVec<T, N>,
+  index: usize,
+) -> T {
+  let last = entries.len() - 1;
+  entries.swap(index, last);
+  entries.pop().unwrap()
+}
+
+/// A view into a single entry of a [`CompactHashMap`], which may be vacant
+/// or occupied.
+///
+/// Created by [`CompactHashMap::entry`].
+pub enum Entry<'a, K, V, const N: usize, S> {
+  /// An entry that already exists in the map.
+  Occupied(OccupiedEntry<'a, K, V, N>),
+  /// An entry that does not exist in the map yet.
+  Vacant(VacantEntry<'a, K, V, N, S>),
+}
+
+impl<'a, K, V, const N: usize, S> Entry<'a, K, V, N, S>
+where
+  K: Eq + Hash,
+  S: BuildHasher,
+{
+  /// Returns a reference to the key of this entry.
+  #[inline]
+  pub fn key(&self) -> &K {
+    match self {
+      Entry::Occupied(entry) => entry.key(),
+      Entry::Vacant(entry) => entry.key(),
+    }
+  }
+
+  /// Inserts `default` if the entry is vacant, and returns a mutable
+  /// reference to the value.
+  #[inline]
+  pub fn or_insert(self, default: V) -> &'a mut V {
+    match self {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => entry.insert(default),
+    }
+  }
+
+  /// Inserts the result of `default` if the entry is vacant, and returns a
+  /// mutable reference to the value.
+  #[inline]
+  pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+    match self {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => entry.insert(default()),
+    }
+  }
+
+  /// Inserts the default value if the entry is vacant, and returns a mutable
+  /// reference to the value.
+  #[inline]
+  pub fn or_default(self) -> &'a mut V
+  where
+    V: Default,
+  {
+    self.or_insert_with(V::default)
+  }
+
+  /// Calls `f` with the value if the entry is occupied.
+  #[inline]
+  pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+    if let Entry::Occupied(entry) = &mut self {
+      f(entry.get_mut());
+    }
+    self
+  }
+}
+
+/// An occupied entry in a [`CompactHashMap`].
+pub struct OccupiedEntry<'a, K, V, const N: usize> {
+  inner: Occupied<'a, K, V, N>,
+}
+
+enum Occupied<'a, K, V, const N: usize> {
+  Inline {
+    entries: &'a mut CompactVec<(K, V), N>,
+    index:   usize,
+  },
+  Table(hash_table::OccupiedEntry<'a, (K, V)>),
+}
+
+impl<'a, K, V, const N: usize> OccupiedEntry<'a, K, V, N> {
+  #[inline]
+  fn pair(&self) -> &(K, V) {
+    match &self.inner {
+      Occupied::Inline { entries, index } => &entries[*index],
+      Occupied::Table(entry) => entry.get(),
+    }
+  }
+
+  /// Returns a reference to the key of this entry.
+  #[inline]
+  pub fn key(&self) -> &K {
+    &self.pair().0
+  }
+
+  /// Returns a reference to the value of this entry.
+  #[inline]
+  pub fn get(&self) -> &V {
+    &self.pair().1
+  }
+
+  /// Returns a mutable reference to the value of this entry.
+  #[inline]
+  pub fn get_mut(&mut self) -> &mut V {
+    match &mut self.inner {
+      Occupied::Inline { entries, index } => &mut entries[*index].1,
+      Occupied::Table(entry) => &mut entry.get_mut().1,
+    }
+  }
+
+  /// Converts the entry into a mutable reference to its value.
+  #[inline]
+  pub fn into_mut(self) -> &'a mut V {
+    match self.inner {
+      Occupied::Inline { entries, index } => &mut entries[index].1,
+      Occupied::Table(entry) => &mut entry.into_mut().1,
+    }
+  }
+
+  /// Replaces the value of this entry, returning the old value.
+  #[inline]
+  pub fn insert(&mut self, value: V) -> V {
+    mem::replace(self.get_mut(), value)
+  }
+
+  /// Removes this entry from the map, returning its value.
+  #[inline]
+  pub fn remove(self) -> V {
+    self.remove_entry().1
+  }
+
+  /// Removes this entry from the map, returning its key and value.
+  #[inline]
+  pub fn remove_entry(self) -> (K, V) {
+    match self.inner {
+      Occupied::Inline { entries, index } => swap_remove(entries, index),
+      Occupied::Table(entry) => entry.remove().0,
+    }
+  }
+}
+
+/// A vacant entry in a [`CompactHashMap`].
+pub struct VacantEntry<'a, K, V, const N: usize, S> {
+  key:   K,
+  inner: Vacant<'a, K, V, N, S>,
+}
+
+enum Vacant<'a, K, V, const N: usize, S> {
+  Inline(&'a mut CompactHashMap<K, V, N, S>),
+  Table(hash_table::VacantEntry<'a, (K, V)>),
+}
+
+impl<'a, K, V, const N: usize, S> VacantEntry<'a, K, V, N, S>
+where
+  K: Eq + Hash,
+  S: BuildHasher,
+{
+  /// Returns a reference to the key that would be inserted.
+  #[inline(always)]
+  pub fn key(&self) -> &K {
+    &self.key
+  }
+
+  /// Takes ownership of the key, leaving the map unchanged.
+  #[inline(always)]
+  pub fn into_key(self) -> K {
+    self.key
+  }
+
+  /// Inserts `value` under this entry's key, moving the map's entries into
+  /// a hash table if they no longer fit inline, and returns a mutable
+  /// reference to it.
+  #[inline]
+  pub fn insert(self, value: V) -> &'a mut V {
+    match self.inner {
+      Vacant::Inline(map) => map.insert_unique(self.key, value),
+      Vacant::Table(entry) => &mut entry.insert((self.key, value)).into_mut().1,
+    }
+  }
+}
+
+impl<K: Clone, V: Clone, const N: usize, S: Clone> Clone
+  for CompactHashMap<K, V, N, S>
+{
+  #[inline]
+  fn clone(&self) -> Self {
+    Self {
+      repr:         self.repr.clone(),
+      hash_builder: self.hash_builder.clone(),
+    }
+  }
+}
+
+impl<K, V, const N: usize, S: Default> Default for CompactHashMap<K, V, N, S> {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::with_hasher(S::default())
+  }
+}
+
+impl<K: Debug, V: Debug, const N: usize, S> Debug
+  for CompactHashMap<K, V, N, S>
+{
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_map().entries(self.iter()).finish()
+  }
+}
+
+impl<K, V, const N: usize, const M: usize, S>
+  PartialEq<CompactHashMap<K, V, M, S>> for CompactHashMap<K, V, N, S>
+where
+  K: Eq + Hash,
+  V: PartialEq,
+  S: BuildHasher,
+{
+  /// Two maps are equal if they contain the same entries, regardless of
+  /// their order or storage.
+  fn eq(&self, other: &CompactHashMap<K, V, M, S>) -> bool {
+    self.len() == other.len()
+      && self
+        .iter()
+        .all(|(key, value)| other.get(key) == Some(value))
+  }
+}
+
+impl<K, V, const N: usize, S> Eq for CompactHashMap<K, V, N, S>
+where
+  K: Eq + Hash,
+  V: Eq,
+  S: BuildHasher,
+{
+}
+
+impl<K, Q, V, const N: usize, S> Index<&Q> for CompactHashMap<K, V, N, S>
+where
+  K: Eq + Hash + Borrow<Q>,
+  Q: ?Sized + Hash + Eq,
+  S: BuildHasher,
+{
+  type Output = V;
+
+  /// Returns a reference to the value for `key`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the key is not present in the map.
+  #[inline]
+  fn index(&self, key: &Q) -> &V {
+    self.get(key).expect("key not found in CompactHashMap")
+  }
+}
+
+impl<K, V, const N: usize, S> Extend<(K, V)> for CompactHashMap<K, V, N, S>
+where
+  K: Eq + Hash,
+  S: BuildHasher,
+{
+  fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+    for (key, value) in iter {
+      self.insert(key, value);
+    }
+  }
+}
+
+impl<K, V, const N: usize, S> FromIterator<(K, V)>
+  for CompactHashMap<K, V, N, S>
+where
+  K: Eq + Hash,
+  S: BuildHasher + Default,
+{
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+    let mut map = Self::default();
+    map.extend(iter);
+    map
+  }
+}
+
+impl<K, V, const N: usize, const M: usize, S> From<[(K, V); M]>
+  for CompactHashMap<K, V, N, S>
+where
+  K: Eq + Hash,
+  S: BuildHasher + Default,
+{
+  #[inline]
+  fn from(entries: [(K, V); M]) -> Self {
+    Self::from_iter(entries)
+  }
+}
+
+impl<K, V, const N: usize, S> IntoIterator for CompactHashMap<K, V, N, S> {
+  type Item = (K, V);
+  type IntoIter = IntoIter<K, V, N>;
+
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    let inner = match self.repr {
+      Repr::Inline(entries) => IntoIterInner::Inline(entries.into_iter()),
+      Repr::Table(table) => IntoIterInner::Table(table.into_iter()),
+    };
+    IntoIter { inner }
+  }
+}
+
+impl<'a, K, V, const N: usize, S> IntoIterator
+  for &'a CompactHashMap<K, V, N, S>
+{
+  type Item = (&'a K, &'a V);
+  type IntoIter = Iter<'a, K, V>;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<'a, K, V, const N: usize, S> IntoIterator
+  for &'a mut CompactHashMap<K, V, N, S>
+{
+  type Item = (&'a K, &'a mut V);
+  type IntoIter = IterMut<'a, K, V>;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter_mut()
+  }
+}
+
+/// An iterator over the entries of a [`CompactHashMap`].
+///
+/// Created by [`CompactHashMap::iter`].
+#[derive(Clone)]
+pub struct Iter<'a, K, V> {
+  inner: IterInner<'a, K, V>,
+}
+
+#[derive(Clone)]
+enum IterInner<'a, K, V> {
+  Inline(slice::Iter<'a, (K, V)>),
+  Table(hash_table::Iter<'a, (K, V)>),
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+  type Item = (&'a K, &'a V);
+
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> {
+    let entry = match &mut self.inner {
+      IterInner::Inline(iter) => iter.next(),
+      IterInner::Table(iter) => iter.next(),
+    };
+    entry.map(|(key, value)| (key, value))
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    match &self.inner {
+      IterInner::Inline(iter) => iter.size_hint(),
+      IterInner::Table(iter) => iter.size_hint(),
+    }
+  }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+/// A mutable iterator over the entries of a [`CompactHashMap`].
+///
+/// Created by [`CompactHashMap::iter_mut`].
+pub struct IterMut<'a, K, V> {
+  inner: IterMutInner<'a, K, V>,
+}
+
+enum IterMutInner<'a, K, V> {
+  Inline(slice::IterMut<'a, (K, V)>),
+  Table(hash_table::IterMut<'a, (K, V)>),
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+  type Item = (&'a K, &'a mut V);
+
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> {
+    let entry = match &mut self.inner {
+      IterMutInner::Inline(iter) => iter.next(),
+      IterMutInner::Table(iter) => iter.next(),
+    };
+    entry.map(|(key, value)| (&*key, value))
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    match &self.inner {
+      IterMutInner::Inline(iter) => iter.size_hint(),
+      IterMutInner::Table(iter) => iter.size_hint(),
+    }
+  }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+/// An owning iterator over the entries of a [`CompactHashMap`].
+///
+/// Created by the [`into_iter`](IntoIterator::into_iter) method on
+/// `CompactHashMap`.
+pub struct IntoIter<K, V, const N: usize> {
+  inner: IntoIterInner<K, V, N>,
+}
+
+enum IntoIterInner<K, V, const N: usize> {
+  Inline(compact_vec::IntoIter<(K, V), N>),
+  Table(hash_table::IntoIter<(K, V)>),
+}
+
+impl<K, V, const N: usize> Iterator for IntoIter<K, V, N> {
+  type Item = (K, V);
+
+  #[inline]
+  fn next(&mut self) -> Option<(K, V)> {
+    match &mut self.inner {
+      IntoIterInner::Inline(iter) => iter.next(),
+      IntoIterInner::Table(iter) => iter.next(),
+    }
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    match &self.inner {
+      IntoIterInner::Inline(iter) => iter.size_hint(),
+      IntoIterInner::Table(iter) => iter.size_hint(),
+    }
+  }
+}
+
+impl<K, V, const N: usize> ExactSizeIterator for IntoIter<K, V, N> {}
+impl<K, V, const N: usize> FusedIterator for IntoIter<K, V, N> {}
+
+/// An iterator over the keys of a [`CompactHashMap`].
+///
+/// Created by [`CompactHashMap::keys`].
+#[derive(Clone)]
+pub struct Keys<'a, K, V> {
+  inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+  type Item = &'a K;
+
+  #[inline]
+  fn next(&mut self) -> Option<&'a K> {
+    self.inner.next().map(|(key, _)| key)
+  }
+
+  #[inline(always)]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+/// An iterator over the values of a [`CompactHashMap`].
+///
+/// Created by [`CompactHashMap::values`].
+#[derive(Clone)]
+pub struct Values<'a, K, V> {
+  inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+  type Item = &'a V;
+
+  #[inline]
+  fn next(&mut self) -> Option<&'a V> {
+    self.inner.next().map(|(_, value)| value)
+  }
+
+  #[inline(always)]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+/// A mutable iterator over the values of a [`CompactHashMap`].
+///
+/// Created by [`CompactHashMap::values_mut`].
+pub struct ValuesMut<'a, K, V> {
+  inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+  type Item = &'a mut V;
+
+  #[inline]
+  fn next(&mut self) -> Option<&'a mut V> {
+    self.inner.next().map(|(_, value)| value)
+  }
+
+  #[inline(always)]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use core::marker::PhantomData;
+
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+  use serde::de;
+
+  use super::*;
+  use crate::compact_vec::serde_impl::cautious_size_hint;
+
+  impl<K: Serialize, V: Serialize, const N: usize, H> Serialize
+    for CompactHashMap<K, V, N, H>
+  {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      serializer.collect_map(self.iter())
+    }
+  }
+
+  struct CompactHashMapVisitor<K, V, const N: usize, H>(PhantomData<(K, V, H)>);
+
+  impl<'de, K, V, const N: usize, H> de::Visitor<'de>
+    for CompactHashMapVisitor<K, V, N, H>
+  where
+    K: Deserialize<'de> + Eq + Hash,
+    V: Deserialize<'de>,
+    H: BuildHasher + Default,
+  {
+    type Value = CompactHashMap<K, V, N, H>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a map")
+    }
+
+    fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+      A: de::MapAccess<'de>,
+    {
+      let mut map = CompactHashMap::default();
+      map.reserve(cautious_size_hint::<(K, V)>(access.size_hint()));
+      while let Some((key, value)) = access.next_entry()? {
+        map.insert(key, value);
+      }
+      Ok(map)
+    }
+  }
+
+  impl<'de, K, V, const N: usize, H> Deserialize<'de>
+    for CompactHashMap<K, V, N, H>
+  where
+    K: Deserialize<'de> + Eq + Hash,
+    V: Deserialize<'de>,
+    H: BuildHasher + Default,
+  {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      deserializer.deserialize_map(CompactHashMapVisitor(PhantomData))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::string::String;
+  use alloc::string::ToString;
+  use alloc::vec::Vec;
+
+  use super::*;
+
+  #[test]
+  fn compact_hash_map_insert_get_remove() {
+    let mut map: CompactHashMap<&str, u32, 2> = CompactHashMap::new();
+    assert_eq!(map.insert("a", 1), None);
+    assert_eq!(map.insert("b", 2), None);
+    assert!(map.is_inline());
+    assert_eq!(map.insert("a", 10), Some(1));
+    assert_eq!(map.len(), 2);
+
+    assert_eq!(map.insert("c", 3), None);
+    assert!(!map.is_inline());
+    assert_eq!(map.get("a"), Some(&10));
+    assert_eq!(map.get_key_value("c"), Some((&"c", &3)));
+    assert!(map.contains_key("b"));
+    assert!(!map.contains_key("z"));
+
+    *map.get_mut("b").unwrap() += 1;
+    assert_eq!(map.remove("b"), Some(3));
+    assert_eq!(map.remove("b"), None);
+    let mut keys: Vec<_> = map.keys().copied().collect();
+    keys.sort();
+    assert_eq!(keys, ["a", "c"]);
+  }
+
+  #[test]
+  fn compact_hash_map_grows_and_shrinks() {
+    let mut map: CompactHashMap<u32, u32, 4> = CompactHashMap::new();
+    map.extend((0..1000).map(|i| (i, i * 2)));
+    assert_eq!(map.len(), 1000);
+    assert!((0..1000).all(|i| map[&i] == i * 2));
+
+    map.retain(|k, _| *k < 3);
+    for value in map.values_mut() {
+      *value += 1;
+    }
+    assert!(!map.is_inline());
+    map.shrink_to_fit();
+    assert!(map.is_inline());
+    assert_eq!(
+      map,
+      CompactHashMap::<u32, u32, 8>::from([(2, 5), (0, 1), (1, 3)])
+    );
+
+    map.reserve(10);
+    assert!(!map.is_inline());
+    assert_eq!(map.values().sum::<u32>(), 9);
+    map.clear();
+    assert!(map.is_empty() && map.is_inline());
+  }
+
+  #[test]
+  fn compact_hash_map_borrowed_lookup() {
+    let mut map: CompactHashMap<String, usize, 1> = CompactHashMap::new();
+    map.insert("one".into(), 1);
+    map.insert("two".into(), 2);
+    assert_eq!(map.get("two"), Some(&2));
+    assert_eq!(map["one"], 1);
+    assert_eq!(map.remove_entry("one"), Some(("one".into(), 1)));
+  }
+
+  #[test]
+  fn compact_hash_map_entry() {
+    for n in [0, 1, 8] {
+      let mut map: CompactHashMap<u8, Vec<u8>, 2> = CompactHashMap::new();
+      map.extend((10..10 + n).map(|i| (i, Vec::new())));
+      map.entry(1).or_default().push(10);
+      map.entry(1).or_default().push(11);
+      map.entry(2).or_insert_with(|| vec![20]);
+      map.entry(2).and_modify(|v| v.push(21)).or_default();
+      assert_eq!(map[&1], [10, 11]);
+      assert_eq!(map[&2], [20, 21]);
+
+      match map.entry(1) {
+        Entry::Occupied(entry) => assert_eq!(entry.remove(), [10, 11]),
+        Entry::Vacant(_) => unreachable!(),
+      }
+      match map.entry(3) {
+        Entry::Vacant(entry) => assert_eq!(entry.into_key(), 3),
+        Entry::Occupied(_) => unreachable!(),
+      }
+      assert_eq!(map.len(), n as usize + 1);
+    }
+  }
+
+  #[test]
+  fn compact_hash_map_into_iter() {
+    for n in [3, 30] {
+      let map: CompactHashMap<u32, String, 4> =
+        (0..n).map(|i| (i, i.to_string())).collect();
+      let mut entries: Vec<_> = map.clone().into_iter().collect();
+      entries.sort();
+      assert_eq!(entries.len(), n as usize);
+      assert_eq!(entries[2], (2, "2".to_string()));
+      assert_eq!(map.iter().len(), n as usize);
+    }
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn compact_hash_map_serde() {
+    let map = CompactHashMap::<String, u32, 2>::from([
+      ("x".to_string(), 1),
+      ("y".to_string(), 2),
+      ("z".to_string(), 3),
+    ]);
+    let json = serde_json::to_string(&map).unwrap();
+    let back: CompactHashMap<String, u32, 2> =
+      serde_json::from_str(&json).unwrap();
+    assert_eq!(back, map);
+  }
+}
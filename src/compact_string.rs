@@ -0,0 +1,783 @@
+use alloc::alloc::Layout;
+use alloc::alloc::alloc;
+use alloc::alloc::dealloc;
+use alloc::alloc::handle_alloc_error;
+use alloc::alloc::realloc;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::borrow::Borrow;
+use core::borrow::BorrowMut;
+use core::cmp::Ordering;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::mem;
+use core::ops::Deref;
+use core::ops::DerefMut;
+use core::ptr;
+use core::slice;
+use core::str;
+use core::str::FromStr;
+
+use crate::CowStr;
+use crate::InlineStr;
+
+/// The size of a [`CompactString`]: three machine words.
+const SIZE: usize = 3 * mem::size_of::<usize>();
+
+/// The maximum length in bytes of a string stored inline in a
+/// [`CompactString`]: 23 bytes on 64-bit targets, 11 on 32-bit targets.
+pub const MAX_INLINE_COMPACT_STRING_LEN: usize = SIZE - 1;
+
+/// Marks an inline string in the last byte; the low bits hold its length.
+const INLINE_TAG: u8 = 0xC0;
+
+/// Marks a heap string in the last byte.
+const HEAP_TAG: u8 = 0xFE;
+
+/// Heap allocations start with a `usize` holding their capacity, followed by
+/// the string data.
+const HEADER: usize = mem::size_of::<usize>();
+
+/// An owned, growable UTF-8 string that stores up to
+/// [`MAX_INLINE_COMPACT_STRING_LEN`] bytes inline, in a struct the size of a
+/// `String`.
+///
+/// `CompactString` sits between [`InlineStr`](crate::InlineStr), which is
+/// fixed-size and fallible, and [`CowStr`], which carries a lifetime: it has
+/// no lifetime parameter and no length limit, and moves to the heap only once
+/// the string outgrows its 23 inline bytes (on 64-bit targets). The last byte
+/// of the struct tells the two layouts apart, so there is no separate
+/// discriminant.
+///
+/// Heap strings keep their capacity at the front of the allocation, which
+/// means a `String` is copied rather than adopted when converted.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::CompactString;
+///
+/// let mut s = CompactString::from("hello");
+/// assert!(s.is_inline());
+/// assert_eq!(size_of::<CompactString>(), size_of::<String>());
+///
+/// s.push_str(", world! this no longer fits");
+/// assert!(!s.is_inline());
+/// assert_eq!(s, "hello, world! this no longer fits");
+///
+/// s.truncate(5);
+/// s.shrink_to_fit();
+/// assert!(s.is_inline());
+/// ```
+pub struct CompactString {
+  repr: Repr,
+}
+
+#[repr(C)]
+union Repr {
+  inline: [u8; SIZE],
+  heap:   Heap,
+}
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Heap {
+  /// Points to the string data, just past the capacity header.
+  ptr:  *mut u8,
+  len:  usize,
+  _pad: [u8; mem::size_of::<usize>() - 1],
+  tag:  u8,
+}
+
+// SAFETY: a `CompactString` uniquely owns its heap allocation, if any.
+unsafe impl Send for CompactString {}
+unsafe impl Sync for CompactString {}
+
+impl CompactString {
+  /// Creates a new, empty `CompactString`. Does not allocate.
+  #[inline]
+  pub const fn new() -> Self {
+    let mut inline = [0; SIZE];
+    inline[SIZE - 1] = INLINE_TAG;
+    Self {
+      repr: Repr { inline },
+    }
+  }
+
+  /// Creates a new, empty `CompactString` with room for at least `capacity`
+  /// bytes.
+  #[inline]
+  pub fn with_capacity(capacity: usize) -> Self {
+    let mut s = Self::new();
+    s.reserve(capacity);
+    s
+  }
+
+  /// Returns the length of the string in bytes.
+  #[inline]
+  pub fn len(&self) -> usize {
+    if self.is_inline() {
+      (self.tag() - INLINE_TAG) as usize
+    } else {
+      unsafe { self.repr.heap.len }
+    }
+  }
+
+  /// Returns `true` if the string is empty.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Returns the number of bytes the string can hold without reallocating.
+  #[inline]
+  pub fn capacity(&self) -> usize {
+    if self.is_inline() {
+      MAX_INLINE_COMPACT_STRING_LEN
+    } else {
+      unsafe { self.repr.heap.ptr.sub(HEADER).cast::<usize>().read() }
+    }
+  }
+
+  /// Returns `true` if the string is stored inline.
+  #[inline(always)]
+  pub fn is_inline(&self) -> bool {
+    self.tag() != HEAP_TAG
+  }
+
+  /// Returns the string as a string slice.
+  #[inline]
+  pub fn as_str(&self) -> &str {
+    unsafe { str::from_utf8_unchecked(self.as_bytes()) }
+  }
+
+  /// Returns the string as a mutable string slice.
+  #[inline]
+  pub fn as_mut_str(&mut self) -> &mut str {
+    let len = self.len();
+    unsafe {
+      str::from_utf8_unchecked_mut(slice::from_raw_parts_mut(
+        self.as_mut_ptr(),
+        len,
+      ))
+    }
+  }
+
+  /// Returns the UTF-8 bytes of the string.
+  #[inline]
+  pub fn as_bytes(&self) -> &[u8] {
+    unsafe { slice::from_raw_parts(self.as_ptr(), self.len()) }
+  }
+
+  /// Appends a character to the end of the string.
+  #[inline]
+  pub fn push(&mut self, c: char) {
+    self.push_str(c.encode_utf8(&mut [0; 4]));
+  }
+
+  /// Appends a string slice to the end of the string.
+  pub fn push_str(&mut self, s: &str) {
+    let len = self.len();
+    self.reserve(s.len());
+    unsafe {
+      ptr::copy_nonoverlapping(s.as_ptr(), self.as_mut_ptr().add(len), s.len());
+      self.set_len(len + s.len());
+    }
+  }
+
+  /// Removes the last character and returns it, or `None` if the string is
+  /// empty.
+  #[inline]
+  pub fn pop(&mut self) -> Option<char> {
+    let c = self.as_str().chars().next_back()?;
+    unsafe { self.set_len(self.len() - c.len_utf8()) };
+    Some(c)
+  }
+
+  /// Shortens the string to `new_len` bytes. Has no effect if `new_len` is
+  /// greater than the current length.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `new_len` does not lie on a `char` boundary.
+  #[inline]
+  pub fn truncate(&mut self, new_len: usize) {
+    if new_len < self.len() {
+      assert!(
+        self.as_str().is_char_boundary(new_len),
+        "new_len does not lie on a char boundary"
+      );
+      unsafe { self.set_len(new_len) };
+    }
+  }
+
+  /// Removes all contents of the string, keeping its capacity.
+  #[inline]
+  pub fn clear(&mut self) {
+    unsafe { self.set_len(0) };
+  }
+
+  /// Reserves room for at least `additional` more bytes, moving the string
+  /// to the heap if it no longer fits inline.
+  pub fn reserve(&mut self, additional: usize) {
+    let len = self.len();
+    let needed = len.checked_add(additional).expect("capacity overflow");
+    let capacity = self.capacity();
+    if needed <= capacity {
+      return;
+    }
+    let new_capacity = needed.max(capacity * 2);
+    if self.is_inline() {
+      unsafe {
+        let ptr = heap_alloc(new_capacity);
+        ptr::copy_nonoverlapping(self.repr.inline.as_ptr(), ptr, len);
+        self.repr.heap = Heap::new(ptr, len);
+      }
+    } else {
+      unsafe {
+        let ptr = heap_realloc(self.repr.heap.ptr, capacity, new_capacity);
+        self.repr.heap.ptr = ptr;
+      }
+    }
+  }
+
+  /// Shrinks the capacity to fit the length, moving the string back inline
+  /// if it fits.
+  pub fn shrink_to_fit(&mut self) {
+    if self.is_inline() {
+      return;
+    }
+    let len = self.len();
+    let capacity = self.capacity();
+    unsafe {
+      let ptr = self.repr.heap.ptr;
+      if len <= MAX_INLINE_COMPACT_STRING_LEN {
+        let mut inline = Self::new();
+        ptr::copy_nonoverlapping(ptr, inline.repr.inline.as_mut_ptr(), len);
+        inline.set_len(len);
+        heap_dealloc(ptr, capacity);
+        ptr::write(self, inline);
+      } else if len < capacity {
+        self.repr.heap.ptr = heap_realloc(ptr, capacity, len);
+      }
+    }
+  }
+
+  /// Converts the string into a `String`.
+  #[inline]
+  pub fn into_string(self) -> String {
+    String::from(self.as_str())
+  }
+
+  #[inline(always)]
+  fn tag(&self) -> u8 {
+    unsafe { self.repr.inline[SIZE - 1] }
+  }
+
+  #[inline(always)]
+  fn as_ptr(&self) -> *const u8 {
+    if self.is_inline() {
+      unsafe { self.repr.inline.as_ptr() }
+    } else {
+      unsafe { self.repr.heap.ptr }
+    }
+  }
+
+  #[inline(always)]
+  fn as_mut_ptr(&mut self) -> *mut u8 {
+    if self.is_inline() {
+      unsafe { self.repr.inline.as_mut_ptr() }
+    } else {
+      unsafe { self.repr.heap.ptr }
+    }
+  }
+
+  /// Sets the length of the string.
+  ///
+  /// # Safety
+  ///
+  /// `len` must be at most the capacity, and the first `len` bytes must be
+  /// valid UTF-8.
+  #[inline(always)]
+  unsafe fn set_len(&mut self, len: usize) {
+    if self.is_inline() {
+      unsafe { self.repr.inline[SIZE - 1] = INLINE_TAG + len as u8 };
+    } else {
+      self.repr.heap.len = len;
+    }
+  }
+}
+
+impl Heap {
+  #[inline(always)]
+  fn new(ptr: *mut u8, len: usize) -> Self {
+    Self {
+      ptr,
+      len,
+      _pad: [0; mem::size_of::<usize>() - 1],
+      tag: HEAP_TAG,
+    }
+  }
+}
+
+#[inline]
+fn heap_layout(capacity: usize) -> Layout {
+  HEADER
+    .checked_add(capacity)
+    .and_then(|size| Layout::from_size_align(size, HEADER).ok())
+    .expect("capacity overflow")
+}
+
+/// Allocates room for `capacity` bytes, returning a pointer to the data.
+unsafe fn heap_alloc(capacity: usize) -> *mut u8 {
+  let layout = heap_layout(capacity);
+  unsafe {
+    let base = alloc(layout);
+    if base.is_null() {
+      handle_alloc_error(layout);
+    }
+    base.cast::<usize>().write(capacity);
+    base.add(HEADER)
+  }
+}
+
+/// Resizes the allocation behind `ptr` to hold `capacity` bytes.
+unsafe fn heap_realloc(ptr: *mut u8, old: usize, capacity: usize) -> *mut u8 {
+  let layout = heap_layout(capacity);
+  unsafe {
+    let base = realloc(ptr.sub(HEADER), heap_layout(old), layout.size());
+    if base.is_null() {
+      handle_alloc_error(layout);
+    }
+    base.cast::<usize>().write(capacity);
+    base.add(HEADER)
+  }
+}
+
+unsafe fn heap_dealloc(ptr: *mut u8, capacity: usize) {
+  unsafe { dealloc(ptr.sub(HEADER), heap_layout(capacity)) }
+}
+
+impl Drop for CompactString {
+  fn drop(&mut self) {
+    if !self.is_inline() {
+      unsafe { heap_dealloc(self.repr.heap.ptr, self.capacity()) };
+    }
+  }
+}
+
+impl Clone for CompactString {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self::from(self.as_str())
+  }
+}
+
+impl Default for CompactString {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Deref for CompactString {
+  type Target = str;
+
+  #[inline(always)]
+  fn deref(&self) -> &str {
+    self.as_str()
+  }
+}
+
+impl DerefMut for CompactString {
+  #[inline(always)]
+  fn deref_mut(&mut self) -> &mut str {
+    self.as_mut_str()
+  }
+}
+
+impl AsRef<str> for CompactString {
+  #[inline(always)]
+  fn as_ref(&self) -> &str {
+    self
+  }
+}
+
+impl AsRef<[u8]> for CompactString {
+  #[inline(always)]
+  fn as_ref(&self) -> &[u8] {
+    self.as_bytes()
+  }
+}
+
+impl Borrow<str> for CompactString {
+  #[inline(always)]
+  fn borrow(&self) -> &str {
+    self
+  }
+}
+
+impl BorrowMut<str> for CompactString {
+  #[inline(always)]
+  fn borrow_mut(&mut self) -> &mut str {
+    self
+  }
+}
+
+impl Debug for CompactString {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Debug::fmt(self.as_str(), f)
+  }
+}
+
+impl Display for CompactString {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Display::fmt(self.as_str(), f)
+  }
+}
+
+impl Hash for CompactString {
+  #[inline]
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.as_str().hash(state);
+  }
+}
+
+impl PartialEq for CompactString {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    self.as_str() == other.as_str()
+  }
+}
+
+impl Eq for CompactString {}
+
+impl PartialEq<str> for CompactString {
+  #[inline]
+  fn eq(&self, other: &str) -> bool {
+    self.as_str() == other
+  }
+}
+
+impl PartialEq<&str> for CompactString {
+  #[inline]
+  fn eq(&self, other: &&str) -> bool {
+    self.as_str() == *other
+  }
+}
+
+impl PartialEq<String> for CompactString {
+  #[inline]
+  fn eq(&self, other: &String) -> bool {
+    self.as_str() == other
+  }
+}
+
+impl PartialEq<CompactString> for str {
+  #[inline]
+  fn eq(&self, other: &CompactString) -> bool {
+    self == other.as_str()
+  }
+}
+
+impl PartialEq<CompactString> for &str {
+  #[inline]
+  fn eq(&self, other: &CompactString) -> bool {
+    *self == other.as_str()
+  }
+}
+
+impl PartialEq<CompactString> for String {
+  #[inline]
+  fn eq(&self, other: &CompactString) -> bool {
+    self == other.as_str()
+  }
+}
+
+impl PartialOrd for CompactString {
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for CompactString {
+  #[inline]
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.as_str().cmp(other.as_str())
+  }
+}
+
+impl From<&str> for CompactString {
+  #[inline]
+  fn from(s: &str) -> Self {
+    let mut this = Self::with_capacity(s.len());
+    this.push_str(s);
+    this
+  }
+}
+
+impl From<&String> for CompactString {
+  #[inline(always)]
+  fn from(s: &String) -> Self {
+    Self::from(s.as_str())
+  }
+}
+
+impl From<String> for CompactString {
+  /// Copies the string, since the allocation of a `String` has no room for
+  /// the capacity header.
+  #[inline(always)]
+  fn from(s: String) -> Self {
+    Self::from(s.as_str())
+  }
+}
+
+impl From<Box<str>> for CompactString {
+  #[inline(always)]
+  fn from(s: Box<str>) -> Self {
+    Self::from(&*s)
+  }
+}
+
+impl From<char> for CompactString {
+  #[inline]
+  fn from(c: char) -> Self {
+    Self::from(c.encode_utf8(&mut [0; 4]) as &str)
+  }
+}
+
+impl<'i> From<Cow<'i, str>> for CompactString {
+  #[inline(always)]
+  fn from(s: Cow<'i, str>) -> Self {
+    Self::from(&*s)
+  }
+}
+
+impl<'i> From<CowStr<'i>> for CompactString {
+  #[inline(always)]
+  fn from(s: CowStr<'i>) -> Self {
+    Self::from(s.as_str())
+  }
+}
+
+impl From<CompactString> for String {
+  #[inline(always)]
+  fn from(s: CompactString) -> Self {
+    s.into_string()
+  }
+}
+
+impl<'i> From<CompactString> for CowStr<'i> {
+  /// Converts to an inlined `CowStr` when the string is short enough, and an
+  /// owned one otherwise.
+  #[inline]
+  fn from(s: CompactString) -> Self {
+    match InlineStr::try_from(s.as_str()) {
+      Ok(inline) => CowStr::Inlined(inline),
+      Err(_) => CowStr::Owned(s.as_str().into()),
+    }
+  }
+}
+
+impl FromStr for CompactString {
+  type Err = core::convert::Infallible;
+
+  #[inline(always)]
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Ok(Self::from(s))
+  }
+}
+
+impl fmt::Write for CompactString {
+  #[inline(always)]
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    self.push_str(s);
+    Ok(())
+  }
+
+  #[inline(always)]
+  fn write_char(&mut self, c: char) -> fmt::Result {
+    self.push(c);
+    Ok(())
+  }
+}
+
+impl Extend<char> for CompactString {
+  #[inline]
+  fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+    let iter = iter.into_iter();
+    self.reserve(iter.size_hint().0);
+    iter.for_each(|c| self.push(c));
+  }
+}
+
+impl<'a> Extend<&'a str> for CompactString {
+  #[inline]
+  fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+    iter.into_iter().for_each(|s| self.push_str(s));
+  }
+}
+
+impl FromIterator<char> for CompactString {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+    let mut s = Self::new();
+    s.extend(iter);
+    s
+  }
+}
+
+impl<'a> FromIterator<&'a str> for CompactString {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+    let mut s = Self::new();
+    s.extend(iter);
+    s
+  }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+  use serde::de;
+
+  use super::*;
+
+  impl Serialize for CompactString {
+    #[inline(always)]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      serializer.serialize_str(self.as_str())
+    }
+  }
+
+  struct CompactStringVisitor;
+
+  impl de::Visitor<'_> for CompactStringVisitor {
+    type Value = CompactString;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+      E: de::Error,
+    {
+      Ok(CompactString::from(v))
+    }
+  }
+
+  impl<'de> Deserialize<'de> for CompactString {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      deserializer.deserialize_str(CompactStringVisitor)
+    }
+  }
+}
+
+#[cfg(feature = "zeroize")]
+mod zeroize_impl {
+  use zeroize::Zeroize;
+
+  use super::*;
+
+  impl Zeroize for CompactString {
+    /// Overwrites the string's bytes, including any spare capacity, with
+    /// zeroes and empties it.
+    fn zeroize(&mut self) {
+      let capacity = self.capacity();
+      unsafe {
+        slice::from_raw_parts_mut(self.as_mut_ptr(), capacity).zeroize();
+      }
+      self.clear();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::format;
+  use alloc::string::ToString;
+  use core::fmt::Write;
+
+  use super::*;
+
+  #[test]
+  fn compact_string_layout() {
+    assert_eq!(size_of::<CompactString>(), size_of::<String>());
+    let full = "a".repeat(MAX_INLINE_COMPACT_STRING_LEN);
+    let s = CompactString::from(full.as_str());
+    assert!(s.is_inline());
+    assert_eq!(s.len(), MAX_INLINE_COMPACT_STRING_LEN);
+    assert_eq!(s, full);
+
+    let mut s = s;
+    s.push('ö');
+    assert!(!s.is_inline());
+    assert_eq!(s, format!("{full}ö"));
+    assert!(s.capacity() >= s.len());
+  }
+
+  #[test]
+  fn compact_string_edits() {
+    let mut s = CompactString::new();
+    let word = "naïve";
+    write!(s, "{word}-{}", 42).unwrap();
+    assert_eq!(s, "naïve-42");
+    assert_eq!(s.pop(), Some('2'));
+    s.truncate(6);
+    assert_eq!(s, "naïve");
+    s.extend(core::iter::repeat_n("abcd", 10));
+    assert_eq!(s.len(), 46);
+    let cloned = s.clone();
+    s.truncate(4);
+    assert!(!s.is_inline());
+    s.shrink_to_fit();
+    assert!(s.is_inline());
+    assert_eq!(s, "naï");
+    assert_eq!(cloned.len(), 46);
+    assert!(cloned > s);
+    s.clear();
+    assert!(s.is_empty());
+    assert_eq!(s.pop(), None);
+  }
+
+  #[test]
+  fn compact_string_conversions() {
+    let long = "a string that is too long to be stored inline".to_string();
+    let s = CompactString::from(long.clone());
+    assert_eq!(String::from(s.clone()), long);
+    assert!(CowStr::from(s).is_owned());
+    assert!(CowStr::from(CompactString::from('x')).is_inlined());
+    let mut s = CompactString::with_capacity(100);
+    assert_eq!(s.capacity(), 100);
+    s.push_str("shrunk");
+    s.shrink_to_fit();
+    assert!(s.is_inline());
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn compact_string_serde() {
+    let s = CompactString::from("round trip");
+    let json = serde_json::to_string(&s).unwrap();
+    assert_eq!(json, r#""round trip""#);
+    assert_eq!(serde_json::from_str::<CompactString>(&json).unwrap(), s);
+  }
+}
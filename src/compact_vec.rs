@@ -0,0 +1,3533 @@
+use alloc::collections::TryReserveError;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::convert::AsMut;
+use core::convert::AsRef;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::mem::MaybeUninit;
+use core::ops::Bound;
+use core::ops::Deref;
+use core::ops::DerefMut;
+use core::ops::Index;
+use core::ops::IndexMut;
+use core::ops::RangeBounds;
+use core::ptr;
+use core::slice;
+use core::slice::SliceIndex;
+
+pub use crate::inline_vec::CapacityError;
+
+/// Determines how much heap capacity a [`CompactVec`] allocates when it
+/// spills, and when its heap buffer subsequently runs out of room.
+///
+/// Policies are zero-sized marker types passed as the third type parameter of
+/// `CompactVec`, so choosing one has no runtime or memory cost. Two policies
+/// are provided: [`Doubling`] (the default) and [`Exact`]. Custom policies
+/// can be defined by implementing this trait on a new marker type.
+///
+/// Any capacity returned that is smaller than `required` is rounded up to
+/// `required`.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::CompactVec;
+/// use moos::GrowthPolicy;
+///
+/// /// Grows the heap buffer in fixed steps of 64 elements.
+/// struct Chunked;
+///
+/// impl GrowthPolicy for Chunked {
+///   fn spill_capacity(_inline: usize, required: usize) -> usize {
+///     required.next_multiple_of(64)
+///   }
+///
+///   fn grow_capacity(_capacity: usize, required: usize) -> usize {
+///     required.next_multiple_of(64)
+///   }
+/// }
+///
+/// let mut v: CompactVec<u8, 4, Chunked> = CompactVec::new();
+/// v.extend([1, 2, 3, 4, 5]);
+/// assert_eq!(v.capacity(), 64);
+/// ```
+pub trait GrowthPolicy {
+  /// Returns the capacity to allocate when a vector with `inline` slots
+  /// spills to the heap, in order to hold at least `required` elements.
+  fn spill_capacity(inline: usize, required: usize) -> usize;
+
+  /// Returns the new capacity when a heap buffer of `capacity` elements needs
+  /// to grow to hold at least `required` elements.
+  fn grow_capacity(capacity: usize, required: usize) -> usize;
+}
+
+/// The default [`GrowthPolicy`]: spills into a heap buffer roughly twice the
+/// inline capacity (`N * 2 + 1`), and doubles its capacity whenever it fills
+/// up, amortizing the cost of repeated pushes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Doubling;
+
+impl GrowthPolicy for Doubling {
+  #[inline(always)]
+  fn spill_capacity(inline: usize, _required: usize) -> usize {
+    inline.saturating_mul(2).saturating_add(1)
+  }
+
+  #[inline(always)]
+  fn grow_capacity(capacity: usize, _required: usize) -> usize {
+    capacity.saturating_mul(2)
+  }
+}
+
+/// A [`GrowthPolicy`] that never over-allocates: the heap buffer is always
+/// sized to exactly the number of elements required.
+///
+/// This minimizes memory usage for vectors that spill rarely and grow little
+/// afterwards, at the cost of a reallocation on every push past capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Exact;
+
+impl GrowthPolicy for Exact {
+  #[inline(always)]
+  fn spill_capacity(_inline: usize, required: usize) -> usize {
+    required
+  }
+
+  #[inline(always)]
+  fn grow_capacity(_capacity: usize, required: usize) -> usize {
+    required
+  }
+}
+
+/// A growable vector that stores up to `N` elements inline (on the stack),
+/// spilling over to a heap-allocated [`Vec`] once that capacity is exceeded.
+///
+/// This is useful for collections that are usually small, but occasionally
+/// need to grow beyond a fixed limit: the common case never touches the heap,
+/// while the uncommon case still works transparently.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::CompactVec;
+///
+/// let mut v: CompactVec<u8, 4> = CompactVec::new();
+/// v.extend([1, 2, 3, 4]);
+/// assert!(v.is_inline());
+///
+/// v.push(5);
+/// assert!(!v.is_inline());
+/// assert_eq!(v, [1, 2, 3, 4, 5]);
+///
+/// v.truncate(2);
+/// assert_eq!(v, [1, 2]);
+/// ```
+///
+/// The optional third parameter selects the [`GrowthPolicy`] used when the
+/// vector spills or its heap buffer fills up; it defaults to [`Doubling`].
+pub struct CompactVec<T, const N: usize, G: GrowthPolicy = Doubling> {
+  /// The number of inline elements, or [`SPILLED`] if the elements are
+  /// stored on the heap (in which case the `Vec` tracks its own length).
+  len:     usize,
+  data:    RawData<T, N>,
+  _growth: PhantomData<fn() -> G>,
+}
+
+/// The memory footprint of a [`CompactVec`], as reported by
+/// [`CompactVec::memory_usage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MemoryUsage {
+  /// The size in bytes of the vector itself, including its inline buffer.
+  /// This is constant for a given type, wherever the vector lives.
+  pub stack: usize,
+  /// The size in bytes of the heap buffer owned by the vector, or `0` if it
+  /// has not spilled.
+  pub heap:  usize,
+}
+
+impl MemoryUsage {
+  /// Returns the total number of bytes used (`stack + heap`).
+  #[inline]
+  pub const fn total(&self) -> usize {
+    self.stack + self.heap
+  }
+}
+
+/// Converts `range` into a pair of `start..end` offsets within a sequence of
+/// length `len`.
+///
+/// # Panics
+///
+/// Panics if the start of the range is greater than its end, or if the end is
+/// greater than `len`.
+pub(crate) fn resolve_range<R>(range: &R, len: usize) -> (usize, usize)
+where
+  R: RangeBounds<usize>,
+{
+  let start = match range.start_bound() {
+    Bound::Included(&n) => n,
+    Bound::Excluded(&n) => n.checked_add(1).expect("range start overflow"),
+    Bound::Unbounded => 0,
+  };
+  let end = match range.end_bound() {
+    Bound::Included(&n) => n.checked_add(1).expect("range end overflow"),
+    Bound::Excluded(&n) => n,
+    Bound::Unbounded => len,
+  };
+  assert!(start <= end, "range start (is {start}) should be <= end");
+  assert!(end <= len, "range end (is {end}) should be <= len");
+  (start, end)
+}
+
+/// Sentinel value for [`CompactVec::len`] indicating that the vector has
+/// spilled to the heap. Since the inline length can never exceed `N`, this
+/// is unambiguous for any practical inline capacity.
+const SPILLED: usize = usize::MAX;
+
+/// The storage of a [`CompactVec`]. The inline buffer and the heap `Vec`
+/// share the same memory, so the struct is only as large as the bigger of
+/// the two, plus a single word for the length/discriminant.
+union RawData<T, const N: usize> {
+  inline: ManuallyDrop<[MaybeUninit<T>; N]>,
+  heap:   ManuallyDrop<Vec<T>>,
+}
+
+impl<T, const N: usize, G: GrowthPolicy> CompactVec<T, N, G> {
+  /// Creates a new, empty `CompactVec` using inline storage.
+  ///
+  /// This is a `const fn`, so it can be used to initialize `static` items
+  /// without any lazy initialization:
+  ///
+  /// ```rust
+  /// use std::sync::Mutex;
+  ///
+  /// use moos::CompactVec;
+  ///
+  /// static QUEUE: Mutex<CompactVec<u32, 16>> = Mutex::new(CompactVec::new());
+  ///
+  /// QUEUE.lock().unwrap().push(1);
+  /// assert_eq!(*QUEUE.lock().unwrap(), [1]);
+  /// ```
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      len:     0,
+      data:    RawData {
+        inline: ManuallyDrop::new([const { MaybeUninit::uninit() }; N]),
+      },
+      _growth: PhantomData,
+    }
+  }
+
+  /// Creates an inline `CompactVec` holding the elements of `array`.
+  ///
+  /// Unlike the [`From<[T;
+  /// M]>`](#impl-From<%5BT;+M%5D>-for-CompactVec<T,+N,+G>) impl, which spills
+  /// to the heap when `M > N`, this is a `const fn` that never allocates, and
+  /// an array that doesn't fit inline fails to compile. It backs the
+  /// [`compact_vec!`](crate::compact_vec) macro.
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  ///
+  /// const PRIMES: CompactVec<u8, 4> = CompactVec::from_array([2, 3, 5]);
+  /// assert_eq!(PRIMES, [2, 3, 5]);
+  /// ```
+  #[inline]
+  pub const fn from_array<const M: usize>(array: [T; M]) -> Self {
+    const {
+      assert!(M <= N, "array is too long for the inline capacity");
+    }
+    let array = ManuallyDrop::new(array);
+    let src = ptr::addr_of!(array).cast::<T>();
+    let mut inline = [const { MaybeUninit::uninit() }; N];
+    let mut i = 0;
+    while i < M {
+      // SAFETY: `i < M`, and each element is read exactly once, with the
+      // array itself wrapped in `ManuallyDrop` so none is dropped twice.
+      inline[i] = MaybeUninit::new(unsafe { ptr::read(src.add(i)) });
+      i += 1;
+    }
+    Self {
+      len:     M,
+      data:    RawData {
+        inline: ManuallyDrop::new(inline),
+      },
+      _growth: PhantomData,
+    }
+  }
+
+  /// Creates an inline `CompactVec` holding a copy of `slice`, in a `const`
+  /// context if need be.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `slice` is longer than `N`, which is a compile error when
+  /// called in a `const` context.
+  #[inline]
+  pub const fn from_inline_slice(slice: &[T]) -> Self
+  where
+    T: Copy,
+  {
+    assert!(
+      slice.len() <= N,
+      "slice is too long for the inline capacity"
+    );
+    let mut inline = [const { MaybeUninit::uninit() }; N];
+    let mut i = 0;
+    while i < slice.len() {
+      inline[i] = MaybeUninit::new(slice[i]);
+      i += 1;
+    }
+    Self {
+      len:     slice.len(),
+      data:    RawData {
+        inline: ManuallyDrop::new(inline),
+      },
+      _growth: PhantomData,
+    }
+  }
+
+  /// Creates a `CompactVec` containing `n` clones of `value`.
+  ///
+  /// The result is stored inline if `n <= N`; otherwise, a heap buffer with
+  /// exactly `n` slots is allocated up front.
+  #[inline]
+  pub fn from_elem(value: T, n: usize) -> Self
+  where
+    T: Clone,
+  {
+    let mut vec = Self::new();
+    vec.reserve_exact(n);
+    vec.resize(n, value);
+    vec
+  }
+
+  /// Creates a `CompactVec` of length `n`, where each element is produced by
+  /// calling `f` with its index.
+  ///
+  /// The result is stored inline if `n <= N`; otherwise, a heap buffer with
+  /// exactly `n` slots is allocated up front.
+  #[inline]
+  pub fn from_fn<F>(n: usize, mut f: F) -> Self
+  where
+    F: FnMut(usize) -> T,
+  {
+    let mut vec = Self::new();
+    vec.reserve_exact(n);
+    for i in 0..n {
+      vec.push(f(i));
+    }
+    vec
+  }
+
+  /// Collects an iterator into an inline `CompactVec`, failing instead of
+  /// spilling to the heap if it yields more than `N` items.
+  ///
+  /// This never allocates, so it can be used to collect with a hard capacity
+  /// guarantee in code that must not touch the heap. The iterator is not
+  /// consumed past the first item that doesn't fit.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [`CapacityError`] containing the first item that didn't fit.
+  /// The items collected before it are dropped.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  ///
+  /// let v = CompactVec::<u8, 4>::try_from_iter(1..=4).unwrap();
+  /// assert!(v.is_inline());
+  ///
+  /// let err = CompactVec::<u8, 4>::try_from_iter(1..=5).unwrap_err();
+  /// assert_eq!(err.into_inner(), 5);
+  /// ```
+  pub fn try_from_iter<I>(iter: I) -> Result<Self, CapacityError<T>>
+  where
+    I: IntoIterator<Item = T>,
+  {
+    let mut vec = Self::new();
+    for value in iter {
+      vec.try_push(value)?;
+    }
+    Ok(vec)
+  }
+
+  /// Returns the number of elements in the vector.
+  #[inline]
+  pub fn len(&self) -> usize {
+    match self.heap() {
+      Some(heap) => heap.len(),
+      None => self.len,
+    }
+  }
+
+  /// Returns `true` if the vector contains no elements.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Returns the number of elements the vector can hold without spilling to
+  /// the heap (when inline) or reallocating (when spilled).
+  #[inline]
+  pub fn capacity(&self) -> usize {
+    match self.heap() {
+      Some(heap) => heap.capacity(),
+      None => N,
+    }
+  }
+
+  /// Returns `true` if the elements are currently stored inline.
+  #[inline]
+  pub fn is_inline(&self) -> bool {
+    self.len != SPILLED
+  }
+
+  /// Returns `true` if the elements have spilled over to the heap.
+  ///
+  /// This is the inverse of [`is_inline`](Self::is_inline).
+  #[inline]
+  pub fn is_spilled(&self) -> bool {
+    self.len == SPILLED
+  }
+
+  /// Returns the size in bytes of the heap buffer owned by the vector, or `0`
+  /// if it is stored inline.
+  ///
+  /// This reflects the allocated capacity, not just the initialized length.
+  #[inline]
+  pub fn heap_capacity_bytes(&self) -> usize {
+    match self.heap() {
+      Some(heap) => heap.capacity() * size_of::<T>(),
+      None => 0,
+    }
+  }
+
+  /// Returns a breakdown of the memory footprint of the vector, split into
+  /// its fixed inline size and any heap allocation it owns.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  ///
+  /// let mut v: CompactVec<u32, 4> = CompactVec::new();
+  /// let usage = v.memory_usage();
+  /// assert_eq!(usage.heap, 0);
+  /// assert_eq!(usage.stack, size_of::<CompactVec<u32, 4>>());
+  ///
+  /// v.reserve_exact(10);
+  /// assert_eq!(v.memory_usage().heap, 10 * size_of::<u32>());
+  /// ```
+  #[inline]
+  pub fn memory_usage(&self) -> MemoryUsage {
+    MemoryUsage {
+      stack: size_of::<Self>(),
+      heap:  self.heap_capacity_bytes(),
+    }
+  }
+
+  /// Returns a slice containing the entire vector.
+  #[inline]
+  pub fn as_slice(&self) -> &[T] {
+    match self.heap() {
+      Some(heap) => heap.as_slice(),
+      None => unsafe { slice::from_raw_parts(self.inline_ptr(), self.len) },
+    }
+  }
+
+  /// Returns a mutable slice containing the entire vector.
+  #[inline]
+  pub fn as_mut_slice(&mut self) -> &mut [T] {
+    let (ptr, len) = (self.inline_mut_ptr(), self.len);
+    match self.heap_mut() {
+      Some(heap) => heap.as_mut_slice(),
+      None => unsafe { slice::from_raw_parts_mut(ptr, len) },
+    }
+  }
+
+  /// Returns a raw pointer to the vector's buffer, which is either the inline
+  /// storage or the heap allocation, depending on the current storage mode.
+  ///
+  /// The pointer is only valid until the vector is moved, spills to the heap,
+  /// or reallocates.
+  #[inline]
+  pub fn as_ptr(&self) -> *const T {
+    match self.heap() {
+      Some(heap) => heap.as_ptr(),
+      None => self.inline_ptr(),
+    }
+  }
+
+  /// Returns a mutable raw pointer to the vector's buffer, which is either the
+  /// inline storage or the heap allocation, depending on the current storage
+  /// mode.
+  ///
+  /// The pointer is only valid until the vector is moved, spills to the heap,
+  /// or reallocates.
+  #[inline]
+  pub fn as_mut_ptr(&mut self) -> *mut T {
+    match self.heap_mut() {
+      Some(heap) => heap.as_mut_ptr(),
+      None => self.inline_mut_ptr(),
+    }
+  }
+
+  /// Returns the remaining spare capacity of the vector as a slice of
+  /// `MaybeUninit<T>`.
+  ///
+  /// The returned slice can be used to fill the vector with data (e.g. by
+  /// reading from a device or socket) before marking the data as initialized
+  /// using [`set_len`](Self::set_len). When inline, this is the unused tail
+  /// of the inline buffer; it never causes the vector to spill.
+  #[inline]
+  pub fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<T>] {
+    let (ptr, len) = (self.inline_mut_ptr(), self.len);
+    match self.heap_mut() {
+      Some(heap) => heap.spare_capacity_mut(),
+      None => unsafe {
+        slice::from_raw_parts_mut(ptr.add(len).cast(), N - len)
+      },
+    }
+  }
+
+  /// Forces the length of the vector to `new_len`.
+  ///
+  /// This is a low-level operation that maintains none of the normal
+  /// invariants of the type. Normally, changing the length is done using one
+  /// of the safe operations instead, such as [`truncate`](Self::truncate),
+  /// [`resize`](Self::resize), or [`clear`](Self::clear).
+  ///
+  /// # Safety
+  ///
+  /// - `new_len` must be less than or equal to [`capacity()`].
+  /// - The elements at `old_len..new_len` must be initialized.
+  ///
+  /// [`capacity()`]: Self::capacity
+  #[inline]
+  pub unsafe fn set_len(&mut self, new_len: usize) {
+    debug_assert!(new_len <= self.capacity());
+    match self.heap_mut() {
+      Some(heap) => unsafe { heap.set_len(new_len) },
+      None => self.len = new_len,
+    }
+  }
+
+  /// Appends an element to the back of the vector, spilling to the heap if
+  /// the inline capacity is exhausted.
+  #[inline]
+  pub fn push(&mut self, value: T) {
+    if let Some(heap) = self.heap_mut() {
+      Self::grow_heap(heap, 1);
+      heap.push(value);
+    } else if self.len < N {
+      unsafe { self.inline_mut_ptr().add(self.len).write(value) };
+      self.len += 1;
+    } else {
+      self.spill(N + 1).push(value);
+    }
+  }
+
+  /// Appends an element to the back of the vector if there is spare capacity,
+  /// without ever allocating.
+  ///
+  /// When inline, this fails once all `N` slots are occupied, rather than
+  /// spilling to the heap. When already spilled, it only succeeds if the heap
+  /// buffer has room without reallocating.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [`CapacityError`] containing `value` if there is no room.
+  #[inline]
+  pub fn try_push(&mut self, value: T) -> Result<(), CapacityError<T>> {
+    if self.len() == self.capacity() {
+      return Err(CapacityError(value));
+    }
+    self.push(value);
+    Ok(())
+  }
+
+  /// Alias for [`try_push`](Self::try_push), mirroring the name of the
+  /// equivalent `Vec` API.
+  #[inline(always)]
+  pub fn push_within_capacity(
+    &mut self,
+    value: T,
+  ) -> Result<(), CapacityError<T>> {
+    self.try_push(value)
+  }
+
+  /// Removes the last element from the vector and returns it, or `None` if
+  /// it is empty.
+  ///
+  /// Popping never changes the storage mode: a spilled vector keeps its heap
+  /// allocation even once its length drops back to `N` or fewer elements, so
+  /// workloads that oscillate around the inline capacity don't repeatedly
+  /// copy between the two. Use [`shrink_to_fit`](Self::shrink_to_fit) to move
+  /// the elements back inline explicitly.
+  #[inline]
+  pub fn pop(&mut self) -> Option<T> {
+    if let Some(heap) = self.heap_mut() {
+      return heap.pop();
+    }
+    if self.len == 0 {
+      return None;
+    }
+    self.len -= 1;
+    Some(unsafe { self.inline_ptr().add(self.len).read() })
+  }
+
+  /// Removes and returns the last element of the vector if `predicate`
+  /// returns `true` for it, or `None` if the predicate returns `false` or the
+  /// vector is empty.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  ///
+  /// let mut v: CompactVec<u32, 4> = CompactVec::from([5, 3, 1]);
+  /// let mut expired = 0;
+  /// while v.pop_if(|t| *t < 4).is_some() {
+  ///   expired += 1;
+  /// }
+  /// assert_eq!(expired, 2);
+  /// assert_eq!(v, [5]);
+  /// ```
+  #[inline]
+  pub fn pop_if(
+    &mut self,
+    predicate: impl FnOnce(&mut T) -> bool,
+  ) -> Option<T> {
+    let last = self.last_mut()?;
+    if predicate(last) { self.pop() } else { None }
+  }
+
+  /// Inserts an element at position `index`, shifting all elements after it
+  /// to the right.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `index > len`.
+  pub fn insert(&mut self, index: usize, value: T) {
+    let len = self.len();
+    assert!(
+      index <= len,
+      "insertion index (is {index}) should be <= len"
+    );
+    if let Some(heap) = self.heap_mut() {
+      Self::grow_heap(heap, 1);
+      heap.insert(index, value);
+    } else if len < N {
+      unsafe {
+        let p = self.inline_mut_ptr().add(index);
+        ptr::copy(p, p.add(1), len - index);
+        ptr::write(p, value);
+      }
+      self.len += 1;
+    } else {
+      self.spill(N + 1).insert(index, value);
+    }
+  }
+
+  /// Clones and inserts all elements of `slice` at position `index`,
+  /// shifting the elements after it to the right.
+  ///
+  /// The new elements are appended in bulk and then rotated into place, so
+  /// the tail is shifted once rather than once per element, and the vector
+  /// spills or reallocates at most once.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `index > len`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  ///
+  /// let mut v: CompactVec<u8, 8> = CompactVec::from([1, 5]);
+  /// v.insert_from_slice(1, &[2, 3, 4]);
+  /// assert_eq!(v, [1, 2, 3, 4, 5]);
+  /// ```
+  pub fn insert_from_slice(&mut self, index: usize, slice: &[T])
+  where
+    T: Clone,
+  {
+    let len = self.len();
+    assert!(
+      index <= len,
+      "insertion index (is {index}) should be <= len"
+    );
+    self.extend_from_slice(slice);
+    self[index..].rotate_right(slice.len());
+  }
+
+  /// Inserts all items yielded by `iter` at position `index`, shifting the
+  /// elements after it to the right.
+  ///
+  /// Like [`insert_from_slice`](Self::insert_from_slice), the tail is
+  /// shifted only once, after all of the items have been appended.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `index > len`.
+  pub fn insert_many<I>(&mut self, index: usize, iter: I)
+  where
+    I: IntoIterator<Item = T>,
+  {
+    let len = self.len();
+    assert!(
+      index <= len,
+      "insertion index (is {index}) should be <= len"
+    );
+    self.extend(iter);
+    let added = self.len() - len;
+    self[index..].rotate_right(added);
+  }
+
+  /// Inserts an element at position `index` if there is spare capacity,
+  /// without ever allocating.
+  ///
+  /// Like [`try_push`](Self::try_push), this fails rather than spilling when
+  /// the inline buffer is full, or reallocating when the heap buffer is full.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [`CapacityError`] containing `value` if there is no room.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `index > len`.
+  #[inline]
+  pub fn try_insert(
+    &mut self,
+    index: usize,
+    value: T,
+  ) -> Result<(), CapacityError<T>> {
+    let len = self.len();
+    assert!(
+      index <= len,
+      "insertion index (is {index}) should be <= len"
+    );
+    if len == self.capacity() {
+      return Err(CapacityError(value));
+    }
+    self.insert(index, value);
+    Ok(())
+  }
+
+  /// Removes and returns the element at position `index`, shifting all
+  /// elements after it to the left.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `index >= len`.
+  pub fn remove(&mut self, index: usize) -> T {
+    let len = self.len();
+    assert!(index < len, "removal index (is {index}) should be < len");
+    if let Some(heap) = self.heap_mut() {
+      return heap.remove(index);
+    }
+    unsafe {
+      let p = self.inline_mut_ptr().add(index);
+      let value = ptr::read(p);
+      ptr::copy(p.add(1), p, len - index - 1);
+      self.len -= 1;
+      value
+    }
+  }
+
+  /// Removes the elements at each of the given `indices` in a single
+  /// compaction pass, returning them in order.
+  ///
+  /// Each run of retained elements between two removed positions is moved
+  /// exactly once, so this is much cheaper than calling
+  /// [`remove`](Self::remove) repeatedly. The storage mode is unchanged, just
+  /// as with [`pop`](Self::pop).
+  ///
+  /// # Panics
+  ///
+  /// Panics if `indices` is not sorted in strictly ascending order, or if any
+  /// index is out of bounds. The vector is left unchanged in that case.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  ///
+  /// let mut edges: CompactVec<u32, 8> = CompactVec::from([10, 11, 12, 13, 14]);
+  /// let pruned = edges.remove_indices(&[0, 2, 3]);
+  /// assert_eq!(pruned, [10, 12, 13]);
+  /// assert_eq!(edges, [11, 14]);
+  /// ```
+  pub fn remove_indices(&mut self, indices: &[usize]) -> Self {
+    let len = self.len();
+    assert!(
+      indices.windows(2).all(|pair| pair[0] < pair[1]),
+      "removal indices should be sorted in strictly ascending order"
+    );
+    if let Some(&last) = indices.last() {
+      assert!(last < len, "removal index (is {last}) should be < len");
+    }
+    let count = indices.len();
+    let mut removed = Self::new();
+    removed.reserve_exact(count);
+    unsafe {
+      let (src, dst) = (self.as_mut_ptr(), removed.as_mut_ptr());
+      for (i, &index) in indices.iter().enumerate() {
+        dst.add(i).write(src.add(index).read());
+        let next = indices.get(i + 1).copied().unwrap_or(len);
+        ptr::copy(src.add(index + 1), src.add(index - i), next - index - 1);
+      }
+      removed.set_len(count);
+      self.set_len(len - count);
+    }
+    removed
+  }
+
+  /// Shortens the vector, keeping the first `len` elements and dropping the
+  /// rest. Has no effect if `len` is greater than or equal to the vector's
+  /// current length.
+  ///
+  /// This does not change the storage mode: a spilled vector remains on the
+  /// heap even if the new length would fit inline.
+  pub fn truncate(&mut self, len: usize) {
+    if let Some(heap) = self.heap_mut() {
+      heap.truncate(len);
+      return;
+    }
+    if len >= self.len {
+      return;
+    }
+    let old_len = self.len;
+    // update the length first, so a panicking destructor can't cause a double
+    // drop of the remaining elements.
+    self.len = len;
+    unsafe {
+      let tail = self.inline_mut_ptr().add(len);
+      ptr::drop_in_place(ptr::slice_from_raw_parts_mut(tail, old_len - len));
+    }
+  }
+
+  /// Resizes the vector in-place so that its length is equal to `new_len`.
+  ///
+  /// If `new_len` is greater than the current length, the vector is extended
+  /// with clones of `value`, filling the remaining inline capacity first and
+  /// only spilling to the heap if `new_len` exceeds `N`. If `new_len` is less
+  /// than the current length, the vector is truncated.
+  pub fn resize(&mut self, new_len: usize, value: T)
+  where
+    T: Clone,
+  {
+    let len = self.len();
+    if new_len <= len {
+      self.truncate(new_len);
+    } else if let Some(heap) = self.heap_mut() {
+      Self::grow_heap(heap, new_len - len);
+      heap.resize(new_len, value);
+    } else if new_len > N {
+      self.spill(new_len).resize(new_len, value);
+    } else {
+      for _ in len + 1..new_len {
+        self.push(value.clone());
+      }
+      self.push(value);
+    }
+  }
+
+  /// Resizes the vector in-place so that its length is equal to `new_len`,
+  /// filling any new slots with values returned by calling `f`.
+  ///
+  /// Like [`resize`](Self::resize), this only spills to the heap if `new_len`
+  /// exceeds the inline capacity `N`.
+  pub fn resize_with<F>(&mut self, new_len: usize, mut f: F)
+  where
+    F: FnMut() -> T,
+  {
+    let len = self.len();
+    if new_len <= len {
+      self.truncate(new_len);
+    } else if let Some(heap) = self.heap_mut() {
+      Self::grow_heap(heap, new_len - len);
+      heap.resize_with(new_len, f);
+    } else if new_len > N {
+      self.spill(new_len).resize_with(new_len, f);
+    } else {
+      for _ in len..new_len {
+        self.push(f());
+      }
+    }
+  }
+
+  /// Overwrites every element of the vector with clones of `value`.
+  ///
+  /// This delegates to [`slice::fill`], and never changes the length.
+  #[inline]
+  pub fn fill(&mut self, value: T)
+  where
+    T: Clone,
+  {
+    self.as_mut_slice().fill(value);
+  }
+
+  /// Overwrites every element of the vector with values returned by calling
+  /// `f` repeatedly.
+  ///
+  /// This delegates to [`slice::fill_with`], and never changes the length.
+  #[inline]
+  pub fn fill_with<F>(&mut self, f: F)
+  where
+    F: FnMut() -> T,
+  {
+    self.as_mut_slice().fill_with(f);
+  }
+
+  /// Clears the vector and refills it with `n` clones of `value`.
+  ///
+  /// Unlike calling [`clear`](Self::clear) followed by
+  /// [`resize`](Self::resize), this keeps any existing heap allocation, so a
+  /// reusable buffer can be reset and reinitialized without reallocating.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  ///
+  /// let mut buf: CompactVec<u8, 4> = CompactVec::from([1, 2, 3, 4, 5, 6]);
+  /// let ptr = buf.as_ptr();
+  ///
+  /// buf.clear_and_fill(3, 0);
+  /// assert_eq!(buf, [0, 0, 0]);
+  /// assert_eq!(buf.as_ptr(), ptr);
+  /// ```
+  #[inline]
+  pub fn clear_and_fill(&mut self, n: usize, value: T)
+  where
+    T: Clone,
+  {
+    self.truncate(0);
+    self.resize(n, value);
+  }
+
+  /// Reserves capacity for at least `additional` more elements.
+  ///
+  /// If the elements are stored inline and the new length would exceed `N`,
+  /// this spills to the heap up front with enough room for them, so that
+  /// subsequent pushes don't need to spill or reallocate incrementally. Does
+  /// nothing if the capacity is already sufficient.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the new capacity overflows `usize`.
+  pub fn reserve(&mut self, additional: usize) {
+    if let Some(heap) = self.heap_mut() {
+      Self::grow_heap(heap, additional);
+      return;
+    }
+    let required = self.len.checked_add(additional);
+    let required = required.expect("capacity overflow");
+    if required > N {
+      self.spill(required);
+    }
+  }
+
+  /// Reserves the minimum capacity for exactly `additional` more elements.
+  ///
+  /// Unlike [`reserve`](Self::reserve), this will not deliberately
+  /// over-allocate when spilling to the heap.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the new capacity overflows `usize`.
+  pub fn reserve_exact(&mut self, additional: usize) {
+    if let Some(heap) = self.heap_mut() {
+      heap.reserve_exact(additional);
+      return;
+    }
+    let required = self.len.checked_add(additional);
+    let required = required.expect("capacity overflow");
+    if required > N {
+      self.spill_into(Vec::with_capacity(required));
+    }
+  }
+
+  /// Tries to reserve capacity for at least `additional` more elements,
+  /// spilling to the heap if necessary.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [`TryReserveError`] if the capacity overflows, or if the
+  /// allocator reports a failure. The vector is left unchanged in that case.
+  pub fn try_reserve(
+    &mut self,
+    additional: usize,
+  ) -> Result<(), TryReserveError> {
+    if let Some(heap) = self.heap_mut() {
+      let required = heap.len().saturating_add(additional);
+      if required > heap.capacity() {
+        let capacity = G::grow_capacity(heap.capacity(), required);
+        heap.try_reserve_exact(capacity.max(required) - heap.len())?;
+      }
+      return Ok(());
+    }
+    let required = self.len.saturating_add(additional);
+    if required > N {
+      let mut heap = Vec::new();
+      heap.try_reserve_exact(G::spill_capacity(N, required).max(required))?;
+      self.spill_into(heap);
+    }
+    Ok(())
+  }
+
+  /// Shrinks the capacity of the vector as much as possible.
+  ///
+  /// If the vector has spilled to the heap but its elements now fit within
+  /// the inline capacity `N`, they are moved back inline and the heap
+  /// allocation is released. Otherwise, the heap buffer is shrunk to fit.
+  pub fn shrink_to_fit(&mut self) {
+    if let Some(heap) = self.heap_mut() {
+      if heap.len() <= N {
+        self.unspill();
+      } else {
+        heap.shrink_to_fit();
+      }
+    }
+  }
+
+  /// Shrinks the capacity of the vector with a lower bound.
+  ///
+  /// The capacity will remain at least as large as both the length and the
+  /// supplied value. If both of those fit within the inline capacity `N`, the
+  /// elements are moved back inline and the heap allocation is released.
+  pub fn shrink_to(&mut self, min_capacity: usize) {
+    if let Some(heap) = self.heap_mut() {
+      if heap.len() <= N && min_capacity <= N {
+        self.unspill();
+      } else {
+        heap.shrink_to(min_capacity);
+      }
+    }
+  }
+
+  /// Creates a `CompactVec` by cloning and concatenating the elements of
+  /// each of the given slices, in order.
+  ///
+  /// The total length is computed up front, so the result is allocated at
+  /// most once, and stays inline if it fits within `N`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  ///
+  /// let v: CompactVec<u8, 8> = CompactVec::concat(&[&[1, 2][..], &[3], &[]]);
+  /// assert_eq!(v, [1, 2, 3]);
+  /// ```
+  pub fn concat<S: AsRef<[T]>>(parts: &[S]) -> Self
+  where
+    T: Clone,
+  {
+    let total = parts.iter().map(|part| part.as_ref().len()).sum();
+    let mut vec = Self::new();
+    vec.reserve_exact(total);
+    for part in parts {
+      vec.extend_from_slice(part.as_ref());
+    }
+    vec
+  }
+
+  /// Clones and appends all elements in a slice to the vector.
+  ///
+  /// Unlike [`extend`](Extend::extend), this spills to the heap at most once,
+  /// and only if the combined length exceeds the inline capacity `N`.
+  ///
+  /// The elements are cloned into the inline buffer in one pass, which the
+  /// optimizer turns into a single `memcpy` for `Copy` types, as
+  /// [`extend_from_copy_slice`](Self::extend_from_copy_slice) does.
+  pub fn extend_from_slice(&mut self, other: &[T])
+  where
+    T: Clone,
+  {
+    self.reserve(other.len());
+    if let Some(heap) = self.heap_mut() {
+      heap.extend_from_slice(other);
+      return;
+    }
+    // if a clone panics, the clones written so far are dropped, and the
+    // length is left unchanged.
+    self.spare_capacity_mut()[..other.len()].write_clone_of_slice(other);
+    self.len += other.len();
+  }
+
+  /// Copies all elements in a slice to the end of the vector.
+  ///
+  /// This is a version of [`extend_from_slice`] for `Copy` types, which
+  /// copies the entire slice into the inline buffer with a single `memcpy`,
+  /// even in unoptimized builds.
+  ///
+  /// [`extend_from_slice`]: Self::extend_from_slice
+  pub fn extend_from_copy_slice(&mut self, other: &[T])
+  where
+    T: Copy,
+  {
+    self.reserve(other.len());
+    if let Some(heap) = self.heap_mut() {
+      heap.extend_from_slice(other);
+      return;
+    }
+    unsafe {
+      ptr::copy_nonoverlapping(
+        other.as_ptr(),
+        self.inline_mut_ptr().add(self.len),
+        other.len(),
+      );
+    }
+    self.len += other.len();
+  }
+
+  /// Removes the elements in `range`, replacing them with the items yielded
+  /// by `replace_with`, and returns the removed elements.
+  ///
+  /// The replacement may be longer or shorter than the removed range; the
+  /// elements after it are shifted accordingly. Unlike `Vec::splice`, the
+  /// removal happens eagerly, so the returned vector owns the removed
+  /// elements outright.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the start of the range is greater than its end, or if the end
+  /// is greater than the length of the vector.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  ///
+  /// let mut ops: CompactVec<&str, 8> = CompactVec::from(["push", "pop", "add"]);
+  /// let removed = ops.splice(1..2, ["dup", "swap"]);
+  /// assert_eq!(removed, ["pop"]);
+  /// assert_eq!(ops, ["push", "dup", "swap", "add"]);
+  /// ```
+  pub fn splice<R, I>(&mut self, range: R, replace_with: I) -> Self
+  where
+    R: RangeBounds<usize>,
+    I: IntoIterator<Item = T>,
+  {
+    let len = self.len();
+    let (start, end) = resolve_range(&range, len);
+
+    let mut removed = Self::new();
+    removed.reserve_exact(end - start);
+    let mut tail = Self::new();
+    tail.reserve_exact(len - end);
+    // move the removed range and the tail out first, so `self` stays valid
+    // even if the replacement iterator panics.
+    unsafe {
+      let p = self.as_mut_ptr();
+      ptr::copy_nonoverlapping(p.add(start), removed.as_mut_ptr(), end - start);
+      removed.set_len(end - start);
+      ptr::copy_nonoverlapping(p.add(end), tail.as_mut_ptr(), len - end);
+      tail.set_len(len - end);
+      self.set_len(start);
+    }
+    self.extend(replace_with);
+    self.append(&mut tail);
+    removed
+  }
+
+  /// Consumes and leaks the `CompactVec`, returning a mutable reference to its
+  /// contents, `&'a mut [T]`.
+  ///
+  /// If the vector has spilled, its heap buffer is leaked directly (including
+  /// any excess capacity). Otherwise, the inline elements are moved into a
+  /// new, exactly-sized heap allocation which is then leaked. This is mainly
+  /// useful for data that lives for the remainder of the program's life, such
+  /// as configuration tables built once at startup.
+  pub fn leak<'a>(self) -> &'a mut [T] {
+    self.into_vec().leak()
+  }
+
+  /// Creates a `CompactVec` that adopts the heap allocation of `vec` as-is,
+  /// without copying, regardless of its length.
+  ///
+  /// Use the [`From<Vec<T>>`](#impl-From<Vec<T>>-for-CompactVec<T,+N>) impl
+  /// instead to move short vectors into inline storage.
+  #[inline]
+  pub fn from_vec(vec: Vec<T>) -> Self {
+    let mut this = Self::new();
+    this.set_heap(vec);
+    this
+  }
+
+  /// Returns a reference to the underlying heap `Vec`, or `None` if the
+  /// elements are currently stored inline.
+  #[inline(always)]
+  pub fn as_vec(&self) -> Option<&Vec<T>> {
+    self.heap()
+  }
+
+  /// Returns a mutable reference to the underlying heap `Vec`, or `None` if
+  /// the elements are currently stored inline.
+  #[inline(always)]
+  pub fn as_mut_vec(&mut self) -> Option<&mut Vec<T>> {
+    self.heap_mut()
+  }
+
+  /// Calls `f` with a mutable reference to the underlying heap `Vec`, first
+  /// spilling the elements to the heap if they are currently stored inline.
+  ///
+  /// This is useful for handing the vector to APIs that operate on
+  /// `&mut Vec<T>`. The vector stays spilled afterwards; use
+  /// [`shrink_to_fit`](Self::shrink_to_fit) to move it back inline.
+  #[inline]
+  pub fn with_heap<R>(&mut self, f: impl FnOnce(&mut Vec<T>) -> R) -> R {
+    if self.is_inline() {
+      let len = self.len;
+      self.spill_into(Vec::with_capacity(len));
+    }
+    f(self.heap_mut().expect("vector should have spilled"))
+  }
+
+  /// Converts the `CompactVec` into a `Vec<T>`.
+  ///
+  /// If the vector has spilled, its heap allocation is returned without any
+  /// copying. Otherwise, the inline elements are moved into a new,
+  /// exactly-sized `Vec`.
+  #[inline]
+  pub fn into_vec(mut self) -> Vec<T> {
+    if self.is_inline() {
+      let len = self.len;
+      self.spill_into(Vec::with_capacity(len));
+    }
+    self.take_heap().unwrap_or_default()
+  }
+
+  /// Converts the `CompactVec` into a `[T; N]` array if it contains exactly
+  /// `N` elements, regardless of where they are stored.
+  ///
+  /// # Errors
+  ///
+  /// If the length is not exactly `N`, returns the elements as a `Vec<T>`
+  /// (see [`into_vec`](Self::into_vec)) instead.
+  pub fn into_inner(mut self) -> Result<[T; N], Vec<T>> {
+    if self.len() != N {
+      return Err(self.into_vec());
+    }
+    let mut array = MaybeUninit::<[T; N]>::uninit();
+    unsafe {
+      ptr::copy_nonoverlapping(self.as_ptr(), array.as_mut_ptr().cast(), N);
+      self.set_len(0);
+      Ok(array.assume_init())
+    }
+  }
+
+  /// Consumes the vector, transforming each element with `f` and collecting
+  /// the results into a `CompactVec<U, N>` with the same growth policy.
+  ///
+  /// Inline vectors are mapped directly into the inline buffer of the result,
+  /// without touching the heap. If the vector has spilled and `U` has the
+  /// same size and alignment as `T`, the elements are transformed in place
+  /// and the heap allocation is reused by the result.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  ///
+  /// let v: CompactVec<u32, 2> = CompactVec::from([1, 2, 3]);
+  /// let ptr = v.as_ptr();
+  ///
+  /// let mapped: CompactVec<i32, 2> = v.map(|n| -(n as i32));
+  /// assert_eq!(mapped, [-1, -2, -3]);
+  /// assert_eq!(mapped.as_ptr().cast(), ptr);
+  /// ```
+  pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> CompactVec<U, N, G> {
+    let same_layout =
+      size_of::<T>() == size_of::<U>() && align_of::<T>() == align_of::<U>();
+    if self.is_inline() || !same_layout {
+      let mut mapped = CompactVec::new();
+      mapped.reserve_exact(self.len());
+      for value in self {
+        mapped.push(f(value));
+      }
+      return mapped;
+    }
+
+    /// Cleans up a partially mapped buffer if `f` panics: the first `mapped`
+    /// slots hold `U`s, the next has been moved out, and the rest hold `T`s.
+    struct Guard<T, U> {
+      ptr:    *mut T,
+      len:    usize,
+      cap:    usize,
+      mapped: usize,
+      _out:   PhantomData<U>,
+    }
+
+    impl<T, U> Drop for Guard<T, U> {
+      fn drop(&mut self) {
+        unsafe {
+          let (ptr, mapped) = (self.ptr, self.mapped);
+          let done = ptr::slice_from_raw_parts_mut(ptr.cast::<U>(), mapped);
+          ptr::drop_in_place(done);
+          let rest = self.len - mapped - 1;
+          ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+            ptr.add(mapped + 1),
+            rest,
+          ));
+          drop(Vec::from_raw_parts(ptr, 0, self.cap));
+        }
+      }
+    }
+
+    let mut vec = ManuallyDrop::new(self.into_vec());
+    let mut guard = Guard::<T, U> {
+      ptr:    vec.as_mut_ptr(),
+      len:    vec.len(),
+      cap:    vec.capacity(),
+      mapped: 0,
+      _out:   PhantomData,
+    };
+    while guard.mapped < guard.len {
+      unsafe {
+        let slot = guard.ptr.add(guard.mapped);
+        let value = f(slot.read());
+        slot.cast::<U>().write(value);
+      }
+      guard.mapped += 1;
+    }
+    let guard = ManuallyDrop::new(guard);
+    let (ptr, len, cap) = (guard.ptr.cast::<U>(), guard.len, guard.cap);
+    CompactVec::from_vec(unsafe { Vec::from_raw_parts(ptr, len, cap) })
+  }
+
+  /// Moves all the elements of `other` into `self`, leaving `other` empty.
+  ///
+  /// Elements are moved with a single bulk copy rather than cloned, and the
+  /// storage for the combined length is chosen to be as cheap as possible:
+  /// the result stays inline if it fits within `N`, and if `self` is empty
+  /// while `other` has spilled, its heap allocation is adopted outright.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the new capacity overflows `usize`.
+  pub fn append<const M: usize, H: GrowthPolicy>(
+    &mut self,
+    other: &mut CompactVec<T, M, H>,
+  ) {
+    let count = other.len();
+    if count == 0 {
+      return;
+    }
+    if self.is_empty() && count > N && !other.is_inline() {
+      drop(self.take_heap());
+      if let Some(heap) = other.take_heap() {
+        self.set_heap(heap);
+      }
+      return;
+    }
+    self.reserve(count);
+    let len = self.len();
+    unsafe {
+      ptr::copy_nonoverlapping(
+        other.as_slice().as_ptr(),
+        self.as_mut_ptr().add(len),
+        count,
+      );
+      self.set_len(len + count);
+      other.set_len(0);
+    }
+    other.clear();
+  }
+
+  /// Removes consecutive repeated elements in the vector according to the
+  /// [`PartialEq`] trait implementation.
+  ///
+  /// If the vector is sorted, this removes all duplicates.
+  #[inline]
+  pub fn dedup(&mut self)
+  where
+    T: PartialEq,
+  {
+    self.dedup_by(|a, b| a == b);
+  }
+
+  /// Removes all but the first of consecutive elements in the vector that
+  /// resolve to the same key.
+  #[inline]
+  pub fn dedup_by_key<F, K>(&mut self, mut key: F)
+  where
+    F: FnMut(&mut T) -> K,
+    K: PartialEq,
+  {
+    self.dedup_by(|a, b| key(a) == key(b));
+  }
+
+  /// Removes all but the first of consecutive elements in the vector that
+  /// satisfy a given equality relation.
+  ///
+  /// The `same_bucket` function is passed references to two elements from
+  /// the vector, in the opposite order from their order in the vector, and
+  /// must determine if the elements compare equal. If it returns `true`, the
+  /// first argument is removed.
+  pub fn dedup_by<F>(&mut self, mut same_bucket: F)
+  where
+    F: FnMut(&mut T, &mut T) -> bool,
+  {
+    if let Some(heap) = self.heap_mut() {
+      heap.dedup_by(same_bucket);
+      return;
+    }
+    let len = self.len;
+    if len <= 1 {
+      return;
+    }
+    // only the retained prefix is ever counted in `len`, so if `same_bucket`
+    // or a destructor panics, the unprocessed tail is leaked, not double
+    // dropped.
+    self.len = 1;
+    let p = self.inline_mut_ptr();
+    for read in 1..len {
+      unsafe {
+        let cur = p.add(read);
+        let prev = p.add(self.len - 1);
+        if same_bucket(&mut *cur, &mut *prev) {
+          ptr::drop_in_place(cur);
+        } else {
+          if read != self.len {
+            ptr::copy_nonoverlapping(cur, p.add(self.len), 1);
+          }
+          self.len += 1;
+        }
+      }
+    }
+  }
+
+  /// Removes all elements from the vector, releasing any heap storage and
+  /// returning to inline mode.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.truncate(0);
+    drop(self.take_heap());
+  }
+
+  /// Swaps the contents of this vector with `other`, including ownership of
+  /// any heap buffers, without cloning or reallocating.
+  ///
+  /// Spilled elements never move: only the heap pointers change hands. Inline
+  /// elements are exchanged with a single bitwise swap of the two buffers.
+  /// This makes it cheap to double-buffer between two vectors.
+  ///
+  /// This is named `swap_contents` rather than `swap` to avoid shadowing
+  /// [`slice::swap`], which swaps two elements within the vector.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  ///
+  /// let mut front: CompactVec<u8, 2> = CompactVec::from([1, 2, 3]);
+  /// let mut back: CompactVec<u8, 2> = CompactVec::new();
+  /// let ptr = front.as_ptr();
+  ///
+  /// front.swap_contents(&mut back);
+  /// assert!(front.is_empty());
+  /// assert_eq!(back, [1, 2, 3]);
+  /// assert_eq!(back.as_ptr(), ptr);
+  /// ```
+  #[inline(always)]
+  pub fn swap_contents(&mut self, other: &mut Self) {
+    core::mem::swap(self, other);
+  }
+
+  /// Returns a raw pointer to the inline buffer.
+  #[inline(always)]
+  fn inline_ptr(&self) -> *const T {
+    (&raw const self.data.inline).cast::<T>()
+  }
+
+  /// Returns a mutable raw pointer to the inline buffer.
+  #[inline(always)]
+  fn inline_mut_ptr(&mut self) -> *mut T {
+    (&raw mut self.data.inline).cast::<T>()
+  }
+
+  /// Returns a reference to the heap `Vec`, if the vector has spilled.
+  #[inline(always)]
+  fn heap(&self) -> Option<&Vec<T>> {
+    if self.len == SPILLED {
+      Some(unsafe { &self.data.heap })
+    } else {
+      None
+    }
+  }
+
+  /// Returns a mutable reference to the heap `Vec`, if the vector has
+  /// spilled.
+  #[inline(always)]
+  fn heap_mut(&mut self) -> Option<&mut Vec<T>> {
+    if self.len == SPILLED {
+      Some(unsafe { &mut self.data.heap })
+    } else {
+      None
+    }
+  }
+
+  /// Takes ownership of the heap `Vec`, if the vector has spilled, leaving
+  /// it empty and inline.
+  #[inline]
+  fn take_heap(&mut self) -> Option<Vec<T>> {
+    if self.len != SPILLED {
+      return None;
+    }
+    let heap = unsafe { ManuallyDrop::take(&mut self.data.heap) };
+    self.len = 0;
+    Some(heap)
+  }
+
+  /// Switches to heap storage backed by the given `Vec`. The caller must
+  /// ensure that any inline elements have already been moved out, and that
+  /// the vector is not currently spilled.
+  #[inline]
+  fn set_heap(&mut self, heap: Vec<T>) -> &mut Vec<T> {
+    debug_assert!(self.len != SPILLED);
+    self.data.heap = ManuallyDrop::new(heap);
+    self.len = SPILLED;
+    unsafe { &mut self.data.heap }
+  }
+
+  /// Moves the inline elements into a newly allocated heap `Vec` with room
+  /// for at least `required` elements (as sized by the growth policy),
+  /// returning a mutable reference to it.
+  fn spill(&mut self, required: usize) -> &mut Vec<T> {
+    let capacity = G::spill_capacity(N, required).max(required);
+    self.spill_into(Vec::with_capacity(capacity))
+  }
+
+  /// Grows `heap` as directed by the growth policy, if needed, so that it has
+  /// room for at least `additional` more elements.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the new capacity overflows `usize`.
+  #[inline]
+  fn grow_heap(heap: &mut Vec<T>, additional: usize) {
+    let required = heap.len().checked_add(additional);
+    let required = required.expect("capacity overflow");
+    if required > heap.capacity() {
+      let capacity = G::grow_capacity(heap.capacity(), required);
+      heap.reserve_exact(capacity.max(required) - heap.len());
+    }
+  }
+
+  /// Moves the inline elements into the given empty `heap`, which must have
+  /// room for at least `len` elements, returning a mutable reference to it.
+  fn spill_into(&mut self, mut heap: Vec<T>) -> &mut Vec<T> {
+    debug_assert!(heap.is_empty() && heap.capacity() >= self.len);
+    unsafe {
+      ptr::copy_nonoverlapping(self.inline_ptr(), heap.as_mut_ptr(), self.len);
+      heap.set_len(self.len);
+    }
+    self.set_heap(heap)
+  }
+
+  /// Moves the heap elements back into inline storage, releasing the heap
+  /// allocation. The caller must ensure the heap holds at most `N` elements.
+  fn unspill(&mut self) {
+    if let Some(mut heap) = self.take_heap() {
+      let len = heap.len();
+      debug_assert!(len <= N);
+      unsafe {
+        heap.set_len(0);
+        ptr::copy_nonoverlapping(heap.as_ptr(), self.inline_mut_ptr(), len);
+      }
+      self.len = len;
+    }
+  }
+}
+
+impl<T, const N: usize, G: GrowthPolicy> Drop for CompactVec<T, N, G> {
+  fn drop(&mut self) {
+    self.truncate(0);
+    // with the `zeroize` feature, wipe the (now empty) inline buffer or heap
+    // allocation before it is released.
+    #[cfg(feature = "zeroize")]
+    zeroize::Zeroize::zeroize(self.spare_capacity_mut());
+    if let Some(heap) = self.heap_mut() {
+      unsafe { ptr::drop_in_place(heap) };
+    }
+  }
+}
+
+impl<T, const N: usize, G: GrowthPolicy> Default for CompactVec<T, N, G> {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: Clone, const N: usize, G: GrowthPolicy> Clone for CompactVec<T, N, G> {
+  #[inline]
+  fn clone(&self) -> Self {
+    self.iter().cloned().collect()
+  }
+
+  /// Overwrites the contents of `self` with a clone of `source`, reusing the
+  /// existing storage (inline or heap) and, where possible, the existing
+  /// elements via [`Clone::clone_from`].
+  fn clone_from(&mut self, source: &Self) {
+    self.truncate(source.len());
+    let (init, tail) = source.split_at(self.len());
+    self.clone_from_slice(init);
+    self.extend_from_slice(tail);
+  }
+}
+
+impl<T: Debug, const N: usize, G: GrowthPolicy> Debug for CompactVec<T, N, G> {
+  /// Formats the elements as a list, like a slice.
+  ///
+  /// In alternate mode (`{:#?}`), the storage mode, length, and capacity are
+  /// included alongside the elements, to help diagnose unexpected spills.
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    if !f.alternate() {
+      return f.debug_list().entries(self.iter()).finish();
+    }
+    let storage = if self.is_inline() {
+      "inline"
+    } else {
+      "spilled"
+    };
+    f.debug_struct("CompactVec")
+      .field("storage", &format_args!("{storage}"))
+      .field("len", &self.len())
+      .field("capacity", &self.capacity())
+      .field("elements", &self.as_slice())
+      .finish()
+  }
+}
+
+impl<T, const N: usize, G: GrowthPolicy> Deref for CompactVec<T, N, G> {
+  type Target = [T];
+
+  #[inline(always)]
+  fn deref(&self) -> &[T] {
+    self.as_slice()
+  }
+}
+
+impl<T, const N: usize, G: GrowthPolicy> DerefMut for CompactVec<T, N, G> {
+  #[inline(always)]
+  fn deref_mut(&mut self) -> &mut [T] {
+    self.as_mut_slice()
+  }
+}
+
+impl<T, I: SliceIndex<[T]>, const N: usize, G: GrowthPolicy> Index<I>
+  for CompactVec<T, N, G>
+{
+  type Output = I::Output;
+
+  #[inline(always)]
+  fn index(&self, index: I) -> &Self::Output {
+    Index::index(self.as_slice(), index)
+  }
+}
+
+impl<T, I: SliceIndex<[T]>, const N: usize, G: GrowthPolicy> IndexMut<I>
+  for CompactVec<T, N, G>
+{
+  #[inline(always)]
+  fn index_mut(&mut self, index: I) -> &mut Self::Output {
+    IndexMut::index_mut(self.as_mut_slice(), index)
+  }
+}
+
+impl<T, const N: usize, G: GrowthPolicy> AsRef<[T]> for CompactVec<T, N, G> {
+  #[inline(always)]
+  fn as_ref(&self) -> &[T] {
+    self.as_slice()
+  }
+}
+
+impl<T, const N: usize, G: GrowthPolicy> AsMut<[T]> for CompactVec<T, N, G> {
+  #[inline(always)]
+  fn as_mut(&mut self) -> &mut [T] {
+    self.as_mut_slice()
+  }
+}
+
+impl<T: Hash, const N: usize, G: GrowthPolicy> Hash for CompactVec<T, N, G> {
+  #[inline(always)]
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.as_slice().hash(state);
+  }
+}
+
+impl<T, const N: usize, const M: usize, G, H> PartialEq<CompactVec<T, M, H>>
+  for CompactVec<T, N, G>
+where
+  T: PartialEq,
+  G: GrowthPolicy,
+  H: GrowthPolicy,
+{
+  #[inline(always)]
+  fn eq(&self, other: &CompactVec<T, M, H>) -> bool {
+    self.as_slice() == other.as_slice()
+  }
+}
+
+impl<T: PartialEq, const N: usize, G: GrowthPolicy> PartialEq<[T]>
+  for CompactVec<T, N, G>
+{
+  #[inline(always)]
+  fn eq(&self, other: &[T]) -> bool {
+    self.as_slice() == other
+  }
+}
+
+impl<T: PartialEq, const N: usize, G: GrowthPolicy> PartialEq<&[T]>
+  for CompactVec<T, N, G>
+{
+  #[inline(always)]
+  fn eq(&self, other: &&[T]) -> bool {
+    self.as_slice() == *other
+  }
+}
+
+impl<T: PartialEq, const N: usize, const M: usize, G: GrowthPolicy>
+  PartialEq<[T; M]> for CompactVec<T, N, G>
+{
+  #[inline(always)]
+  fn eq(&self, other: &[T; M]) -> bool {
+    self.as_slice() == other.as_slice()
+  }
+}
+
+impl<T: PartialEq, const N: usize, G: GrowthPolicy> PartialEq<Vec<T>>
+  for CompactVec<T, N, G>
+{
+  #[inline(always)]
+  fn eq(&self, other: &Vec<T>) -> bool {
+    self.as_slice() == other.as_slice()
+  }
+}
+
+impl<T: PartialEq, const N: usize, G: GrowthPolicy>
+  PartialEq<CompactVec<T, N, G>> for Vec<T>
+{
+  #[inline(always)]
+  fn eq(&self, other: &CompactVec<T, N, G>) -> bool {
+    self.as_slice() == other.as_slice()
+  }
+}
+
+impl<T: Eq, const N: usize, G: GrowthPolicy> Eq for CompactVec<T, N, G> {}
+
+impl<T: PartialOrd, const N: usize, G: GrowthPolicy> PartialOrd
+  for CompactVec<T, N, G>
+{
+  #[inline(always)]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    self.as_slice().partial_cmp(other.as_slice())
+  }
+}
+
+impl<T: Ord, const N: usize, G: GrowthPolicy> Ord for CompactVec<T, N, G> {
+  #[inline(always)]
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.as_slice().cmp(other.as_slice())
+  }
+}
+
+impl<T, const N: usize, const M: usize, G: GrowthPolicy> From<[T; M]>
+  for CompactVec<T, N, G>
+{
+  /// Converts an array into a `CompactVec`, storing its elements inline if
+  /// `M <= N`, or on the heap otherwise.
+  #[inline]
+  fn from(array: [T; M]) -> Self {
+    let mut vec = Self::new();
+    if M > N {
+      vec.set_heap(Vec::from(array));
+      return vec;
+    }
+    let array = ManuallyDrop::new(array);
+    unsafe {
+      ptr::copy_nonoverlapping(array.as_ptr(), vec.inline_mut_ptr(), M);
+    }
+    vec.len = M;
+    vec
+  }
+}
+
+impl<T, const N: usize, G: GrowthPolicy> From<Vec<T>> for CompactVec<T, N, G> {
+  /// Converts a `Vec` into a `CompactVec`, adopting its heap allocation
+  /// without copying, unless its elements fit inline, in which case they are
+  /// moved into inline storage and the allocation is released.
+  #[inline]
+  fn from(vec: Vec<T>) -> Self {
+    let fits = vec.len() <= N;
+    let mut this = Self::new();
+    this.set_heap(vec);
+    if fits {
+      this.unspill();
+    }
+    this
+  }
+}
+
+impl<T, const N: usize, G: GrowthPolicy> From<CompactVec<T, N, G>> for Vec<T> {
+  /// Converts a `CompactVec` into a `Vec`, reusing its heap allocation if it
+  /// has spilled.
+  #[inline(always)]
+  fn from(vec: CompactVec<T, N, G>) -> Self {
+    vec.into_vec()
+  }
+}
+
+impl<T, const N: usize, const M: usize, G: GrowthPolicy>
+  TryFrom<CompactVec<T, N, G>> for [T; M]
+{
+  type Error = CompactVec<T, N, G>;
+
+  /// Moves the elements of a `CompactVec` into an array, if its length is
+  /// exactly `M`; otherwise, the vector is returned unchanged as the error.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  ///
+  /// let v: CompactVec<u8, 4> = CompactVec::from([1, 2, 3]);
+  /// let array: [u8; 3] = v.try_into().unwrap();
+  /// assert_eq!(array, [1, 2, 3]);
+  ///
+  /// let v: CompactVec<u8, 4> = CompactVec::from([1, 2]);
+  /// let result: Result<[u8; 3], _> = v.try_into();
+  /// assert_eq!(result.unwrap_err(), [1, 2]);
+  /// ```
+  fn try_from(mut vec: CompactVec<T, N, G>) -> Result<Self, Self::Error> {
+    if vec.len() != M {
+      return Err(vec);
+    }
+    let mut array = MaybeUninit::<[T; M]>::uninit();
+    unsafe {
+      ptr::copy_nonoverlapping(vec.as_ptr(), array.as_mut_ptr().cast(), M);
+      vec.set_len(0);
+      Ok(array.assume_init())
+    }
+  }
+}
+
+impl<T: Clone, const N: usize, G: GrowthPolicy> From<&[T]>
+  for CompactVec<T, N, G>
+{
+  /// Clones the elements of a slice into a new `CompactVec`.
+  #[inline]
+  fn from(slice: &[T]) -> Self {
+    let mut vec = Self::new();
+    vec.extend_from_slice(slice);
+    vec
+  }
+}
+
+impl<T, const N: usize, G: GrowthPolicy> Extend<T> for CompactVec<T, N, G> {
+  /// Extends the vector with the contents of an iterator.
+  ///
+  /// If the iterator's lower size hint shows that its elements won't fit in
+  /// the remaining capacity, the vector reserves room for all of them up
+  /// front, so it spills (or reallocates) at most once instead of partway
+  /// through the iteration.
+  #[inline]
+  fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+    let iter = iter.into_iter();
+    let (lower, _) = iter.size_hint();
+    if lower > self.capacity() - self.len() {
+      self.reserve(lower);
+    }
+    for value in iter {
+      self.push(value);
+    }
+  }
+}
+
+impl<'a, T: Copy + 'a, const N: usize, G: GrowthPolicy> Extend<&'a T>
+  for CompactVec<T, N, G>
+{
+  #[inline]
+  fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+    self.extend(iter.into_iter().copied());
+  }
+}
+
+impl<T, const N: usize, G: GrowthPolicy> FromIterator<T>
+  for CompactVec<T, N, G>
+{
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    let mut vec = Self::new();
+    vec.extend(iter);
+    vec
+  }
+}
+
+impl<T, const N: usize, const M: usize, G, H> FromIterator<CompactVec<T, M, H>>
+  for CompactVec<T, N, G>
+where
+  G: GrowthPolicy,
+  H: GrowthPolicy,
+{
+  /// Flattens an iterator of vectors into a single `CompactVec`.
+  ///
+  /// The parts are gathered first (inline, for up to 8 of them), so that the
+  /// total length is known and the result is allocated at most once. If the
+  /// first non-empty part has spilled and holds everything, its heap buffer
+  /// is adopted outright.
+  fn from_iter<I>(iter: I) -> Self
+  where
+    I: IntoIterator<Item = CompactVec<T, M, H>>,
+  {
+    let parts: CompactVec<CompactVec<T, M, H>, 8> = iter.into_iter().collect();
+    let total = parts.iter().map(CompactVec::len).sum();
+    let mut vec = Self::new();
+    for mut part in parts {
+      if vec.is_empty() && part.len() < total {
+        vec.reserve_exact(total);
+      }
+      vec.append(&mut part);
+    }
+    vec
+  }
+}
+
+impl<'a, T, const N: usize, G: GrowthPolicy> IntoIterator
+  for &'a CompactVec<T, N, G>
+{
+  type Item = &'a T;
+  type IntoIter = slice::Iter<'a, T>;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<'a, T, const N: usize, G: GrowthPolicy> IntoIterator
+  for &'a mut CompactVec<T, N, G>
+{
+  type Item = &'a mut T;
+  type IntoIter = slice::IterMut<'a, T>;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter_mut()
+  }
+}
+
+impl<T, const N: usize, G: GrowthPolicy> IntoIterator for CompactVec<T, N, G> {
+  type Item = T;
+  type IntoIter = IntoIter<T, N>;
+
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    let mut this = ManuallyDrop::new(self);
+    let inner = match this.take_heap() {
+      Some(heap) => IntoIterInner::Heap(heap.into_iter()),
+      None => IntoIterInner::Inline {
+        buf:   unsafe { ptr::read(&*this.data.inline) },
+        start: 0,
+        end:   this.len,
+      },
+    };
+    IntoIter { inner }
+  }
+}
+
+/// An owning iterator over the elements of a [`CompactVec`].
+///
+/// Created by the [`into_iter`](IntoIterator::into_iter) method on
+/// `CompactVec`.
+pub struct IntoIter<T, const N: usize> {
+  inner: IntoIterInner<T, N>,
+}
+
+enum IntoIterInner<T, const N: usize> {
+  Inline {
+    buf:   [MaybeUninit<T>; N],
+    start: usize,
+    end:   usize,
+  },
+  Heap(vec::IntoIter<T>),
+}
+
+impl<T, const N: usize> IntoIter<T, N> {
+  /// Returns the remaining elements as a slice.
+  #[inline]
+  pub fn as_slice(&self) -> &[T] {
+    match &self.inner {
+      IntoIterInner::Inline { buf, start, end } => unsafe {
+        slice::from_raw_parts(buf.as_ptr().cast::<T>().add(*start), end - start)
+      },
+      IntoIterInner::Heap(iter) => iter.as_slice(),
+    }
+  }
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+  type Item = T;
+
+  #[inline]
+  fn next(&mut self) -> Option<T> {
+    match &mut self.inner {
+      IntoIterInner::Inline { buf, start, end } => {
+        if start == end {
+          return None;
+        }
+        let value = unsafe { buf[*start].assume_init_read() };
+        *start += 1;
+        Some(value)
+      }
+      IntoIterInner::Heap(iter) => iter.next(),
+    }
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let len = self.as_slice().len();
+    (len, Some(len))
+  }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+  #[inline]
+  fn next_back(&mut self) -> Option<T> {
+    match &mut self.inner {
+      IntoIterInner::Inline { buf, start, end } => {
+        if start == end {
+          return None;
+        }
+        *end -= 1;
+        Some(unsafe { buf[*end].assume_init_read() })
+      }
+      IntoIterInner::Heap(iter) => iter.next_back(),
+    }
+  }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> FusedIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> Drop for IntoIter<T, N> {
+  fn drop(&mut self) {
+    if let IntoIterInner::Inline { buf, start, end } = &mut self.inner {
+      let remaining = *end - *start;
+      let head = unsafe { buf.as_mut_ptr().cast::<T>().add(*start) };
+      *start = *end;
+      unsafe {
+        ptr::drop_in_place(ptr::slice_from_raw_parts_mut(head, remaining));
+      }
+    }
+  }
+}
+
+impl<const N: usize, G: GrowthPolicy> CompactVec<u8, N, G> {
+  /// Returns a [`Reader`] that consumes bytes from the front of the vector.
+  ///
+  /// Reads only advance a cursor; the consumed bytes are removed from the
+  /// vector in a single move when the reader is dropped, rather than after
+  /// every read. The storage is never reallocated, so a small receive buffer
+  /// can be handed to parsers expecting a reader and then refilled.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// # #[cfg(feature = "std")] {
+  /// use std::io::Read;
+  ///
+  /// use moos::CompactVec;
+  ///
+  /// let mut buf: CompactVec<u8, 16> = CompactVec::from(*b"hello world");
+  /// let mut word = [0; 5];
+  /// buf.reader().read_exact(&mut word).unwrap();
+  /// assert_eq!(&word, b"hello");
+  /// assert_eq!(buf, *b" world");
+  /// # }
+  /// ```
+  #[inline]
+  pub fn reader(&mut self) -> Reader<'_, N, G> {
+    Reader { vec: self, pos: 0 }
+  }
+}
+
+/// A reader that consumes bytes from the front of a `CompactVec<u8, N>`.
+///
+/// Created by [`CompactVec::reader`]. Implements `std::io::Read` and
+/// `std::io::BufRead` when the `std` feature is enabled, and the
+/// `embedded-io` `Read` and `BufRead` traits when the `embedded-io` feature
+/// is enabled.
+pub struct Reader<'a, const N: usize, G: GrowthPolicy = Doubling> {
+  vec: &'a mut CompactVec<u8, N, G>,
+  pos: usize,
+}
+
+impl<const N: usize, G: GrowthPolicy> Reader<'_, N, G> {
+  /// Returns the bytes that have not been read yet.
+  #[inline]
+  pub fn remaining(&self) -> &[u8] {
+    &self.vec[self.pos..]
+  }
+
+  /// Returns the number of bytes consumed so far.
+  #[inline(always)]
+  pub fn position(&self) -> usize {
+    self.pos
+  }
+
+  /// Copies as many unread bytes as fit into `buf`, returning how many were
+  /// read. Returns `0` once all bytes have been consumed.
+  #[inline]
+  pub fn read_into(&mut self, buf: &mut [u8]) -> usize {
+    let remaining = self.remaining();
+    let count = remaining.len().min(buf.len());
+    buf[..count].copy_from_slice(&remaining[..count]);
+    self.pos += count;
+    count
+  }
+
+  /// Marks `amount` more bytes as consumed, clamped to the number of unread
+  /// bytes.
+  #[inline]
+  pub fn consume(&mut self, amount: usize) {
+    self.pos = self.pos.saturating_add(amount).min(self.vec.len());
+  }
+}
+
+impl<const N: usize, G: GrowthPolicy> Drop for Reader<'_, N, G> {
+  /// Removes the consumed bytes from the front of the vector.
+  fn drop(&mut self) {
+    let len = self.vec.len();
+    if self.pos > 0 {
+      self.vec.copy_within(self.pos.., 0);
+      self.vec.truncate(len - self.pos);
+    }
+  }
+}
+
+/// Creates a [`CompactVec`] containing the arguments, like `vec!`.
+///
+/// A list of elements is moved into inline storage by the `const`
+/// [`CompactVec::from_array`], so listing more elements than the inline
+/// capacity is a compile error rather than a silent spill to the heap. The
+/// `[value; n]` form clones `value` `n` times with
+/// [`CompactVec::from_elem`], and may spill.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::CompactVec;
+/// use moos::compact_vec;
+///
+/// let v: CompactVec<u16, 4> = compact_vec![80, 443];
+/// assert!(v.is_inline());
+/// assert_eq!(v, [80, 443]);
+///
+/// let zeros: CompactVec<u8, 4> = compact_vec![0; 8];
+/// assert_eq!(zeros.len(), 8);
+/// assert!(!zeros.is_inline());
+/// ```
+///
+/// Too many elements fail to compile:
+///
+/// ```rust,compile_fail
+/// let v: moos::CompactVec<u8, 2> = moos::compact_vec![1, 2, 3];
+/// ```
+#[macro_export]
+macro_rules! compact_vec {
+  () => {
+    $crate::CompactVec::new()
+  };
+  ($elem:expr; $n:expr) => {
+    $crate::CompactVec::from_elem($elem, $n)
+  };
+  ($($x:expr),+ $(,)?) => {
+    $crate::CompactVec::from_array([$($x),+])
+  };
+}
+
+#[cfg(feature = "std")]
+mod std_io_impl {
+  use std::io;
+
+  use super::*;
+
+  impl<const N: usize, G: GrowthPolicy> io::Read for Reader<'_, N, G> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      Ok(self.read_into(buf))
+    }
+  }
+
+  impl<const N: usize, G: GrowthPolicy> io::BufRead for Reader<'_, N, G> {
+    #[inline]
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+      Ok(self.remaining())
+    }
+
+    #[inline]
+    fn consume(&mut self, amount: usize) {
+      Reader::consume(self, amount);
+    }
+  }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) mod serde_impl {
+  use core::fmt;
+
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+  use serde::de;
+
+  use super::*;
+
+  impl<T: Serialize, const N: usize, G: GrowthPolicy> Serialize
+    for CompactVec<T, N, G>
+  {
+    #[inline(always)]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      serializer.collect_seq(self.iter())
+    }
+  }
+
+  /// Upper bound (in bytes) on the capacity preallocated from a sequence's
+  /// size hint, so a malicious length prefix can't trigger a huge allocation
+  /// before any elements have actually been read.
+  const MAX_PREALLOC_BYTES: usize = 1024 * 1024;
+
+  /// Returns a conservative capacity to reserve for a sequence, based on its
+  /// (untrusted) size hint.
+  #[inline]
+  pub(crate) fn cautious_size_hint<T>(hint: Option<usize>) -> usize {
+    let max = MAX_PREALLOC_BYTES / core::mem::size_of::<T>().max(1);
+    hint.unwrap_or(0).min(max)
+  }
+
+  struct CompactVecVisitor<T, const N: usize, G>(PhantomData<(T, G)>);
+
+  impl<'de, T, const N: usize, G> de::Visitor<'de> for CompactVecVisitor<T, N, G>
+  where
+    T: Deserialize<'de>,
+    G: GrowthPolicy,
+  {
+    type Value = CompactVec<T, N, G>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a sequence")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+      A: de::SeqAccess<'de>,
+    {
+      let mut vec = CompactVec::new();
+      // spill once up front if the sequence is known not to fit inline.
+      vec.reserve(cautious_size_hint::<T>(seq.size_hint()));
+      while let Some(value) = seq.next_element()? {
+        vec.push(value);
+      }
+      Ok(vec)
+    }
+  }
+
+  struct InPlaceVisitor<'a, T, const N: usize, G: GrowthPolicy>(
+    &'a mut CompactVec<T, N, G>,
+  );
+
+  impl<'de, T, const N: usize, G> de::Visitor<'de> for InPlaceVisitor<'_, T, N, G>
+  where
+    T: Deserialize<'de>,
+    G: GrowthPolicy,
+  {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a sequence")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+      A: de::SeqAccess<'de>,
+    {
+      // truncating (rather than clearing) keeps any existing heap allocation
+      // around to be reused.
+      self.0.truncate(0);
+      self.0.reserve(cautious_size_hint::<T>(seq.size_hint()));
+      while let Some(value) = seq.next_element()? {
+        self.0.push(value);
+      }
+      Ok(())
+    }
+  }
+
+  impl<'de, T, const N: usize, G: GrowthPolicy> Deserialize<'de>
+    for CompactVec<T, N, G>
+  where
+    T: Deserialize<'de>,
+  {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      deserializer.deserialize_seq(CompactVecVisitor(PhantomData))
+    }
+
+    fn deserialize_in_place<D>(
+      deserializer: D,
+      place: &mut Self,
+    ) -> Result<(), D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      deserializer.deserialize_seq(InPlaceVisitor(place))
+    }
+  }
+}
+
+/// Serializes a [`CompactVec`] as a fixed-size tuple of exactly `N`
+/// elements, rather than as a length-prefixed sequence.
+///
+/// Binary formats such as `bincode` and `postcard` can then omit the length
+/// prefix, which is useful for fixed-width records. Use it with serde's
+/// `with` attribute:
+///
+/// ```rust
+/// use moos::CompactVec;
+/// use serde::Deserialize;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Pixel {
+///   #[serde(with = "moos::compact_vec::serde_fixed")]
+///   rgba: CompactVec<u8, 4>,
+/// }
+///
+/// let pixel = Pixel {
+///   rgba: CompactVec::from([255, 128, 0, 255]),
+/// };
+/// let json = serde_json::to_string(&pixel).unwrap();
+/// assert_eq!(json, r#"{"rgba":[255,128,0,255]}"#);
+///
+/// let short = serde_json::from_str::<Pixel>(r#"{"rgba":[255,128]}"#);
+/// assert!(short.is_err());
+/// ```
+///
+/// Serialization fails if the vector does not contain exactly `N` elements,
+/// and so does deserialization if the input holds fewer than `N`.
+#[cfg(feature = "serde")]
+pub mod serde_fixed {
+  use core::fmt;
+  use core::marker::PhantomData;
+
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+  use serde::de;
+  use serde::ser;
+  use serde::ser::SerializeTuple;
+
+  use super::CompactVec;
+  use super::GrowthPolicy;
+
+  /// Serializes `vec` as a tuple of exactly `N` elements.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if `vec.len() != N`, or if the serializer fails.
+  pub fn serialize<T, S, const N: usize, G>(
+    vec: &CompactVec<T, N, G>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error>
+  where
+    T: Serialize,
+    S: Serializer,
+    G: GrowthPolicy,
+  {
+    if vec.len() != N {
+      return Err(ser::Error::custom(format_args!(
+        "expected exactly {N} elements, found {}",
+        vec.len()
+      )));
+    }
+    let mut tuple = serializer.serialize_tuple(N)?;
+    for value in vec {
+      tuple.serialize_element(value)?;
+    }
+    tuple.end()
+  }
+
+  /// Deserializes a tuple of exactly `N` elements into an inline
+  /// `CompactVec`.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if the input holds fewer than `N` elements, or if the
+  /// deserializer fails.
+  pub fn deserialize<'de, T, D, const N: usize, G>(
+    deserializer: D,
+  ) -> Result<CompactVec<T, N, G>, D::Error>
+  where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+    G: GrowthPolicy,
+  {
+    deserializer.deserialize_tuple(N, FixedVisitor(PhantomData))
+  }
+
+  struct FixedVisitor<T, const N: usize, G>(PhantomData<(T, G)>);
+
+  impl<'de, T, const N: usize, G> de::Visitor<'de> for FixedVisitor<T, N, G>
+  where
+    T: Deserialize<'de>,
+    G: GrowthPolicy,
+  {
+    type Value = CompactVec<T, N, G>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      write!(formatter, "a tuple of size {N}")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+      A: de::SeqAccess<'de>,
+    {
+      let mut vec = CompactVec::new();
+      while vec.len() < N {
+        match seq.next_element()? {
+          Some(value) => vec.push(value),
+          None => return Err(de::Error::invalid_length(vec.len(), &self)),
+        }
+      }
+      Ok(vec)
+    }
+  }
+}
+
+#[cfg(feature = "bytemuck")]
+mod bytemuck_impl {
+  use bytemuck::Pod;
+  use bytemuck::PodCastError;
+
+  use super::*;
+
+  impl<T: Pod, const N: usize, G: GrowthPolicy> CompactVec<T, N, G> {
+    /// Returns the elements of the vector reinterpreted as raw bytes,
+    /// without copying.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moos::CompactVec;
+    ///
+    /// let v: CompactVec<u16, 4> = CompactVec::from([1, 2]);
+    /// assert_eq!(v.as_bytes().len(), 4);
+    /// ```
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+      bytemuck::cast_slice(self.as_slice())
+    }
+
+    /// Returns the elements of the vector reinterpreted as mutable raw
+    /// bytes, without copying.
+    #[inline]
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+      bytemuck::cast_slice_mut(self.as_mut_slice())
+    }
+
+    /// Creates a `CompactVec` by copying `bytes` into its storage as a
+    /// sequence of `T`s.
+    ///
+    /// The bytes need not be aligned for `T`. They are stored inline if they
+    /// fit within `N` elements, or in an exactly-sized heap buffer otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PodCastError::OutputSliceWouldHaveSlop`] if the length of
+    /// `bytes` is not a multiple of the size of `T` (or if `T` is zero-sized
+    /// and `bytes` is not empty).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moos::CompactVec;
+    ///
+    /// let bytes = 0x0102_0304u32.to_ne_bytes();
+    /// let v = CompactVec::<u32, 2>::from_byte_slice(&bytes).unwrap();
+    /// assert_eq!(v, [0x0102_0304]);
+    ///
+    /// assert!(CompactVec::<u32, 2>::from_byte_slice(&bytes[1..]).is_err());
+    /// ```
+    pub fn from_byte_slice(bytes: &[u8]) -> Result<Self, PodCastError> {
+      let size = size_of::<T>();
+      if size == 0 && bytes.is_empty() {
+        return Ok(Self::new());
+      }
+      if size == 0 || !bytes.len().is_multiple_of(size) {
+        return Err(PodCastError::OutputSliceWouldHaveSlop);
+      }
+      let len = bytes.len() / size;
+      let mut vec = Self::new();
+      vec.reserve_exact(len);
+      unsafe {
+        let dst = vec.as_mut_ptr().cast::<u8>();
+        ptr::copy_nonoverlapping(bytes.as_ptr(), dst, bytes.len());
+        vec.set_len(len);
+      }
+      Ok(vec)
+    }
+  }
+}
+
+#[cfg(feature = "embedded-io")]
+mod embedded_io_impl {
+  use core::convert::Infallible;
+
+  use embedded_io::BufRead;
+  use embedded_io::ErrorType;
+  use embedded_io::Read;
+  use embedded_io::Write;
+
+  use super::*;
+
+  impl<const N: usize, G: GrowthPolicy> ErrorType for CompactVec<u8, N, G> {
+    type Error = Infallible;
+  }
+
+  impl<const N: usize, G: GrowthPolicy> Write for CompactVec<u8, N, G> {
+    /// Appends the bytes to the end of the vector, spilling to the heap if
+    /// needed. Always writes the entire buffer.
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+      self.extend_from_copy_slice(buf);
+      Ok(buf.len())
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> Result<(), Self::Error> {
+      Ok(())
+    }
+  }
+
+  impl<const N: usize, G: GrowthPolicy> Read for CompactVec<u8, N, G> {
+    /// Consumes bytes from the front of the vector, shifting the remaining
+    /// bytes down in place. Returns `Ok(0)` once the vector is empty.
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+      let len = self.len();
+      let count = len.min(buf.len());
+      buf[..count].copy_from_slice(&self[..count]);
+      self.copy_within(count.., 0);
+      self.truncate(len - count);
+      Ok(count)
+    }
+  }
+
+  impl<const N: usize, G: GrowthPolicy> ErrorType for Reader<'_, N, G> {
+    type Error = Infallible;
+  }
+
+  impl<const N: usize, G: GrowthPolicy> Read for Reader<'_, N, G> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+      Ok(self.read_into(buf))
+    }
+  }
+
+  impl<const N: usize, G: GrowthPolicy> BufRead for Reader<'_, N, G> {
+    #[inline]
+    fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+      Ok(self.remaining())
+    }
+
+    #[inline]
+    fn consume(&mut self, amount: usize) {
+      Reader::consume(self, amount);
+    }
+  }
+}
+
+#[cfg(feature = "zeroize")]
+mod zeroize_impl {
+  use zeroize::Zeroize;
+  use zeroize::ZeroizeOnDrop;
+
+  use super::*;
+
+  impl<T: Zeroize, const N: usize, G: GrowthPolicy> Zeroize
+    for CompactVec<T, N, G>
+  {
+    /// Zeroizes all elements, clears the vector, and then wipes its entire
+    /// capacity (the inline buffer, or the heap allocation if spilled).
+    fn zeroize(&mut self) {
+      self.iter_mut().zeroize();
+      self.truncate(0);
+      self.spare_capacity_mut().zeroize();
+    }
+  }
+
+  /// The [`Drop`] impl of `CompactVec` wipes its storage after dropping the
+  /// elements when the `zeroize` feature is enabled.
+  impl<T, const N: usize, G: GrowthPolicy> ZeroizeOnDrop for CompactVec<T, N, G> {}
+}
+
+#[cfg(test)]
+mod tests {
+  use std::rc::Rc;
+
+  use super::*;
+
+  #[test]
+  fn compact_vec_size() {
+    let word = size_of::<usize>();
+    assert_eq!(size_of::<CompactVec<u8, 32>>(), 32 + word);
+    assert_eq!(size_of::<CompactVec<u8, 1>>(), size_of::<Vec<u8>>() + word);
+    assert_eq!(size_of::<CompactVec<u64, 4>>(), 4 * 8 + word);
+  }
+
+  #[test]
+  fn compact_vec_from_elem() {
+    let v = CompactVec::<u8, 4>::from_elem(0, 3);
+    assert!(v.is_inline());
+    assert_eq!(v, [0, 0, 0]);
+
+    let v = CompactVec::<u8, 4>::from_elem(1, 6);
+    assert!(!v.is_inline());
+    assert_eq!(v.capacity(), 6);
+    assert_eq!(v, [1; 6]);
+  }
+
+  #[test]
+  fn compact_vec_from_fn() {
+    let v = CompactVec::<usize, 4>::from_fn(4, |i| i * i);
+    assert!(v.is_inline());
+    assert_eq!(v, [0, 1, 4, 9]);
+
+    let v = CompactVec::<usize, 2>::from_fn(3, |i| i);
+    assert!(!v.is_inline());
+    assert_eq!(v, [0, 1, 2]);
+  }
+
+  #[test]
+  fn compact_vec_const_new() {
+    const EMPTY: CompactVec<u8, 4> = CompactVec::new();
+    let mut v = EMPTY;
+    v.push(1);
+    assert_eq!(v, [1]);
+    assert!(EMPTY.is_empty());
+  }
+
+  #[test]
+  fn compact_vec_const_from_array() {
+    const PORTS: CompactVec<u16, 4> = CompactVec::from_array([80, 443]);
+    let mut v = PORTS;
+    v.extend([8080, 8443, 9000]);
+    assert!(!v.is_inline());
+    assert_eq!(PORTS, [80, 443]);
+
+    let count = Rc::new(());
+    let v: CompactVec<Rc<()>, 3> = compact_vec![count.clone(), count.clone()];
+    assert_eq!(Rc::strong_count(&count), 3);
+    drop(v);
+    assert_eq!(Rc::strong_count(&count), 1);
+
+    const BYTES: CompactVec<u8, 8> = CompactVec::from_inline_slice(b"moo");
+    assert_eq!(BYTES, *b"moo");
+    let empty: CompactVec<u8, 2> = compact_vec![];
+    assert!(empty.is_empty());
+  }
+
+  #[test]
+  #[should_panic]
+  fn compact_vec_from_inline_slice_too_long() {
+    let _ = CompactVec::<u8, 2>::from_inline_slice(&[1, 2, 3]);
+  }
+
+  #[test]
+  fn compact_vec_push_stays_inline() {
+    let mut v: CompactVec<u32, 4> = CompactVec::new();
+    v.extend([1, 2, 3, 4]);
+    assert!(v.is_inline());
+    assert_eq!(v.len(), 4);
+    assert_eq!(v.capacity(), 4);
+    assert_eq!(v, [1, 2, 3, 4]);
+  }
+
+  #[test]
+  fn compact_vec_push_spills_to_heap() {
+    let mut v: CompactVec<u32, 2> = CompactVec::new();
+    v.extend([1, 2, 3]);
+    assert!(!v.is_inline());
+    assert_eq!(v, [1, 2, 3]);
+  }
+
+  #[test]
+  fn compact_vec_try_push_never_spills() {
+    let mut v: CompactVec<u32, 2> = CompactVec::new();
+    assert_eq!(v.try_push(1), Ok(()));
+    assert_eq!(v.push_within_capacity(2), Ok(()));
+    assert_eq!(v.try_push(3), Err(CapacityError(3)));
+    assert!(v.is_inline());
+    assert_eq!(v, [1, 2]);
+  }
+
+  #[test]
+  fn compact_vec_try_push_spilled() {
+    let mut v: CompactVec<u32, 1> = CompactVec::new();
+    v.extend([1, 2]);
+    let capacity = v.capacity();
+    while v.len() < capacity {
+      assert!(v.try_push(0).is_ok());
+    }
+    assert!(v.try_push(0).is_err());
+    assert_eq!(v.capacity(), capacity);
+  }
+
+  #[test]
+  fn compact_vec_reserve() {
+    let mut v: CompactVec<u8, 4> = CompactVec::new();
+    v.reserve(4);
+    assert!(v.is_inline());
+    v.push(1);
+    v.reserve(20);
+    assert!(!v.is_inline());
+    assert!(v.capacity() >= 21);
+    assert_eq!(v, [1]);
+
+    let mut v: CompactVec<u8, 4> = CompactVec::new();
+    v.reserve_exact(5);
+    assert!(!v.is_inline());
+    assert_eq!(v.capacity(), 5);
+  }
+
+  #[test]
+  fn compact_vec_try_reserve() {
+    let mut v: CompactVec<u8, 4> = [1, 2].into_iter().collect();
+    assert!(v.try_reserve(2).is_ok());
+    assert!(v.is_inline());
+    assert!(v.try_reserve(usize::MAX).is_err());
+    assert!(v.is_inline());
+    assert!(v.try_reserve(10).is_ok());
+    assert!(!v.is_inline());
+    assert_eq!(v, [1, 2]);
+  }
+
+  #[test]
+  fn compact_vec_extend_reserves_from_size_hint() {
+    let mut v: CompactVec<u8, 2> = CompactVec::new();
+    v.push(0);
+    v.extend(1..30);
+    assert_eq!(v.capacity(), 30);
+    assert_eq!(v.len(), 30);
+
+    // an iterator without a useful size hint still grows incrementally.
+    let mut v: CompactVec<u8, 2> = CompactVec::new();
+    v.extend((0..30).filter(|_| true));
+    assert_eq!(v.len(), 30);
+    assert!(v.capacity() > 30);
+  }
+
+  #[test]
+  fn compact_vec_swap_contents() {
+    let mut a: CompactVec<u8, 4> = CompactVec::from([1, 2]);
+    let mut b: CompactVec<u8, 4> = (0..10).collect();
+    let ptr = b.as_ptr();
+    a.swap_contents(&mut b);
+    assert_eq!(a, (0..10).collect::<Vec<_>>());
+    assert_eq!(a.as_ptr(), ptr);
+    assert!(b.is_inline());
+    assert_eq!(b, [1, 2]);
+    b.swap(0, 1);
+    assert_eq!(b, [2, 1]);
+  }
+
+  #[test]
+  fn compact_vec_map() {
+    let v: CompactVec<u8, 4> = CompactVec::from([1, 2, 3]);
+    let mapped: CompactVec<u64, 4> = v.map(u64::from);
+    assert!(mapped.is_inline());
+    assert_eq!(mapped, [1, 2, 3]);
+
+    // mismatched layouts still work, but allocate a new buffer.
+    let v: CompactVec<u8, 2> = CompactVec::from([1, 2, 3]);
+    let mapped = v.map(|n| [n; 4]);
+    assert_eq!(mapped, [[1; 4], [2; 4], [3; 4]]);
+  }
+
+  #[test]
+  fn compact_vec_map_panic_drops_elements() {
+    let rc = Rc::new(());
+    let v: CompactVec<Rc<()>, 1> = (0..4).map(|_| rc.clone()).collect();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+      let mut count = 0;
+      v.map(|item| {
+        count += 1;
+        assert!(count < 3, "boom");
+        Some(item)
+      })
+    }));
+    assert!(result.is_err());
+    assert_eq!(Rc::strong_count(&rc), 1);
+  }
+
+  #[test]
+  fn compact_vec_try_insert() {
+    let mut v: CompactVec<u8, 3> = CompactVec::from([1, 3]);
+    assert!(v.try_insert(1, 2).is_ok());
+    assert_eq!(v.try_insert(0, 0), Err(CapacityError(0)));
+    assert!(v.is_inline());
+    assert_eq!(v, [1, 2, 3]);
+
+    v.reserve_exact(1);
+    assert!(v.try_insert(0, 0).is_ok());
+    assert_eq!(v.try_insert(4, 4), Err(CapacityError(4)));
+    assert_eq!(v, [0, 1, 2, 3]);
+  }
+
+  #[test]
+  fn compact_vec_pop_if() {
+    let mut v: CompactVec<u8, 2> = CompactVec::from([1, 2, 3]);
+    assert_eq!(v.pop_if(|n| *n == 2), None);
+    assert_eq!(v.pop_if(|n| *n == 3), Some(3));
+    assert_eq!(
+      v.pop_if(|n| {
+        *n += 1;
+        false
+      }),
+      None
+    );
+    assert_eq!(v, [1, 3]);
+    v.clear();
+    assert_eq!(v.pop_if(|_| true), None);
+  }
+
+  #[test]
+  fn compact_vec_splice() {
+    let mut v: CompactVec<u8, 4> = CompactVec::from([1, 2, 3, 4]);
+    let removed = v.splice(1..3, [7, 8, 9]);
+    assert_eq!(removed, [2, 3]);
+    assert_eq!(v, [1, 7, 8, 9, 4]);
+
+    let removed = v.splice(..=3, []);
+    assert_eq!(removed, [1, 7, 8, 9]);
+    assert_eq!(v, [4]);
+
+    assert!(v.splice(1.., [5, 6]).is_empty());
+    assert_eq!(v, [4, 5, 6]);
+  }
+
+  #[test]
+  fn compact_vec_splice_drops_elements() {
+    let rc = Rc::new(());
+    let mut v: CompactVec<Rc<()>, 2> = (0..4).map(|_| rc.clone()).collect();
+    let removed = v.splice(1..2, (0..3).map(|_| rc.clone()));
+    assert_eq!((v.len(), removed.len()), (6, 1));
+    assert_eq!(Rc::strong_count(&rc), 8);
+    drop((v, removed));
+    assert_eq!(Rc::strong_count(&rc), 1);
+  }
+
+  #[test]
+  #[should_panic]
+  fn compact_vec_splice_out_of_bounds() {
+    let mut v: CompactVec<u8, 4> = CompactVec::from([1, 2]);
+    v.splice(1..3, []);
+  }
+
+  #[test]
+  fn compact_vec_fill() {
+    let mut v: CompactVec<u8, 4> = CompactVec::from([1, 2, 3]);
+    v.fill(7);
+    assert_eq!(v, [7, 7, 7]);
+    let mut n = 0;
+    v.fill_with(|| {
+      n += 1;
+      n
+    });
+    assert_eq!(v, [1, 2, 3]);
+    v.clear_and_fill(6, 9);
+    assert_eq!(v, [9; 6]);
+    assert!(v.is_spilled());
+  }
+
+  #[test]
+  fn compact_vec_concat_and_flatten() {
+    let a: CompactVec<u8, 2> = CompactVec::from([1, 2]);
+    let b: CompactVec<u8, 2> = CompactVec::from([3, 4, 5]);
+    let v: CompactVec<u8, 4> = CompactVec::concat(&[&a, &b]);
+    assert_eq!(v, [1, 2, 3, 4, 5]);
+    assert_eq!(v.capacity(), 5);
+
+    let v: CompactVec<u8, 8> = [a, CompactVec::new(), b].into_iter().collect();
+    assert!(v.is_inline());
+    assert_eq!(v, [1, 2, 3, 4, 5]);
+
+    let parts = (0..20).map(|n| CompactVec::<u8, 2>::from([n, n]));
+    let v: CompactVec<u8, 4> = parts.collect();
+    assert_eq!(v.len(), 40);
+    assert_eq!(v.capacity(), 40);
+  }
+
+  #[test]
+  fn compact_vec_try_from_iter() {
+    let v = CompactVec::<u8, 3>::try_from_iter([1, 2]).unwrap();
+    assert_eq!(v, [1, 2]);
+    let mut iter = 0..10;
+    let err = CompactVec::<u8, 3>::try_from_iter(&mut iter).unwrap_err();
+    assert_eq!(err, CapacityError(3));
+    assert_eq!(iter.next(), Some(4));
+  }
+
+  #[test]
+  fn compact_vec_memory_usage() {
+    let mut v: CompactVec<u16, 4> = (0..4).collect();
+    assert!(!v.is_spilled());
+    assert_eq!(v.heap_capacity_bytes(), 0);
+    let usage = v.memory_usage();
+    assert_eq!(usage.stack, size_of::<CompactVec<u16, 4>>());
+    assert_eq!(usage.total(), usage.stack);
+
+    v.push(4);
+    assert!(v.is_spilled());
+    assert_eq!(v.heap_capacity_bytes(), v.capacity() * 2);
+    assert_eq!(v.memory_usage().heap, v.heap_capacity_bytes());
+  }
+
+  #[test]
+  fn compact_vec_growth_policy() {
+    let mut v: CompactVec<u8, 4> = (0..5).collect();
+    assert_eq!(v.capacity(), 9);
+    v.extend(5..10);
+    assert_eq!(v.capacity(), 18);
+
+    let mut v: CompactVec<u8, 4, Exact> = (0..5).collect();
+    assert_eq!(v.capacity(), 5);
+    v.push(5);
+    assert_eq!(v.capacity(), 6);
+    v.insert(0, 6);
+    v.resize(10, 0);
+    assert_eq!(v.capacity(), 10);
+    v.reserve(5);
+    assert_eq!(v.capacity(), 15);
+    assert_eq!(v, [6, 0, 1, 2, 3, 4, 5, 0, 0, 0]);
+  }
+
+  #[test]
+  fn compact_vec_shrink_to_fit() {
+    let mut v: CompactVec<u8, 4> = (0..10).collect();
+    v.truncate(6);
+    v.shrink_to_fit();
+    assert!(!v.is_inline());
+    assert_eq!(v.capacity(), 6);
+    v.truncate(3);
+    v.shrink_to_fit();
+    assert!(v.is_inline());
+    assert_eq!(v, [0, 1, 2]);
+  }
+
+  #[test]
+  fn compact_vec_shrink_to() {
+    let mut v: CompactVec<u8, 4> = (0..10).collect();
+    v.truncate(2);
+    v.shrink_to(8);
+    assert!(!v.is_inline());
+    assert!(v.capacity() >= 8);
+    v.shrink_to(4);
+    assert!(v.is_inline());
+    assert_eq!(v, [0, 1]);
+  }
+
+  #[test]
+  fn compact_vec_pop_keeps_heap_storage() {
+    let mut v: CompactVec<u32, 2> = [1, 2, 3].into_iter().collect();
+    assert_eq!(v.pop(), Some(3));
+    assert!(!v.is_inline());
+    v.push(3);
+    assert_eq!(v.pop(), Some(3));
+    v.shrink_to_fit();
+    assert!(v.is_inline());
+    assert_eq!(v.pop(), Some(2));
+    assert_eq!(v.pop(), Some(1));
+    assert_eq!(v.pop(), None);
+  }
+
+  #[test]
+  fn compact_vec_insert_and_remove() {
+    let mut v: CompactVec<char, 3> = CompactVec::new();
+    v.insert(0, 'b');
+    v.insert(0, 'a');
+    v.insert(2, 'd');
+    v.insert(2, 'c');
+    assert!(!v.is_inline());
+    assert_eq!(v, ['a', 'b', 'c', 'd']);
+    assert_eq!(v.remove(1), 'b');
+    assert_eq!(v, ['a', 'c', 'd']);
+
+    let mut v: CompactVec<char, 3> = ['a', 'b', 'c'].into_iter().collect();
+    assert_eq!(v.remove(0), 'a');
+    assert_eq!(v, ['b', 'c']);
+  }
+
+  #[test]
+  fn compact_vec_truncate_inline() {
+    let mut v: CompactVec<u8, 8> = [1, 2, 3, 4, 5].into_iter().collect();
+    v.truncate(10);
+    assert_eq!(v.len(), 5);
+    v.truncate(2);
+    assert!(v.is_inline());
+    assert_eq!(v, [1, 2]);
+    v.truncate(0);
+    assert!(v.is_empty());
+  }
+
+  #[test]
+  fn compact_vec_truncate_spilled() {
+    let mut v: CompactVec<u8, 2> = [1, 2, 3, 4, 5].into_iter().collect();
+    v.truncate(3);
+    assert!(!v.is_inline());
+    assert_eq!(v, [1, 2, 3]);
+    v.truncate(1);
+    assert_eq!(v, [1]);
+  }
+
+  #[test]
+  fn compact_vec_truncate_drops_elements() {
+    let rc = Rc::new(());
+    let mut v: CompactVec<Rc<()>, 4> = CompactVec::new();
+    v.extend((0..4).map(|_| rc.clone()));
+    assert_eq!(Rc::strong_count(&rc), 5);
+    v.truncate(1);
+    assert_eq!(Rc::strong_count(&rc), 2);
+    drop(v);
+    assert_eq!(Rc::strong_count(&rc), 1);
+  }
+
+  #[test]
+  fn compact_vec_resize() {
+    let mut v: CompactVec<u8, 4> = CompactVec::new();
+    v.resize(3, 7);
+    assert!(v.is_inline());
+    assert_eq!(v, [7, 7, 7]);
+    v.resize(1, 0);
+    assert_eq!(v, [7]);
+    v.resize(6, 1);
+    assert!(!v.is_inline());
+    assert_eq!(v, [7, 1, 1, 1, 1, 1]);
+  }
+
+  #[test]
+  fn compact_vec_resize_with() {
+    let mut n = 0;
+    let mut v: CompactVec<u8, 4> = CompactVec::new();
+    v.resize_with(4, || {
+      n += 1;
+      n
+    });
+    assert!(v.is_inline());
+    assert_eq!(v, [1, 2, 3, 4]);
+    v.resize_with(5, Default::default);
+    assert!(!v.is_inline());
+    assert_eq!(v, [1, 2, 3, 4, 0]);
+  }
+
+  #[test]
+  fn compact_vec_extend_from_slice() {
+    let mut v: CompactVec<String, 3> = CompactVec::new();
+    v.extend_from_slice(&["a".to_owned(), "b".to_owned()]);
+    assert!(v.is_inline());
+    v.extend_from_slice(&["c".to_owned(), "d".to_owned()]);
+    assert!(!v.is_inline());
+    assert_eq!(v, ["a", "b", "c", "d"].map(String::from));
+  }
+
+  #[test]
+  fn compact_vec_extend_from_slice_panic() {
+    use std::panic;
+    use std::rc::Rc;
+
+    #[derive(Debug)]
+    struct Bomb(Rc<()>, bool);
+
+    impl Clone for Bomb {
+      fn clone(&self) -> Self {
+        assert!(!self.1, "boom");
+        Bomb(self.0.clone(), false)
+      }
+    }
+
+    let rc = Rc::new(());
+    let source = [Bomb(rc.clone(), false), Bomb(rc.clone(), true)];
+    let mut v: CompactVec<Bomb, 4> = CompactVec::new();
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+      v.extend_from_slice(&source);
+    }));
+    assert!(result.is_err());
+    assert!(v.is_empty());
+    assert_eq!(Rc::strong_count(&rc), 3);
+  }
+
+  #[test]
+  fn compact_vec_extend_from_copy_slice() {
+    let mut v: CompactVec<u8, 8> = CompactVec::new();
+    v.extend_from_copy_slice(b"hello");
+    assert!(v.is_inline());
+    assert_eq!(v, *b"hello");
+    v.extend_from_copy_slice(b", world");
+    assert!(!v.is_inline());
+    assert_eq!(v, *b"hello, world");
+  }
+
+  #[test]
+  fn compact_vec_leak() {
+    let v: CompactVec<u8, 4> = [1, 2, 3].into();
+    let leaked: &'static mut [u8] = v.leak();
+    leaked[0] = 0;
+    assert_eq!(leaked, [0, 2, 3]);
+
+    let v: CompactVec<u8, 2> = [1, 2, 3].into();
+    let leaked: &'static [u8] = v.leak();
+    assert_eq!(leaked, [1, 2, 3]);
+  }
+
+  #[test]
+  fn compact_vec_spare_capacity_and_set_len() {
+    let mut v: CompactVec<u8, 8> = [1, 2].into();
+    let spare = v.spare_capacity_mut();
+    assert_eq!(spare.len(), 6);
+    spare[0].write(3);
+    spare[1].write(4);
+    unsafe { v.set_len(4) };
+    assert!(v.is_inline());
+    assert_eq!(v, [1, 2, 3, 4]);
+
+    let mut v: CompactVec<u8, 2> = CompactVec::new();
+    v.reserve_exact(4);
+    for (i, slot) in v.spare_capacity_mut().iter_mut().enumerate() {
+      slot.write(i as u8);
+    }
+    unsafe { v.set_len(4) };
+    assert_eq!(v, [0, 1, 2, 3]);
+  }
+
+  #[test]
+  fn compact_vec_raw_pointers() {
+    let mut v: CompactVec<u8, 4> = [1, 2].into();
+    assert_eq!(v.as_ptr(), v.as_slice().as_ptr());
+    unsafe { *v.as_mut_ptr().add(1) = 5 };
+    assert_eq!(v, [1, 5]);
+  }
+
+  #[test]
+  fn compact_vec_clone_from_reuses_storage() {
+    let source: CompactVec<String, 2> =
+      ["a", "b", "c"].map(String::from).into();
+    let mut target: CompactVec<String, 2> =
+      CompactVec::from_elem("x".into(), 8);
+    let ptr = target.as_ptr();
+    target.clone_from(&source);
+    assert_eq!(target, source);
+    assert_eq!(target.as_ptr(), ptr);
+
+    let mut target: CompactVec<String, 2> = ["z".to_owned()].into();
+    target.clone_from(&source);
+    assert_eq!(target, source);
+  }
+
+  #[test]
+  fn compact_vec_dedup() {
+    let mut v: CompactVec<u8, 8> = [1, 1, 2, 3, 3, 3, 1].into_iter().collect();
+    v.dedup();
+    assert!(v.is_inline());
+    assert_eq!(v, [1, 2, 3, 1]);
+
+    let mut v: CompactVec<u8, 2> = [1, 1, 2, 3, 3, 3, 1].into_iter().collect();
+    v.dedup();
+    assert_eq!(v, [1, 2, 3, 1]);
+  }
+
+  #[test]
+  fn compact_vec_dedup_by_key_drops_removed() {
+    let rc = Rc::new(());
+    let mut v: CompactVec<(u8, Rc<()>), 8> = CompactVec::new();
+    v.extend([1, 1, 2, 2, 2, 3].map(|n| (n, rc.clone())));
+    v.dedup_by_key(|(n, _)| *n);
+    assert_eq!(v.iter().map(|(n, _)| *n).collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(Rc::strong_count(&rc), 4);
+  }
+
+  #[test]
+  fn compact_vec_append() {
+    let mut a: CompactVec<u8, 4> = [1, 2].into_iter().collect();
+    let mut b: CompactVec<u8, 2> = [3, 4].into_iter().collect();
+    a.append(&mut b);
+    assert!(a.is_inline());
+    assert_eq!(a, [1, 2, 3, 4]);
+    assert!(b.is_empty());
+
+    let mut c: CompactVec<u8, 8> = (5..10).collect();
+    a.append(&mut c);
+    assert!(!a.is_inline());
+    assert_eq!(a, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    assert!(c.is_empty());
+  }
+
+  #[test]
+  fn compact_vec_append_adopts_heap() {
+    let rc = Rc::new(());
+    let mut a: CompactVec<Rc<()>, 2> = CompactVec::new();
+    let mut b: CompactVec<Rc<()>, 2> = (0..5).map(|_| rc.clone()).collect();
+    let ptr = b.as_ptr();
+    a.append(&mut b);
+    assert_eq!(a.as_ptr(), ptr);
+    assert!(b.is_empty() && b.is_inline());
+    assert_eq!(Rc::strong_count(&rc), 6);
+    drop((a, b));
+    assert_eq!(Rc::strong_count(&rc), 1);
+  }
+
+  #[test]
+  fn compact_vec_clear_returns_inline() {
+    let mut v: CompactVec<u8, 2> = [1, 2, 3].into_iter().collect();
+    v.clear();
+    assert!(v.is_empty());
+    assert!(v.is_inline());
+  }
+
+  #[test]
+  fn compact_vec_index_ranges() {
+    let mut v: CompactVec<u8, 4> = [1, 2, 3, 4, 5].into();
+    assert_eq!(v[0], 1);
+    assert_eq!(&v[1..3], [2, 3]);
+    assert_eq!(&v[3..], [4, 5]);
+    assert_eq!(&v[..=1], [1, 2]);
+    assert_eq!(&v[..], [1, 2, 3, 4, 5]);
+    v[..2].copy_from_slice(&[9, 8]);
+    v[4] = 0;
+    assert_eq!(v, [9, 8, 3, 4, 0]);
+  }
+
+  #[test]
+  #[should_panic]
+  fn compact_vec_index_out_of_bounds() {
+    let v: CompactVec<u8, 4> = [1, 2].into();
+    let _ = &v[1..3];
+  }
+
+  #[test]
+  fn compact_vec_from_array() {
+    let v: CompactVec<u8, 8> = [1, 2, 3].into();
+    assert!(v.is_inline());
+    assert_eq!(v, [1, 2, 3]);
+
+    let v: CompactVec<u8, 2> = [1, 2, 3].into();
+    assert!(!v.is_inline());
+    assert_eq!(v, [1, 2, 3]);
+
+    let rc = Rc::new(());
+    let v: CompactVec<Rc<()>, 2> = [rc.clone(), rc.clone()].into();
+    assert_eq!(Rc::strong_count(&rc), 3);
+    drop(v);
+    assert_eq!(Rc::strong_count(&rc), 1);
+  }
+
+  #[test]
+  fn compact_vec_from_vec() {
+    let v: CompactVec<u8, 4> = vec![1, 2, 3].into();
+    assert!(v.is_inline());
+    assert_eq!(v, [1, 2, 3]);
+
+    let heap = vec![1, 2, 3, 4, 5];
+    let ptr = heap.as_ptr();
+    let v: CompactVec<u8, 4> = heap.into();
+    assert!(!v.is_inline());
+    assert_eq!(v.as_ptr(), ptr);
+  }
+
+  #[test]
+  fn compact_vec_from_vec_adopts_heap() {
+    let heap = vec![1u8, 2];
+    let ptr = heap.as_ptr();
+    let mut v = CompactVec::<u8, 4>::from_vec(heap);
+    assert!(!v.is_inline());
+    assert_eq!(v.as_vec().map(Vec::as_ptr), Some(ptr));
+    v.as_mut_vec().unwrap().push(3);
+    let heap = Vec::from(v);
+    assert_eq!(heap.as_ptr(), ptr);
+  }
+
+  #[test]
+  fn compact_vec_with_heap() {
+    let mut v: CompactVec<u8, 4> = [3, 1, 2].into();
+    assert_eq!(v.as_vec(), None);
+    v.with_heap(|heap| heap.retain(|&n| n != 1));
+    assert!(!v.is_inline());
+    assert_eq!(v, [3, 2]);
+  }
+
+  #[test]
+  fn compact_vec_into_inner() {
+    let v: CompactVec<String, 2> = ["a", "b"].map(String::from).into();
+    assert_eq!(v.into_inner(), Ok(["a", "b"].map(String::from)));
+
+    let v: CompactVec<u8, 2> = [1].into();
+    assert_eq!(v.into_inner(), Err(vec![1]));
+
+    let mut v: CompactVec<u8, 2> = [1, 2, 3].into();
+    v.pop();
+    assert_eq!(v.into_inner(), Ok([1, 2]));
+  }
+
+  #[test]
+  fn compact_vec_from_slice() {
+    let words = ["a".to_owned(), "b".to_owned()];
+    let v: CompactVec<String, 4> = words.as_slice().into();
+    assert!(v.is_inline());
+    assert_eq!(v, words);
+
+    let v: CompactVec<u8, 2> = CompactVec::from(&b"abc"[..]);
+    assert!(!v.is_inline());
+    assert_eq!(v, *b"abc");
+  }
+
+  #[test]
+  fn compact_vec_into_iter() {
+    let v: CompactVec<u8, 4> = [1, 2, 3].into_iter().collect();
+    let mut it = v.into_iter();
+    assert_eq!(it.next_back(), Some(3));
+    assert_eq!(it.collect::<Vec<_>>(), [1, 2]);
+
+    let rc = Rc::new(());
+    let v: CompactVec<Rc<()>, 4> = (0..3).map(|_| rc.clone()).collect();
+    let mut it = v.into_iter();
+    it.next();
+    drop(it);
+    assert_eq!(Rc::strong_count(&rc), 1);
+  }
+
+  #[test]
+  #[cfg(feature = "embedded-io")]
+  fn compact_vec_embedded_io() {
+    use embedded_io::Read;
+    use embedded_io::Write;
+
+    let mut v: CompactVec<u8, 4> = CompactVec::new();
+    v.write_all(b"abc").unwrap();
+    assert!(v.is_inline());
+    v.write_all(b"defg").unwrap();
+    assert_eq!(v, *b"abcdefg");
+
+    let mut buf = [0u8; 5];
+    assert_eq!(v.read(&mut buf).unwrap(), 5);
+    assert_eq!(&buf, b"abcde");
+    assert_eq!(v.read(&mut buf).unwrap(), 2);
+    assert_eq!(&buf[..2], b"fg");
+    assert_eq!(v.read(&mut buf).unwrap(), 0);
+  }
+
+  #[test]
+  #[cfg(feature = "zeroize")]
+  fn compact_vec_zeroize() {
+    use zeroize::Zeroize;
+
+    let mut v: CompactVec<u8, 4> = [1, 2, 3].into();
+    v.zeroize();
+    assert!(v.is_empty() && v.is_inline());
+    let spare = v.spare_capacity_mut();
+    assert!(spare.iter().all(|b| unsafe { b.assume_init() } == 0));
+
+    let mut v: CompactVec<u8, 2> = [1, 2, 3].into();
+    v.zeroize();
+    assert!(v.is_empty() && !v.is_inline());
+    let spare = v.spare_capacity_mut();
+    assert!(spare.iter().all(|b| unsafe { b.assume_init() } == 0));
+  }
+
+  #[test]
+  fn compact_vec_try_into_array() {
+    let rc = Rc::new(());
+    let v: CompactVec<Rc<()>, 1> = (0..3).map(|_| rc.clone()).collect();
+    let array: [Rc<()>; 3] = v.try_into().unwrap();
+    assert_eq!(Rc::strong_count(&rc), 4);
+    drop(array);
+    assert_eq!(Rc::strong_count(&rc), 1);
+
+    let v: CompactVec<u8, 4> = CompactVec::from([1, 2]);
+    let v = <[u8; 4]>::try_from(v).unwrap_err();
+    assert_eq!(v, [1, 2]);
+  }
+
+  #[test]
+  fn compact_vec_insert_many() {
+    let mut v: CompactVec<u8, 4> = CompactVec::from([1, 6]);
+    v.insert_from_slice(1, &[2, 3]);
+    assert!(v.is_inline());
+    v.insert_many(3, [4, 5]);
+    assert_eq!(v, [1, 2, 3, 4, 5, 6]);
+    v.insert_many(6, 7..9);
+    v.insert_from_slice(0, &[]);
+    assert_eq!(v, [1, 2, 3, 4, 5, 6, 7, 8]);
+  }
+
+  #[test]
+  #[should_panic]
+  fn compact_vec_insert_many_out_of_bounds() {
+    let mut v: CompactVec<u8, 4> = CompactVec::from([1]);
+    v.insert_many(2, [2]);
+  }
+
+  #[test]
+  fn compact_vec_remove_indices() {
+    let mut v: CompactVec<u8, 4> = (0..10).collect();
+    let removed = v.remove_indices(&[0, 1, 5, 9]);
+    assert_eq!(removed, [0, 1, 5, 9]);
+    assert!(removed.is_inline());
+    assert_eq!(v, [2, 3, 4, 6, 7, 8]);
+    assert!(v.remove_indices(&[]).is_empty());
+    assert_eq!(v.len(), 6);
+  }
+
+  #[test]
+  #[should_panic]
+  fn compact_vec_remove_indices_unsorted() {
+    let mut v: CompactVec<u8, 4> = CompactVec::from([1, 2, 3]);
+    v.remove_indices(&[2, 1]);
+  }
+
+  #[test]
+  fn compact_vec_debug() {
+    let mut v: CompactVec<u8, 2> = CompactVec::from([1]);
+    assert_eq!(format!("{v:?}"), "[1]");
+    let pretty = format!("{v:#?}");
+    assert!(pretty.starts_with("CompactVec {\n    storage: inline,\n"));
+    assert!(pretty.contains("len: 1,\n    capacity: 2,"));
+    v.extend([2, 3]);
+    assert!(format!("{v:#?}").contains("storage: spilled,"));
+  }
+
+  #[test]
+  fn compact_vec_reader() {
+    let mut v: CompactVec<u8, 8> = CompactVec::from(*b"abcdef");
+    let ptr = v.as_ptr();
+    {
+      let mut reader = v.reader();
+      let mut buf = [0; 4];
+      assert_eq!(reader.read_into(&mut buf[..2]), 2);
+      assert_eq!(&buf[..2], b"ab");
+      assert_eq!(reader.remaining(), b"cdef");
+      reader.consume(1);
+      assert_eq!(reader.read_into(&mut buf), 3);
+      assert_eq!(reader.position(), 6);
+      assert_eq!(reader.read_into(&mut buf), 0);
+    }
+    assert!(v.is_empty());
+    assert_eq!(v.as_ptr(), ptr);
+
+    v.extend_from_slice(b"xyz");
+    v.reader().consume(1);
+    assert_eq!(v, *b"yz");
+
+    let mut reader = v.reader();
+    reader.consume(1);
+    reader.consume(usize::MAX);
+    assert_eq!(reader.position(), 2);
+    drop(reader);
+    assert!(v.is_empty());
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn compact_vec_reader_std_io() {
+    use std::io::BufRead;
+
+    let mut v: CompactVec<u8, 4> = CompactVec::from(*b"one\ntwo\n");
+    let mut line = String::new();
+    v.reader().read_line(&mut line).unwrap();
+    assert_eq!(line, "one\n");
+    assert_eq!(v, *b"two\n");
+  }
+
+  #[test]
+  #[cfg(feature = "bytemuck")]
+  fn compact_vec_bytemuck() {
+    let mut v: CompactVec<u16, 2> = CompactVec::from([0x0101, 0x0202]);
+    assert_eq!(v.as_bytes(), [1, 1, 2, 2]);
+    v.as_bytes_mut()[0] = 3;
+    assert_eq!(v[0], u16::from_ne_bytes([3, 1]));
+
+    let bytes = [0u8, 1, 2, 3, 4, 5, 6, 7];
+    // deliberately misaligned input.
+    let v = CompactVec::<u16, 2>::from_byte_slice(&bytes[1..7]).unwrap();
+    assert!(v.is_spilled());
+    assert_eq!(v.as_bytes(), &bytes[1..7]);
+    assert!(CompactVec::<u16, 2>::from_byte_slice(&bytes[..3]).is_err());
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn compact_vec_serde_roundtrip() {
+    let v: CompactVec<u8, 2> = [1, 2, 3].into_iter().collect();
+    let json = serde_json::to_string(&v).unwrap();
+    assert_eq!(json, "[1,2,3]");
+    let back: CompactVec<u8, 2> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, v);
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn compact_vec_serde_fixed() {
+    let v: CompactVec<u8, 3> = CompactVec::from([1, 2, 3]);
+    let mut json = Vec::new();
+    let mut ser = serde_json::Serializer::new(&mut json);
+    serde_fixed::serialize(&v, &mut ser).unwrap();
+    assert_eq!(json, b"[1,2,3]");
+
+    let mut de = serde_json::Deserializer::from_slice(&json);
+    let back: CompactVec<u8, 3> = serde_fixed::deserialize(&mut de).unwrap();
+    assert_eq!(back, v);
+
+    let short: CompactVec<u8, 3> = CompactVec::from([1, 2]);
+    let mut ser = serde_json::Serializer::new(Vec::new());
+    assert!(serde_fixed::serialize(&short, &mut ser).is_err());
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn compact_vec_serde_in_place_reuses_heap() {
+    use serde::Deserialize;
+
+    let mut place: CompactVec<u8, 2> = CompactVec::new();
+    place.reserve(16);
+    place.extend([9, 9, 9]);
+    let ptr = place.as_ptr();
+    let mut de = serde_json::Deserializer::from_str("[1,2,3,4]");
+    CompactVec::deserialize_in_place(&mut de, &mut place).unwrap();
+    assert_eq!(place, [1, 2, 3, 4]);
+    assert_eq!(place.as_ptr(), ptr);
+  }
+}
@@ -0,0 +1,657 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::mem::ManuallyDrop;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+use core::ops::DerefMut;
+use core::ptr;
+use core::ptr::NonNull;
+use core::slice;
+
+/// A variant of [`CompactVec`](crate::CompactVec) that stores its length and
+/// capacity as `u32`, and keeps only a thin pointer to its heap buffer.
+///
+/// Where a `CompactVec` embeds a full `Vec` (three words) alongside its
+/// length, a `CompactVec32` packs its length and capacity into a single word
+/// and shares the rest of its space between the inline buffer and a bare
+/// heap pointer. On 64-bit targets that makes it 16 bytes smaller for small
+/// `N`, which adds up when storing very large numbers of tiny vectors.
+///
+/// The trade-off is that a `CompactVec32` can hold at most `u32::MAX`
+/// elements; exceeding that panics with a capacity overflow.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::CompactVec32;
+///
+/// let mut v: CompactVec32<u8, 8> = CompactVec32::new();
+/// v.extend([1, 2, 3]);
+/// assert!(v.is_inline());
+///
+/// v.extend(4..=10);
+/// assert!(v.is_spilled());
+/// assert_eq!(v.len(), 10);
+///
+/// # #[cfg(target_pointer_width = "64")]
+/// assert_eq!(size_of::<CompactVec32<u8, 8>>(), 16);
+/// ```
+pub struct CompactVec32<T, const N: usize> {
+  /// The number of elements, whether inline or on the heap.
+  len:  u32,
+  /// The capacity of the heap buffer, or `0` while stored inline.
+  cap:  u32,
+  data: RawData<T, N>,
+}
+
+/// The storage of a [`CompactVec32`]: either the inline buffer, or a pointer
+/// to the heap buffer (whose length and capacity live in the struct itself).
+union RawData<T, const N: usize> {
+  inline: ManuallyDrop<[MaybeUninit<T>; N]>,
+  heap:   NonNull<T>,
+}
+
+/// A temporary `Vec` view of the heap buffer of a [`CompactVec32`], which
+/// writes its (possibly reallocated) pointer, length, and capacity back into
+/// the owner when dropped, including while unwinding.
+struct HeapGuard<'a, T, const N: usize> {
+  vec:   ManuallyDrop<Vec<T>>,
+  owner: &'a mut CompactVec32<T, N>,
+}
+
+impl<T, const N: usize> Deref for HeapGuard<'_, T, N> {
+  type Target = Vec<T>;
+
+  #[inline(always)]
+  fn deref(&self) -> &Vec<T> {
+    &self.vec
+  }
+}
+
+impl<T, const N: usize> DerefMut for HeapGuard<'_, T, N> {
+  #[inline(always)]
+  fn deref_mut(&mut self) -> &mut Vec<T> {
+    &mut self.vec
+  }
+}
+
+impl<T, const N: usize> Drop for HeapGuard<'_, T, N> {
+  #[inline]
+  fn drop(&mut self) {
+    let vec = unsafe { ManuallyDrop::take(&mut self.vec) };
+    self.owner.store_heap(vec);
+  }
+}
+
+/// Converts a length or capacity to `u32`, panicking if it doesn't fit.
+#[inline(always)]
+fn to_u32(n: usize) -> u32 {
+  u32::try_from(n).expect("capacity overflow")
+}
+
+impl<T, const N: usize> CompactVec32<T, N> {
+  /// Creates a new, empty `CompactVec32` using inline storage.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      len:  0,
+      cap:  0,
+      data: RawData {
+        inline: ManuallyDrop::new([const { MaybeUninit::uninit() }; N]),
+      },
+    }
+  }
+
+  /// Returns the number of elements in the vector.
+  #[inline(always)]
+  pub const fn len(&self) -> usize {
+    self.len as usize
+  }
+
+  /// Returns `true` if the vector contains no elements.
+  #[inline(always)]
+  pub const fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns the number of elements the vector can hold without spilling to
+  /// the heap (when inline) or reallocating (when spilled).
+  #[inline]
+  pub const fn capacity(&self) -> usize {
+    if self.is_inline() {
+      N
+    } else {
+      self.cap as usize
+    }
+  }
+
+  /// Returns `true` if the elements are currently stored inline.
+  #[inline(always)]
+  pub const fn is_inline(&self) -> bool {
+    self.cap == 0
+  }
+
+  /// Returns `true` if the elements have spilled over to the heap.
+  #[inline(always)]
+  pub const fn is_spilled(&self) -> bool {
+    self.cap != 0
+  }
+
+  /// Returns a slice containing the entire vector.
+  #[inline]
+  pub fn as_slice(&self) -> &[T] {
+    unsafe { slice::from_raw_parts(self.as_ptr(), self.len()) }
+  }
+
+  /// Returns a mutable slice containing the entire vector.
+  #[inline]
+  pub fn as_mut_slice(&mut self) -> &mut [T] {
+    let len = self.len();
+    unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), len) }
+  }
+
+  /// Returns a raw pointer to the vector's buffer, inline or on the heap.
+  #[inline]
+  pub fn as_ptr(&self) -> *const T {
+    if self.is_inline() {
+      (&raw const self.data.inline).cast::<T>()
+    } else {
+      unsafe { self.data.heap.as_ptr() }
+    }
+  }
+
+  /// Returns a raw mutable pointer to the vector's buffer, inline or on the
+  /// heap.
+  #[inline]
+  pub fn as_mut_ptr(&mut self) -> *mut T {
+    if self.is_inline() {
+      (&raw mut self.data.inline).cast::<T>()
+    } else {
+      unsafe { self.data.heap.as_ptr() }
+    }
+  }
+
+  /// Appends an element to the back of the vector, spilling to the heap if
+  /// the inline capacity is exhausted.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the new length exceeds `u32::MAX`.
+  #[inline]
+  pub fn push(&mut self, value: T) {
+    let len = self.len();
+    if len == self.capacity() {
+      self.reserve(1);
+    }
+    unsafe { self.as_mut_ptr().add(len).write(value) };
+    self.len += 1;
+  }
+
+  /// Removes the last element from the vector and returns it, or `None` if it
+  /// is empty.
+  #[inline]
+  pub fn pop(&mut self) -> Option<T> {
+    if self.len == 0 {
+      return None;
+    }
+    self.len -= 1;
+    let len = self.len();
+    Some(unsafe { self.as_mut_ptr().add(len).read() })
+  }
+
+  /// Inserts an element at position `index`, shifting all elements after it
+  /// to the right.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `index > len`, or if the new length exceeds `u32::MAX`.
+  pub fn insert(&mut self, index: usize, value: T) {
+    let len = self.len();
+    assert!(
+      index <= len,
+      "insertion index (is {index}) should be <= len (is {len})"
+    );
+    if len == self.capacity() {
+      self.reserve(1);
+    }
+    unsafe {
+      let p = self.as_mut_ptr().add(index);
+      ptr::copy(p, p.add(1), len - index);
+      ptr::write(p, value);
+    }
+    self.len += 1;
+  }
+
+  /// Removes and returns the element at position `index`, shifting all
+  /// elements after it to the left.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `index` is out of bounds.
+  pub fn remove(&mut self, index: usize) -> T {
+    let len = self.len();
+    assert!(
+      index < len,
+      "removal index (is {index}) should be < len (is {len})"
+    );
+    unsafe {
+      let p = self.as_mut_ptr().add(index);
+      let value = ptr::read(p);
+      ptr::copy(p.add(1), p, len - index - 1);
+      self.len -= 1;
+      value
+    }
+  }
+
+  /// Shortens the vector, keeping the first `len` elements and dropping the
+  /// rest. Has no effect if `len` is greater than the current length.
+  ///
+  /// Like [`CompactVec::truncate`](crate::CompactVec::truncate), this never
+  /// moves spilled elements back inline.
+  pub fn truncate(&mut self, len: usize) {
+    let old_len = self.len();
+    if len >= old_len {
+      return;
+    }
+    unsafe {
+      // set the length first, so a panicking destructor can't cause the
+      // remaining elements to be dropped twice.
+      self.len = len as u32;
+      let tail = self.as_mut_ptr().add(len);
+      ptr::drop_in_place(ptr::slice_from_raw_parts_mut(tail, old_len - len));
+    }
+  }
+
+  /// Removes all elements, releasing any heap allocation and returning the
+  /// vector to inline storage.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.truncate(0);
+    drop(self.take_heap());
+  }
+
+  /// Reserves capacity for at least `additional` more elements, spilling to
+  /// the heap if needed. Heap buffers grow by doubling, as with `Vec`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the new capacity exceeds `u32::MAX`.
+  pub fn reserve(&mut self, additional: usize) {
+    let len = self.len();
+    let required = len.checked_add(additional).expect("capacity overflow");
+    if required <= self.capacity() {
+      return;
+    }
+    assert!(required <= u32::MAX as usize, "capacity overflow");
+    let capacity = self.capacity().saturating_mul(2).max(required);
+    let capacity = capacity.min(u32::MAX as usize);
+    if let Some(mut heap) = self.heap_mut() {
+      heap.reserve_exact(capacity - len);
+    } else {
+      self.spill(capacity);
+    }
+  }
+
+  /// Shrinks the capacity of the vector as much as possible, moving its
+  /// elements back inline if they fit.
+  pub fn shrink_to_fit(&mut self) {
+    if self.len() <= N {
+      if let Some(mut heap) = self.take_heap() {
+        let len = heap.len();
+        unsafe {
+          heap.set_len(0);
+          ptr::copy_nonoverlapping(heap.as_ptr(), self.as_mut_ptr(), len);
+        }
+        self.len = len as u32;
+      }
+    } else if let Some(mut heap) = self.heap_mut() {
+      heap.shrink_to_fit();
+    }
+  }
+
+  /// Converts the vector into a `Vec<T>`, reusing the heap allocation if it
+  /// has spilled.
+  pub fn into_vec(self) -> Vec<T> {
+    let mut this = ManuallyDrop::new(self);
+    match this.take_heap() {
+      Some(heap) => heap,
+      None => {
+        let len = this.len();
+        let mut vec = Vec::with_capacity(len);
+        unsafe {
+          ptr::copy_nonoverlapping(this.as_ptr(), vec.as_mut_ptr(), len);
+          vec.set_len(len);
+        }
+        vec
+      }
+    }
+  }
+
+  /// Returns a guard giving `Vec` access to the heap buffer, if spilled.
+  #[inline]
+  fn heap_mut(&mut self) -> Option<HeapGuard<'_, T, N>> {
+    let vec = self.take_heap()?;
+    Some(HeapGuard {
+      vec:   ManuallyDrop::new(vec),
+      owner: self,
+    })
+  }
+
+  /// Takes ownership of the heap buffer as a `Vec`, leaving the vector empty
+  /// and inline. Returns `None` if the vector is stored inline.
+  #[inline]
+  fn take_heap(&mut self) -> Option<Vec<T>> {
+    if self.is_inline() {
+      return None;
+    }
+    let (len, cap) = (self.len(), self.cap as usize);
+    // zero-sized types report a capacity of `usize::MAX`, which is clamped
+    // when stored, but `from_raw_parts` ignores it for them anyway.
+    let vec = unsafe { Vec::from_raw_parts(self.data.heap.as_ptr(), len, cap) };
+    self.len = 0;
+    self.cap = 0;
+    Some(vec)
+  }
+
+  /// Switches to heap storage backed by the given `Vec`. The caller must
+  /// ensure that the vector is currently empty and inline.
+  fn store_heap(&mut self, vec: Vec<T>) {
+    debug_assert!(self.is_inline() && self.is_empty());
+    if vec.capacity() == 0 {
+      // an unallocated `Vec` can't be told apart from inline storage.
+      return;
+    }
+    let cap = match size_of::<T>() {
+      0 => u32::MAX,
+      _ => to_u32(vec.capacity()),
+    };
+    let len = to_u32(vec.len());
+    let mut vec = ManuallyDrop::new(vec);
+    self.data.heap = unsafe { NonNull::new_unchecked(vec.as_mut_ptr()) };
+    self.len = len;
+    self.cap = cap;
+  }
+
+  /// Moves the inline elements into a newly allocated heap buffer with room
+  /// for `capacity` elements.
+  fn spill(&mut self, capacity: usize) {
+    let len = self.len();
+    let mut vec = Vec::with_capacity(capacity);
+    unsafe {
+      ptr::copy_nonoverlapping(self.as_ptr(), vec.as_mut_ptr(), len);
+      vec.set_len(len);
+    }
+    self.len = 0;
+    self.store_heap(vec);
+  }
+}
+
+impl<T, const N: usize> Drop for CompactVec32<T, N> {
+  fn drop(&mut self) {
+    match self.take_heap() {
+      Some(heap) => drop(heap),
+      None => self.truncate(0),
+    }
+  }
+}
+
+impl<T, const N: usize> Default for CompactVec32<T, N> {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: Clone, const N: usize> Clone for CompactVec32<T, N> {
+  fn clone(&self) -> Self {
+    self.iter().cloned().collect()
+  }
+}
+
+impl<T: Debug, const N: usize> Debug for CompactVec32<T, N> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_list().entries(self.iter()).finish()
+  }
+}
+
+impl<T, const N: usize> Deref for CompactVec32<T, N> {
+  type Target = [T];
+
+  #[inline(always)]
+  fn deref(&self) -> &[T] {
+    self.as_slice()
+  }
+}
+
+impl<T, const N: usize> DerefMut for CompactVec32<T, N> {
+  #[inline(always)]
+  fn deref_mut(&mut self) -> &mut [T] {
+    self.as_mut_slice()
+  }
+}
+
+impl<T, const N: usize> AsRef<[T]> for CompactVec32<T, N> {
+  #[inline(always)]
+  fn as_ref(&self) -> &[T] {
+    self.as_slice()
+  }
+}
+
+impl<T, const N: usize> AsMut<[T]> for CompactVec32<T, N> {
+  #[inline(always)]
+  fn as_mut(&mut self) -> &mut [T] {
+    self.as_mut_slice()
+  }
+}
+
+impl<T: Hash, const N: usize> Hash for CompactVec32<T, N> {
+  #[inline]
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.as_slice().hash(state);
+  }
+}
+
+impl<T: PartialEq, const N: usize, const M: usize> PartialEq<CompactVec32<T, M>>
+  for CompactVec32<T, N>
+{
+  #[inline]
+  fn eq(&self, other: &CompactVec32<T, M>) -> bool {
+    self.as_slice() == other.as_slice()
+  }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq<[T]> for CompactVec32<T, N> {
+  #[inline]
+  fn eq(&self, other: &[T]) -> bool {
+    self.as_slice() == other
+  }
+}
+
+impl<T: PartialEq, const N: usize, const M: usize> PartialEq<[T; M]>
+  for CompactVec32<T, N>
+{
+  #[inline]
+  fn eq(&self, other: &[T; M]) -> bool {
+    self.as_slice() == other
+  }
+}
+
+impl<T: Eq, const N: usize> Eq for CompactVec32<T, N> {}
+
+impl<T: PartialOrd, const N: usize> PartialOrd for CompactVec32<T, N> {
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    self.as_slice().partial_cmp(other.as_slice())
+  }
+}
+
+impl<T: Ord, const N: usize> Ord for CompactVec32<T, N> {
+  #[inline]
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.as_slice().cmp(other.as_slice())
+  }
+}
+
+impl<T, const N: usize> From<Vec<T>> for CompactVec32<T, N> {
+  /// Converts a `Vec` into a `CompactVec32`, adopting its heap allocation
+  /// unless its elements fit inline.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the capacity of `vec` exceeds `u32::MAX`.
+  fn from(vec: Vec<T>) -> Self {
+    let mut this = Self::new();
+    this.store_heap(vec);
+    if this.len() <= N {
+      this.shrink_to_fit();
+    }
+    this
+  }
+}
+
+impl<T, const N: usize> From<CompactVec32<T, N>> for Vec<T> {
+  #[inline(always)]
+  fn from(vec: CompactVec32<T, N>) -> Self {
+    vec.into_vec()
+  }
+}
+
+impl<T, const N: usize, const M: usize> From<[T; M]> for CompactVec32<T, N> {
+  #[inline]
+  fn from(array: [T; M]) -> Self {
+    let mut vec = Self::new();
+    vec.reserve(M);
+    vec.extend(array);
+    vec
+  }
+}
+
+impl<T, const N: usize> Extend<T> for CompactVec32<T, N> {
+  #[inline]
+  fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+    let iter = iter.into_iter();
+    let (lower, _) = iter.size_hint();
+    if lower > self.capacity() - self.len() {
+      self.reserve(lower);
+    }
+    for value in iter {
+      self.push(value);
+    }
+  }
+}
+
+impl<T, const N: usize> FromIterator<T> for CompactVec32<T, N> {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    let mut vec = Self::new();
+    vec.extend(iter);
+    vec
+  }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a CompactVec32<T, N> {
+  type Item = &'a T;
+  type IntoIter = slice::Iter<'a, T>;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut CompactVec32<T, N> {
+  type Item = &'a mut T;
+  type IntoIter = slice::IterMut<'a, T>;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter_mut()
+  }
+}
+
+impl<T, const N: usize> IntoIterator for CompactVec32<T, N> {
+  type Item = T;
+  type IntoIter = alloc::vec::IntoIter<T>;
+
+  /// Converts the vector into an owning iterator, by way of
+  /// [`into_vec`](CompactVec32::into_vec).
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    self.into_vec().into_iter()
+  }
+}
+
+unsafe impl<T: Send, const N: usize> Send for CompactVec32<T, N> {}
+unsafe impl<T: Sync, const N: usize> Sync for CompactVec32<T, N> {}
+
+#[cfg(test)]
+mod tests {
+  use std::rc::Rc;
+
+  use super::*;
+
+  #[test]
+  fn compact_vec32_size() {
+    let word = size_of::<usize>();
+    assert_eq!(size_of::<CompactVec32<u8, 8>>(), 8.max(word) + 8);
+    assert!(
+      size_of::<CompactVec32<u8, 8>>() < size_of::<crate::CompactVec<u8, 8>>()
+    );
+  }
+
+  #[test]
+  fn compact_vec32_push_pop_spill() {
+    let mut v: CompactVec32<u32, 2> = CompactVec32::new();
+    v.push(1);
+    v.push(2);
+    assert!(v.is_inline());
+    v.push(3);
+    assert!(v.is_spilled());
+    assert_eq!(v, [1, 2, 3]);
+    assert!(v.capacity() >= 3);
+    assert_eq!(v.pop(), Some(3));
+    v.insert(0, 0);
+    assert_eq!(v.remove(1), 1);
+    assert_eq!(v, [0, 2]);
+    v.shrink_to_fit();
+    assert!(v.is_inline());
+    assert_eq!(v, [0, 2]);
+  }
+
+  #[test]
+  fn compact_vec32_drops_elements() {
+    let rc = Rc::new(());
+    let mut v: CompactVec32<Rc<()>, 2> = CompactVec32::new();
+    v.extend((0..5).map(|_| rc.clone()));
+    assert_eq!(Rc::strong_count(&rc), 6);
+    v.truncate(1);
+    assert_eq!(Rc::strong_count(&rc), 2);
+    drop(v);
+    assert_eq!(Rc::strong_count(&rc), 1);
+  }
+
+  #[test]
+  fn compact_vec32_vec_roundtrip() {
+    let v: CompactVec32<u8, 2> = Vec::from([1, 2, 3]).into();
+    assert!(v.is_spilled());
+    let ptr = v.as_ptr();
+    let vec = v.into_vec();
+    assert_eq!(vec.as_ptr(), ptr);
+    let v: CompactVec32<u8, 4> = vec.into();
+    assert!(v.is_inline());
+    assert_eq!(v.into_iter().collect::<Vec<_>>(), [1, 2, 3]);
+  }
+
+  #[test]
+  fn compact_vec32_zero_sized() {
+    let mut v: CompactVec32<(), 1> = CompactVec32::new();
+    v.extend([(); 10]);
+    assert_eq!(v.len(), 10);
+    v.clear();
+    assert!(v.is_inline() && v.is_empty());
+  }
+}
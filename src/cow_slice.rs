@@ -0,0 +1,459 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::ops::Bound;
+use core::ops::Deref;
+use core::ops::RangeBounds;
+
+use crate::CapacityError;
+use crate::InlineVec;
+
+/// Copy-on-write slice that can be borrowed, inlined, or owned: the
+/// [`CowStr`](crate::CowStr) idea for arbitrary element types.
+///
+/// # Variants
+///
+/// 1. [`Borrowed`](CowSlice::Borrowed): a slice borrowed for the lifetime `'a`,
+///    such as a field list pointing into an input buffer.
+/// 2. [`Inlined`](CowSlice::Inlined): up to `N` elements stored inline in an
+///    [`InlineVec`]. Like `InlineVec`, this variant is only available for
+///    element types that don't need to be dropped, so the constructors that
+///    produce it require `T: Copy`.
+/// 3. [`Owned`](CowSlice::Owned): a boxed slice that owns its elements.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::CowSlice;
+///
+/// let input = [3u16, 1, 4, 1, 5];
+/// let mut fields: CowSlice<u16, 4> = CowSlice::from(&input[..]);
+/// assert!(fields.is_borrowed());
+/// assert_eq!(fields.slice(1..3), [1, 4]);
+///
+/// fields.to_mut()[0] = 9;
+/// assert!(fields.is_owned());
+/// assert_eq!(fields, [9, 1, 4, 1, 5]);
+/// assert_eq!(input[0], 3);
+///
+/// let small = CowSlice::<u16, 4>::try_inline(&[1, 2]).unwrap();
+/// assert!(small.is_inlined());
+/// ```
+#[cfg_attr(feature = "is_variant", derive(derive_more::IsVariant))]
+pub enum CowSlice<'a, T, const N: usize> {
+  /// A borrowed slice that does not own its elements.
+  Borrowed(&'a [T]),
+  /// Up to `N` elements stored inline, without a heap allocation.
+  Inlined(InlineVec<T, N>),
+  /// A boxed slice that owns its elements.
+  Owned(Box<[T]>),
+}
+
+impl<'a, T, const N: usize> CowSlice<'a, T, N> {
+  /// Returns the elements as a slice.
+  #[inline(always)]
+  pub fn as_slice(&self) -> &[T] {
+    match self {
+      CowSlice::Borrowed(s) => s,
+      CowSlice::Inlined(v) => v,
+      CowSlice::Owned(b) => b,
+    }
+  }
+
+  /// Returns the number of elements.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.as_slice().len()
+  }
+
+  /// Returns `true` if there are no elements.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.as_slice().is_empty()
+  }
+
+  /// Returns a borrowed `CowSlice` of the elements in `range`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the range is out of bounds.
+  #[inline]
+  pub fn slice(&self, range: impl RangeBounds<usize>) -> CowSlice<'_, T, N> {
+    let bounds: (Bound<usize>, Bound<usize>) =
+      (range.start_bound().cloned(), range.end_bound().cloned());
+    CowSlice::Borrowed(&self.as_slice()[bounds])
+  }
+
+  /// Returns a mutable slice of the elements, cloning borrowed elements into
+  /// an owned slice first.
+  #[inline]
+  pub fn to_mut(&mut self) -> &mut [T]
+  where
+    T: Clone,
+  {
+    if let CowSlice::Borrowed(s) = *self {
+      *self = CowSlice::Owned(s.into());
+    }
+    match self {
+      CowSlice::Borrowed(_) => unreachable!(),
+      CowSlice::Inlined(v) => v,
+      CowSlice::Owned(b) => b,
+    }
+  }
+
+  /// Converts the `CowSlice` into a `Vec`, cloning the elements unless they
+  /// are already owned.
+  #[inline]
+  pub fn into_vec(self) -> Vec<T>
+  where
+    T: Clone,
+  {
+    match self {
+      CowSlice::Owned(b) => b.into_vec(),
+      other => other.as_slice().to_vec(),
+    }
+  }
+
+  /// Converts the `CowSlice` into one that owns its elements, inlining them
+  /// if there are at most `N`.
+  #[inline]
+  pub fn into_static(self) -> CowSlice<'static, T, N>
+  where
+    T: Copy + 'static,
+  {
+    match self {
+      CowSlice::Owned(b) => CowSlice::Owned(b),
+      CowSlice::Inlined(v) => CowSlice::Inlined(v),
+      CowSlice::Borrowed(s) => {
+        CowSlice::try_inline(s).unwrap_or_else(|_| CowSlice::Owned(s.into()))
+      }
+    }
+  }
+}
+
+impl<T: Copy, const N: usize> CowSlice<'_, T, N> {
+  /// Attempts to create an inline `CowSlice` holding a copy of `slice`.
+  ///
+  /// Returns an error if there are more than `N` elements.
+  #[inline]
+  pub fn try_inline<'a>(
+    slice: &[T],
+  ) -> Result<CowSlice<'a, T, N>, CapacityError> {
+    InlineVec::try_from(slice).map(CowSlice::Inlined)
+  }
+}
+
+#[cfg(not(feature = "is_variant"))]
+impl<T, const N: usize> CowSlice<'_, T, N> {
+  /// Returns `true` if the `CowSlice` is the `Borrowed` variant.
+  #[inline(always)]
+  pub const fn is_borrowed(&self) -> bool {
+    matches!(self, CowSlice::Borrowed(_))
+  }
+
+  /// Returns `true` if the `CowSlice` is the `Inlined` variant.
+  #[inline(always)]
+  pub const fn is_inlined(&self) -> bool {
+    matches!(self, CowSlice::Inlined(_))
+  }
+
+  /// Returns `true` if the `CowSlice` is the `Owned` variant.
+  #[inline(always)]
+  pub const fn is_owned(&self) -> bool {
+    matches!(self, CowSlice::Owned(_))
+  }
+}
+
+impl<T: Clone, const N: usize> Clone for CowSlice<'_, T, N> {
+  #[inline]
+  fn clone(&self) -> Self {
+    match self {
+      CowSlice::Borrowed(s) => CowSlice::Borrowed(s),
+      CowSlice::Inlined(v) => CowSlice::Inlined(v.clone()),
+      CowSlice::Owned(b) => CowSlice::Owned(b.clone()),
+    }
+  }
+}
+
+impl<T, const N: usize> Default for CowSlice<'_, T, N> {
+  #[inline(always)]
+  fn default() -> Self {
+    CowSlice::Borrowed(&[])
+  }
+}
+
+impl<T, const N: usize> Deref for CowSlice<'_, T, N> {
+  type Target = [T];
+
+  #[inline(always)]
+  fn deref(&self) -> &[T] {
+    self.as_slice()
+  }
+}
+
+impl<T, const N: usize> AsRef<[T]> for CowSlice<'_, T, N> {
+  #[inline(always)]
+  fn as_ref(&self) -> &[T] {
+    self.as_slice()
+  }
+}
+
+impl<T, const N: usize> Borrow<[T]> for CowSlice<'_, T, N> {
+  #[inline(always)]
+  fn borrow(&self) -> &[T] {
+    self.as_slice()
+  }
+}
+
+impl<T: Debug, const N: usize> Debug for CowSlice<'_, T, N> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Debug::fmt(self.as_slice(), f)
+  }
+}
+
+impl<T: Hash, const N: usize> Hash for CowSlice<'_, T, N> {
+  #[inline]
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.as_slice().hash(state);
+  }
+}
+
+impl<'b, T, const N: usize, const M: usize> PartialEq<CowSlice<'b, T, M>>
+  for CowSlice<'_, T, N>
+where
+  T: PartialEq,
+{
+  #[inline]
+  fn eq(&self, other: &CowSlice<'b, T, M>) -> bool {
+    self.as_slice() == other.as_slice()
+  }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq<[T]> for CowSlice<'_, T, N> {
+  #[inline]
+  fn eq(&self, other: &[T]) -> bool {
+    self.as_slice() == other
+  }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq<&[T]> for CowSlice<'_, T, N> {
+  #[inline]
+  fn eq(&self, other: &&[T]) -> bool {
+    self.as_slice() == *other
+  }
+}
+
+impl<T: PartialEq, const N: usize, const M: usize> PartialEq<[T; M]>
+  for CowSlice<'_, T, N>
+{
+  #[inline]
+  fn eq(&self, other: &[T; M]) -> bool {
+    self.as_slice() == other
+  }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq<Vec<T>> for CowSlice<'_, T, N> {
+  #[inline]
+  fn eq(&self, other: &Vec<T>) -> bool {
+    self.as_slice() == other.as_slice()
+  }
+}
+
+impl<T: Eq, const N: usize> Eq for CowSlice<'_, T, N> {}
+
+impl<T: PartialOrd, const N: usize> PartialOrd for CowSlice<'_, T, N> {
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    self.as_slice().partial_cmp(other.as_slice())
+  }
+}
+
+impl<T: Ord, const N: usize> Ord for CowSlice<'_, T, N> {
+  #[inline]
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.as_slice().cmp(other.as_slice())
+  }
+}
+
+impl<'a, T, const N: usize> From<&'a [T]> for CowSlice<'a, T, N> {
+  #[inline(always)]
+  fn from(slice: &'a [T]) -> Self {
+    CowSlice::Borrowed(slice)
+  }
+}
+
+impl<'a, T, const N: usize> From<&'a Vec<T>> for CowSlice<'a, T, N> {
+  #[inline(always)]
+  fn from(vec: &'a Vec<T>) -> Self {
+    CowSlice::Borrowed(vec)
+  }
+}
+
+impl<T, const N: usize> From<Vec<T>> for CowSlice<'_, T, N> {
+  #[inline(always)]
+  fn from(vec: Vec<T>) -> Self {
+    CowSlice::Owned(vec.into_boxed_slice())
+  }
+}
+
+impl<T, const N: usize> From<Box<[T]>> for CowSlice<'_, T, N> {
+  #[inline(always)]
+  fn from(boxed: Box<[T]>) -> Self {
+    CowSlice::Owned(boxed)
+  }
+}
+
+impl<'a, T: Clone, const N: usize> From<Cow<'a, [T]>> for CowSlice<'a, T, N> {
+  #[inline]
+  fn from(cow: Cow<'a, [T]>) -> Self {
+    match cow {
+      Cow::Borrowed(s) => CowSlice::Borrowed(s),
+      Cow::Owned(v) => CowSlice::from(v),
+    }
+  }
+}
+
+impl<T: Clone, const N: usize> From<CowSlice<'_, T, N>> for Vec<T> {
+  #[inline(always)]
+  fn from(slice: CowSlice<'_, T, N>) -> Self {
+    slice.into_vec()
+  }
+}
+
+impl<'s, T, const N: usize> IntoIterator for &'s CowSlice<'_, T, N> {
+  type IntoIter = core::slice::Iter<'s, T>;
+  type Item = &'s T;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.as_slice().iter()
+  }
+}
+
+impl<T, const N: usize> FromIterator<T> for CowSlice<'_, T, N> {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    CowSlice::Owned(iter.into_iter().collect())
+  }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use core::marker::PhantomData;
+
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+  use serde::de;
+
+  use super::*;
+
+  impl<T: Serialize, const N: usize> Serialize for CowSlice<'_, T, N> {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      serializer.collect_seq(self.as_slice())
+    }
+  }
+
+  struct CowSliceVisitor<'a, T, const N: usize>(
+    PhantomData<CowSlice<'a, T, N>>,
+  );
+
+  impl<'de, 'a, T, const N: usize> de::Visitor<'de> for CowSliceVisitor<'a, T, N>
+  where
+    T: Deserialize<'de>,
+  {
+    type Value = CowSlice<'a, T, N>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a sequence")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+      A: de::SeqAccess<'de>,
+    {
+      let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+      while let Some(value) = seq.next_element()? {
+        vec.push(value);
+      }
+      Ok(CowSlice::from(vec))
+    }
+  }
+
+  impl<'de, T, const N: usize> Deserialize<'de> for CowSlice<'_, T, N>
+  where
+    T: Deserialize<'de>,
+  {
+    /// Deserializes a sequence into an owned slice, since generic elements
+    /// can't be borrowed from the input.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      deserializer.deserialize_seq(CowSliceVisitor(PhantomData))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::string::String;
+  use alloc::vec;
+
+  use super::*;
+
+  #[test]
+  fn cow_slice_variants() {
+    let data = [1u32, 2, 3];
+    let borrowed: CowSlice<u32, 2> = CowSlice::from(&data[..]);
+    assert!(borrowed.is_borrowed());
+    assert!(CowSlice::<u32, 2>::try_inline(&data).is_err());
+    let owned = borrowed.clone().into_static();
+    assert!(owned.is_owned());
+    let inlined = CowSlice::<u32, 4>::from(&data[..2]).into_static();
+    assert!(inlined.is_inlined());
+    assert_eq!(inlined, [1, 2]);
+    assert_eq!(owned, borrowed);
+    assert_eq!(borrowed.slice(..=1), inlined);
+    assert!(CowSlice::<u32, 2>::default().is_empty());
+  }
+
+  #[test]
+  fn cow_slice_to_mut() {
+    let names = vec![String::from("a"), String::from("b")];
+    let mut slice: CowSlice<String, 2> = CowSlice::from(&names);
+    slice.to_mut()[1].push('!');
+    assert!(slice.is_owned());
+    assert_eq!(slice.into_vec(), ["a", "b!"]);
+    assert_eq!(names[1], "b");
+
+    let mut inline = CowSlice::<u8, 4>::try_inline(b"abc").unwrap();
+    inline.to_mut().reverse();
+    assert!(inline.is_inlined());
+    assert_eq!(inline, *b"cba");
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn cow_slice_serde() {
+    let slice: CowSlice<i32, 2> = CowSlice::from(&[-1, 0, 1][..]);
+    let json = serde_json::to_string(&slice).unwrap();
+    assert_eq!(json, "[-1,0,1]");
+    let back: CowSlice<i32, 2> = serde_json::from_str(&json).unwrap();
+    assert!(back.is_owned());
+    assert_eq!(back, slice);
+  }
+}
@@ -13,12 +13,14 @@ use core::fmt;
 use core::fmt::Display;
 use core::hash::Hash;
 use core::hash::Hasher;
+use core::mem;
 use core::mem::transmute_copy;
 use core::ops::Deref;
 use core::ops::DerefMut;
 use core::str;
 use core::str::FromStr;
 
+use crate::SmallString;
 use crate::inline_str::*;
 
 /// Copy-on-write string that can be owned, borrowed, or inlined.
@@ -147,21 +149,19 @@ impl<'i> CowStr<'i> {
   #[deprecated(since = "0.2.0", note = "use `into_string` instead")]
   #[inline(always)]
   pub fn into_owned(self) -> String {
-    match self {
-      CowStr::Owned(s) => s.into(),
-      CowStr::Borrowed(s) => s.to_owned(),
-      CowStr::Inlined(s) => s.deref().to_owned(),
-    }
+    self.into_string()
   }
 
   /// Converts the `CowStr` into an owned `String`, cloning the data if
   /// necessary.
   #[inline(always)]
-  pub fn into_string(self) -> String {
-    match self {
-      CowStr::Owned(b) => b.into(),
-      CowStr::Borrowed(b) => b.to_owned(),
-      CowStr::Inlined(s) => s.deref().to_owned(),
+  pub fn into_string(mut self) -> String {
+    // the box is taken out through a reference, since `CowStr` has a `Drop`
+    // impl with the `zeroize` feature, which rules out moving out of it.
+    match &mut self {
+      CowStr::Owned(b) => mem::take(b).into(),
+      CowStr::Borrowed(b) => (*b).to_owned(),
+      CowStr::Inlined(s) => s.as_str().to_owned(),
     }
   }
 }
@@ -388,9 +388,8 @@ impl<'i> From<CowStr<'i>> for Cow<'i, str> {
   #[inline(always)]
   fn from(s: CowStr<'i>) -> Self {
     match s {
-      CowStr::Owned(s) => Cow::Owned(s.to_string()),
-      CowStr::Inlined(s) => Cow::Owned(s.to_string()),
       CowStr::Borrowed(s) => Cow::Borrowed(s),
+      s => Cow::Owned(s.into_string()),
     }
   }
 }
@@ -478,6 +477,55 @@ impl CowStr<'_> {
   pub fn from_char(c: char) -> CowStr<'static> {
     CowStr::Inlined(c.into())
   }
+
+  /// Moves a string that was built up for a `CowStr` into one, inline if it
+  /// fits.
+  #[inline]
+  pub(crate) fn from_small(
+    s: SmallString<MAX_INLINE_STR_LEN>,
+  ) -> CowStr<'static> {
+    match InlineStr::try_from(s.as_str()) {
+      Ok(inline) => CowStr::Inlined(inline),
+      Err(_) => CowStr::Owned(s.into_boxed_str()),
+    }
+  }
+}
+
+/// Creates a [`CowStr`] from a string literal or constant, in a `const`
+/// context if need be.
+///
+/// A plain string becomes a [`Borrowed`](CowStr::Borrowed) `CowStr`. Prefix
+/// it with `inline` to store a copy in an [`Inlined`](CowStr::Inlined) one
+/// instead, which is checked at compile time to fit in
+/// [`MAX_INLINE_STR_LEN`] bytes, unlike the panicking [`CowStr::inline`].
+///
+/// # Example
+///
+/// ```rust
+/// use moos::CowStr;
+/// use moos::cowstr;
+///
+/// const NAME: CowStr<'static> = cowstr!("moos");
+/// assert!(NAME.is_borrowed());
+///
+/// let unit = cowstr!(inline "ms");
+/// assert!(unit.is_inlined());
+/// assert_eq!(unit, "ms");
+/// ```
+///
+/// An inline string that is too long fails to compile:
+///
+/// ```rust,compile_fail
+/// let s = moos::cowstr!(inline "This string is too long to fit in an InlineStr");
+/// ```
+#[macro_export]
+macro_rules! cowstr {
+  (inline $s:expr $(,)?) => {
+    $crate::CowStr::Inlined($crate::inline_str!($s))
+  };
+  ($s:expr $(,)?) => {
+    $crate::CowStr::Borrowed($s)
+  };
 }
 
 #[cfg(feature = "serde")]
@@ -546,6 +594,48 @@ mod serde_impl {
   }
 }
 
+#[cfg(feature = "zeroize")]
+mod zeroize_impl {
+  use zeroize::Zeroize;
+  use zeroize::ZeroizeOnDrop;
+
+  use super::*;
+
+  impl CowStr<'_> {
+    /// Wipes any owned or inlined string data in place. Borrowed data is not
+    /// owned by the `CowStr`, and is therefore left untouched.
+    fn wipe(&mut self) {
+      match self {
+        CowStr::Owned(s) => s.zeroize(),
+        CowStr::Inlined(s) => s.zeroize(),
+        CowStr::Borrowed(_) => {}
+      }
+    }
+  }
+
+  impl<'i> Zeroize for CowStr<'i> {
+    /// Wipes any owned or inlined string data, then resets the `CowStr` to
+    /// an empty borrowed string. Borrowed data is not owned by the `CowStr`,
+    /// and is therefore left untouched.
+    fn zeroize(&mut self) {
+      self.wipe();
+      *self = CowStr::Borrowed("");
+    }
+  }
+
+  /// Wipes any owned or inlined string data when the `CowStr` is dropped.
+  impl Drop for CowStr<'_> {
+    #[inline]
+    fn drop(&mut self) {
+      // unlike `zeroize`, this doesn't reset `self`, which would drop it
+      // again.
+      self.wipe();
+    }
+  }
+
+  impl ZeroizeOnDrop for CowStr<'_> {}
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -557,6 +647,15 @@ mod tests {
     assert_eq!(3 * word_size, size);
   }
 
+  #[test]
+  fn cowstr_macro() {
+    const EMPTY: CowStr<'static> = crate::cowstr!("");
+    assert!(EMPTY.is_borrowed() && EMPTY.is_empty());
+    let s = crate::cowstr!(inline "inlined");
+    assert!(s.is_inlined());
+    assert_eq!(s, CowStr::Borrowed("inlined"));
+  }
+
   #[test]
   fn cowstr_char_to_string() {
     let c = '藏';
@@ -660,6 +759,20 @@ mod tests {
     assert!(variant_eq(&actual, &expected));
   }
 
+  #[test]
+  #[cfg(feature = "zeroize")]
+  fn cow_str_zeroize() {
+    use zeroize::Zeroize;
+
+    let mut s = CowStr::from("a rather long secret passphrase".to_owned());
+    s.zeroize();
+    assert!(s.is_empty() && s.is_borrowed());
+
+    let mut s = CowStr::inline("hunter2");
+    s.zeroize();
+    assert!(s.is_empty() && s.is_borrowed());
+  }
+
   fn variant_eq<T>(a: &T, b: &T) -> bool {
     std::mem::discriminant(a) == std::mem::discriminant(b)
   }
@@ -0,0 +1,229 @@
+use alloc::string::String;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+
+use crate::CompactVec;
+use crate::CowStr;
+use crate::InlineStr;
+use crate::MAX_INLINE_STR_LEN;
+
+/// A builder that collects string fragments without copying them, and joins
+/// them into a single [`CowStr`] at the end.
+///
+/// Borrowed and owned fragments are kept as they are until
+/// [`finish`](Self::finish), which allocates exactly once for the whole
+/// result, or not at all if it fits inline or consists of a single fragment.
+/// Runs of short fragments are packed into a shared inline buffer, and the
+/// first few fragments are tracked without an allocation.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::CowStrBuilder;
+///
+/// let name = String::from("world");
+/// let mut builder = CowStrBuilder::new();
+/// builder.push("Hello, ").push(name.as_str()).push('!');
+/// assert_eq!(builder.len(), 13);
+///
+/// let greeting = builder.finish();
+/// assert!(greeting.is_inlined());
+/// assert_eq!(greeting, "Hello, world!");
+/// ```
+#[derive(Clone, Default)]
+pub struct CowStrBuilder<'i> {
+  parts: CompactVec<CowStr<'i>, 4>,
+  len:   usize,
+}
+
+impl<'i> CowStrBuilder<'i> {
+  /// Creates a new, empty builder.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      parts: CompactVec::new(),
+      len:   0,
+    }
+  }
+
+  /// Returns the total length in bytes of the fragments pushed so far.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Returns `true` if the builder holds no text.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns the fragments pushed so far. Adjacent short fragments may have
+  /// been merged.
+  #[inline(always)]
+  pub fn fragments(&self) -> &[CowStr<'i>] {
+    &self.parts
+  }
+
+  /// Appends a fragment, which may be a `&str`, `String`, `char`, or
+  /// `CowStr`. Borrowed and owned fragments are stored without copying.
+  pub fn push(&mut self, fragment: impl Into<CowStr<'i>>) -> &mut Self {
+    let fragment = fragment.into();
+    if fragment.is_empty() {
+      return self;
+    }
+    self.len += fragment.len();
+    if let Some(last) = self.parts.last_mut() {
+      let (start, end) = (last.len(), last.len() + fragment.len());
+      if end <= MAX_INLINE_STR_LEN {
+        let mut inline = match last {
+          CowStr::Inlined(inline) => *inline,
+          _ => InlineStr::from(&*last),
+        };
+        inline.buf[start..end].copy_from_slice(fragment.as_bytes());
+        inline.len = end as u8;
+        *last = CowStr::Inlined(inline);
+        return self;
+      }
+    }
+    self.parts.push(fragment);
+    self
+  }
+
+  /// Removes all fragments, keeping any storage for reuse.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.parts.clear();
+    self.len = 0;
+  }
+
+  /// Joins the fragments into a single `CowStr`.
+  ///
+  /// A single fragment is returned as-is, results of up to
+  /// [`MAX_INLINE_STR_LEN`] bytes are inlined, and anything else is copied
+  /// into one allocation of exactly the right size.
+  pub fn finish(mut self) -> CowStr<'i> {
+    match self.parts.len() {
+      0 => CowStr::Borrowed(""),
+      1 => self.parts.pop().unwrap(),
+      _ if self.len <= MAX_INLINE_STR_LEN => {
+        let mut inline = InlineStr::default();
+        for part in &self.parts {
+          let start = inline.len();
+          inline.buf[start..start + part.len()]
+            .copy_from_slice(part.as_bytes());
+          inline.len += part.len() as u8;
+        }
+        CowStr::Inlined(inline)
+      }
+      _ => {
+        let mut s = String::with_capacity(self.len);
+        for part in &self.parts {
+          s.push_str(part);
+        }
+        CowStr::Owned(s.into_boxed_str())
+      }
+    }
+  }
+}
+
+impl fmt::Write for CowStrBuilder<'_> {
+  /// Appends a copy of `s`, since formatted fragments are not borrowed for
+  /// the builder's lifetime.
+  #[inline]
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    match InlineStr::try_from(s) {
+      Ok(inline) => self.push(CowStr::Inlined(inline)),
+      Err(_) => self.push(CowStr::Owned(s.into())),
+    };
+    Ok(())
+  }
+
+  #[inline]
+  fn write_char(&mut self, c: char) -> fmt::Result {
+    self.push(c);
+    Ok(())
+  }
+}
+
+impl Display for CowStrBuilder<'_> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    self.parts.iter().try_for_each(|part| f.write_str(part))
+  }
+}
+
+impl Debug for CowStrBuilder<'_> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_list().entries(self.parts.iter()).finish()
+  }
+}
+
+impl<'i, T: Into<CowStr<'i>>> Extend<T> for CowStrBuilder<'i> {
+  #[inline]
+  fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+    for fragment in iter {
+      self.push(fragment);
+    }
+  }
+}
+
+impl<'i, T: Into<CowStr<'i>>> FromIterator<T> for CowStrBuilder<'i> {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    let mut builder = Self::new();
+    builder.extend(iter);
+    builder
+  }
+}
+
+impl<'i> From<CowStrBuilder<'i>> for CowStr<'i> {
+  #[inline(always)]
+  fn from(builder: CowStrBuilder<'i>) -> Self {
+    builder.finish()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::format;
+  use alloc::string::ToString;
+  use core::fmt::Write;
+
+  use super::*;
+
+  const LONG: &str = "a string that is too long to be stored inline";
+
+  #[test]
+  fn cow_str_builder_finish() {
+    assert!(CowStrBuilder::new().finish().is_borrowed());
+
+    let single: CowStrBuilder = [LONG].into_iter().collect();
+    let single = single.finish();
+    assert!(single.is_borrowed());
+    assert_eq!(single.as_ptr(), LONG.as_ptr());
+
+    let mut builder = CowStrBuilder::new();
+    builder.push(LONG).push(String::from(" and ")).push(LONG);
+    assert_eq!(builder.fragments().len(), 3);
+    assert_eq!(builder.to_string(), format!("{LONG} and {LONG}"));
+    let joined = builder.finish();
+    assert!(joined.is_owned());
+    assert_eq!(joined, format!("{LONG} and {LONG}"));
+  }
+
+  #[test]
+  fn cow_str_builder_packs_short_fragments() {
+    let mut builder: CowStrBuilder = "a-b-c".split('-').collect();
+    builder.push("");
+    assert_eq!(builder.fragments().len(), 1);
+    assert_eq!(builder.fragments()[0], "abc");
+
+    write!(builder, "{}{}", 1, 2).unwrap();
+    builder.push(LONG).push('x');
+    assert_eq!(builder.fragments().len(), 3);
+    assert_eq!(builder.len(), 6 + LONG.len());
+    assert_eq!(builder.finish(), format!("abc12{LONG}x"));
+  }
+}
@@ -0,0 +1,288 @@
+//! Splitting of CSV and TSV records into fields.
+//!
+//! Most fields of a delimited record are plain text, so the iterators here
+//! hand each one out as a [`Borrowed`](CowStr::Borrowed) slice of the
+//! record. A field is only copied, inline when it is short enough, when
+//! unquoting or unescaping it changes its text, such as a CSV field with a
+//! doubled `""` quote or a TSV field with a `\t` escape. Quoted fields
+//! without doubled quotes are borrowed too, without their quotes.
+//!
+//! The iterators split a single record. A quoted CSV field may contain
+//! newlines, but it is up to the caller to find where each record ends.
+//!
+//! # Example
+//!
+//! ```rust
+//! use moos::delimited;
+//!
+//! let fields: Vec<_> =
+//!   delimited::csv(r#"42,"Bessie","says ""moo""",,"#).collect();
+//! assert_eq!(fields, ["42", "Bessie", r#"says "moo""#, "", ""]);
+//! assert!(fields[1].is_borrowed());
+//! assert!(fields[2].is_inlined());
+//!
+//! let fields: Vec<_> = delimited::tsv("cow\tmoo\\tmoo").collect();
+//! assert_eq!(fields, ["cow", "moo\tmoo"]);
+//! ```
+
+use core::iter::FusedIterator;
+
+use crate::CowStr;
+use crate::MAX_INLINE_STR_LEN;
+use crate::SmallString;
+
+/// The buffer copied fields are built up in.
+type Buf = SmallString<MAX_INLINE_STR_LEN>;
+
+/// How the fields of a record are delimited, quoted, and escaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Dialect {
+  /// The character between fields.
+  pub delimiter: char,
+  /// The character that quotes a field, if any.
+  ///
+  /// A field that starts with it runs to the next lone quote, and may
+  /// contain delimiters. Within it, a doubled quote stands for one quote.
+  /// Any text between the closing quote and the next delimiter is kept as
+  /// is, and a field with no closing quote runs to the end of the record.
+  pub quote:     Option<char>,
+  /// Whether unquoted fields may contain the backslash escapes `\t`, `\n`,
+  /// `\r`, and `\\`. Any other backslash is kept as is.
+  pub escapes:   bool,
+}
+
+impl Dialect {
+  /// Comma-separated values, as described in RFC 4180.
+  pub const CSV: Self = Self {
+    delimiter: ',',
+    quote:     Some('"'),
+    escapes:   false,
+  };
+  /// Tab-separated values, as written by databases and spreadsheets, with
+  /// backslash escapes and no quoting.
+  pub const TSV: Self = Self {
+    delimiter: '\t',
+    quote:     None,
+    escapes:   true,
+  };
+
+  /// Returns a dialect that splits fields on `delimiter`, with no quoting
+  /// or escapes.
+  #[inline]
+  pub const fn new(delimiter: char) -> Self {
+    Self {
+      delimiter,
+      quote: None,
+      escapes: false,
+    }
+  }
+
+  /// Splits `record` into its fields.
+  #[inline]
+  pub fn split<'a>(&self, record: &'a str) -> Fields<'a> {
+    Fields {
+      rest:    Some(record),
+      dialect: *self,
+    }
+  }
+}
+
+impl Default for Dialect {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::CSV
+  }
+}
+
+/// Splits a CSV `record` into its fields, using [`Dialect::CSV`].
+#[inline]
+pub fn csv(record: &str) -> Fields<'_> {
+  Dialect::CSV.split(record)
+}
+
+/// Splits a TSV `record` into its fields, using [`Dialect::TSV`].
+#[inline]
+pub fn tsv(record: &str) -> Fields<'_> {
+  Dialect::TSV.split(record)
+}
+
+/// An iterator over the fields of a delimited record, created by
+/// [`Dialect::split`], [`csv`], or [`tsv`].
+///
+/// An empty record has a single, empty field, and a record that ends with a
+/// delimiter has an empty last field.
+#[derive(Debug, Clone)]
+pub struct Fields<'a> {
+  /// The part of the record not yet split, or `None` once the last field
+  /// has been returned.
+  rest:    Option<&'a str>,
+  dialect: Dialect,
+}
+
+impl<'a> Fields<'a> {
+  /// Returns the part of the record not yet split into fields.
+  #[inline]
+  pub fn as_str(&self) -> &'a str {
+    self.rest.unwrap_or_default()
+  }
+
+  /// Splits `s` at the first delimiter, returning the text before it and
+  /// the text after it, if there is a delimiter.
+  #[inline]
+  fn split_field(&self, s: &'a str) -> (&'a str, Option<&'a str>) {
+    let delimiter = self.dialect.delimiter;
+    match s.find(delimiter) {
+      Some(i) => (&s[..i], Some(&s[i + delimiter.len_utf8()..])),
+      None => (s, None),
+    }
+  }
+
+  /// Splits off a quoted field from `body`, the text after its opening
+  /// quote.
+  fn quoted(&mut self, body: &'a str, quote: char) -> CowStr<'a> {
+    let q = quote.len_utf8();
+    let mut copy: Option<Buf> = None;
+    let mut pos = 0;
+    while let Some(i) = body[pos..].find(quote) {
+      let end = pos + i;
+      let after = &body[end + q..];
+      if after.starts_with(quote) {
+        copy
+          .get_or_insert_with(|| Buf::with_capacity(body.len()))
+          .push_str(&body[pos..end + q]);
+        pos = end + 2 * q;
+        continue;
+      }
+      let (tail, rest) = self.split_field(after);
+      self.rest = rest;
+      if copy.is_none() && tail.is_empty() {
+        return CowStr::Borrowed(&body[..end]);
+      }
+      let mut copy = copy.unwrap_or_default();
+      copy.push_str(&body[pos..end]);
+      copy.push_str(tail);
+      return CowStr::from_small(copy);
+    }
+    // no closing quote, so the field runs to the end of the record.
+    self.rest = None;
+    match copy {
+      None => CowStr::Borrowed(body),
+      Some(mut copy) => {
+        copy.push_str(&body[pos..]);
+        CowStr::from_small(copy)
+      }
+    }
+  }
+}
+
+/// Replaces the backslash escapes in `field`, borrowing it if it has none.
+fn unescape(field: &str) -> CowStr<'_> {
+  let Some(first) = field.find('\\') else {
+    return CowStr::Borrowed(field);
+  };
+  let mut out = Buf::with_capacity(field.len());
+  out.push_str(&field[..first]);
+  let mut chars = field[first..].chars();
+  while let Some(c) = chars.next() {
+    if c != '\\' {
+      out.push(c);
+      continue;
+    }
+    match chars.clone().next() {
+      Some('t') => out.push('\t'),
+      Some('n') => out.push('\n'),
+      Some('r') => out.push('\r'),
+      Some('\\') => out.push('\\'),
+      _ => {
+        out.push('\\');
+        continue;
+      }
+    }
+    chars.next();
+  }
+  CowStr::from_small(out)
+}
+
+impl<'a> Iterator for Fields<'a> {
+  type Item = CowStr<'a>;
+
+  fn next(&mut self) -> Option<CowStr<'a>> {
+    let record = self.rest?;
+    let quoted = self
+      .dialect
+      .quote
+      .and_then(|quote| Some((quote, record.strip_prefix(quote)?)));
+    if let Some((quote, body)) = quoted {
+      return Some(self.quoted(body, quote));
+    }
+    let (field, rest) = self.split_field(record);
+    self.rest = rest;
+    Some(match self.dialect.escapes {
+      true => unescape(field),
+      false => CowStr::Borrowed(field),
+    })
+  }
+}
+
+impl FusedIterator for Fields<'_> {}
+
+#[cfg(test)]
+mod tests {
+  use alloc::vec::Vec;
+
+  use super::*;
+
+  #[test]
+  fn delimited_csv() {
+    let fields: Vec<_> = csv("a,b,,c").collect();
+    assert_eq!(fields, ["a", "b", "", "c"]);
+    assert!(fields.iter().all(CowStr::is_borrowed));
+    assert_eq!(csv("").collect::<Vec<_>>(), [""]);
+    assert_eq!(csv(",").collect::<Vec<_>>(), ["", ""]);
+
+    let fields: Vec<_> = csv(r#""a,b","","x""y",z"#).collect();
+    assert_eq!(fields, ["a,b", "", "x\"y", "z"]);
+    assert!(fields[0].is_borrowed() && fields[1].is_borrowed());
+    assert!(fields[2].is_inlined());
+
+    let long = r#""a rather long field with ""quotes"" in it""#;
+    let field = csv(long).next().unwrap();
+    assert!(field.is_owned());
+    assert_eq!(field, r#"a rather long field with "quotes" in it"#);
+  }
+
+  #[test]
+  fn delimited_csv_lenient() {
+    let fields: Vec<_> = csv(
+      r#""ab"cd,"multi
+line"#,
+    )
+    .collect();
+    assert_eq!(fields, ["abcd", "multi\nline"]);
+    assert_eq!(csv(r#""x""y"#).collect::<Vec<_>>(), ["x\"y"]);
+    assert_eq!(csv(r#"a"b,c"#).collect::<Vec<_>>(), ["a\"b", "c"]);
+  }
+
+  #[test]
+  fn delimited_tsv() {
+    let fields: Vec<_> = tsv("id\tname\\\\path\ta\\nb\t\\q").collect();
+    assert_eq!(fields, ["id", "name\\path", "a\nb", "\\q"]);
+    assert!(fields[0].is_borrowed() && fields[3].is_inlined());
+    assert_eq!(tsv("\"quoted\"\t").collect::<Vec<_>>(), ["\"quoted\"", ""]);
+  }
+
+  #[test]
+  fn delimited_custom_dialect() {
+    let dialect = Dialect {
+      quote: Some('\''),
+      ..Dialect::new('→')
+    };
+    let mut fields = dialect.split("a→'b→c'→d");
+    assert_eq!(fields.next().unwrap(), "a");
+    assert_eq!(fields.as_str(), "'b→c'→d");
+    assert_eq!(fields.next().unwrap(), "b→c");
+    assert_eq!(fields.next().unwrap(), "d");
+    assert_eq!(fields.next(), None);
+    assert_eq!(fields.next(), None);
+  }
+}
@@ -0,0 +1,444 @@
+//! Hex, base64, and percent encoding into the crate's small-string types.
+//!
+//! Short tokens, digests, and URL components are the typical inputs here,
+//! so every encoder produces an inline-first type instead of a `String` or
+//! `Vec`: [`hex`] and [`base64`] encode into a [`SmallString`] and decode
+//! into a [`CompactVec`], while [`percent`] works on [`CowStr`] and hands
+//! back the input itself when nothing needs escaping.
+//!
+//! # Example
+//!
+//! ```rust
+//! use moos::CowStr;
+//! use moos::encoding::base64;
+//! use moos::encoding::hex;
+//! use moos::encoding::percent;
+//!
+//! let digest = hex::encode_small::<16>(&[0xde, 0xad, 0xbe, 0xef]);
+//! assert_eq!(digest, "deadbeef");
+//! assert!(digest.is_inline());
+//!
+//! let token = base64::encode_small::<16>(b"moose");
+//! assert_eq!(token, "bW9vc2U=");
+//! assert_eq!(
+//!   base64::decode_to_compact_vec::<8>(&token).unwrap(),
+//!   *b"moose"
+//! );
+//!
+//! assert!(matches!(
+//!   percent::encode("plain"),
+//!   CowStr::Borrowed("plain")
+//! ));
+//! assert_eq!(percent::encode("a b&c"), "a%20b%26c");
+//! ```
+
+use core::fmt;
+use core::fmt::Display;
+use core::fmt::Formatter;
+
+use crate::CompactVec;
+use crate::CowStr;
+use crate::MAX_INLINE_STR_LEN;
+use crate::SmallString;
+
+/// Error returned when decoding malformed hex, base64, or percent-encoded
+/// input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+  /// The input contains a byte that is not valid at `position`.
+  InvalidByte {
+    /// The position of the byte in the input.
+    position: usize,
+    /// The byte itself.
+    byte:     u8,
+  },
+  /// The input has a length that no encoded value can have.
+  InvalidLength {
+    /// The length of the input.
+    len: usize,
+  },
+  /// The decoded bytes are not valid UTF-8, where a string was expected.
+  InvalidUtf8,
+}
+
+impl Display for DecodeError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::InvalidByte { position, byte } => {
+        write!(f, "invalid byte {byte:#04x} at position {position}")
+      }
+      Self::InvalidLength { len } => write!(f, "invalid input length {len}"),
+      Self::InvalidUtf8 => f.write_str("decoded bytes are not valid UTF-8"),
+    }
+  }
+}
+
+impl core::error::Error for DecodeError {}
+
+const HEX_LOWER: &[u8; 16] = b"0123456789abcdef";
+const HEX_UPPER: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Returns the value of an ASCII hex digit, in either case.
+#[inline]
+const fn hex_value(byte: u8) -> Option<u8> {
+  match byte {
+    b'0'..=b'9' => Some(byte - b'0'),
+    b'a'..=b'f' => Some(byte - b'a' + 10),
+    b'A'..=b'F' => Some(byte - b'A' + 10),
+    _ => None,
+  }
+}
+
+/// Turns a buffer of ASCII bytes into a `SmallString`.
+#[inline(always)]
+fn ascii_to_small<const N: usize>(bytes: CompactVec<u8, N>) -> SmallString<N> {
+  debug_assert!(bytes.is_ascii());
+  // SAFETY: the encoders only ever write ASCII bytes.
+  unsafe { SmallString::from_utf8_unchecked(bytes) }
+}
+
+/// Base 16, with two digits per byte.
+pub mod hex {
+  use super::*;
+
+  /// Encodes `bytes` as lower-case hex.
+  #[inline]
+  pub fn encode_small<const N: usize>(bytes: &[u8]) -> SmallString<N> {
+    encode_with(bytes, HEX_LOWER)
+  }
+
+  /// Encodes `bytes` as upper-case hex.
+  #[inline]
+  pub fn encode_upper_small<const N: usize>(bytes: &[u8]) -> SmallString<N> {
+    encode_with(bytes, HEX_UPPER)
+  }
+
+  fn encode_with<const N: usize>(
+    bytes: &[u8],
+    digits: &[u8; 16],
+  ) -> SmallString<N> {
+    let mut out = CompactVec::new();
+    out.reserve_exact(bytes.len() * 2);
+    for &byte in bytes {
+      out.push(digits[(byte >> 4) as usize]);
+      out.push(digits[(byte & 0xf) as usize]);
+    }
+    ascii_to_small(out)
+  }
+
+  /// Decodes hex digits of either case into bytes.
+  ///
+  /// Returns an error if the input has an odd length or contains anything
+  /// but hex digits.
+  pub fn decode_to_compact_vec<const N: usize>(
+    input: impl AsRef<[u8]>,
+  ) -> Result<CompactVec<u8, N>, DecodeError> {
+    let input = input.as_ref();
+    if !input.len().is_multiple_of(2) {
+      return Err(DecodeError::InvalidLength { len: input.len() });
+    }
+    let digit = |position: usize| {
+      let byte = input[position];
+      hex_value(byte).ok_or(DecodeError::InvalidByte { position, byte })
+    };
+    let mut out = CompactVec::new();
+    out.reserve_exact(input.len() / 2);
+    for i in (0..input.len()).step_by(2) {
+      out.push(digit(i)? << 4 | digit(i + 1)?);
+    }
+    Ok(out)
+  }
+}
+
+/// Base 64, as described in RFC 4648, in the standard and URL-safe
+/// alphabets.
+pub mod base64 {
+  use super::*;
+
+  const STANDARD: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+  const URL_SAFE: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+  const STANDARD_VALUES: [u8; 256] = decode_table(STANDARD);
+  const URL_SAFE_VALUES: [u8; 256] = decode_table(URL_SAFE);
+
+  /// Marks bytes outside the alphabet in a decode table.
+  const INVALID: u8 = 0xff;
+
+  const fn decode_table(alphabet: &[u8; 64]) -> [u8; 256] {
+    let mut table = [INVALID; 256];
+    let mut i = 0;
+    while i < 64 {
+      table[alphabet[i] as usize] = i as u8;
+      i += 1;
+    }
+    table
+  }
+
+  /// Encodes `bytes` in the standard alphabet, padded with `=`.
+  #[inline]
+  pub fn encode_small<const N: usize>(bytes: &[u8]) -> SmallString<N> {
+    encode_with(bytes, STANDARD, true)
+  }
+
+  /// Encodes `bytes` in the URL-safe alphabet, without padding, as used in
+  /// URLs and JSON Web Tokens.
+  #[inline]
+  pub fn encode_url_safe_small<const N: usize>(bytes: &[u8]) -> SmallString<N> {
+    encode_with(bytes, URL_SAFE, false)
+  }
+
+  fn encode_with<const N: usize>(
+    bytes: &[u8],
+    alphabet: &[u8; 64],
+    pad: bool,
+  ) -> SmallString<N> {
+    let mut out = CompactVec::new();
+    out.reserve_exact(bytes.len().div_ceil(3) * 4);
+    let mut chunks = bytes.chunks_exact(3);
+    for chunk in &mut chunks {
+      let n = u32::from_be_bytes([0, chunk[0], chunk[1], chunk[2]]);
+      for shift in [18, 12, 6, 0] {
+        out.push(alphabet[(n >> shift & 0x3f) as usize]);
+      }
+    }
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+      let n =
+        u32::from_be_bytes([0, rest[0], rest.get(1).copied().unwrap_or(0), 0]);
+      // one byte needs two digits, and two bytes need three.
+      for shift in [18, 12, 6].into_iter().take(rest.len() + 1) {
+        out.push(alphabet[(n >> shift & 0x3f) as usize]);
+      }
+      if pad {
+        for _ in rest.len()..3 {
+          out.push(b'=');
+        }
+      }
+    }
+    ascii_to_small(out)
+  }
+
+  /// Decodes base64 in the standard alphabet, with or without padding.
+  #[inline]
+  pub fn decode_to_compact_vec<const N: usize>(
+    input: impl AsRef<[u8]>,
+  ) -> Result<CompactVec<u8, N>, DecodeError> {
+    decode_with(input.as_ref(), &STANDARD_VALUES)
+  }
+
+  /// Decodes base64 in the URL-safe alphabet, with or without padding.
+  #[inline]
+  pub fn decode_url_safe_to_compact_vec<const N: usize>(
+    input: impl AsRef<[u8]>,
+  ) -> Result<CompactVec<u8, N>, DecodeError> {
+    decode_with(input.as_ref(), &URL_SAFE_VALUES)
+  }
+
+  fn decode_with<const N: usize>(
+    input: &[u8],
+    values: &[u8; 256],
+  ) -> Result<CompactVec<u8, N>, DecodeError> {
+    let mut data = input;
+    if let [rest @ .., b'='] = data {
+      data = rest;
+      if let [rest @ .., b'='] = data {
+        data = rest;
+      }
+      if !input.len().is_multiple_of(4) {
+        return Err(DecodeError::InvalidLength { len: input.len() });
+      }
+    }
+    if data.len() % 4 == 1 {
+      return Err(DecodeError::InvalidLength { len: input.len() });
+    }
+
+    let mut out = CompactVec::new();
+    out.reserve_exact(data.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for (position, &byte) in data.iter().enumerate() {
+      let value = values[byte as usize];
+      if value == INVALID {
+        return Err(DecodeError::InvalidByte { position, byte });
+      }
+      acc = acc << 6 | value as u32;
+      bits += 6;
+      if bits >= 8 {
+        bits -= 8;
+        out.push((acc >> bits) as u8);
+      }
+    }
+    Ok(out)
+  }
+}
+
+/// Percent encoding of URL components, as described in RFC 3986.
+pub mod percent {
+  use super::*;
+
+  /// Returns `true` for the bytes RFC 3986 lists as unreserved, which are
+  /// never escaped.
+  #[inline(always)]
+  const fn is_unreserved(byte: u8) -> bool {
+    matches!(byte, b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~')
+  }
+
+  /// Escapes every byte of `s` except the unreserved ones as `%XX`.
+  ///
+  /// Returns `s` itself, borrowed, if nothing needs escaping; otherwise the
+  /// result is inline if it is short enough.
+  pub fn encode(s: &str) -> CowStr<'_> {
+    let bytes = s.as_bytes();
+    let Some(first) = bytes.iter().position(|&b| !is_unreserved(b)) else {
+      return CowStr::Borrowed(s);
+    };
+    let mut out = SmallString::<MAX_INLINE_STR_LEN>::new();
+    out.push_str(&s[..first]);
+    for &byte in &bytes[first..] {
+      if is_unreserved(byte) {
+        out.push(byte as char);
+      } else {
+        out.push('%');
+        out.push(HEX_UPPER[(byte >> 4) as usize] as char);
+        out.push(HEX_UPPER[(byte & 0xf) as usize] as char);
+      }
+    }
+    CowStr::from_small(out)
+  }
+
+  /// Replaces each `%XX` escape in `s` with the byte it stands for.
+  ///
+  /// Returns `s` itself, borrowed, if it contains no escapes. Returns an
+  /// error if an escape is malformed, or if the decoded bytes are not valid
+  /// UTF-8.
+  pub fn decode(s: &str) -> Result<CowStr<'_>, DecodeError> {
+    let bytes = s.as_bytes();
+    let Some(first) = bytes.iter().position(|&b| b == b'%') else {
+      return Ok(CowStr::Borrowed(s));
+    };
+    let mut out = CompactVec::<u8, MAX_INLINE_STR_LEN>::new();
+    out.extend_from_copy_slice(&bytes[..first]);
+    let mut i = first;
+    while i < bytes.len() {
+      let byte = bytes[i];
+      if byte != b'%' {
+        out.push(byte);
+        i += 1;
+        continue;
+      }
+      let digit = |position: usize| {
+        let byte = *bytes.get(position).unwrap_or(&b'%');
+        let position = position.min(bytes.len() - 1);
+        hex_value(byte).ok_or(DecodeError::InvalidByte { position, byte })
+      };
+      out.push(digit(i + 1)? << 4 | digit(i + 2)?);
+      i += 3;
+    }
+    let out =
+      SmallString::from_utf8(out).map_err(|_| DecodeError::InvalidUtf8)?;
+    Ok(CowStr::from_small(out))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn hex_round_trip() {
+    let bytes = [0x00, 0x7f, 0x80, 0xff];
+    assert_eq!(hex::encode_small::<8>(&bytes), "007f80ff");
+    assert_eq!(hex::encode_upper_small::<8>(&bytes), "007F80FF");
+    let decoded = hex::decode_to_compact_vec::<4>("007F80ff").unwrap();
+    assert_eq!(decoded, bytes);
+    assert!(decoded.is_inline());
+
+    assert_eq!(
+      hex::decode_to_compact_vec::<4>("abc"),
+      Err(DecodeError::InvalidLength { len: 3 })
+    );
+    assert_eq!(
+      hex::decode_to_compact_vec::<4>("0g"),
+      Err(DecodeError::InvalidByte {
+        position: 1,
+        byte:     b'g',
+      })
+    );
+  }
+
+  #[test]
+  fn base64_round_trip() {
+    let vectors = [
+      ("", ""),
+      ("f", "Zg=="),
+      ("fo", "Zm8="),
+      ("foo", "Zm9v"),
+      ("foob", "Zm9vYg=="),
+      ("fooba", "Zm9vYmE="),
+      ("foobar", "Zm9vYmFy"),
+    ];
+    for (plain, encoded) in vectors {
+      assert_eq!(base64::encode_small::<8>(plain.as_bytes()), encoded);
+      let decoded = base64::decode_to_compact_vec::<8>(encoded).unwrap();
+      assert_eq!(decoded, plain.as_bytes());
+      let unpadded = encoded.trim_end_matches('=');
+      let decoded = base64::decode_to_compact_vec::<8>(unpadded).unwrap();
+      assert_eq!(decoded, plain.as_bytes());
+    }
+
+    let bytes = [0xfb, 0xff, 0xbf];
+    assert_eq!(base64::encode_small::<4>(&bytes), "+/+/");
+    assert_eq!(base64::encode_url_safe_small::<4>(&bytes), "-_-_");
+    assert_eq!(
+      base64::decode_url_safe_to_compact_vec::<4>("-_-_").unwrap(),
+      bytes
+    );
+
+    assert_eq!(
+      base64::decode_to_compact_vec::<4>("Zg="),
+      Err(DecodeError::InvalidLength { len: 3 })
+    );
+    assert_eq!(
+      base64::decode_to_compact_vec::<4>("Z"),
+      Err(DecodeError::InvalidLength { len: 1 })
+    );
+    assert_eq!(
+      base64::decode_to_compact_vec::<4>("Z=g="),
+      Err(DecodeError::InvalidByte {
+        position: 1,
+        byte:     b'=',
+      })
+    );
+  }
+
+  #[test]
+  fn percent_round_trip() {
+    assert!(percent::encode("a-b_c.d~e").is_borrowed());
+    let encoded = percent::encode("naïve café?");
+    assert_eq!(encoded, "na%C3%AFve%20caf%C3%A9%3F");
+    assert!(encoded.is_owned());
+    assert!(percent::encode("a/b").is_inlined());
+
+    assert!(percent::decode("plain").unwrap().is_borrowed());
+    assert_eq!(percent::decode(&encoded).unwrap(), "naïve café?");
+    assert_eq!(percent::decode("%2f%2F").unwrap(), "//");
+
+    assert_eq!(
+      percent::decode("100%"),
+      Err(DecodeError::InvalidByte {
+        position: 3,
+        byte:     b'%',
+      })
+    );
+    assert_eq!(
+      percent::decode("%zz"),
+      Err(DecodeError::InvalidByte {
+        position: 1,
+        byte:     b'z',
+      })
+    );
+    assert_eq!(percent::decode("%ff"), Err(DecodeError::InvalidUtf8));
+  }
+}
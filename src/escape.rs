@@ -0,0 +1,391 @@
+//! Escaping and unescaping of backslash, JSON, and HTML text.
+//!
+//! Most strings that pass through an escaper come out unchanged, so every
+//! function here takes a [`CowStr`] (or anything that converts into one)
+//! and hands the input straight back when there is nothing to do: a
+//! borrowed string stays borrowed and an owned one keeps its allocation.
+//! Only strings that actually change are rebuilt, inline when the result
+//! is short enough.
+//!
+//! # Example
+//!
+//! ```rust
+//! use moos::CowStr;
+//! use moos::escape::html;
+//! use moos::escape::json;
+//!
+//! assert!(html::escape("plain text").is_borrowed());
+//! assert_eq!(html::escape("<b>&</b>"), "&lt;b&gt;&amp;&lt;/b&gt;");
+//! assert_eq!(html::unescape("caf&eacute; &#x2615;"), "caf&eacute; ☕");
+//!
+//! let line = json::escape("say \"moo\"\n");
+//! assert_eq!(line, r#"say \"moo\"\n"#);
+//! assert_eq!(json::unescape(line).unwrap(), "say \"moo\"\n");
+//! ```
+
+use core::fmt;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::fmt::Write;
+
+use crate::CowStr;
+use crate::MAX_INLINE_STR_LEN;
+use crate::SmallString;
+
+/// Error returned when a string contains an escape sequence that can't be
+/// unescaped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnescapeError {
+  position: usize,
+}
+
+impl UnescapeError {
+  /// Returns the position of the backslash that starts the invalid escape
+  /// sequence.
+  #[inline(always)]
+  pub const fn position(&self) -> usize {
+    self.position
+  }
+}
+
+impl Display for UnescapeError {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "invalid escape sequence at position {}", self.position)
+  }
+}
+
+impl core::error::Error for UnescapeError {}
+
+/// The buffer escaped strings are built up in.
+type Buf = SmallString<MAX_INLINE_STR_LEN>;
+
+/// Rebuilds `s` if any of its characters need escaping, copying the others
+/// through unchanged and passing the rest to `escape_char`.
+fn escape_with<'a>(
+  s: CowStr<'a>,
+  needs_escape: impl Fn(char) -> bool,
+  escape_char: impl Fn(char, &mut Buf),
+) -> CowStr<'a> {
+  let Some(first) = s.find(&needs_escape) else {
+    return s;
+  };
+  let mut out = Buf::with_capacity(s.len() + 8);
+  out.push_str(&s[..first]);
+  for c in s[first..].chars() {
+    if needs_escape(c) {
+      escape_char(c, &mut out);
+    } else {
+      out.push(c);
+    }
+  }
+  CowStr::from_small(out)
+}
+
+/// Returns the value of `digits` if it is a non-empty run of hex digits.
+#[inline]
+fn parse_hex(digits: &str) -> Option<u32> {
+  if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+    return None;
+  }
+  u32::from_str_radix(digits, 16).ok()
+}
+
+/// Escapes in the style of Rust and C string literals.
+pub mod backslash {
+  use super::*;
+
+  #[inline(always)]
+  fn needs_escape(c: char) -> bool {
+    matches!(c, '\\' | '"' | '\'') || c.is_control()
+  }
+
+  /// Escapes backslashes, quotes, and control characters, using `\n`,
+  /// `\r`, `\t`, and `\0` where possible and `\u{..}` otherwise.
+  pub fn escape<'a>(s: impl Into<CowStr<'a>>) -> CowStr<'a> {
+    escape_with(s.into(), needs_escape, |c, out| match c {
+      '\\' => out.push_str("\\\\"),
+      '"' => out.push_str("\\\""),
+      '\'' => out.push_str("\\'"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\t' => out.push_str("\\t"),
+      '\0' => out.push_str("\\0"),
+      c => {
+        let _ = write!(out, "\\u{{{:x}}}", c as u32);
+      }
+    })
+  }
+
+  /// Replaces each escape sequence in `s` with the character it stands
+  /// for.
+  ///
+  /// Recognizes `\\`, `\"`, `\'`, `\n`, `\r`, `\t`, `\0`, `\xHH` for ASCII
+  /// characters, and `\u{H..}` with up to six hex digits.
+  pub fn unescape<'a>(
+    s: impl Into<CowStr<'a>>,
+  ) -> Result<CowStr<'a>, UnescapeError> {
+    let s = s.into();
+    let Some(first) = s.find('\\') else {
+      return Ok(s);
+    };
+    let mut out = Buf::with_capacity(s.len());
+    out.push_str(&s[..first]);
+    let mut pos = first;
+    while pos < s.len() {
+      let rest = &s[pos..];
+      let Some(rest) = rest.strip_prefix('\\') else {
+        let next = rest.find('\\').unwrap_or(rest.len());
+        out.push_str(&rest[..next]);
+        pos += next;
+        continue;
+      };
+      let error = UnescapeError { position: pos };
+      let (c, len) = match rest.as_bytes().first() {
+        Some(b'\\') => ('\\', 1),
+        Some(b'"') => ('"', 1),
+        Some(b'\'') => ('\'', 1),
+        Some(b'n') => ('\n', 1),
+        Some(b'r') => ('\r', 1),
+        Some(b't') => ('\t', 1),
+        Some(b'0') => ('\0', 1),
+        Some(b'x') => {
+          let code = rest.get(1..3).and_then(parse_hex).ok_or(error)?;
+          (char::from_u32(code).filter(char::is_ascii).ok_or(error)?, 3)
+        }
+        Some(b'u') => {
+          let body = rest.strip_prefix("u{").ok_or(error)?;
+          let end = body.find('}').filter(|&end| end <= 6).ok_or(error)?;
+          let code = parse_hex(&body[..end]).ok_or(error)?;
+          (char::from_u32(code).ok_or(error)?, end + 3)
+        }
+        _ => return Err(error),
+      };
+      out.push(c);
+      pos += 1 + len;
+    }
+    Ok(CowStr::from_small(out))
+  }
+}
+
+/// Escapes for the contents of JSON string literals, as described in
+/// RFC 8259.
+pub mod json {
+  use super::*;
+
+  #[inline(always)]
+  fn needs_escape(c: char) -> bool {
+    matches!(c, '\\' | '"' | '\0'..='\x1f')
+  }
+
+  /// Escapes quotes, backslashes, and control characters. The result does
+  /// not include the surrounding quotes.
+  pub fn escape<'a>(s: impl Into<CowStr<'a>>) -> CowStr<'a> {
+    escape_with(s.into(), needs_escape, |c, out| match c {
+      '\\' => out.push_str("\\\\"),
+      '"' => out.push_str("\\\""),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\t' => out.push_str("\\t"),
+      '\x08' => out.push_str("\\b"),
+      '\x0c' => out.push_str("\\f"),
+      c => {
+        let _ = write!(out, "\\u{:04x}", c as u32);
+      }
+    })
+  }
+
+  /// Replaces each escape sequence in the contents of a JSON string with
+  /// the character it stands for, combining surrogate pairs.
+  ///
+  /// Returns an error for unknown escapes and unpaired surrogates.
+  pub fn unescape<'a>(
+    s: impl Into<CowStr<'a>>,
+  ) -> Result<CowStr<'a>, UnescapeError> {
+    let s = s.into();
+    let Some(first) = s.find('\\') else {
+      return Ok(s);
+    };
+    let mut out = Buf::with_capacity(s.len());
+    out.push_str(&s[..first]);
+    let mut pos = first;
+    while pos < s.len() {
+      let rest = &s[pos..];
+      let Some(rest) = rest.strip_prefix('\\') else {
+        let next = rest.find('\\').unwrap_or(rest.len());
+        out.push_str(&rest[..next]);
+        pos += next;
+        continue;
+      };
+      let error = UnescapeError { position: pos };
+      let (c, len) = match rest.as_bytes().first() {
+        Some(b'"') => ('"', 1),
+        Some(b'\\') => ('\\', 1),
+        Some(b'/') => ('/', 1),
+        Some(b'b') => ('\x08', 1),
+        Some(b'f') => ('\x0c', 1),
+        Some(b'n') => ('\n', 1),
+        Some(b'r') => ('\r', 1),
+        Some(b't') => ('\t', 1),
+        Some(b'u') => {
+          let unit = |at: usize| rest.get(at..at + 4).and_then(parse_hex);
+          let high = unit(1).ok_or(error)?;
+          match high {
+            0xd800..=0xdbff => {
+              let low = rest
+                .get(5..7)
+                .filter(|&u| u == "\\u")
+                .and_then(|_| unit(7))
+                .filter(|low| matches!(low, 0xdc00..=0xdfff))
+                .ok_or(error)?;
+              let code = 0x10000 + ((high - 0xd800) << 10 | (low - 0xdc00));
+              (char::from_u32(code).ok_or(error)?, 11)
+            }
+            _ => (char::from_u32(high).ok_or(error)?, 5),
+          }
+        }
+        _ => return Err(error),
+      };
+      out.push(c);
+      pos += 1 + len;
+    }
+    Ok(CowStr::from_small(out))
+  }
+}
+
+/// Escapes for HTML text and attribute values.
+pub mod html {
+  use super::*;
+
+  #[inline(always)]
+  fn needs_escape(c: char) -> bool {
+    matches!(c, '&' | '<' | '>' | '"' | '\'')
+  }
+
+  /// Escapes `&`, `<`, `>`, `"`, and `'`, making the result safe to use in
+  /// both text and quoted attribute values.
+  pub fn escape<'a>(s: impl Into<CowStr<'a>>) -> CowStr<'a> {
+    escape_with(s.into(), needs_escape, |c, out| {
+      out.push_str(match c {
+        '&' => "&amp;",
+        '<' => "&lt;",
+        '>' => "&gt;",
+        '"' => "&quot;",
+        _ => "&#39;",
+      });
+    })
+  }
+
+  /// Returns the character a character reference stands for, given the
+  /// text between its `&` and `;`.
+  fn decode_reference(name: &str) -> Option<char> {
+    let code = match name {
+      "amp" => return Some('&'),
+      "lt" => return Some('<'),
+      "gt" => return Some('>'),
+      "quot" => return Some('"'),
+      "apos" => return Some('\''),
+      "nbsp" => return Some('\u{a0}'),
+      _ => name.strip_prefix('#')?,
+    };
+    let value = match code.strip_prefix(['x', 'X']) {
+      Some(hex) => parse_hex(hex)?,
+      None if !code.is_empty() && code.bytes().all(|b| b.is_ascii_digit()) => {
+        code.parse().ok()?
+      }
+      None => return None,
+    };
+    char::from_u32(value).filter(|&c| c != '\0')
+  }
+
+  /// Replaces the basic named character references (`&amp;`, `&lt;`,
+  /// `&gt;`, `&quot;`, `&apos;`, and `&nbsp;`) and all numeric ones with
+  /// the characters they stand for.
+  ///
+  /// Anything else that starts with `&`, including the many other named
+  /// references, is left as it is.
+  pub fn unescape<'a>(s: impl Into<CowStr<'a>>) -> CowStr<'a> {
+    let s = s.into();
+    let Some(first) = s.find('&') else {
+      return s;
+    };
+    let mut out = Buf::with_capacity(s.len());
+    out.push_str(&s[..first]);
+    let mut changed = false;
+    let mut rest = &s[first..];
+    while let Some(amp) = rest.find('&') {
+      out.push_str(&rest[..amp]);
+      rest = &rest[amp + 1..];
+      // the longest reference worth looking for is `&#x10ffff;`.
+      let reference = rest
+        .bytes()
+        .take(9)
+        .position(|b| b == b';')
+        .and_then(|end| Some((end, decode_reference(&rest[..end])?)));
+      match reference {
+        Some((end, c)) => {
+          out.push(c);
+          rest = &rest[end + 1..];
+          changed = true;
+        }
+        None => out.push('&'),
+      }
+    }
+    out.push_str(rest);
+    if changed { CowStr::from_small(out) } else { s }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn backslash_round_trip() {
+    assert!(backslash::escape("no escapes").is_borrowed());
+    let escaped = backslash::escape("tab\there \"q\" \\ \u{1b}[0m");
+    assert_eq!(escaped, r#"tab\there \"q\" \\ \u{1b}[0m"#);
+    assert_eq!(
+      backslash::unescape(escaped).unwrap(),
+      "tab\there \"q\" \\ \u{1b}[0m"
+    );
+    assert_eq!(backslash::unescape(r"\x41\u{1F42E}\0").unwrap(), "A🐮\0");
+
+    for bad in [r"\q", r"\x80", r"\x4", r"\u{110000}", r"\u{1234567}", "\\"] {
+      let err = backslash::unescape(bad).unwrap_err();
+      assert_eq!(err.position(), 0, "{bad}");
+    }
+  }
+
+  #[test]
+  fn json_round_trip() {
+    let owned = CowStr::from(alloc::string::String::from("unchanged"));
+    assert!(json::escape(owned).is_owned());
+
+    let escaped = json::escape("a\"b\\c\n\u{1}");
+    assert_eq!(escaped, r#"a\"b\\c\n\u0001"#);
+    assert_eq!(json::unescape(escaped).unwrap(), "a\"b\\c\n\u{1}");
+    assert_eq!(json::unescape(r"🐮 é\/").unwrap(), "🐮 é/");
+
+    let err = json::unescape(r"ok \ud83d alone").unwrap_err();
+    assert_eq!(err.position(), 3);
+    assert_eq!(err.to_string(), "invalid escape sequence at position 3");
+    assert!(json::unescape(r"\x41").is_err());
+    assert!(json::unescape(r"\u12").is_err());
+  }
+
+  #[test]
+  fn html_round_trip() {
+    let escaped = html::escape(r#"<a href="x">Tom & Jerry's</a>"#);
+    assert_eq!(
+      escaped,
+      "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+    );
+    assert_eq!(html::unescape(escaped), r#"<a href="x">Tom & Jerry's</a>"#);
+
+    assert!(html::unescape("fish & chips &copy; &#0; &#xzz;").is_borrowed());
+    assert_eq!(html::unescape("&#65;&#X42;&#x43&apos;"), "AB&#x43'");
+    assert_eq!(html::unescape("&&amp;;"), "&&;");
+    assert_eq!(html::unescape("&lt;éé&gt;"), "<éé>");
+  }
+}
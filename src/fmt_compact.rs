@@ -0,0 +1,400 @@
+//! Allocation-free formatting of numbers into inline strings.
+//!
+//! The `write_*` functions format a number into any [`fmt::Write`] sink,
+//! such as an [`InlineStr`](crate::InlineStr) or a
+//! [`SmallString`](crate::SmallString), without going through
+//! `format!` and its `String`. The digits are produced in a [`NumBuffer`] on
+//! the stack and appended with a single `write_str` call, so a fixed-capacity
+//! sink that is too short is left unchanged rather than holding half a
+//! number.
+//!
+//! Integers use a two-digits-at-a-time decimal routine in the style of
+//! `itoa`, and can be written in any base from 2 to 36 with a [`Radix`].
+//! Floats are written with the shortest digits that parse back to the same
+//! value, in the same notation as their `Debug` output.
+//!
+//! # Example
+//!
+//! ```rust
+//! use moos::InlineStr;
+//! use moos::SmallString;
+//! use moos::fmt_compact;
+//! use moos::fmt_compact::Radix;
+//!
+//! let mut reading = SmallString::<16>::new();
+//! fmt_compact::write_f32(&mut reading, 21.5).unwrap();
+//! reading.push_str(" C");
+//! assert_eq!(reading, "21.5 C");
+//! assert!(reading.is_inline());
+//!
+//! let mut id = InlineStr::default();
+//! fmt_compact::write_u64_radix(&mut id, 0xbeef, Radix::UPPER_HEX).unwrap();
+//! assert_eq!(id, "BEEF");
+//!
+//! // an `InlineStr` can't hold 64 binary digits.
+//! assert!(
+//!   fmt_compact::write_u64_radix(&mut id, u64::MAX, Radix::BINARY).is_err()
+//! );
+//! assert_eq!(id, "BEEF");
+//! ```
+
+use core::fmt;
+use core::fmt::Write;
+use core::str;
+
+/// The number of bytes in a [`NumBuffer`]: enough for `i64::MIN` in binary.
+const NUM_BUFFER_LEN: usize = 65;
+
+/// Pairs of decimal digits for every number from `00` to `99`.
+const DEC_DIGITS_LUT: &[u8; 200] = b"\
+  0001020304050607080910111213141516171819\
+  2021222324252627282930313233343536373839\
+  4041424344454647484950515253545556575859\
+  6061626364656667686970717273747576777879\
+  8081828384858687888990919293949596979899";
+
+const LOWER_DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+const UPPER_DIGITS: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// The base in which an integer is written, from 2 to 36, and whether
+/// digits above 9 are written in upper case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Radix {
+  base:      u8,
+  uppercase: bool,
+}
+
+impl Radix {
+  /// Base 2.
+  pub const BINARY: Self = Self::from_base(2);
+  /// Base 8.
+  pub const OCTAL: Self = Self::from_base(8);
+  /// Base 10.
+  pub const DECIMAL: Self = Self::from_base(10);
+  /// Base 16, with lower-case digits.
+  pub const HEX: Self = Self::from_base(16);
+  /// Base 16, with upper-case digits.
+  pub const UPPER_HEX: Self = Self::from_base(16).uppercase();
+
+  /// Returns the radix for `base`, or `None` if it is not between 2 and 36.
+  #[inline]
+  pub const fn new(base: u32) -> Option<Self> {
+    if matches!(base, 2..=36) {
+      Some(Self::from_base(base as u8))
+    } else {
+      None
+    }
+  }
+
+  #[inline(always)]
+  const fn from_base(base: u8) -> Self {
+    Self {
+      base,
+      uppercase: false,
+    }
+  }
+
+  /// Returns this radix with digits above 9 written in upper case.
+  #[inline(always)]
+  pub const fn uppercase(self) -> Self {
+    Self {
+      uppercase: true,
+      ..self
+    }
+  }
+
+  /// Returns the base of this radix.
+  #[inline(always)]
+  pub const fn base(self) -> u32 {
+    self.base as u32
+  }
+}
+
+impl Default for Radix {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::DECIMAL
+  }
+}
+
+/// A stack buffer that numbers are formatted into, for callers that want
+/// the digits as a `&str` rather than appended to a string.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::fmt_compact::NumBuffer;
+///
+/// let mut buf = NumBuffer::new();
+/// assert_eq!(buf.format_i64(-1234), "-1234");
+/// assert_eq!(buf.format_f64(1e21), "1e21");
+/// ```
+#[derive(Clone)]
+pub struct NumBuffer {
+  bytes: [u8; NUM_BUFFER_LEN],
+}
+
+impl NumBuffer {
+  /// Creates an empty buffer.
+  #[inline(always)]
+  pub const fn new() -> Self {
+    Self {
+      bytes: [0; NUM_BUFFER_LEN],
+    }
+  }
+
+  /// Formats `value` in decimal.
+  #[inline]
+  pub fn format_u64(&mut self, value: u64) -> &str {
+    self.format_u64_radix(value, Radix::DECIMAL)
+  }
+
+  /// Formats `value` in decimal.
+  #[inline]
+  pub fn format_i64(&mut self, value: i64) -> &str {
+    self.format_i64_radix(value, Radix::DECIMAL)
+  }
+
+  /// Formats `value` in the given radix, without a prefix such as `0x`.
+  #[inline]
+  pub fn format_u64_radix(&mut self, value: u64, radix: Radix) -> &str {
+    let start = self.write_digits(value, radix);
+    self.tail(start)
+  }
+
+  /// Formats `value` in the given radix, with a leading `-` if it is
+  /// negative.
+  #[inline]
+  pub fn format_i64_radix(&mut self, value: i64, radix: Radix) -> &str {
+    let mut start = self.write_digits(value.unsigned_abs(), radix);
+    if value < 0 {
+      start -= 1;
+      self.bytes[start] = b'-';
+    }
+    self.tail(start)
+  }
+
+  /// Formats `value` with the shortest digits that round-trip, as `{:?}`
+  /// would: `1.0`, `0.001`, `1e-7`, `inf`, `NaN`.
+  #[inline]
+  pub fn format_f32(&mut self, value: f32) -> &str {
+    self.format_debug(value)
+  }
+
+  /// Formats `value` with the shortest digits that round-trip, as `{:?}`
+  /// would.
+  #[inline]
+  pub fn format_f64(&mut self, value: f64) -> &str {
+    self.format_debug(value)
+  }
+
+  /// Writes the digits of `value` at the end of the buffer, returning the
+  /// index of the first one.
+  fn write_digits(&mut self, mut value: u64, radix: Radix) -> usize {
+    let mut pos = NUM_BUFFER_LEN;
+    if radix.base == 10 {
+      while value >= 100 {
+        let d = (value % 100) as usize * 2;
+        value /= 100;
+        pos -= 2;
+        self.bytes[pos..pos + 2].copy_from_slice(&DEC_DIGITS_LUT[d..d + 2]);
+      }
+      if value >= 10 {
+        let d = value as usize * 2;
+        pos -= 2;
+        self.bytes[pos..pos + 2].copy_from_slice(&DEC_DIGITS_LUT[d..d + 2]);
+      } else {
+        pos -= 1;
+        self.bytes[pos] = b'0' + value as u8;
+      }
+    } else {
+      let digits = if radix.uppercase {
+        UPPER_DIGITS
+      } else {
+        LOWER_DIGITS
+      };
+      let base = radix.base as u64;
+      loop {
+        pos -= 1;
+        self.bytes[pos] = digits[(value % base) as usize];
+        value /= base;
+        if value == 0 {
+          break;
+        }
+      }
+    }
+    pos
+  }
+
+  #[inline(always)]
+  fn tail(&self, start: usize) -> &str {
+    // SAFETY: the buffer holds ASCII digits and signs from `start` on.
+    unsafe { str::from_utf8_unchecked(&self.bytes[start..]) }
+  }
+
+  fn format_debug(&mut self, value: impl fmt::Debug) -> &str {
+    let mut cursor = Cursor {
+      buf: &mut self.bytes,
+      len: 0,
+    };
+    // the longest output, such as `-2.2250738585072014e-308`, fits easily.
+    write!(cursor, "{value:?}").expect("float output fits in a NumBuffer");
+    let len = cursor.len;
+    // SAFETY: the first `len` bytes were copied from whole `&str`s.
+    unsafe { str::from_utf8_unchecked(&self.bytes[..len]) }
+  }
+}
+
+impl Default for NumBuffer {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl fmt::Debug for NumBuffer {
+  #[inline]
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("NumBuffer { .. }")
+  }
+}
+
+/// A [`fmt::Write`] sink over a byte slice, which fails once it is full.
+struct Cursor<'a> {
+  buf: &'a mut [u8],
+  len: usize,
+}
+
+impl Write for Cursor<'_> {
+  #[inline]
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    let end = self.len + s.len();
+    let dst = self.buf.get_mut(self.len..end).ok_or(fmt::Error)?;
+    dst.copy_from_slice(s.as_bytes());
+    self.len = end;
+    Ok(())
+  }
+}
+
+/// Appends `value` to `out` in decimal.
+#[inline]
+pub fn write_u64<W: Write + ?Sized>(out: &mut W, value: u64) -> fmt::Result {
+  out.write_str(NumBuffer::new().format_u64(value))
+}
+
+/// Appends `value` to `out` in decimal.
+#[inline]
+pub fn write_i64<W: Write + ?Sized>(out: &mut W, value: i64) -> fmt::Result {
+  out.write_str(NumBuffer::new().format_i64(value))
+}
+
+/// Appends `value` to `out` in the given radix, without a prefix.
+#[inline]
+pub fn write_u64_radix<W: Write + ?Sized>(
+  out: &mut W,
+  value: u64,
+  radix: Radix,
+) -> fmt::Result {
+  out.write_str(NumBuffer::new().format_u64_radix(value, radix))
+}
+
+/// Appends `value` to `out` in the given radix, with a leading `-` if it is
+/// negative.
+#[inline]
+pub fn write_i64_radix<W: Write + ?Sized>(
+  out: &mut W,
+  value: i64,
+  radix: Radix,
+) -> fmt::Result {
+  out.write_str(NumBuffer::new().format_i64_radix(value, radix))
+}
+
+/// Appends `value` to `out` with the shortest digits that round-trip.
+#[inline]
+pub fn write_f32<W: Write + ?Sized>(out: &mut W, value: f32) -> fmt::Result {
+  out.write_str(NumBuffer::new().format_f32(value))
+}
+
+/// Appends `value` to `out` with the shortest digits that round-trip.
+#[inline]
+pub fn write_f64<W: Write + ?Sized>(out: &mut W, value: f64) -> fmt::Result {
+  out.write_str(NumBuffer::new().format_f64(value))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::InlineStr;
+  use crate::MAX_INLINE_STR_LEN;
+  use crate::SmallString;
+
+  #[test]
+  fn fmt_compact_integers() {
+    let mut buf = NumBuffer::new();
+    for value in [0, 7, 10, 99, 100, 12345, 1 << 40, u64::MAX] {
+      assert_eq!(buf.format_u64(value), format!("{value}"));
+      assert_eq!(
+        buf.format_u64_radix(value, Radix::BINARY),
+        format!("{value:b}")
+      );
+      assert_eq!(
+        buf.format_u64_radix(value, Radix::OCTAL),
+        format!("{value:o}")
+      );
+      assert_eq!(
+        buf.format_u64_radix(value, Radix::HEX),
+        format!("{value:x}")
+      );
+      assert_eq!(
+        buf.format_u64_radix(value, Radix::UPPER_HEX),
+        format!("{value:X}")
+      );
+    }
+    for value in [i64::MIN, -100, -1, 0, 42, i64::MAX] {
+      assert_eq!(buf.format_i64(value), format!("{value}"));
+    }
+    assert_eq!(
+      buf.format_i64_radix(i64::MIN, Radix::BINARY).len(),
+      NUM_BUFFER_LEN
+    );
+    assert_eq!(buf.format_u64_radix(35, Radix::new(36).unwrap()), "z");
+    assert_eq!(Radix::new(1), None);
+    assert_eq!(Radix::new(37), None);
+  }
+
+  #[test]
+  fn fmt_compact_floats() {
+    let mut buf = NumBuffer::new();
+    for value in [
+      0.0,
+      -0.0,
+      1.0,
+      21.5,
+      1e-7,
+      1e16,
+      f32::MIN_POSITIVE,
+      f32::MAX,
+    ] {
+      assert_eq!(buf.format_f32(value), format!("{value:?}"));
+    }
+    for value in [f64::MIN_POSITIVE, -f64::MAX, f64::NAN, f64::INFINITY] {
+      assert_eq!(buf.format_f64(value), format!("{value:?}"));
+    }
+  }
+
+  #[test]
+  fn fmt_compact_sinks() {
+    let mut s = InlineStr::default();
+    write_i64(&mut s, -42).unwrap();
+    assert_eq!(s, "-42");
+    while write_u64(&mut s, 7).is_ok() {}
+    assert_eq!(s.len(), MAX_INLINE_STR_LEN);
+    assert!(s.ends_with("777"));
+
+    let mut small = SmallString::<8>::new();
+    write_u64_radix(&mut small, u64::MAX, Radix::BINARY).unwrap();
+    assert_eq!(small.len(), 64);
+    write_f64(&mut small, 0.5).unwrap();
+    assert!(small.ends_with("10.5"));
+  }
+}
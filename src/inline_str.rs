@@ -1,9 +1,13 @@
-use alloc::borrow::Borrow;
-use alloc::borrow::BorrowMut;
+#[cfg(feature = "alloc")]
 use alloc::borrow::Cow;
+#[cfg(feature = "alloc")]
 use alloc::borrow::ToOwned;
+#[cfg(feature = "alloc")]
 use alloc::string::String;
+#[cfg(feature = "alloc")]
 use alloc::string::ToString;
+use core::borrow::Borrow;
+use core::borrow::BorrowMut;
 use core::cmp::Ordering;
 use core::convert::AsMut;
 use core::convert::AsRef;
@@ -21,6 +25,7 @@ use core::ops::DerefMut;
 use core::str;
 use core::str::FromStr;
 
+#[cfg(feature = "alloc")]
 use crate::CowStr;
 
 /// Maximum length of an inline string in bytes. On 64-bit systems this is
@@ -102,6 +107,33 @@ impl InlineStr {
     Self { buf, len }
   }
 
+  /// Creates an `InlineStr` holding a copy of `s`, in a `const` context if
+  /// need be.
+  ///
+  /// This is what the [`inline_str!`](crate::inline_str!) macro uses to
+  /// check string literals at compile time.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [`StringTooLongError`] if `s` is longer than
+  /// [`MAX_INLINE_STR_LEN`] bytes.
+  pub const fn try_from_str(s: &str) -> Result<Self, StringTooLongError> {
+    let bytes = s.as_bytes();
+    if bytes.len() > MAX_INLINE_STR_LEN {
+      return Err(StringTooLongError);
+    }
+    let mut buf = [0; MAX_INLINE_STR_LEN];
+    let mut i = 0;
+    while i < bytes.len() {
+      buf[i] = bytes[i];
+      i += 1;
+    }
+    Ok(Self {
+      buf,
+      len: bytes.len() as u8,
+    })
+  }
+
   /// Returns the length of the string.
   #[inline]
   pub const fn len(&self) -> usize {
@@ -170,6 +202,43 @@ impl InlineStr {
   }
 }
 
+/// Creates an [`InlineStr`] from a string literal or constant, checking its
+/// length at compile time.
+///
+/// The string is built in a `const` block, so a string that doesn't fit in
+/// [`MAX_INLINE_STR_LEN`] bytes is a compile error rather than a runtime
+/// [`StringTooLongError`].
+///
+/// # Example
+///
+/// ```rust
+/// use moos::InlineStr;
+/// use moos::inline_str;
+///
+/// const GREETING: InlineStr = inline_str!("moo");
+/// assert_eq!(GREETING, "moo");
+/// assert_eq!(inline_str!("").len(), 0);
+/// ```
+///
+/// A string that is too long fails to compile:
+///
+/// ```rust,compile_fail
+/// let s = moos::inline_str!("This string is too long to fit in an InlineStr");
+/// ```
+#[macro_export]
+macro_rules! inline_str {
+  ($s:expr $(,)?) => {
+    const {
+      match $crate::InlineStr::try_from_str($s) {
+        ::core::result::Result::Ok(s) => s,
+        ::core::result::Result::Err(_) => {
+          ::core::panic!("string is too long for an InlineStr")
+        }
+      }
+    }
+  };
+}
+
 impl Default for InlineStr {
   #[inline(always)]
   fn default() -> Self {
@@ -187,6 +256,22 @@ impl Display for InlineStr {
   }
 }
 
+impl fmt::Write for InlineStr {
+  /// Appends `s`, or returns an error and leaves the string unchanged if it
+  /// would exceed [`MAX_INLINE_STR_LEN`] bytes.
+  #[inline]
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    let start = self.len();
+    let end = start + s.len();
+    if end > MAX_INLINE_STR_LEN {
+      return Err(fmt::Error);
+    }
+    self.buf[start..end].copy_from_slice(s.as_bytes());
+    self.len = end as u8;
+    Ok(())
+  }
+}
+
 impl Borrow<str> for InlineStr {
   #[inline(always)]
   fn borrow(&self) -> &str {
@@ -231,6 +316,7 @@ impl AsMut<str> for InlineStr {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl From<InlineStr> for String {
   #[inline(always)]
   fn from(s: InlineStr) -> Self {
@@ -238,6 +324,7 @@ impl From<InlineStr> for String {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl From<&InlineStr> for String {
   #[inline(always)]
   fn from(s: &InlineStr) -> Self {
@@ -263,6 +350,7 @@ impl From<char> for InlineStr {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl<'i> From<Cow<'i, str>> for InlineStr {
   #[inline(always)]
   fn from(cow: Cow<'i, str>) -> Self {
@@ -284,6 +372,7 @@ impl FromStr for InlineStr {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl From<String> for InlineStr {
   #[inline(always)]
   fn from(s: String) -> Self {
@@ -301,14 +390,7 @@ impl TryFrom<&str> for InlineStr {
 
   #[inline(always)]
   fn try_from(s: &str) -> Result<InlineStr, StringTooLongError> {
-    let len = s.len();
-    if len > MAX_INLINE_STR_LEN {
-      return Err(StringTooLongError);
-    }
-    let mut buf = [0u8; MAX_INLINE_STR_LEN];
-    buf[..len].copy_from_slice(s.as_bytes());
-    let len = len as u8;
-    Ok(Self { buf, len })
+    InlineStr::try_from_str(s)
   }
 }
 
@@ -319,6 +401,7 @@ impl Hash for InlineStr {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl<T: ToString> PartialEq<T> for InlineStr {
   #[inline(always)]
   fn eq(&self, other: &T) -> bool {
@@ -326,6 +409,24 @@ impl<T: ToString> PartialEq<T> for InlineStr {
   }
 }
 
+// without `alloc`, the comparisons covered by the `ToString` impl above are
+// spelled out for the types that don't need to be converted to a `String`.
+#[cfg(not(feature = "alloc"))]
+impl PartialEq for InlineStr {
+  #[inline(always)]
+  fn eq(&self, other: &InlineStr) -> bool {
+    self.deref() == other.deref()
+  }
+}
+
+#[cfg(not(feature = "alloc"))]
+impl PartialEq<&str> for InlineStr {
+  #[inline(always)]
+  fn eq(&self, other: &&str) -> bool {
+    self.deref() == *other
+  }
+}
+
 impl PartialEq<InlineStr> for &InlineStr {
   #[inline(always)]
   fn eq(&self, other: &InlineStr) -> bool {
@@ -340,6 +441,7 @@ impl PartialEq<str> for InlineStr {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl<'i> PartialEq<InlineStr> for Cow<'i, str> {
   #[inline(always)]
   fn eq(&self, other: &InlineStr) -> bool {
@@ -347,6 +449,7 @@ impl<'i> PartialEq<InlineStr> for Cow<'i, str> {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl<'i> PartialEq<InlineStr> for CowStr<'i> {
   #[inline(always)]
   fn eq(&self, other: &InlineStr) -> bool {
@@ -380,6 +483,7 @@ impl PartialEq<InlineStr> for char {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl PartialEq<InlineStr> for String {
   #[inline(always)]
   fn eq(&self, other: &InlineStr) -> bool {
@@ -387,6 +491,7 @@ impl PartialEq<InlineStr> for String {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl PartialEq<InlineStr> for &String {
   #[inline(always)]
   fn eq(&self, other: &InlineStr) -> bool {
@@ -408,6 +513,7 @@ impl PartialEq<InlineStr> for &mut str {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl PartialEq<InlineStr> for &mut String {
   #[inline(always)]
   fn eq(&self, other: &InlineStr) -> bool {
@@ -450,12 +556,14 @@ impl PartialOrd<InlineStr> for char {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl PartialOrd<InlineStr> for String {
   fn partial_cmp(&self, other: &InlineStr) -> Option<Ordering> {
     Some(self.as_str().cmp(other.deref()))
   }
 }
 
+#[cfg(feature = "alloc")]
 impl PartialOrd<InlineStr> for &String {
   #[inline(always)]
   fn partial_cmp(&self, other: &InlineStr) -> Option<Ordering> {
@@ -477,6 +585,7 @@ impl PartialOrd<InlineStr> for &mut str {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl PartialOrd<InlineStr> for &mut String {
   #[inline(always)]
   fn partial_cmp(&self, other: &InlineStr) -> Option<Ordering> {
@@ -491,6 +600,7 @@ impl PartialOrd<InlineStr> for &mut InlineStr {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl<'i> PartialOrd<InlineStr> for Cow<'i, str> {
   #[inline(always)]
   fn partial_cmp(&self, other: &InlineStr) -> Option<Ordering> {
@@ -498,6 +608,7 @@ impl<'i> PartialOrd<InlineStr> for Cow<'i, str> {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl<'i> PartialOrd<InlineStr> for CowStr<'i> {
   #[inline(always)]
   fn partial_cmp(&self, other: &InlineStr) -> Option<Ordering> {
@@ -505,6 +616,7 @@ impl<'i> PartialOrd<InlineStr> for CowStr<'i> {
   }
 }
 
+#[cfg(feature = "alloc")]
 impl<T: ToString> PartialOrd<T> for InlineStr {
   #[inline(always)]
   fn partial_cmp(&self, other: &T) -> Option<Ordering> {
@@ -513,6 +625,22 @@ impl<T: ToString> PartialOrd<T> for InlineStr {
   }
 }
 
+#[cfg(not(feature = "alloc"))]
+impl PartialOrd for InlineStr {
+  #[inline(always)]
+  fn partial_cmp(&self, other: &InlineStr) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+#[cfg(not(feature = "alloc"))]
+impl PartialOrd<&str> for InlineStr {
+  #[inline(always)]
+  fn partial_cmp(&self, other: &&str) -> Option<Ordering> {
+    Some(self.deref().cmp(*other))
+  }
+}
+
 impl Ord for InlineStr {
   #[inline(always)]
   fn cmp(&self, other: &Self) -> Ordering {
@@ -520,6 +648,25 @@ impl Ord for InlineStr {
   }
 }
 
+#[cfg(feature = "zeroize")]
+mod zeroize_impl {
+  use zeroize::Zeroize;
+
+  use super::*;
+
+  impl Zeroize for InlineStr {
+    /// Wipes the entire inline buffer and resets the length to zero.
+    ///
+    /// Since `InlineStr` is `Copy`, this cannot account for any copies that
+    /// were made before it was called. For the same reason, it can't have a
+    /// destructor, so it isn't wiped on drop like a [`CowStr`] holding it is.
+    fn zeroize(&mut self) {
+      self.buf.zeroize();
+      self.len.zeroize();
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -602,6 +749,15 @@ mod tests {
     assert!(matches!(err, Err(StringTooLongError)));
   }
 
+  #[test]
+  fn inline_str_macro() {
+    const MOO: InlineStr = inline_str!("moo");
+    assert_eq!(MOO.as_str(), "moo");
+    let s = inline_str!("🍔");
+    assert_eq!(s.len(), 4);
+    assert!(InlineStr::try_from_str(&"x".repeat(MAX_INLINE_STR_LEN)).is_ok());
+  }
+
   #[test]
   fn inline_str_equality() {
     let s1: InlineStr = "Hello".try_into().unwrap();
@@ -630,6 +786,17 @@ mod tests {
     assert_eq!(cow, s);
   }
 
+  #[test]
+  #[cfg(feature = "zeroize")]
+  fn inline_str_zeroize() {
+    use zeroize::Zeroize;
+
+    let mut s: InlineStr = "hunter2".try_into().unwrap();
+    s.zeroize();
+    assert!(s.is_empty());
+    assert!(s.buf.iter().all(|&b| b == 0));
+  }
+
   #[test]
   fn inline_str_as_mut_str() {
     let mut s: InlineStr = "Hello".try_into().unwrap();
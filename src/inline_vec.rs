@@ -0,0 +1,570 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::iter::FusedIterator;
+use core::mem::MaybeUninit;
+use core::mem::needs_drop;
+use core::ops::Deref;
+use core::ops::DerefMut;
+use core::ptr;
+use core::slice;
+
+/// Error type returned by the operations of [`InlineVec`], and by the
+/// non-allocating operations of [`CompactVec`](crate::CompactVec) (such as
+/// [`try_push`](crate::CompactVec::try_push)), when there is no spare
+/// capacity left for a new element.
+///
+/// The element that could not be added is handed back to the caller, and can
+/// be recovered with [`into_inner`](CapacityError::into_inner).
+///
+/// # Example
+///
+/// ```rust
+/// use moos::CompactVec;
+///
+/// let mut v: CompactVec<u8, 2> = CompactVec::new();
+/// assert!(v.try_push(1).is_ok());
+/// assert!(v.try_push(2).is_ok());
+///
+/// let err = v.try_push(3).unwrap_err();
+/// assert_eq!(err.into_inner(), 3);
+/// assert!(v.is_inline());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CapacityError<T = ()>(pub T);
+
+impl<T> CapacityError<T> {
+  /// Returns the element that could not be added.
+  #[inline(always)]
+  pub fn into_inner(self) -> T {
+    self.0
+  }
+}
+
+impl<T> Display for CapacityError<T> {
+  #[inline(always)]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.write_str("insufficient capacity")
+  }
+}
+
+impl<T: Debug> core::error::Error for CapacityError<T> {}
+
+/// A fixed-capacity vector that stores up to `N` elements inline, and never
+/// falls back to the heap.
+///
+/// Where [`CompactVec`](crate::CompactVec) spills over to the heap on demand,
+/// every operation on an `InlineVec` that would exceed its capacity fails
+/// with a [`CapacityError`] instead, handing the rejected element back. It
+/// never allocates, and doesn't depend on `alloc`, so it is available even
+/// with the crate's `alloc` feature turned off. This makes it suitable for
+/// hard-real-time code where any allocation is forbidden.
+///
+/// `InlineVec` is `Copy` whenever `T` is. To make that possible, it has no
+/// destructor of its own, so element types that need to be dropped (such as
+/// `String` or `Box<T>`) are rejected at compile time rather than leaked.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::InlineVec;
+///
+/// let mut v: InlineVec<u8, 2> = InlineVec::new();
+/// assert!(v.push(1).is_ok());
+/// assert!(v.push(2).is_ok());
+/// assert_eq!(v.push(3).unwrap_err().into_inner(), 3);
+///
+/// let copy = v;
+/// assert_eq!(copy, v);
+/// assert_eq!(v, [1, 2]);
+/// ```
+///
+/// Types with destructors don't compile:
+///
+/// ```compile_fail
+/// use moos::InlineVec;
+///
+/// let v: InlineVec<String, 2> = InlineVec::new();
+/// ```
+pub struct InlineVec<T, const N: usize> {
+  len: usize,
+  buf: [MaybeUninit<T>; N],
+}
+
+impl<T, const N: usize> InlineVec<T, N> {
+  /// Creates a new, empty `InlineVec`.
+  ///
+  /// Fails to compile if `T` needs to be dropped (see the
+  /// [type-level documentation](InlineVec)).
+  #[inline]
+  pub const fn new() -> Self {
+    const {
+      assert!(
+        !needs_drop::<T>(),
+        "InlineVec cannot hold types that need to be dropped"
+      );
+    }
+    Self {
+      len: 0,
+      buf: [const { MaybeUninit::uninit() }; N],
+    }
+  }
+
+  /// Returns the number of elements in the vector.
+  #[inline(always)]
+  pub const fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Returns `true` if the vector contains no elements.
+  #[inline(always)]
+  pub const fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns `true` if all `N` slots are occupied.
+  #[inline(always)]
+  pub const fn is_full(&self) -> bool {
+    self.len == N
+  }
+
+  /// Returns the fixed capacity of the vector, `N`.
+  #[inline(always)]
+  pub const fn capacity(&self) -> usize {
+    N
+  }
+
+  /// Returns the number of elements that can still be added.
+  #[inline(always)]
+  pub const fn remaining_capacity(&self) -> usize {
+    N - self.len
+  }
+
+  /// Returns a slice containing the entire vector.
+  #[inline(always)]
+  pub const fn as_slice(&self) -> &[T] {
+    unsafe { slice::from_raw_parts(self.buf.as_ptr().cast(), self.len) }
+  }
+
+  /// Returns a mutable slice containing the entire vector.
+  #[inline(always)]
+  pub fn as_mut_slice(&mut self) -> &mut [T] {
+    unsafe { slice::from_raw_parts_mut(self.buf.as_mut_ptr().cast(), self.len) }
+  }
+
+  /// Appends an element to the back of the vector.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [`CapacityError`] containing `value` if the vector is full.
+  #[inline]
+  pub fn push(&mut self, value: T) -> Result<(), CapacityError<T>> {
+    if self.is_full() {
+      return Err(CapacityError(value));
+    }
+    self.buf[self.len].write(value);
+    self.len += 1;
+    Ok(())
+  }
+
+  /// Removes the last element from the vector and returns it, or `None` if it
+  /// is empty.
+  #[inline]
+  pub fn pop(&mut self) -> Option<T> {
+    if self.len == 0 {
+      return None;
+    }
+    self.len -= 1;
+    Some(unsafe { self.buf[self.len].assume_init_read() })
+  }
+
+  /// Inserts an element at position `index`, shifting all elements after it
+  /// to the right.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [`CapacityError`] containing `value` if the vector is full.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `index > len`.
+  pub fn insert(
+    &mut self,
+    index: usize,
+    value: T,
+  ) -> Result<(), CapacityError<T>> {
+    let len = self.len;
+    assert!(
+      index <= len,
+      "insertion index (is {index}) should be <= len"
+    );
+    if self.is_full() {
+      return Err(CapacityError(value));
+    }
+    unsafe {
+      let p = self.buf.as_mut_ptr().add(index);
+      ptr::copy(p, p.add(1), len - index);
+      (*p).write(value);
+    }
+    self.len += 1;
+    Ok(())
+  }
+
+  /// Removes and returns the element at position `index`, shifting all
+  /// elements after it to the left.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `index` is out of bounds.
+  pub fn remove(&mut self, index: usize) -> T {
+    let len = self.len;
+    assert!(index < len, "removal index (is {index}) should be < len");
+    unsafe {
+      let p = self.buf.as_mut_ptr().add(index);
+      let value = (*p).assume_init_read();
+      ptr::copy(p.add(1), p, len - index - 1);
+      self.len -= 1;
+      value
+    }
+  }
+
+  /// Removes the element at position `index` and returns it, replacing it
+  /// with the last element. This does not preserve ordering, but is O(1).
+  ///
+  /// # Panics
+  ///
+  /// Panics if `index` is out of bounds.
+  pub fn swap_remove(&mut self, index: usize) -> T {
+    let len = self.len;
+    assert!(
+      index < len,
+      "swap_remove index (is {index}) should be < len"
+    );
+    self.as_mut_slice().swap(index, len - 1);
+    self.len -= 1;
+    unsafe { self.buf[self.len].assume_init_read() }
+  }
+
+  /// Shortens the vector, keeping the first `len` elements. Has no effect if
+  /// `len` is greater than the current length.
+  #[inline]
+  pub fn truncate(&mut self, len: usize) {
+    self.len = self.len.min(len);
+  }
+
+  /// Removes all elements from the vector.
+  #[inline(always)]
+  pub fn clear(&mut self) {
+    self.len = 0;
+  }
+
+  /// Clones and appends all elements in `slice` to the vector, if they all
+  /// fit.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [`CapacityError`] if there isn't room for the entire slice,
+  /// in which case the vector is left unchanged.
+  pub fn extend_from_slice(&mut self, slice: &[T]) -> Result<(), CapacityError>
+  where
+    T: Clone,
+  {
+    if slice.len() > self.remaining_capacity() {
+      return Err(CapacityError(()));
+    }
+    for value in slice {
+      self.buf[self.len].write(value.clone());
+      self.len += 1;
+    }
+    Ok(())
+  }
+
+  /// Collects an iterator into an `InlineVec`.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [`CapacityError`] containing the first item that didn't fit.
+  pub fn try_from_iter<I>(iter: I) -> Result<Self, CapacityError<T>>
+  where
+    I: IntoIterator<Item = T>,
+  {
+    let mut vec = Self::new();
+    for value in iter {
+      vec.push(value)?;
+    }
+    Ok(vec)
+  }
+}
+
+impl<T, const N: usize> Default for InlineVec<T, N> {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: Clone, const N: usize> Clone for InlineVec<T, N> {
+  fn clone(&self) -> Self {
+    let mut vec = Self::new();
+    for value in self {
+      vec.buf[vec.len].write(value.clone());
+      vec.len += 1;
+    }
+    vec
+  }
+}
+
+impl<T: Copy, const N: usize> Copy for InlineVec<T, N> {}
+
+impl<T: Debug, const N: usize> Debug for InlineVec<T, N> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_list().entries(self.iter()).finish()
+  }
+}
+
+impl<T, const N: usize> Deref for InlineVec<T, N> {
+  type Target = [T];
+
+  #[inline(always)]
+  fn deref(&self) -> &[T] {
+    self.as_slice()
+  }
+}
+
+impl<T, const N: usize> DerefMut for InlineVec<T, N> {
+  #[inline(always)]
+  fn deref_mut(&mut self) -> &mut [T] {
+    self.as_mut_slice()
+  }
+}
+
+impl<T, const N: usize> AsRef<[T]> for InlineVec<T, N> {
+  #[inline(always)]
+  fn as_ref(&self) -> &[T] {
+    self.as_slice()
+  }
+}
+
+impl<T, const N: usize> AsMut<[T]> for InlineVec<T, N> {
+  #[inline(always)]
+  fn as_mut(&mut self) -> &mut [T] {
+    self.as_mut_slice()
+  }
+}
+
+impl<T: Hash, const N: usize> Hash for InlineVec<T, N> {
+  #[inline]
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.as_slice().hash(state);
+  }
+}
+
+impl<T: PartialEq, const N: usize, const M: usize> PartialEq<InlineVec<T, M>>
+  for InlineVec<T, N>
+{
+  #[inline]
+  fn eq(&self, other: &InlineVec<T, M>) -> bool {
+    self.as_slice() == other.as_slice()
+  }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq<[T]> for InlineVec<T, N> {
+  #[inline]
+  fn eq(&self, other: &[T]) -> bool {
+    self.as_slice() == other
+  }
+}
+
+impl<T: PartialEq, const N: usize, const M: usize> PartialEq<[T; M]>
+  for InlineVec<T, N>
+{
+  #[inline]
+  fn eq(&self, other: &[T; M]) -> bool {
+    self.as_slice() == other
+  }
+}
+
+impl<T: Eq, const N: usize> Eq for InlineVec<T, N> {}
+
+impl<T: PartialOrd, const N: usize> PartialOrd for InlineVec<T, N> {
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    self.as_slice().partial_cmp(other.as_slice())
+  }
+}
+
+impl<T: Ord, const N: usize> Ord for InlineVec<T, N> {
+  #[inline]
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.as_slice().cmp(other.as_slice())
+  }
+}
+
+impl<T, const N: usize> From<[T; N]> for InlineVec<T, N> {
+  #[inline]
+  fn from(array: [T; N]) -> Self {
+    let mut vec = Self::new();
+    for (slot, value) in vec.buf.iter_mut().zip(array) {
+      slot.write(value);
+    }
+    vec.len = N;
+    vec
+  }
+}
+
+impl<T: Clone, const N: usize> TryFrom<&[T]> for InlineVec<T, N> {
+  type Error = CapacityError;
+
+  #[inline]
+  fn try_from(slice: &[T]) -> Result<Self, Self::Error> {
+    let mut vec = Self::new();
+    vec.extend_from_slice(slice)?;
+    Ok(vec)
+  }
+}
+
+impl<T, const N: usize> Extend<T> for InlineVec<T, N> {
+  /// Extends the vector with the contents of an iterator.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the iterator yields more items than the remaining capacity.
+  /// Use [`push`](InlineVec::push) to handle overflow gracefully.
+  #[inline]
+  fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+    for value in iter {
+      if self.push(value).is_err() {
+        panic!("InlineVec capacity (is {N}) exceeded");
+      }
+    }
+  }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a InlineVec<T, N> {
+  type Item = &'a T;
+  type IntoIter = slice::Iter<'a, T>;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut InlineVec<T, N> {
+  type Item = &'a mut T;
+  type IntoIter = slice::IterMut<'a, T>;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter_mut()
+  }
+}
+
+impl<T, const N: usize> IntoIterator for InlineVec<T, N> {
+  type Item = T;
+  type IntoIter = IntoIter<T, N>;
+
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    IntoIter {
+      start: 0,
+      end:   self.len,
+      buf:   self.buf,
+    }
+  }
+}
+
+/// An owning iterator over the elements of an [`InlineVec`].
+pub struct IntoIter<T, const N: usize> {
+  buf:   [MaybeUninit<T>; N],
+  start: usize,
+  end:   usize,
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+  type Item = T;
+
+  #[inline]
+  fn next(&mut self) -> Option<T> {
+    if self.start == self.end {
+      return None;
+    }
+    self.start += 1;
+    Some(unsafe { self.buf[self.start - 1].assume_init_read() })
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let len = self.end - self.start;
+    (len, Some(len))
+  }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+  #[inline]
+  fn next_back(&mut self) -> Option<T> {
+    if self.start == self.end {
+      return None;
+    }
+    self.end -= 1;
+    Some(unsafe { self.buf[self.end].assume_init_read() })
+  }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> FusedIterator for IntoIter<T, N> {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn inline_vec_push_pop() {
+    let mut v: InlineVec<u8, 3> = InlineVec::new();
+    assert!(v.push(1).is_ok());
+    assert!(v.insert(0, 0).is_ok());
+    assert!(v.push(2).is_ok());
+    assert!(v.is_full());
+    assert_eq!(v.push(3), Err(CapacityError(3)));
+    assert_eq!(v.insert(0, 4), Err(CapacityError(4)));
+    assert_eq!(v, [0, 1, 2]);
+    assert_eq!(v.remove(0), 0);
+    assert_eq!(v.pop(), Some(2));
+    assert_eq!(v.pop(), Some(1));
+    assert_eq!(v.pop(), None);
+  }
+
+  #[test]
+  fn inline_vec_is_copy() {
+    let a: InlineVec<u32, 4> = InlineVec::from([1, 2, 3, 4]);
+    let mut b = a;
+    b.truncate(2);
+    assert_eq!(a, [1, 2, 3, 4]);
+    assert_eq!(b, [1, 2]);
+    assert_eq!(b.clone(), b);
+  }
+
+  #[test]
+  fn inline_vec_slices_and_iters() {
+    let mut v = InlineVec::<u8, 4>::try_from(&[1, 2][..]).unwrap();
+    assert!(v.extend_from_slice(&[3, 4, 5]).is_err());
+    assert_eq!(v.len(), 2);
+    v.extend([3, 4]);
+    assert_eq!(v.swap_remove(0), 1);
+    assert_eq!(v.into_iter().rev().collect::<Vec<_>>(), [3, 2, 4]);
+    assert!(InlineVec::<u8, 2>::try_from_iter(0..3).is_err());
+  }
+
+  #[test]
+  #[should_panic]
+  fn inline_vec_extend_overflow() {
+    let mut v: InlineVec<u8, 2> = InlineVec::new();
+    v.extend(0..3);
+  }
+}
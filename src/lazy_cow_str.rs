@@ -0,0 +1,371 @@
+use core::cell::Cell;
+use core::cell::OnceCell;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::ops::Deref;
+
+use crate::CowStr;
+
+/// A [`CowStr`] that is computed by a closure the first time it is used,
+/// and cached from then on.
+///
+/// Display strings such as labels, error messages, and debug summaries are
+/// often built eagerly but only rarely shown. Wrapping the code that builds
+/// them in a `LazyCowStr` defers the formatting (and any allocation) until
+/// the string is actually read, and skips it entirely when it never is.
+///
+/// The string is computed on the first call to [`force`](Self::force), on
+/// the first dereference, or when it is displayed. A `LazyCowStr` can't be
+/// shared between threads; see `SyncLazyCowStr` (with the `std` feature)
+/// for one that can.
+///
+/// # Example
+///
+/// ```rust
+/// use std::cell::Cell;
+///
+/// use moos::CowStr;
+/// use moos::LazyCowStr;
+///
+/// let renders = Cell::new(0);
+/// let label = LazyCowStr::new(|| {
+///   renders.set(renders.get() + 1);
+///   CowStr::from(format!("{} of {} items", 3, 7))
+/// });
+/// assert!(!label.is_computed());
+///
+/// assert_eq!(label.len(), 12);
+/// assert_eq!(label.to_string(), "3 of 7 items");
+/// assert_eq!(renders.get(), 1);
+/// ```
+pub struct LazyCowStr<'i, F = fn() -> CowStr<'i>> {
+  value: OnceCell<CowStr<'i>>,
+  init:  Cell<Option<F>>,
+}
+
+impl<'i, F: FnOnce() -> CowStr<'i>> LazyCowStr<'i, F> {
+  /// Creates a string that will be computed by `init` when first used.
+  #[inline(always)]
+  pub const fn new(init: F) -> Self {
+    Self {
+      value: OnceCell::new(),
+      init:  Cell::new(Some(init)),
+    }
+  }
+
+  /// Computes the string if it hasn't been already, and returns it.
+  ///
+  /// # Panics
+  ///
+  /// Panics if an earlier call to the closure panicked.
+  #[inline]
+  pub fn force(&self) -> &CowStr<'i> {
+    self.value.get_or_init(|| match self.init.take() {
+      Some(init) => init(),
+      None => panic!("LazyCowStr instance has previously been poisoned"),
+    })
+  }
+
+  /// Computes the string if it hasn't been already, and returns it as a
+  /// string slice.
+  #[inline(always)]
+  pub fn as_str(&self) -> &str {
+    self.force()
+  }
+
+  /// Computes the string if it hasn't been already, and returns it by
+  /// value.
+  #[inline]
+  pub fn into_cow_str(self) -> CowStr<'i> {
+    self.force();
+    self.value.into_inner().unwrap()
+  }
+}
+
+impl<'i, F> LazyCowStr<'i, F> {
+  /// Creates a string that has already been computed.
+  #[inline]
+  pub fn computed(value: CowStr<'i>) -> Self {
+    Self {
+      value: OnceCell::from(value),
+      init:  Cell::new(None),
+    }
+  }
+
+  /// Returns the string if it has been computed, without computing it.
+  #[inline(always)]
+  pub fn get(&self) -> Option<&CowStr<'i>> {
+    self.value.get()
+  }
+
+  /// Returns `true` if the string has been computed.
+  #[inline(always)]
+  pub fn is_computed(&self) -> bool {
+    self.value.get().is_some()
+  }
+}
+
+impl<'i, F: FnOnce() -> CowStr<'i>> Deref for LazyCowStr<'i, F> {
+  type Target = str;
+
+  #[inline(always)]
+  fn deref(&self) -> &str {
+    self.as_str()
+  }
+}
+
+impl<'i, F: FnOnce() -> CowStr<'i>> AsRef<str> for LazyCowStr<'i, F> {
+  #[inline(always)]
+  fn as_ref(&self) -> &str {
+    self.as_str()
+  }
+}
+
+impl<'i, F: FnOnce() -> CowStr<'i>> Display for LazyCowStr<'i, F> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Display::fmt(self.as_str(), f)
+  }
+}
+
+impl<F> Debug for LazyCowStr<'_, F> {
+  /// Shows the string if it has been computed, without computing it.
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    let mut tuple = f.debug_tuple("LazyCowStr");
+    match self.get() {
+      Some(value) => tuple.field(&value.as_str()),
+      None => tuple.field(&format_args!("<uncomputed>")),
+    };
+    tuple.finish()
+  }
+}
+
+impl<'i, F: FnOnce() -> CowStr<'i>> PartialEq<str> for LazyCowStr<'i, F> {
+  #[inline]
+  fn eq(&self, other: &str) -> bool {
+    self.as_str() == other
+  }
+}
+
+impl<'i, F: FnOnce() -> CowStr<'i>> PartialEq<&str> for LazyCowStr<'i, F> {
+  #[inline]
+  fn eq(&self, other: &&str) -> bool {
+    self.as_str() == *other
+  }
+}
+
+impl<'i, F> From<CowStr<'i>> for LazyCowStr<'i, F> {
+  #[inline(always)]
+  fn from(value: CowStr<'i>) -> Self {
+    Self::computed(value)
+  }
+}
+
+#[cfg(feature = "std")]
+mod sync_impl {
+  use std::sync::Mutex;
+  use std::sync::OnceLock;
+  use std::sync::PoisonError;
+
+  use super::*;
+
+  /// A thread-safe [`LazyCowStr`], which can be shared between threads and
+  /// stored in a `static`.
+  ///
+  /// If several threads use the string at once, the closure still runs
+  /// only once, and the others wait for its result.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CowStr;
+  /// use moos::lazy_cow_str::SyncLazyCowStr;
+  ///
+  /// static BANNER: SyncLazyCowStr<'static> = SyncLazyCowStr::new(|| {
+  ///   CowStr::from(format!("moos v{}", env!("CARGO_PKG_VERSION")))
+  /// });
+  ///
+  /// std::thread::scope(|scope| {
+  ///   for _ in 0..4 {
+  ///     scope.spawn(|| assert!(BANNER.starts_with("moos v")));
+  ///   }
+  /// });
+  /// assert!(BANNER.is_computed());
+  /// ```
+  pub struct SyncLazyCowStr<'i, F = fn() -> CowStr<'i>> {
+    value: OnceLock<CowStr<'i>>,
+    init:  Mutex<Option<F>>,
+  }
+
+  impl<'i, F: FnOnce() -> CowStr<'i>> SyncLazyCowStr<'i, F> {
+    /// Creates a string that will be computed by `init` when first used.
+    #[inline(always)]
+    pub const fn new(init: F) -> Self {
+      Self {
+        value: OnceLock::new(),
+        init:  Mutex::new(Some(init)),
+      }
+    }
+
+    /// Computes the string if it hasn't been already, and returns it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an earlier call to the closure panicked.
+    #[inline]
+    pub fn force(&self) -> &CowStr<'i> {
+      self.value.get_or_init(|| {
+        let init = self
+          .init
+          .lock()
+          .unwrap_or_else(PoisonError::into_inner)
+          .take();
+        match init {
+          Some(init) => init(),
+          None => {
+            panic!("SyncLazyCowStr instance has previously been poisoned")
+          }
+        }
+      })
+    }
+
+    /// Computes the string if it hasn't been already, and returns it as a
+    /// string slice.
+    #[inline(always)]
+    pub fn as_str(&self) -> &str {
+      self.force()
+    }
+
+    /// Computes the string if it hasn't been already, and returns it by
+    /// value.
+    #[inline]
+    pub fn into_cow_str(self) -> CowStr<'i> {
+      self.force();
+      self.value.into_inner().unwrap()
+    }
+  }
+
+  impl<'i, F> SyncLazyCowStr<'i, F> {
+    /// Creates a string that has already been computed.
+    #[inline]
+    pub fn computed(value: CowStr<'i>) -> Self {
+      Self {
+        value: OnceLock::from(value),
+        init:  Mutex::new(None),
+      }
+    }
+
+    /// Returns the string if it has been computed, without computing it.
+    #[inline(always)]
+    pub fn get(&self) -> Option<&CowStr<'i>> {
+      self.value.get()
+    }
+
+    /// Returns `true` if the string has been computed.
+    #[inline(always)]
+    pub fn is_computed(&self) -> bool {
+      self.value.get().is_some()
+    }
+  }
+
+  impl<'i, F: FnOnce() -> CowStr<'i>> Deref for SyncLazyCowStr<'i, F> {
+    type Target = str;
+
+    #[inline(always)]
+    fn deref(&self) -> &str {
+      self.as_str()
+    }
+  }
+
+  impl<'i, F: FnOnce() -> CowStr<'i>> Display for SyncLazyCowStr<'i, F> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+      Display::fmt(self.as_str(), f)
+    }
+  }
+
+  impl<F> Debug for SyncLazyCowStr<'_, F> {
+    /// Shows the string if it has been computed, without computing it.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+      let mut tuple = f.debug_tuple("SyncLazyCowStr");
+      match self.get() {
+        Some(value) => tuple.field(&value.as_str()),
+        None => tuple.field(&format_args!("<uncomputed>")),
+      };
+      tuple.finish()
+    }
+  }
+
+  impl<'i, F> From<CowStr<'i>> for SyncLazyCowStr<'i, F> {
+    #[inline(always)]
+    fn from(value: CowStr<'i>) -> Self {
+      Self::computed(value)
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+pub use sync_impl::SyncLazyCowStr;
+
+#[cfg(test)]
+mod tests {
+  use alloc::string::ToString;
+
+  use super::*;
+
+  #[test]
+  fn lazy_cow_str_computes_once() {
+    let calls = Cell::new(0);
+    let lazy = LazyCowStr::new(|| {
+      calls.set(calls.get() + 1);
+      CowStr::Borrowed("computed")
+    });
+    assert_eq!(format!("{lazy:?}"), "LazyCowStr(<uncomputed>)");
+    assert_eq!(lazy.get(), None);
+    assert_eq!(lazy, "computed");
+    assert_eq!(&lazy[..4], "comp");
+    assert_eq!(calls.get(), 1);
+    assert_eq!(format!("{lazy:?}"), r#"LazyCowStr("computed")"#);
+    assert!(lazy.into_cow_str().is_borrowed());
+
+    let ready = LazyCowStr::<fn() -> CowStr<'static>>::computed("ready".into());
+    assert!(ready.is_computed());
+    assert_eq!(ready.to_string(), "ready");
+  }
+
+  #[test]
+  fn lazy_cow_str_poisoned() {
+    let lazy = LazyCowStr::new(|| -> CowStr<'static> { panic!("boom") });
+    let first = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+      lazy.force();
+    }));
+    assert!(first.is_err());
+    let second =
+      std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+        lazy.force();
+      }));
+    assert!(second.is_err());
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn sync_lazy_cow_str_computes_once() {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    let calls = AtomicUsize::new(0);
+    let lazy = SyncLazyCowStr::new(|| {
+      calls.fetch_add(1, Ordering::Relaxed);
+      CowStr::from("shared".to_string())
+    });
+    std::thread::scope(|scope| {
+      for _ in 0..4 {
+        scope.spawn(|| assert_eq!(lazy.as_str(), "shared"));
+      }
+    });
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+    assert!(lazy.into_cow_str().is_owned());
+  }
+}
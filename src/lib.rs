@@ -4,8 +4,9 @@
 //!
 //! This crate (pronounced "moose") is a small collection of Rust primitives
 //! that prioritize memory efficiency and performance in constrained/embedded
-//! environments. At present, this crate includes 2 main types: [`CowStr`] and
-//! [`InlineStr`], which are described in detail below.
+//! environments. At present, this crate includes 4 main types: [`CowStr`],
+//! [`InlineStr`], [`CompactVec`], and [`SmallString`], which are described in
+//! detail below.
 //!
 //! ---
 //!
@@ -45,6 +46,97 @@
 //! On 64-bit systems, this usually equates to a maximum size of 22 B of UTF-8
 //! data, while on 32-bit systems, the maximum size is typically 10 B.
 //!
+//! ## [`CompactVec`]
+//!
+//! A growable vector that stores up to `N` elements inline, and transparently
+//! spills over to the heap when more capacity is needed.
+//!
+//! ### Example
+//!
+//! ```rust
+//! use moos::CompactVec;
+//!
+//! let mut v: CompactVec<u8, 4> = CompactVec::new();
+//! v.extend([1, 2, 3]);
+//! assert!(v.is_inline());
+//! ```
+//!
+//! When storing very large numbers of tiny vectors, [`CompactVec32`] trades
+//! a `u32::MAX` length limit for a footprint that is 16 bytes smaller on
+//! 64-bit targets.
+//!
+//! For code that must never allocate, [`InlineVec`] is a fixed-capacity
+//! sibling that returns an error instead of spilling to the heap.
+//!
+//! ## [`SmallString`]
+//!
+//! A growable UTF-8 string built on `CompactVec<u8, N>`, which keeps strings
+//! of up to `N` bytes inline and spills longer ones over to the heap.
+//!
+//! ### Example
+//!
+//! ```rust
+//! use moos::SmallString;
+//!
+//! let mut s: SmallString<16> = SmallString::from("moo");
+//! s.push_str("se");
+//! assert_eq!(s, "moose");
+//! assert!(s.is_inline());
+//! ```
+//!
+//! The [`fmt_compact`] module writes numbers straight into an `InlineStr` or
+//! `SmallString`, so formatting them never needs to allocate, and the
+//! [`encoding`] module encodes and decodes hex, base64, and percent-encoded
+//! text into the same small types, while [`escape`] escapes and unescapes
+//! backslash, JSON, and HTML text, borrowing whatever needs no changes. The
+//! [`delimited`] module splits CSV and TSV records into `CowStr` fields the
+//! same way, copying only the fields that quotes or escapes change, and
+//! [`similarity`] computes edit distances for "did you mean" suggestions
+//! without allocating for short strings. For the most common values of all,
+//! such as `"true"` or small integers, [`well_known`] returns static strings
+//! that need no storage at all.
+//!
+//! ## Other types
+//!
+//! - [`CompactString`]: an owned string the size of a `String` that keeps up to
+//!   23 bytes inline, with no lifetime parameter and no length limit.
+//! - [`ArcStr`]: an immutable, reference-counted string behind a single thin
+//!   pointer, for values shared across threads.
+//! - [`ArcSlice`]: a reference-counted slice with O(1) clones and subslice
+//!   views, for large read-mostly buffers shared between tasks.
+//! - [`CompactBitSet`]: a bitmap set of small integers with `N * 8` bits
+//!   inline, for feature flags and small ID sets.
+//! - [`CompactBytes`]: a byte string with inline storage, like [`SmallString`]
+//!   without the UTF-8 requirement.
+//! - [`SmallCString`]: a NUL-terminated string for FFI that keeps short strings
+//!   inline.
+//! - [`SmallDeque`]: a ring-buffer double-ended queue with `N` inline slots.
+//! - [`SmallMap`]: an insertion-ordered map that keeps up to `N` entries
+//!   inline, for the many maps that only ever hold a handful of entries.
+//! - [`CompactHashMap`]: a map that scans up to `N` inline entries, then moves
+//!   them into a `hashbrown` hash table, with `HashMap` semantics throughout
+//!   (requires the `hashbrown` feature).
+//! - [`SmallSet`]: the matching insertion-ordered set, with set operations.
+//! - [`SmallArena`]: a typed arena whose first `N` values live inline, handing
+//!   out `&mut T` references, for the short-lived nodes of a parser.
+//! - [`SmallQueue`]: a lock-free single-producer single-consumer queue with `N`
+//!   inline slots, for passing messages between an interrupt handler and the
+//!   main loop.
+//! - [`SmallBox`]: a box that keeps small values, including trait objects
+//!   created with [`smallbox!`], inline instead of on the heap.
+//! - [`StrPool`]: a string interner that hands out 4-byte [`Symbol`] handles,
+//!   with a thread-safe `SyncStrPool` variant under the `std` feature.
+//! - [`StrArena`]: a bump allocator that copies strings into shared chunks and
+//!   frees them all at once.
+//! - [`SsoString`]: a trait over `InlineStr`, `SmallString`, `CowStr`, and
+//!   `CompactString`, for generic code that lets callers choose the storage.
+//! - [`LazyCowStr`]: a [`CowStr`] computed by a closure on first use, with a
+//!   thread-safe `SyncLazyCowStr` variant under the `std` feature.
+//! - [`PathStr`]: a `/`-separated path over a [`CowStr`], with component
+//!   iteration, joining, and normalization for `no_std` file systems.
+//! - [`PreHashed`]: a hash map key that caches the hash of its value, so
+//!   repeated lookups of the same keys skip rehashing.
+//!
 //! ---
 //!
 //! ## `no_std` Support
@@ -52,23 +144,175 @@
 //! These types are designed to be used in `no_std` environments, making them
 //! suitable for embedded systems and other resource-constrained applications.
 //!
+//! Targets without a global allocator can also turn off the default `alloc`
+//! feature, which leaves only the types that never allocate: [`InlineStr`],
+//! [`InlineVec`], and [`PreHashed`], along with the [`fmt_compact`] number
+//! buffers.
+//!
 //! ---
 //!
 //! ## Features
 //!
+//! - `alloc`†: Enables every type that can store its contents on the heap.
+//!   Without it, the crate doesn't link against `alloc` at all.
 //! - `std`: Enables integration with the Rust standard library. When disabled,
 //!   which is the default, the crate operates in `no_std` mode.
 //! - `serde`†: Enables serialization and deserialization support via Serde.
+//! - `embedded-io`: Implements the [`embedded-io`] `Read` and `Write` traits
+//!   for byte-oriented types such as `CompactVec<u8, N>`, and `Write` for the
+//!   UTF-8 validating `SmallString` writer.
+//! - `zeroize`: Implements [`zeroize`] traits for the crate's types, so that
+//!   secrets stored in them (including spilled heap buffers) can be wiped. The
+//!   heap-backed types, and `CowStr`, also wipe themselves when dropped.
+//! - `bytemuck`: Adds zero-copy byte views (`as_bytes`) and byte-slice
+//!   constructors to `CompactVec<T, N>` for [`bytemuck`] `Pod` elements.
+//! - `hashbrown`: Adds [`CompactHashMap`], a small map that moves into a
+//!   [`hashbrown`] hash table once it outgrows its inline storage.
+//! - `unicode`: Adds the `unicode` module, with full Unicode case folding
+//!   (`fold_case`) and caseless comparison (`caseless_eq`) that work without
+//!   `std` and without allocating.
 //!
 //! > † enabled by default
+//!
+//! [`embedded-io`]: https://docs.rs/embedded-io
+//! [`zeroize`]: https://docs.rs/zeroize
+//! [`bytemuck`]: https://docs.rs/bytemuck
+//! [`hashbrown`]: https://docs.rs/hashbrown
 
 #![cfg_attr(not(any(test, feature = "std")), no_std)]
 
+#[cfg(feature = "alloc")]
 extern crate alloc;
 extern crate core;
 
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub mod arc_slice;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub mod arc_str;
+#[cfg(feature = "alloc")]
+pub mod compact_bit_set;
+#[cfg(feature = "alloc")]
+pub mod compact_bytes;
+#[cfg(feature = "hashbrown")]
+pub mod compact_hash_map;
+#[cfg(feature = "alloc")]
+pub mod compact_string;
+#[cfg(feature = "alloc")]
+pub mod compact_vec;
+#[cfg(feature = "alloc")]
+pub mod compact_vec32;
+#[cfg(feature = "alloc")]
+pub mod cow_slice;
+#[cfg(feature = "alloc")]
 pub mod cow_str;
+#[cfg(feature = "alloc")]
+pub mod cow_str_builder;
+#[cfg(feature = "alloc")]
+pub mod delimited;
+#[cfg(feature = "alloc")]
+pub mod encoding;
+#[cfg(feature = "alloc")]
+pub mod escape;
+pub mod fmt_compact;
 pub mod inline_str;
+pub mod inline_vec;
+#[cfg(feature = "alloc")]
+pub mod lazy_cow_str;
+#[cfg(feature = "alloc")]
+pub mod path_str;
+pub mod pre_hashed;
+#[cfg(feature = "alloc")]
+pub mod similarity;
+#[cfg(feature = "alloc")]
+pub mod small_arena;
+#[cfg(feature = "alloc")]
+pub mod small_box;
+#[cfg(feature = "alloc")]
+pub mod small_cstring;
+#[cfg(feature = "alloc")]
+pub mod small_deque;
+#[cfg(feature = "alloc")]
+pub mod small_map;
+#[cfg(feature = "alloc")]
+pub mod small_queue;
+#[cfg(feature = "alloc")]
+pub mod small_set;
+#[cfg(feature = "alloc")]
+pub mod small_string;
+#[cfg(feature = "alloc")]
+pub mod sso_string;
+#[cfg(feature = "alloc")]
+pub mod str_arena;
+#[cfg(feature = "alloc")]
+pub mod str_pool;
+#[cfg(feature = "alloc")]
+pub mod umbra_str;
+#[cfg(feature = "unicode")]
+pub mod unicode;
+#[cfg(feature = "alloc")]
+pub mod well_known;
 
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub use arc_slice::ArcSlice;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub use arc_str::ArcStr;
+#[cfg(feature = "alloc")]
+pub use compact_bit_set::CompactBitSet;
+#[cfg(feature = "alloc")]
+pub use compact_bytes::CompactBytes;
+#[cfg(feature = "hashbrown")]
+pub use compact_hash_map::CompactHashMap;
+#[cfg(feature = "alloc")]
+pub use compact_string::CompactString;
+#[cfg(feature = "alloc")]
+pub use compact_string::MAX_INLINE_COMPACT_STRING_LEN;
+#[cfg(feature = "alloc")]
+pub use compact_vec::CompactVec;
+#[cfg(feature = "alloc")]
+pub use compact_vec::GrowthPolicy;
+#[cfg(feature = "alloc")]
+pub use compact_vec::MemoryUsage;
+#[cfg(feature = "alloc")]
+pub use compact_vec32::CompactVec32;
+#[cfg(feature = "alloc")]
+pub use cow_slice::CowSlice;
+#[cfg(feature = "alloc")]
 pub use cow_str::*;
+#[cfg(feature = "alloc")]
+pub use cow_str_builder::CowStrBuilder;
 pub use inline_str::*;
+pub use inline_vec::CapacityError;
+pub use inline_vec::InlineVec;
+#[cfg(feature = "alloc")]
+pub use lazy_cow_str::LazyCowStr;
+#[cfg(feature = "alloc")]
+pub use path_str::PathStr;
+pub use pre_hashed::PreHashed;
+#[cfg(feature = "alloc")]
+pub use small_arena::SmallArena;
+#[cfg(feature = "alloc")]
+pub use small_box::SmallBox;
+#[cfg(feature = "alloc")]
+pub use small_cstring::SmallCString;
+#[cfg(feature = "alloc")]
+pub use small_deque::SmallDeque;
+#[cfg(feature = "alloc")]
+pub use small_map::SmallMap;
+#[cfg(feature = "alloc")]
+pub use small_queue::SmallQueue;
+#[cfg(feature = "alloc")]
+pub use small_set::SmallSet;
+#[cfg(feature = "alloc")]
+pub use small_string::SmallString;
+#[cfg(feature = "alloc")]
+pub use sso_string::SsoString;
+#[cfg(feature = "alloc")]
+pub use str_arena::StrArena;
+#[cfg(feature = "alloc")]
+pub use str_pool::StrPool;
+#[cfg(feature = "alloc")]
+pub use str_pool::Symbol;
+#[cfg(feature = "alloc")]
+pub use umbra_str::MAX_INLINE_UMBRA_STR_LEN;
+#[cfg(feature = "alloc")]
+pub use umbra_str::UmbraStr;
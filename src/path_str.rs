@@ -0,0 +1,564 @@
+use alloc::string::String;
+use core::cmp::Ordering;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::iter::FusedIterator;
+use core::mem;
+
+use crate::CowStr;
+use crate::MAX_INLINE_STR_LEN;
+use crate::SmallString;
+
+/// A `/`-separated path, stored in a [`CowStr`], for code that addresses
+/// files without `std::path`.
+///
+/// Firmware that reads files from a FAT or littlefs volume needs to split,
+/// join, and clean up paths, but has no `std::path::Path` to do it with.
+/// `PathStr` provides the same lexical operations over a plain string: it
+/// never touches a file system, so `..` is resolved by
+/// [`normalize`](Self::normalize) purely by dropping the previous
+/// component. Only `/` is a separator.
+///
+/// A `PathStr` borrows the string it is created from where it can, and paths
+/// built by [`join`](Self::join) or [`normalize`](Self::normalize) are kept
+/// inline when they are short enough.
+///
+/// Paths compare equal when their [components](Self::components) do, so
+/// `a//b/` and `a/b` are the same path.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::PathStr;
+///
+/// let config = PathStr::new("/sd/config").join("wifi.json");
+/// assert_eq!(config.as_str(), "/sd/config/wifi.json");
+/// assert!(config.into_cow_str().is_inlined());
+///
+/// let log = PathStr::new("/sd/logs/../logs/./2024/boot.log");
+/// assert_eq!(log.file_name(), Some("boot.log"));
+/// assert_eq!(log.extension(), Some("log"));
+/// assert_eq!(log.normalize().as_str(), "/sd/logs/2024/boot.log");
+/// ```
+#[derive(Clone, Default)]
+pub struct PathStr<'a>(CowStr<'a>);
+
+impl<'a> PathStr<'a> {
+  /// Creates a path from a string.
+  #[inline]
+  pub fn new(path: impl Into<CowStr<'a>>) -> Self {
+    Self(path.into())
+  }
+
+  /// Returns the path as a string slice.
+  #[inline(always)]
+  pub fn as_str(&self) -> &str {
+    self.0.as_str()
+  }
+
+  /// Converts the path into the [`CowStr`] that holds it.
+  #[inline(always)]
+  pub fn into_cow_str(self) -> CowStr<'a> {
+    self.0
+  }
+
+  /// Returns `true` if the path starts at the root, with a `/`.
+  #[inline]
+  pub fn is_absolute(&self) -> bool {
+    self.as_str().starts_with('/')
+  }
+
+  /// Returns `true` if the path doesn't start at the root.
+  #[inline]
+  pub fn is_relative(&self) -> bool {
+    !self.is_absolute()
+  }
+
+  /// Returns an iterator over the components of the path.
+  ///
+  /// Repeated and trailing separators are skipped, but `.` and `..` are
+  /// reported as they appear; see [`normalize`](Self::normalize) to resolve
+  /// them.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::PathStr;
+  /// use moos::path_str::Component;
+  ///
+  /// let path = PathStr::new("/data//../log.txt/");
+  /// let components: Vec<_> = path.components().collect();
+  /// assert_eq!(
+  ///   components,
+  ///   [
+  ///     Component::RootDir,
+  ///     Component::Normal("data"),
+  ///     Component::ParentDir,
+  ///     Component::Normal("log.txt"),
+  ///   ]
+  /// );
+  /// ```
+  #[inline]
+  pub fn components(&self) -> Components<'_> {
+    Components {
+      path:     self.as_str(),
+      has_root: self.is_absolute(),
+    }
+  }
+
+  /// Returns the final component of the path, if it is a normal file or
+  /// directory name.
+  ///
+  /// Trailing `.` components are ignored, and `None` is returned if the
+  /// path is empty, is the root, or ends in `..`.
+  pub fn file_name(&self) -> Option<&str> {
+    let last = self
+      .components()
+      .rev()
+      .find(|component| *component != Component::CurDir)?;
+    match last {
+      Component::Normal(name) => Some(name),
+      _ => None,
+    }
+  }
+
+  /// Returns the [file name](Self::file_name) without its extension.
+  ///
+  /// A leading `.` doesn't start an extension, so the stem of `.profile` is
+  /// `.profile`.
+  pub fn file_stem(&self) -> Option<&str> {
+    let name = self.file_name()?;
+    match name.rfind('.') {
+      None | Some(0) => Some(name),
+      Some(dot) => Some(&name[..dot]),
+    }
+  }
+
+  /// Returns the extension of the [file name](Self::file_name): the part
+  /// after its last `.`, unless that is its first character.
+  pub fn extension(&self) -> Option<&str> {
+    let name = self.file_name()?;
+    match name.rfind('.') {
+      None | Some(0) => None,
+      Some(dot) => Some(&name[dot + 1..]),
+    }
+  }
+
+  /// Returns the path without its final component, or `None` if the path
+  /// is empty or the root.
+  ///
+  /// The parent of a single relative component, such as `boot.log`, is the
+  /// empty path.
+  pub fn parent(&self) -> Option<PathStr<'_>> {
+    let mut components = self.components();
+    match components.next_back()? {
+      Component::RootDir => None,
+      _ => Some(PathStr(CowStr::Borrowed(components.as_str()))),
+    }
+  }
+
+  /// Returns `path` appended to this path, with a `/` between them.
+  ///
+  /// If `path` is absolute, it replaces this path entirely. The result is
+  /// stored inline if it fits in [`MAX_INLINE_STR_LEN`] bytes.
+  pub fn join(&self, path: impl AsRef<str>) -> PathStr<'static> {
+    let path = path.as_ref();
+    if path.starts_with('/') {
+      return PathStr(CowStr::from_small(SmallString::from(path)));
+    }
+    let base = self.as_str();
+    let mut joined = SmallString::with_capacity(base.len() + 1 + path.len());
+    joined.push_str(base);
+    if !base.is_empty() && !base.ends_with('/') {
+      joined.push('/');
+    }
+    joined.push_str(path);
+    PathStr(CowStr::from_small(joined))
+  }
+
+  /// Appends `path` to this path in place, as [`join`](Self::join) does.
+  #[inline]
+  pub fn push(&mut self, path: impl AsRef<str>) {
+    *self = self.join(path);
+  }
+
+  /// Returns the shortest path equivalent to this one, resolving `.` and
+  /// `..` lexically, like Go's `path.Clean`.
+  ///
+  /// Repeated and trailing separators and `.` components are removed, and
+  /// each `..` removes the component before it. A `..` that would climb
+  /// above the root is dropped, while leading `..`s of a relative path are
+  /// kept. An empty result becomes `.`.
+  ///
+  /// A path that is already normalized is borrowed rather than copied.
+  pub fn normalize(&self) -> PathStr<'_> {
+    let path = self.as_str();
+    if is_normalized(path) {
+      return PathStr(CowStr::Borrowed(path));
+    }
+    let rooted = self.is_absolute();
+    let mut normal = SmallString::<MAX_INLINE_STR_LEN>::new();
+    if rooted {
+      normal.push('/');
+    }
+    let base = normal.len();
+    // the end of the leading run of `..`s, which can't be backtracked over.
+    let mut floor = base;
+    for segment in path.split('/') {
+      match segment {
+        "" | "." => {}
+        ".." if normal.len() > floor => {
+          let cut = normal[floor..].rfind('/').map_or(floor, |i| floor + i);
+          normal.truncate(cut);
+        }
+        ".." if rooted => {}
+        ".." => {
+          if !normal.is_empty() {
+            normal.push('/');
+          }
+          normal.push_str("..");
+          floor = normal.len();
+        }
+        _ => {
+          if normal.len() > base {
+            normal.push('/');
+          }
+          normal.push_str(segment);
+        }
+      }
+    }
+    if normal.is_empty() {
+      normal.push('.');
+    }
+    PathStr(CowStr::from_small(normal))
+  }
+}
+
+/// Returns `true` if [`PathStr::normalize`] would return `path` unchanged.
+fn is_normalized(path: &str) -> bool {
+  if path == "/" || path == "." {
+    return true;
+  }
+  if path.is_empty() || path.ends_with('/') {
+    return false;
+  }
+  let (rooted, body) = match path.strip_prefix('/') {
+    Some(body) => (true, body),
+    None => (false, path),
+  };
+  let mut leading = !rooted;
+  for segment in body.split('/') {
+    match segment {
+      "" | "." => return false,
+      ".." if !leading => return false,
+      ".." => {}
+      _ => leading = false,
+    }
+  }
+  true
+}
+
+/// A single component of a [`PathStr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Component<'a> {
+  /// The root of an absolute path, `/`.
+  RootDir,
+  /// The current directory, `.`.
+  CurDir,
+  /// The parent directory, `..`.
+  ParentDir,
+  /// A file or directory name.
+  Normal(&'a str),
+}
+
+impl<'a> Component<'a> {
+  #[inline]
+  fn from_segment(segment: &'a str) -> Self {
+    match segment {
+      "." => Component::CurDir,
+      ".." => Component::ParentDir,
+      _ => Component::Normal(segment),
+    }
+  }
+
+  /// Returns the component as a string slice.
+  #[inline]
+  pub fn as_str(&self) -> &'a str {
+    match self {
+      Component::RootDir => "/",
+      Component::CurDir => ".",
+      Component::ParentDir => "..",
+      Component::Normal(name) => name,
+    }
+  }
+}
+
+/// An iterator over the [`Component`]s of a [`PathStr`].
+///
+/// Created by [`PathStr::components`].
+#[derive(Debug, Clone)]
+pub struct Components<'a> {
+  /// The part of the path not yet iterated over, including the root while
+  /// `has_root` is set.
+  path:     &'a str,
+  has_root: bool,
+}
+
+impl<'a> Components<'a> {
+  /// Returns the part of the path not yet iterated over.
+  #[inline(always)]
+  pub fn as_str(&self) -> &'a str {
+    self.path
+  }
+}
+
+impl<'a> Iterator for Components<'a> {
+  type Item = Component<'a>;
+
+  fn next(&mut self) -> Option<Component<'a>> {
+    if mem::take(&mut self.has_root) {
+      self.path = self.path.trim_start_matches('/');
+      return Some(Component::RootDir);
+    }
+    while !self.path.is_empty() {
+      let (segment, rest) =
+        self.path.split_once('/').unwrap_or((self.path, ""));
+      self.path = rest;
+      if !segment.is_empty() {
+        return Some(Component::from_segment(segment));
+      }
+    }
+    None
+  }
+}
+
+impl<'a> DoubleEndedIterator for Components<'a> {
+  fn next_back(&mut self) -> Option<Component<'a>> {
+    loop {
+      let rest = match self.has_root {
+        true => self.path.trim_start_matches('/'),
+        false => self.path,
+      };
+      if rest.is_empty() {
+        self.path = "";
+        return mem::take(&mut self.has_root).then_some(Component::RootDir);
+      }
+      let (rest, segment) =
+        self.path.rsplit_once('/').unwrap_or(("", self.path));
+      // keep the root in the remaining path once only it is left.
+      self.path = match rest.is_empty() && self.has_root {
+        true => &self.path[..1],
+        false => rest,
+      };
+      if !segment.is_empty() {
+        return Some(Component::from_segment(segment));
+      }
+    }
+  }
+}
+
+impl FusedIterator for Components<'_> {}
+
+impl Debug for PathStr<'_> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Debug::fmt(self.as_str(), f)
+  }
+}
+
+impl Display for PathStr<'_> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Display::fmt(self.as_str(), f)
+  }
+}
+
+impl AsRef<str> for PathStr<'_> {
+  #[inline(always)]
+  fn as_ref(&self) -> &str {
+    self.as_str()
+  }
+}
+
+impl PartialEq for PathStr<'_> {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    self.as_str() == other.as_str() || self.components().eq(other.components())
+  }
+}
+
+impl Eq for PathStr<'_> {}
+
+impl PartialOrd for PathStr<'_> {
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for PathStr<'_> {
+  /// Compares paths component by component.
+  #[inline]
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.components().cmp(other.components())
+  }
+}
+
+impl Hash for PathStr<'_> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    for component in self.components() {
+      component.hash(state);
+    }
+  }
+}
+
+impl<'a> From<&'a str> for PathStr<'a> {
+  #[inline(always)]
+  fn from(path: &'a str) -> Self {
+    Self(CowStr::Borrowed(path))
+  }
+}
+
+impl From<String> for PathStr<'_> {
+  #[inline(always)]
+  fn from(path: String) -> Self {
+    Self(CowStr::from(path))
+  }
+}
+
+impl<'a> From<CowStr<'a>> for PathStr<'a> {
+  #[inline(always)]
+  fn from(path: CowStr<'a>) -> Self {
+    Self(path)
+  }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+
+  use super::*;
+
+  impl Serialize for PathStr<'_> {
+    #[inline(always)]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      self.0.serialize(serializer)
+    }
+  }
+
+  impl<'a, 'de: 'a> Deserialize<'de> for PathStr<'a> {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      CowStr::deserialize(deserializer).map(Self)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::vec::Vec;
+
+  use super::*;
+
+  fn names(path: &str) -> Vec<String> {
+    PathStr::new(path)
+      .components()
+      .map(|c| String::from(c.as_str()))
+      .collect()
+  }
+
+  #[test]
+  fn path_str_components() {
+    assert_eq!(names("/a//b/./c/"), ["/", "a", "b", ".", "c"]);
+    assert_eq!(names("../a"), ["..", "a"]);
+    assert_eq!(names("//"), ["/"]);
+    assert!(names("").is_empty());
+
+    let path = PathStr::new("/a/b/c");
+    let back: Vec<_> = path.components().rev().map(|c| c.as_str()).collect();
+    assert_eq!(back, ["c", "b", "a", "/"]);
+    let mut components = path.components();
+    assert_eq!(components.next(), Some(Component::RootDir));
+    assert_eq!(components.next_back(), Some(Component::Normal("c")));
+    assert_eq!(components.as_str(), "a/b");
+  }
+
+  #[test]
+  fn path_str_accessors() {
+    let path = PathStr::new("fw/images/app.v2.bin");
+    assert_eq!(path.file_name(), Some("app.v2.bin"));
+    assert_eq!(path.file_stem(), Some("app.v2"));
+    assert_eq!(path.extension(), Some("bin"));
+    assert_eq!(path.parent().unwrap().as_str(), "fw/images");
+
+    let hidden = PathStr::new("/home/.profile/.");
+    assert_eq!(hidden.file_name(), Some(".profile"));
+    assert_eq!(hidden.extension(), None);
+    assert_eq!(PathStr::new("a/..").file_name(), None);
+
+    assert_eq!(PathStr::new("/a").parent().unwrap().as_str(), "/");
+    assert_eq!(PathStr::new("a").parent().unwrap().as_str(), "");
+    assert!(PathStr::new("/").parent().is_none());
+    assert!(PathStr::new("").parent().is_none());
+  }
+
+  #[test]
+  fn path_str_join() {
+    let mut path = PathStr::new("/sd");
+    path.push("logs");
+    assert_eq!(path.as_str(), "/sd/logs");
+    assert_eq!(PathStr::new("a/").join("b").as_str(), "a/b");
+    assert_eq!(PathStr::new("").join("b").as_str(), "b");
+    assert_eq!(PathStr::new("a").join("/b").as_str(), "/b");
+
+    let long = PathStr::new("/sd/a/rather/deep/directory").join("file.txt");
+    assert!(long.clone().into_cow_str().is_owned());
+    assert_eq!(long.as_str(), "/sd/a/rather/deep/directory/file.txt");
+  }
+
+  #[test]
+  fn path_str_normalize() {
+    let cases = [
+      ("", "."),
+      (".", "."),
+      ("/", "/"),
+      ("//", "/"),
+      ("a/b/", "a/b"),
+      ("./a/./b", "a/b"),
+      ("a/../b", "b"),
+      ("a/..", "."),
+      ("../a/../../b", "../../b"),
+      ("/../a", "/a"),
+      ("/a/b/../../..", "/"),
+      ("a//b/c/../d", "a/b/d"),
+    ];
+    for (path, expected) in cases {
+      let path = PathStr::new(path);
+      assert_eq!(path.normalize().as_str(), expected, "{path:?}");
+      assert!(is_normalized(expected));
+    }
+    let clean = PathStr::new(String::from("../../lib/mod.rs"));
+    assert!(clean.normalize().into_cow_str().is_borrowed());
+  }
+
+  #[test]
+  fn path_str_equality() {
+    assert_eq!(PathStr::new("a//b/"), PathStr::new("a/b"));
+    assert_ne!(PathStr::new("/a"), PathStr::new("a"));
+    assert!(PathStr::new("a/b") < PathStr::new("a/c"));
+    assert_eq!(format!("{:?}", PathStr::new("x")), r#""x""#);
+  }
+}
@@ -0,0 +1,310 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::hash::BuildHasher;
+use core::hash::BuildHasherDefault;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::ops::Deref;
+
+/// A key wrapper that computes the hash of its value once, up front, and
+/// reuses it every time the key is hashed or compared.
+///
+/// Hashing a `PreHashed` writes only the cached `u64`, and equality checks
+/// compare the cached hashes before the values, so looking the same small
+/// keys up over and over skips rehashing their contents. Pair it with
+/// [`BuildPreHashed`] to pass the cached hash straight through as the map's
+/// hash code, instead of hashing it a second time.
+///
+/// The value can't be mutated in place, since that would leave the cached
+/// hash stale; use [`into_inner`](Self::into_inner) and wrap it again.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use moos::InlineStr;
+/// use moos::PreHashed;
+/// use moos::pre_hashed::BuildPreHashed;
+///
+/// let key = PreHashed::new(InlineStr::try_from("temperature").unwrap());
+/// let mut readings: HashMap<_, f32, BuildPreHashed> = HashMap::default();
+/// readings.insert(key.clone(), 21.5);
+///
+/// // later lookups reuse the hash computed above.
+/// for _ in 0..3 {
+///   *readings.get_mut(&key).unwrap() += 0.5;
+/// }
+/// assert_eq!(readings[&key], 23.0);
+/// ```
+#[derive(Clone, Copy)]
+pub struct PreHashed<S> {
+  hash:  u64,
+  value: S,
+}
+
+impl<S: Hash> PreHashed<S> {
+  /// Wraps `value`, hashing it with [`FxHasher`].
+  #[inline]
+  pub fn new(value: S) -> Self {
+    Self::with_hasher(value, &BuildHasherDefault::<FxHasher>::default())
+  }
+
+  /// Wraps `value`, hashing it with a hasher from `build_hasher`.
+  ///
+  /// Keys that are compared with each other must all be hashed the same way.
+  #[inline]
+  pub fn with_hasher(value: S, build_hasher: &impl BuildHasher) -> Self {
+    Self {
+      hash: build_hasher.hash_one(&value),
+      value,
+    }
+  }
+}
+
+impl<S> PreHashed<S> {
+  /// Returns the cached hash of the value.
+  #[inline(always)]
+  pub const fn cached_hash(&self) -> u64 {
+    self.hash
+  }
+
+  /// Returns a reference to the value.
+  #[inline(always)]
+  pub const fn get(&self) -> &S {
+    &self.value
+  }
+
+  /// Unwraps the value, discarding the cached hash.
+  #[inline(always)]
+  pub fn into_inner(self) -> S {
+    self.value
+  }
+}
+
+impl<S> Deref for PreHashed<S> {
+  type Target = S;
+
+  #[inline(always)]
+  fn deref(&self) -> &S {
+    &self.value
+  }
+}
+
+impl<S> AsRef<S> for PreHashed<S> {
+  #[inline(always)]
+  fn as_ref(&self) -> &S {
+    &self.value
+  }
+}
+
+impl<S> Hash for PreHashed<S> {
+  /// Writes the cached hash, without hashing the value again.
+  #[inline(always)]
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    state.write_u64(self.hash);
+  }
+}
+
+impl<S: PartialEq> PartialEq for PreHashed<S> {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    self.hash == other.hash && self.value == other.value
+  }
+}
+
+impl<S: Eq> Eq for PreHashed<S> {}
+
+impl<S: PartialOrd> PartialOrd for PreHashed<S> {
+  /// Orders keys by their values, not their hashes.
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    self.value.partial_cmp(&other.value)
+  }
+}
+
+impl<S: Ord> Ord for PreHashed<S> {
+  #[inline]
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.value.cmp(&other.value)
+  }
+}
+
+impl<S: Debug> Debug for PreHashed<S> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Debug::fmt(&self.value, f)
+  }
+}
+
+impl<S: Display> Display for PreHashed<S> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Display::fmt(&self.value, f)
+  }
+}
+
+impl<S: Hash> From<S> for PreHashed<S> {
+  #[inline(always)]
+  fn from(value: S) -> Self {
+    Self::new(value)
+  }
+}
+
+/// A fast, non-cryptographic hasher using the Fx algorithm from `rustc`.
+///
+/// It is the default hasher of [`PreHashed`], and suits short keys that
+/// don't come from untrusted input: it offers no protection against
+/// deliberately colliding keys.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FxHasher {
+  hash: u64,
+}
+
+impl FxHasher {
+  const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+  #[inline(always)]
+  fn add(&mut self, word: u64) {
+    self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(Self::SEED);
+  }
+}
+
+impl Hasher for FxHasher {
+  #[inline]
+  fn write(&mut self, bytes: &[u8]) {
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+      self.add(u64::from_le_bytes(chunk.try_into().unwrap()));
+    }
+    let mut tail = [0; 8];
+    tail[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    self.add(u64::from_le_bytes(tail));
+  }
+
+  #[inline(always)]
+  fn write_u8(&mut self, i: u8) {
+    self.add(i as u64);
+  }
+
+  #[inline(always)]
+  fn write_u32(&mut self, i: u32) {
+    self.add(i as u64);
+  }
+
+  #[inline(always)]
+  fn write_u64(&mut self, i: u64) {
+    self.add(i);
+  }
+
+  #[inline(always)]
+  fn write_usize(&mut self, i: usize) {
+    self.add(i as u64);
+  }
+
+  #[inline(always)]
+  fn finish(&self) -> u64 {
+    self.hash
+  }
+}
+
+/// A [`BuildHasher`] for maps keyed on [`PreHashed`] values, which uses the
+/// cached hash of each key as its hash code.
+pub type BuildPreHashed = BuildHasherDefault<PreHashedHasher>;
+
+/// The [`Hasher`] behind [`BuildPreHashed`].
+///
+/// It returns the last `u64` written to it unchanged, as written by
+/// [`PreHashed`]'s `Hash` impl. Other writes are mixed in with [`FxHasher`],
+/// so it still works, more slowly, for keys of other types.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PreHashedHasher {
+  inner: FxHasher,
+}
+
+impl Hasher for PreHashedHasher {
+  #[inline]
+  fn write(&mut self, bytes: &[u8]) {
+    self.inner.write(bytes);
+  }
+
+  #[inline(always)]
+  fn write_u64(&mut self, i: u64) {
+    self.inner.hash = i;
+  }
+
+  #[inline(always)]
+  fn finish(&self) -> u64 {
+    self.inner.finish()
+  }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+
+  use super::*;
+
+  impl<S: Serialize> Serialize for PreHashed<S> {
+    /// Serializes the value alone; the hash is recomputed on the way back.
+    #[inline(always)]
+    fn serialize<T>(&self, serializer: T) -> Result<T::Ok, T::Error>
+    where
+      T: Serializer,
+    {
+      self.value.serialize(serializer)
+    }
+  }
+
+  impl<'de, S: Deserialize<'de> + Hash> Deserialize<'de> for PreHashed<S> {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      S::deserialize(deserializer).map(Self::new)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::*;
+  use crate::CowStr;
+  use crate::SmallString;
+
+  #[test]
+  fn pre_hashed_caches_hash() {
+    let a = PreHashed::new(SmallString::<16>::from("key"));
+    let b = PreHashed::from(SmallString::<16>::from("key"));
+    let c = PreHashed::new(SmallString::<16>::from("other"));
+    assert_eq!(a.cached_hash(), b.cached_hash());
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_eq!(a.as_str(), "key");
+
+    let mut hasher = PreHashedHasher::default();
+    a.hash(&mut hasher);
+    assert_eq!(hasher.finish(), a.cached_hash());
+  }
+
+  #[test]
+  fn pre_hashed_map_lookups() {
+    let mut map: HashMap<PreHashed<CowStr>, usize, BuildPreHashed> =
+      HashMap::default();
+    for (i, word) in ["a", "b", "c", "a"].into_iter().enumerate() {
+      map.insert(PreHashed::new(CowStr::Borrowed(word)), i);
+    }
+    assert_eq!(map.len(), 3);
+    assert_eq!(map[&PreHashed::new(CowStr::Borrowed("a"))], 3);
+    assert_eq!(PreHashed::new(3).into_inner(), 3);
+  }
+}
@@ -0,0 +1,238 @@
+//! Edit distances and fuzzy matching for short strings.
+//!
+//! These are meant for "did you mean" suggestions on the short identifiers
+//! of a command line, such as subcommand, flag, and key names. Distances
+//! are counted in `char`s, and the characters and rows of the distance
+//! table are kept on the stack for strings of up to
+//! [`MAX_INLINE_STR_LEN`] characters, so matching short strings never
+//! allocates. Longer strings still work, on the heap.
+//!
+//! # Example
+//!
+//! ```rust
+//! use moos::similarity;
+//!
+//! let commands = ["build", "check", "clean", "install", "publish"];
+//! assert_eq!(similarity::suggest("biuld", commands), Some("build"));
+//! assert_eq!(similarity::suggest("inst", commands), Some("install"));
+//! assert_eq!(similarity::suggest("deploy", commands), None);
+//!
+//! assert_eq!(similarity::levenshtein("kitten", "sitting"), 3);
+//! assert_eq!(similarity::damerau_levenshtein("ca", "ac"), 1);
+//! ```
+
+use core::cmp::Reverse;
+use core::mem;
+
+use crate::CompactVec;
+use crate::MAX_INLINE_STR_LEN;
+
+/// The characters of a string being compared.
+type Chars = CompactVec<char, MAX_INLINE_STR_LEN>;
+
+/// A row of the distance table, one longer than the string it runs along.
+type Row = CompactVec<usize, { MAX_INLINE_STR_LEN + 1 }>;
+
+/// Returns the characters of `a` and `b`, without the prefix and suffix
+/// they share, which don't change the distance between them.
+fn trimmed_chars(a: &str, b: &str) -> (Chars, Chars) {
+  let prefix = a
+    .char_indices()
+    .zip(b.chars())
+    .find(|&((_, x), y)| x != y)
+    .map_or(a.len().min(b.len()), |((i, _), _)| i);
+  let (a, b) = (&a[prefix..], &b[prefix..]);
+  let suffix = a
+    .chars()
+    .rev()
+    .zip(b.chars().rev())
+    .take_while(|(x, y)| x == y)
+    .map(|(x, _)| x.len_utf8())
+    .sum::<usize>();
+  (
+    a[..a.len() - suffix].chars().collect(),
+    b[..b.len() - suffix].chars().collect(),
+  )
+}
+
+/// Returns the Levenshtein distance between `a` and `b`: the fewest
+/// single-character insertions, deletions, and substitutions that turn one
+/// into the other.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::similarity::levenshtein;
+///
+/// assert_eq!(levenshtein("flaw", "lawn"), 2);
+/// assert_eq!(levenshtein("", "moo"), 3);
+/// assert_eq!(levenshtein("naïve", "naive"), 1);
+/// ```
+pub fn levenshtein(a: &str, b: &str) -> usize {
+  let (a, b) = trimmed_chars(a, b);
+  if a.is_empty() || b.is_empty() {
+    return a.len().max(b.len());
+  }
+  let mut row: Row = (0..=b.len()).collect();
+  for (i, &x) in a.iter().enumerate() {
+    // the value of the previous row, one column to the left.
+    let mut diagonal = mem::replace(&mut row[0], i + 1);
+    for (j, &y) in b.iter().enumerate() {
+      let above = row[j + 1];
+      row[j + 1] = match x == y {
+        true => diagonal,
+        false => 1 + diagonal.min(above).min(row[j]),
+      };
+      diagonal = above;
+    }
+  }
+  row[b.len()]
+}
+
+/// Returns the Damerau-Levenshtein distance between `a` and `b`, which also
+/// counts swapping two adjacent characters as a single edit.
+///
+/// This is the restricted, or optimal string alignment, variant, in which
+/// no part of the string is edited more than once. It catches the most
+/// common typo, a transposition, that [`levenshtein`] counts as two edits.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::similarity::damerau_levenshtein;
+/// use moos::similarity::levenshtein;
+///
+/// assert_eq!(damerau_levenshtein("stauts", "status"), 1);
+/// assert_eq!(levenshtein("stauts", "status"), 2);
+/// ```
+pub fn damerau_levenshtein(a: &str, b: &str) -> usize {
+  let (a, b) = trimmed_chars(a, b);
+  if a.is_empty() || b.is_empty() {
+    return a.len().max(b.len());
+  }
+  let n = b.len();
+  let mut before: Row = CompactVec::from_elem(0, n + 1);
+  let mut previous: Row = (0..=n).collect();
+  let mut current: Row = CompactVec::from_elem(0, n + 1);
+  for i in 1..=a.len() {
+    current[0] = i;
+    for j in 1..=n {
+      let cost = usize::from(a[i - 1] != b[j - 1]);
+      let mut distance = (previous[j] + 1)
+        .min(current[j - 1] + 1)
+        .min(previous[j - 1] + cost);
+      if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+        distance = distance.min(before[j - 2] + 1);
+      }
+      current[j] = distance;
+    }
+    mem::swap(&mut before, &mut previous);
+    mem::swap(&mut previous, &mut current);
+  }
+  previous[n]
+}
+
+/// Returns the number of leading characters `input` and `candidate` have in
+/// common, ignoring ASCII case.
+///
+/// A score equal to the length of `input` in characters means that
+/// `candidate` starts with `input`, as when a user types an abbreviation.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::similarity::prefix_score;
+///
+/// assert_eq!(prefix_score("Conf", "config"), 4);
+/// assert_eq!(prefix_score("conf", "cnofig"), 1);
+/// ```
+pub fn prefix_score(input: &str, candidate: &str) -> usize {
+  input
+    .chars()
+    .zip(candidate.chars())
+    .take_while(|(x, y)| x.eq_ignore_ascii_case(y))
+    .count()
+}
+
+/// Returns the candidate that `input` was most likely meant to be, if any
+/// is close enough.
+///
+/// A candidate is close enough if `input` is a prefix of it, or if their
+/// [`damerau_levenshtein`] distance is at most a third of the length of
+/// `input`, and at least 1. The candidate with the smallest distance wins,
+/// then the one with the highest [`prefix_score`], then the one that comes
+/// first.
+pub fn suggest<'c>(
+  input: &str,
+  candidates: impl IntoIterator<Item = &'c str>,
+) -> Option<&'c str> {
+  let len = input.chars().count();
+  let max_distance = (len / 3).max(1);
+  candidates
+    .into_iter()
+    .filter_map(|candidate| {
+      let distance = damerau_levenshtein(input, candidate);
+      let score = prefix_score(input, candidate);
+      let close = distance <= max_distance || (len > 0 && score == len);
+      close.then_some((distance, Reverse(score), candidate))
+    })
+    .min_by_key(|&(distance, score, _)| (distance, score))
+    .map(|(_, _, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn similarity_levenshtein() {
+    let cases = [
+      ("", "", 0),
+      ("moo", "moo", 0),
+      ("moo", "", 3),
+      ("kitten", "sitting", 3),
+      ("saturday", "sunday", 3),
+      ("ab", "ba", 2),
+      ("über", "uber", 1),
+    ];
+    for (a, b, distance) in cases {
+      assert_eq!(levenshtein(a, b), distance, "{a:?} {b:?}");
+      assert_eq!(levenshtein(b, a), distance, "{b:?} {a:?}");
+    }
+  }
+
+  #[test]
+  fn similarity_damerau_levenshtein() {
+    let cases = [
+      ("ab", "ba", 1),
+      ("abc", "ca", 3),
+      ("recieve", "receive", 1),
+      ("kitten", "sitting", 3),
+      ("", "ab", 2),
+    ];
+    for (a, b, distance) in cases {
+      assert_eq!(damerau_levenshtein(a, b), distance, "{a:?} {b:?}");
+      assert_eq!(damerau_levenshtein(b, a), distance, "{b:?} {a:?}");
+    }
+  }
+
+  #[test]
+  fn similarity_long_strings() {
+    let a = "the quick brown fox jumps over the lazy dog";
+    let b = "the quick brown cat jumps over a lazy dog!";
+    assert!(a.len() > MAX_INLINE_STR_LEN);
+    assert_eq!(levenshtein(a, b), 7);
+    assert_eq!(damerau_levenshtein(a, b), 7);
+  }
+
+  #[test]
+  fn similarity_suggest() {
+    let flags = ["--verbose", "--version", "--quiet", "--help"];
+    assert_eq!(suggest("--verison", flags), Some("--version"));
+    assert_eq!(suggest("--vers", flags), Some("--version"));
+    assert_eq!(suggest("--QUIET", flags), Some("--quiet"));
+    assert_eq!(suggest("--output", flags), None);
+    assert_eq!(suggest("", flags), None);
+    assert_eq!(suggest("x", ["y", "xy"]), Some("xy"));
+  }
+}
@@ -0,0 +1,302 @@
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::iter::Chain;
+use core::iter::Flatten;
+use core::iter::FusedIterator;
+use core::slice;
+
+/// The smallest heap chunk a [`SmallArena`] allocates once its inline chunk
+/// is full.
+const MIN_CHUNK_LEN: usize = 8;
+
+/// A typed arena that hands out `&mut T` references to the values moved into
+/// it, and drops them all at once.
+///
+/// The first `N` values are stored inline in the arena itself, so an arena
+/// that never outgrows them doesn't allocate at all. After that, values go
+/// into heap chunks, each twice the size of the last. Chunks are never moved
+/// or resized, so references handed out stay valid for as long as the arena
+/// is borrowed.
+///
+/// This makes a `SmallArena` a good fit for the short-lived trees of a
+/// parser, such as an AST whose nodes refer to each other through plain
+/// references. Since the arena has no `Drop` impl of its own, its values may
+/// borrow from the arena itself; in exchange, each inline slot is stored as
+/// an `Option<T>`, which can be larger than a `T`.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::SmallArena;
+///
+/// enum Expr<'a> {
+///   Num(i64),
+///   Add(&'a Expr<'a>, &'a Expr<'a>),
+///   Mul(&'a Expr<'a>, &'a Expr<'a>),
+/// }
+///
+/// fn eval(expr: &Expr<'_>) -> i64 {
+///   match expr {
+///     Expr::Num(n) => *n,
+///     Expr::Add(a, b) => eval(a) + eval(b),
+///     Expr::Mul(a, b) => eval(a) * eval(b),
+///   }
+/// }
+///
+/// let arena = SmallArena::<Expr<'_>, 8>::new();
+/// let two = arena.alloc(Expr::Num(2));
+/// let sum = arena.alloc(Expr::Add(two, arena.alloc(Expr::Num(3))));
+/// let product = arena.alloc(Expr::Mul(sum, two));
+/// assert_eq!(eval(product), 10);
+/// assert_eq!(arena.len(), 4);
+/// assert!(arena.is_inline());
+/// ```
+pub struct SmallArena<T, const N: usize> {
+  inline: UnsafeCell<[Option<T>; N]>,
+  chunks: UnsafeCell<Vec<Vec<T>>>,
+  /// The number of values in the arena, inline or not.
+  len:    Cell<usize>,
+}
+
+impl<T, const N: usize> SmallArena<T, N> {
+  /// Creates a new, empty arena. Does not allocate until more than `N`
+  /// values are allocated.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      inline: UnsafeCell::new([const { None }; N]),
+      chunks: UnsafeCell::new(Vec::new()),
+      len:    Cell::new(0),
+    }
+  }
+
+  /// Moves `value` into the arena, returning a mutable reference to it.
+  #[inline]
+  #[allow(clippy::mut_from_ref)]
+  pub fn alloc(&self, value: T) -> &mut T {
+    let len = self.len.get();
+    self.len.set(len + 1);
+    if len < N {
+      // SAFETY: each inline slot is handed out at most once, and `len < N`
+      // keeps the pointer within the array.
+      let slot =
+        unsafe { &mut *self.inline.get().cast::<Option<T>>().add(len) };
+      return slot.insert(value);
+    }
+    self.alloc_slow(value)
+  }
+
+  #[cold]
+  #[allow(clippy::mut_from_ref)]
+  fn alloc_slow(&self, value: T) -> &mut T {
+    // SAFETY: the chunk list is only borrowed for the duration of this call,
+    // and the arena is not `Sync`.
+    let chunks = unsafe { &mut *self.chunks.get() };
+    let full = chunks
+      .last()
+      .is_none_or(|chunk| chunk.len() == chunk.capacity());
+    if full {
+      let last = chunks.last().map_or(N, Vec::capacity);
+      chunks.push(Vec::with_capacity(
+        last.saturating_mul(2).max(MIN_CHUNK_LEN),
+      ));
+    }
+    let chunk = chunks.last_mut().unwrap();
+    let index = chunk.len();
+    chunk.push(value);
+    // SAFETY: a chunk is never pushed past its capacity, so its buffer, and
+    // the values already handed out from it, never move.
+    unsafe { &mut *chunk.as_mut_ptr().add(index) }
+  }
+
+  /// Returns the number of values in the arena.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.len.get()
+  }
+
+  /// Returns `true` if the arena holds no values.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.len.get() == 0
+  }
+
+  /// Returns `true` if every value in the arena is stored inline.
+  #[inline(always)]
+  pub fn is_inline(&self) -> bool {
+    self.len.get() <= N
+  }
+
+  /// Returns an iterator over mutable references to the values in the
+  /// arena, in the order they were allocated.
+  #[inline]
+  pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+    let inline = self.inline.get_mut().iter_mut().flatten();
+    let chunks = self.chunks.get_mut().iter_mut().flatten();
+    IterMut {
+      inner: inline.chain(chunks),
+    }
+  }
+
+  /// Drops every value in the arena at once.
+  ///
+  /// The largest heap chunk is kept for reuse, so an arena that is reset
+  /// between passes of similar size stops allocating after the first.
+  pub fn reset(&mut self) {
+    self
+      .inline
+      .get_mut()
+      .iter_mut()
+      .for_each(|slot| *slot = None);
+    let chunks = self.chunks.get_mut();
+    if let Some(largest) =
+      (0..chunks.len()).max_by_key(|&i| chunks[i].capacity())
+    {
+      let mut chunk = chunks.swap_remove(largest);
+      chunks.clear();
+      chunk.clear();
+      chunks.push(chunk);
+    }
+    self.len.set(0);
+  }
+
+  /// Moves the values out of the arena into a `Vec`, in the order they were
+  /// allocated.
+  pub fn into_vec(self) -> Vec<T> {
+    let mut vec = Vec::with_capacity(self.len.get());
+    vec.extend(self.inline.into_inner().into_iter().flatten());
+    for chunk in self.chunks.into_inner() {
+      vec.extend(chunk);
+    }
+    vec
+  }
+}
+
+impl<T, const N: usize> Default for SmallArena<T, N> {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T, const N: usize> Debug for SmallArena<T, N> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_struct("SmallArena")
+      .field("len", &self.len())
+      .field("inline", &self.is_inline())
+      .finish()
+  }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut SmallArena<T, N> {
+  type IntoIter = IterMut<'a, T>;
+  type Item = &'a mut T;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter_mut()
+  }
+}
+
+/// An iterator over mutable references to the values in a [`SmallArena`],
+/// created by [`SmallArena::iter_mut`].
+pub struct IterMut<'a, T> {
+  inner: Chain<InlineIter<'a, T>, ChunksIter<'a, T>>,
+}
+
+type InlineIter<'a, T> = Flatten<slice::IterMut<'a, Option<T>>>;
+type ChunksIter<'a, T> = Flatten<slice::IterMut<'a, Vec<T>>>;
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+  type Item = &'a mut T;
+
+  #[inline(always)]
+  fn next(&mut self) -> Option<&'a mut T> {
+    self.inner.next()
+  }
+}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+  use alloc::rc::Rc;
+  use alloc::string::String;
+  use alloc::vec;
+
+  use super::*;
+
+  #[test]
+  fn small_arena_inline_then_chunks() {
+    let arena = SmallArena::<u32, 2>::new();
+    let a = arena.alloc(1);
+    let b = arena.alloc(2);
+    assert!(arena.is_inline());
+    let c = arena.alloc(3);
+    assert!(!arena.is_inline());
+    *a += 10;
+    *c += 10;
+    assert_eq!((*a, *b, *c), (11, 2, 13));
+
+    for i in 4..40 {
+      assert_eq!(*arena.alloc(i), i);
+    }
+    assert_eq!(arena.len(), 39);
+    assert_eq!(arena.into_vec()[..4], [11, 2, 13, 4]);
+  }
+
+  #[test]
+  fn small_arena_self_references() {
+    struct Node<'a> {
+      name:   String,
+      parent: Option<&'a Node<'a>>,
+    }
+
+    let arena = SmallArena::<Node<'_>, 1>::new();
+    let root = arena.alloc(Node {
+      name:   String::from("root"),
+      parent: None,
+    });
+    let child = arena.alloc(Node {
+      name:   String::from("child"),
+      parent: Some(root),
+    });
+    assert_eq!(child.parent.unwrap().name, "root");
+  }
+
+  #[test]
+  fn small_arena_drops_and_resets() {
+    let count = Rc::new(());
+    let mut arena = SmallArena::<Rc<()>, 2>::new();
+    for _ in 0..20 {
+      arena.alloc(count.clone());
+    }
+    assert_eq!(Rc::strong_count(&count), 21);
+    assert_eq!(arena.iter_mut().count(), 20);
+
+    arena.reset();
+    assert!(arena.is_empty());
+    assert_eq!(Rc::strong_count(&count), 1);
+    arena.alloc(count.clone());
+    arena.alloc(count.clone());
+    arena.alloc(count.clone());
+    drop(arena);
+    assert_eq!(Rc::strong_count(&count), 1);
+  }
+
+  #[test]
+  fn small_arena_iter_mut() {
+    let mut arena = SmallArena::<i32, 3>::default();
+    for i in 0..6 {
+      arena.alloc(i);
+    }
+    for value in &mut arena {
+      *value *= 2;
+    }
+    assert_eq!(arena.into_vec(), vec![0, 2, 4, 6, 8, 10]);
+  }
+}
@@ -0,0 +1,351 @@
+use alloc::alloc::Layout;
+use alloc::alloc::alloc;
+use alloc::alloc::dealloc;
+use alloc::alloc::handle_alloc_error;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::marker::PhantomData;
+use core::mem;
+use core::mem::ManuallyDrop;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+use core::ops::DerefMut;
+use core::ptr;
+
+/// A box that stores its value inline when it fits within `N` bytes, and on
+/// the heap otherwise.
+///
+/// Unlike [`Box`](alloc::boxed::Box), `T` may be a trait object or slice:
+/// values are unsized into a `SmallBox<dyn Trait, N>` with the [`smallbox!`]
+/// macro, so closures and small polymorphic payloads can be queued without
+/// an allocation each. A value is kept inline if its size is at most `N`
+/// and its alignment is at most that of `usize`.
+///
+/// Because inline values move along with the box, a `SmallBox` cannot be
+/// used to pin a value that is not [`Unpin`].
+///
+/// # Example
+///
+/// ```rust
+/// use moos::SmallBox;
+/// use moos::smallbox;
+///
+/// let mut queue: Vec<SmallBox<dyn Fn(u32) -> u32, 16>> = Vec::new();
+/// let offset = 10;
+/// queue.push(smallbox!(move |x| x + offset));
+/// queue.push(smallbox!(|x| x * 2));
+///
+/// assert!(queue.iter().all(|f| f.is_inline()));
+/// let results: Vec<u32> = queue.iter().map(|f| f(4)).collect();
+/// assert_eq!(results, [14, 8]);
+/// ```
+pub struct SmallBox<T: ?Sized, const N: usize> {
+  space:   Space<N>,
+  /// Points to the heap allocation holding the value, or is null if the
+  /// value is stored inline in `space`.
+  heap:    *mut u8,
+  /// Restores the pointer metadata of `T` onto a pointer to the value.
+  coerce:  fn(*mut u8) -> *mut T,
+  _marker: PhantomData<T>,
+}
+
+/// Inline storage for a [`SmallBox`], aligned for `usize`.
+#[repr(C)]
+struct Space<const N: usize> {
+  _align: [usize; 0],
+  bytes:  [MaybeUninit<u8>; N],
+}
+
+unsafe impl<T: ?Sized + Send, const N: usize> Send for SmallBox<T, N> {}
+unsafe impl<T: ?Sized + Sync, const N: usize> Sync for SmallBox<T, N> {}
+
+impl<T, const N: usize> SmallBox<T, N> {
+  /// Creates a new `SmallBox` containing `value`, stored inline if it fits.
+  ///
+  /// To store the value as an unsized type such as `dyn Trait`, use the
+  /// [`smallbox!`] macro instead.
+  #[inline]
+  pub fn new(value: T) -> Self {
+    // SAFETY: the identity function leaves the pointer unchanged.
+    unsafe { Self::new_unchecked(value, |ptr| ptr) }
+  }
+
+  /// Moves the value out of the box, releasing any heap storage.
+  pub fn into_inner(self) -> T {
+    let this = ManuallyDrop::new(self);
+    let ptr = this.as_ptr();
+    unsafe {
+      let value = ptr::read(ptr);
+      if !this.heap.is_null() && mem::size_of::<T>() != 0 {
+        dealloc(this.heap, Layout::new::<T>());
+      }
+      value
+    }
+  }
+}
+
+impl<T: ?Sized, const N: usize> SmallBox<T, N> {
+  /// Creates a new `SmallBox` from `value`, using `coerce` to convert a
+  /// pointer to it into a pointer to `T`.
+  ///
+  /// This is the building block of the [`smallbox!`] macro, which should be
+  /// preferred.
+  ///
+  /// # Safety
+  ///
+  /// `coerce` must return its argument unchanged apart from (possibly) an
+  /// unsizing coercion, such as `|ptr| ptr`.
+  #[doc(hidden)]
+  pub unsafe fn new_unchecked<U>(
+    value: U,
+    coerce: fn(*mut U) -> *mut T,
+  ) -> Self {
+    let layout = Layout::new::<U>();
+    let fits = layout.size() <= N && layout.align() <= mem::align_of::<usize>();
+    let heap = if fits {
+      ptr::null_mut()
+    } else if layout.size() == 0 {
+      ptr::NonNull::<U>::dangling().as_ptr().cast()
+    } else {
+      let heap = unsafe { alloc(layout) };
+      if heap.is_null() {
+        handle_alloc_error(layout);
+      }
+      heap
+    };
+    let mut this = Self {
+      space: Space {
+        _align: [],
+        bytes:  [MaybeUninit::uninit(); N],
+      },
+      heap,
+      // SAFETY: `*mut U` and `*mut u8` are both thin pointers, so the two
+      // function pointer types are ABI-compatible.
+      coerce: unsafe {
+        mem::transmute::<fn(*mut U) -> *mut T, fn(*mut u8) -> *mut T>(coerce)
+      },
+      _marker: PhantomData,
+    };
+    unsafe { this.data_ptr().cast::<U>().write(value) };
+    this
+  }
+
+  /// Returns `true` if the value is stored inline.
+  #[inline(always)]
+  pub fn is_inline(&self) -> bool {
+    self.heap.is_null()
+  }
+
+  /// Returns a raw pointer to the value.
+  #[inline]
+  pub fn as_ptr(&self) -> *const T {
+    let data = if self.heap.is_null() {
+      self.space.bytes.as_ptr().cast::<u8>().cast_mut()
+    } else {
+      self.heap
+    };
+    (self.coerce)(data)
+  }
+
+  /// Returns a raw mutable pointer to the value.
+  #[inline]
+  pub fn as_mut_ptr(&mut self) -> *mut T {
+    (self.coerce)(self.data_ptr())
+  }
+
+  /// Returns a thin pointer to where the value is stored.
+  #[inline(always)]
+  fn data_ptr(&mut self) -> *mut u8 {
+    if self.heap.is_null() {
+      self.space.bytes.as_mut_ptr().cast()
+    } else {
+      self.heap
+    }
+  }
+}
+
+impl<T: ?Sized, const N: usize> Drop for SmallBox<T, N> {
+  fn drop(&mut self) {
+    let ptr = self.as_mut_ptr();
+    unsafe {
+      let layout = Layout::for_value(&*ptr);
+      ptr::drop_in_place(ptr);
+      if !self.heap.is_null() && layout.size() != 0 {
+        dealloc(self.heap, layout);
+      }
+    }
+  }
+}
+
+impl<T: ?Sized, const N: usize> Deref for SmallBox<T, N> {
+  type Target = T;
+
+  #[inline(always)]
+  fn deref(&self) -> &T {
+    unsafe { &*self.as_ptr() }
+  }
+}
+
+impl<T: ?Sized, const N: usize> DerefMut for SmallBox<T, N> {
+  #[inline(always)]
+  fn deref_mut(&mut self) -> &mut T {
+    unsafe { &mut *self.as_mut_ptr() }
+  }
+}
+
+impl<T: ?Sized, const N: usize> AsRef<T> for SmallBox<T, N> {
+  #[inline(always)]
+  fn as_ref(&self) -> &T {
+    self
+  }
+}
+
+impl<T: ?Sized, const N: usize> AsMut<T> for SmallBox<T, N> {
+  #[inline(always)]
+  fn as_mut(&mut self) -> &mut T {
+    self
+  }
+}
+
+impl<T: Clone, const N: usize> Clone for SmallBox<T, N> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self::new(T::clone(self))
+  }
+}
+
+impl<T: Default, const N: usize> Default for SmallBox<T, N> {
+  #[inline]
+  fn default() -> Self {
+    Self::new(T::default())
+  }
+}
+
+impl<T, const N: usize> From<T> for SmallBox<T, N> {
+  #[inline(always)]
+  fn from(value: T) -> Self {
+    Self::new(value)
+  }
+}
+
+impl<T: ?Sized + Debug, const N: usize> Debug for SmallBox<T, N> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Debug::fmt(&**self, f)
+  }
+}
+
+impl<T: ?Sized + Display, const N: usize> Display for SmallBox<T, N> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Display::fmt(&**self, f)
+  }
+}
+
+impl<T: ?Sized + PartialEq, const N: usize> PartialEq for SmallBox<T, N> {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    **self == **other
+  }
+}
+
+impl<T: ?Sized + Eq, const N: usize> Eq for SmallBox<T, N> {}
+
+/// Creates a [`SmallBox`] holding the given value, unsizing it to the
+/// box's target type (such as a `dyn Trait`) when required.
+///
+/// # Example
+///
+/// ```rust
+/// use std::fmt::Display;
+///
+/// use moos::SmallBox;
+/// use moos::smallbox;
+///
+/// let values: [SmallBox<dyn Display, 8>; 2] = [smallbox!(42), smallbox!("hi")];
+/// assert_eq!(values[0].to_string(), "42");
+///
+/// let slice: SmallBox<[u8], 4> = smallbox!([1, 2, 3, 4, 5, 6]);
+/// assert!(!slice.is_inline());
+/// assert_eq!(slice.len(), 6);
+/// ```
+#[macro_export]
+macro_rules! smallbox {
+  ($value:expr) => {{
+    let value = $value;
+    // SAFETY: the identity closure only performs an unsizing coercion.
+    unsafe { $crate::SmallBox::new_unchecked(value, |ptr| ptr) }
+  }};
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::rc::Rc;
+  use core::cell::Cell;
+
+  use super::*;
+
+  struct DropCounter(Rc<Cell<u32>>);
+
+  impl Drop for DropCounter {
+    fn drop(&mut self) {
+      self.0.set(self.0.get() + 1);
+    }
+  }
+
+  #[test]
+  fn small_box_inline_and_heap() {
+    let inline: SmallBox<u64, 8> = SmallBox::new(7);
+    assert!(inline.is_inline());
+    assert_eq!(*inline, 7);
+
+    let heap: SmallBox<[u64; 2], 8> = SmallBox::new([1, 2]);
+    assert!(!heap.is_inline());
+    assert_eq!(*heap, [1, 2]);
+    assert_eq!(heap.clone().into_inner(), [1, 2]);
+
+    let zst: SmallBox<(), 0> = SmallBox::new(());
+    assert!(zst.is_inline());
+  }
+
+  #[test]
+  fn small_box_trait_objects() {
+    let mut boxes: Vec<SmallBox<dyn FnMut() -> usize, 16>> = Vec::new();
+    let mut calls = 0;
+    boxes.push(smallbox!(move || {
+      calls += 1;
+      calls
+    }));
+    let big = [7usize; 4];
+    boxes.push(smallbox!(move || big.iter().sum()));
+    assert!(boxes[0].is_inline());
+    assert!(!boxes[1].is_inline());
+
+    // moving the boxes around must not invalidate inline values.
+    let mut moved: Vec<_> = boxes.into_iter().rev().collect();
+    assert_eq!(moved[0](), 28);
+    assert_eq!(moved[1](), 1);
+    assert_eq!(moved[1](), 2);
+  }
+
+  #[test]
+  fn small_box_drops_values() {
+    let count = Rc::new(Cell::new(0));
+    {
+      let _inline: SmallBox<dyn Send, 16> = smallbox!(());
+      let _a: SmallBox<DropCounter, 16> =
+        SmallBox::new(DropCounter(count.clone()));
+      let _b: SmallBox<[DropCounter], 0> =
+        smallbox!([DropCounter(count.clone()), DropCounter(count.clone())]);
+    }
+    assert_eq!(count.get(), 3);
+
+    let boxed = SmallBox::<_, 0>::new(DropCounter(count.clone()));
+    let inner = boxed.into_inner();
+    assert_eq!(count.get(), 3);
+    drop(inner);
+    assert_eq!(count.get(), 4);
+  }
+}
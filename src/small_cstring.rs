@@ -0,0 +1,301 @@
+use alloc::ffi::CString;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::ffi::CStr;
+use core::ffi::c_char;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::ops::Deref;
+use core::str::Utf8Error;
+
+use crate::CompactVec;
+
+/// Error returned when the bytes given to a [`SmallCString`] contain a NUL
+/// byte, which would cut the string short when read from C.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InteriorNulError {
+  position: usize,
+}
+
+impl InteriorNulError {
+  /// Returns the position of the first NUL byte in the input.
+  #[inline(always)]
+  pub const fn nul_position(&self) -> usize {
+    self.position
+  }
+}
+
+impl Display for InteriorNulError {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "nul byte found in provided data at position: {}",
+      self.position
+    )
+  }
+}
+
+impl core::error::Error for InteriorNulError {}
+
+/// An owned, NUL-terminated C string that stores up to `N` bytes (including
+/// the terminator) inline, spilling over to the heap for longer strings.
+///
+/// `SmallCString` is to [`CString`] what [`SmallString`](crate::SmallString)
+/// is to `String`: passing short names and paths to C APIs no longer needs an
+/// allocation per call. Construction checks for interior NUL bytes, so
+/// [`as_ptr`](Self::as_ptr) always points at a well-formed C string.
+///
+/// # Example
+///
+/// ```rust
+/// use std::ffi::CStr;
+///
+/// use moos::SmallCString;
+///
+/// let name = SmallCString::<16>::new("eth0").unwrap();
+/// assert!(name.is_inline());
+/// assert_eq!(name.as_bytes_with_nul(), b"eth0\0");
+///
+/// // `as_ptr` can be handed straight to a C function.
+/// let from_c = unsafe { CStr::from_ptr(name.as_ptr()) };
+/// assert_eq!(from_c.to_str(), Ok("eth0"));
+///
+/// assert!(SmallCString::<16>::new("a\0b").is_err());
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SmallCString<const N: usize> {
+  /// The string's bytes, always followed by exactly one NUL byte.
+  buf: CompactVec<u8, N>,
+}
+
+impl<const N: usize> SmallCString<N> {
+  /// Creates a C string from the given bytes, appending the NUL terminator.
+  ///
+  /// Returns an error if the bytes contain a NUL byte.
+  pub fn new(bytes: impl AsRef<[u8]>) -> Result<Self, InteriorNulError> {
+    let bytes = bytes.as_ref();
+    if let Some(position) = bytes.iter().position(|&b| b == 0) {
+      return Err(InteriorNulError { position });
+    }
+    let mut buf = CompactVec::new();
+    buf.reserve_exact(bytes.len() + 1);
+    buf.extend_from_copy_slice(bytes);
+    buf.push(0);
+    Ok(Self { buf })
+  }
+
+  /// Creates a copy of a borrowed C string.
+  #[inline]
+  pub fn from_c_str(s: &CStr) -> Self {
+    let bytes = s.to_bytes_with_nul();
+    let mut buf = CompactVec::new();
+    buf.reserve_exact(bytes.len());
+    buf.extend_from_copy_slice(bytes);
+    Self { buf }
+  }
+
+  /// Returns the length of the string in bytes, excluding the terminator.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.buf.len() - 1
+  }
+
+  /// Returns `true` if the string is empty.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Returns `true` if the string is stored inline.
+  #[inline(always)]
+  pub fn is_inline(&self) -> bool {
+    self.buf.is_inline()
+  }
+
+  /// Returns a pointer to the NUL-terminated string, for passing to C.
+  ///
+  /// The pointer is valid for as long as the `SmallCString` is neither
+  /// moved nor dropped: an inline string lives inside the value itself.
+  #[inline(always)]
+  pub fn as_ptr(&self) -> *const c_char {
+    self.buf.as_ptr().cast()
+  }
+
+  /// Returns the string as a borrowed `CStr`.
+  #[inline(always)]
+  pub fn as_c_str(&self) -> &CStr {
+    // SAFETY: the buffer ends with its only NUL byte.
+    unsafe { CStr::from_bytes_with_nul_unchecked(&self.buf) }
+  }
+
+  /// Returns the bytes of the string, excluding the terminator.
+  #[inline(always)]
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.buf[..self.len()]
+  }
+
+  /// Returns the bytes of the string, including the terminator.
+  #[inline(always)]
+  pub fn as_bytes_with_nul(&self) -> &[u8] {
+    &self.buf
+  }
+
+  /// Returns the string as a `&str` if it is valid UTF-8.
+  #[inline]
+  pub fn to_str(&self) -> Result<&str, Utf8Error> {
+    core::str::from_utf8(self.as_bytes())
+  }
+
+  /// Converts the string into a `CString`.
+  #[inline]
+  pub fn into_c_string(self) -> CString {
+    let mut bytes = self.buf.into_vec();
+    bytes.pop();
+    // SAFETY: the bytes contain no NUL bytes.
+    unsafe { CString::from_vec_unchecked(bytes) }
+  }
+
+  /// Converts the string into its bytes, excluding the terminator.
+  #[inline]
+  pub fn into_bytes(self) -> Vec<u8> {
+    let mut bytes = self.buf.into_vec();
+    bytes.pop();
+    bytes
+  }
+}
+
+impl<const N: usize> Default for SmallCString<N> {
+  #[inline]
+  fn default() -> Self {
+    let mut buf = CompactVec::new();
+    buf.push(0);
+    Self { buf }
+  }
+}
+
+impl<const N: usize> Deref for SmallCString<N> {
+  type Target = CStr;
+
+  #[inline(always)]
+  fn deref(&self) -> &CStr {
+    self.as_c_str()
+  }
+}
+
+impl<const N: usize> AsRef<CStr> for SmallCString<N> {
+  #[inline(always)]
+  fn as_ref(&self) -> &CStr {
+    self.as_c_str()
+  }
+}
+
+impl<const N: usize> Borrow<CStr> for SmallCString<N> {
+  #[inline(always)]
+  fn borrow(&self) -> &CStr {
+    self.as_c_str()
+  }
+}
+
+impl<const N: usize> Debug for SmallCString<N> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Debug::fmt(self.as_c_str(), f)
+  }
+}
+
+impl<const N: usize> PartialEq<CStr> for SmallCString<N> {
+  #[inline]
+  fn eq(&self, other: &CStr) -> bool {
+    self.as_c_str() == other
+  }
+}
+
+impl<const N: usize> PartialEq<&CStr> for SmallCString<N> {
+  #[inline]
+  fn eq(&self, other: &&CStr) -> bool {
+    self.as_c_str() == *other
+  }
+}
+
+impl<const N: usize> From<&CStr> for SmallCString<N> {
+  #[inline(always)]
+  fn from(s: &CStr) -> Self {
+    Self::from_c_str(s)
+  }
+}
+
+impl<const N: usize> From<SmallCString<N>> for CString {
+  #[inline(always)]
+  fn from(s: SmallCString<N>) -> Self {
+    s.into_c_string()
+  }
+}
+
+impl<const N: usize> TryFrom<&str> for SmallCString<N> {
+  type Error = InteriorNulError;
+
+  #[inline(always)]
+  fn try_from(s: &str) -> Result<Self, Self::Error> {
+    Self::new(s)
+  }
+}
+
+impl<const N: usize> TryFrom<&[u8]> for SmallCString<N> {
+  type Error = InteriorNulError;
+
+  #[inline(always)]
+  fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+    Self::new(bytes)
+  }
+}
+
+impl<const N: usize> TryFrom<String> for SmallCString<N> {
+  type Error = InteriorNulError;
+
+  #[inline(always)]
+  fn try_from(s: String) -> Result<Self, Self::Error> {
+    Self::new(s)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn small_cstring_inline_and_heap() {
+    let short = SmallCString::<8>::new("1234567").unwrap();
+    assert!(short.is_inline());
+    assert_eq!(short.len(), 7);
+    assert_eq!(short.to_str(), Ok("1234567"));
+
+    let long = SmallCString::<8>::new("12345678").unwrap();
+    assert!(!long.is_inline());
+    assert_eq!(long.as_c_str(), c"12345678");
+    assert_eq!(unsafe { CStr::from_ptr(long.as_ptr()) }, c"12345678");
+
+    let empty = SmallCString::<0>::default();
+    assert!(empty.is_empty());
+    assert_eq!(empty.as_bytes_with_nul(), b"\0");
+  }
+
+  #[test]
+  fn small_cstring_conversions() {
+    let err = SmallCString::<8>::new(b"ab\0c").unwrap_err();
+    assert_eq!(err.nul_position(), 2);
+    assert_eq!(
+      err.to_string(),
+      "nul byte found in provided data at position: 2"
+    );
+
+    let s = SmallCString::<8>::from(c"hi");
+    assert_eq!(s, c"hi");
+    assert_eq!(format!("{s:?}"), r#""hi""#);
+    assert_eq!(CString::from(s.clone()), CString::new("hi").unwrap());
+    assert_eq!(s.into_bytes(), b"hi");
+  }
+}
@@ -0,0 +1,773 @@
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::iter::Chain;
+use core::iter::FusedIterator;
+use core::mem::MaybeUninit;
+use core::ops::Index;
+use core::ops::IndexMut;
+use core::ptr;
+use core::slice;
+
+use crate::CompactVec;
+
+/// A double-ended queue implemented as a ring buffer with room for `N`
+/// elements inline, spilling to the heap when it outgrows them.
+///
+/// Elements can be pushed and popped at either end in constant time, which
+/// makes `SmallDeque` a fit for small work queues that are usually drained
+/// before they grow, such as the run queue of an embedded executor. Like
+/// `VecDeque`, the elements may wrap around the end of the buffer, so they
+/// are exposed as two slices by [`as_slices`](Self::as_slices).
+///
+/// # Example
+///
+/// ```rust
+/// use moos::SmallDeque;
+///
+/// let mut queue: SmallDeque<u32, 4> = SmallDeque::new();
+/// queue.push_back(2);
+/// queue.push_back(3);
+/// queue.push_front(1);
+/// assert!(queue.is_inline());
+/// assert_eq!(queue.pop_front(), Some(1));
+///
+/// queue.extend([4, 5, 6]);
+/// assert!(!queue.is_inline());
+/// assert_eq!(queue.iter().copied().collect::<Vec<_>>(), [2, 3, 4, 5, 6]);
+/// assert_eq!(queue.pop_back(), Some(6));
+/// ```
+pub struct SmallDeque<T, const N: usize> {
+  /// Ring buffer storage. Its length is kept at zero so that its capacity is
+  /// all spare slots; occupancy is tracked by `head` and `len` instead.
+  buf:  CompactVec<MaybeUninit<T>, N>,
+  /// The physical index of the front element.
+  head: usize,
+  len:  usize,
+}
+
+impl<T, const N: usize> SmallDeque<T, N> {
+  /// Creates a new, empty deque using inline storage.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      buf:  CompactVec::new(),
+      head: 0,
+      len:  0,
+    }
+  }
+
+  /// Creates a new, empty deque with room for at least `capacity` elements.
+  #[inline]
+  pub fn with_capacity(capacity: usize) -> Self {
+    let mut deque = Self::new();
+    deque.reserve(capacity);
+    deque
+  }
+
+  /// Returns the number of elements in the deque.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Returns `true` if the deque is empty.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns the number of elements the deque can hold without reallocating.
+  #[inline(always)]
+  pub fn capacity(&self) -> usize {
+    self.buf.capacity()
+  }
+
+  /// Returns `true` if the elements are stored inline.
+  #[inline(always)]
+  pub fn is_inline(&self) -> bool {
+    self.buf.is_inline()
+  }
+
+  /// Appends an element to the back of the deque.
+  #[inline]
+  pub fn push_back(&mut self, value: T) {
+    if self.len == self.capacity() {
+      self.grow(1);
+    }
+    let slot = self.physical(self.len);
+    unsafe { self.slot_ptr(slot).write(value) };
+    self.len += 1;
+  }
+
+  /// Prepends an element to the front of the deque.
+  #[inline]
+  pub fn push_front(&mut self, value: T) {
+    if self.len == self.capacity() {
+      self.grow(1);
+    }
+    self.head = self.physical(self.capacity() - 1);
+    unsafe { self.slot_ptr(self.head).write(value) };
+    self.len += 1;
+  }
+
+  /// Removes and returns the back element, or `None` if the deque is empty.
+  #[inline]
+  pub fn pop_back(&mut self) -> Option<T> {
+    if self.len == 0 {
+      return None;
+    }
+    self.len -= 1;
+    let slot = self.physical(self.len);
+    Some(unsafe { self.slot_ptr(slot).read() })
+  }
+
+  /// Removes and returns the front element, or `None` if the deque is empty.
+  #[inline]
+  pub fn pop_front(&mut self) -> Option<T> {
+    if self.len == 0 {
+      return None;
+    }
+    let value = unsafe { self.slot_ptr(self.head).read() };
+    self.head = self.physical(1);
+    self.len -= 1;
+    Some(value)
+  }
+
+  /// Returns a reference to the front element, if any.
+  #[inline]
+  pub fn front(&self) -> Option<&T> {
+    self.get(0)
+  }
+
+  /// Returns a mutable reference to the front element, if any.
+  #[inline]
+  pub fn front_mut(&mut self) -> Option<&mut T> {
+    self.get_mut(0)
+  }
+
+  /// Returns a reference to the back element, if any.
+  #[inline]
+  pub fn back(&self) -> Option<&T> {
+    self.get(self.len.wrapping_sub(1))
+  }
+
+  /// Returns a mutable reference to the back element, if any.
+  #[inline]
+  pub fn back_mut(&mut self) -> Option<&mut T> {
+    self.get_mut(self.len.wrapping_sub(1))
+  }
+
+  /// Returns a reference to the element at `index` from the front, if any.
+  #[inline]
+  pub fn get(&self, index: usize) -> Option<&T> {
+    if index >= self.len {
+      return None;
+    }
+    let slot = self.physical(index);
+    Some(unsafe { &*self.buf.as_ptr().add(slot).cast::<T>() })
+  }
+
+  /// Returns a mutable reference to the element at `index` from the front,
+  /// if any.
+  #[inline]
+  pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+    if index >= self.len {
+      return None;
+    }
+    let slot = self.physical(index);
+    Some(unsafe { &mut *self.slot_ptr(slot) })
+  }
+
+  /// Returns the elements as two slices, which joined together hold them in
+  /// order from front to back. The second slice is empty unless the elements
+  /// wrap around the end of the buffer.
+  #[inline]
+  pub fn as_slices(&self) -> (&[T], &[T]) {
+    let (first, second) = self.split();
+    let ptr = self.buf.as_ptr().cast::<T>();
+    unsafe {
+      (
+        slice::from_raw_parts(ptr.add(self.head), first),
+        slice::from_raw_parts(ptr, second),
+      )
+    }
+  }
+
+  /// Returns the elements as two mutable slices, in the same way as
+  /// [`as_slices`](Self::as_slices).
+  #[inline]
+  pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+    let (first, second) = self.split();
+    let ptr = self.buf.as_mut_ptr().cast::<T>();
+    unsafe {
+      (
+        slice::from_raw_parts_mut(ptr.add(self.head), first),
+        slice::from_raw_parts_mut(ptr, second),
+      )
+    }
+  }
+
+  /// Rearranges the elements so they are contiguous, and returns them as a
+  /// single slice.
+  pub fn make_contiguous(&mut self) -> &mut [T] {
+    if self.len > self.capacity() - self.head {
+      // rotating the whole buffer left by `head` moves the front element to
+      // index 0; the unused slots in between are uninitialized either way.
+      let capacity = self.capacity();
+      unsafe { self.buf.set_len(capacity) };
+      self.buf.as_mut_slice().rotate_left(self.head);
+      unsafe { self.buf.set_len(0) };
+      self.head = 0;
+    }
+    self.as_mut_slices().0
+  }
+
+  /// Returns an iterator over the elements from front to back.
+  #[inline]
+  pub fn iter(&self) -> Iter<'_, T> {
+    let (first, second) = self.as_slices();
+    Iter {
+      inner: first.iter().chain(second),
+    }
+  }
+
+  /// Returns an iterator over mutable references to the elements from front
+  /// to back.
+  #[inline]
+  pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+    let (first, second) = self.as_mut_slices();
+    IterMut {
+      inner: first.iter_mut().chain(second),
+    }
+  }
+
+  /// Shortens the deque to `len` elements, dropping the rest from the back.
+  pub fn truncate(&mut self, len: usize) {
+    while self.len > len {
+      self.pop_back();
+    }
+  }
+
+  /// Removes and drops all elements.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.truncate(0);
+    self.head = 0;
+  }
+
+  /// Reserves room for at least `additional` more elements.
+  #[inline]
+  pub fn reserve(&mut self, additional: usize) {
+    let required = self.len.checked_add(additional);
+    if required.expect("capacity overflow") > self.capacity() {
+      self.grow(additional);
+    }
+  }
+
+  /// Grows the buffer to fit `additional` more elements, and moves any
+  /// elements that wrapped around so they stay in order.
+  #[cold]
+  fn grow(&mut self, additional: usize) {
+    let old = self.capacity();
+    // the buffer's length is raised to its capacity for the duration, so
+    // that all of the old slots are carried over into the new buffer.
+    unsafe { self.buf.set_len(old) };
+    self.buf.reserve(self.len + additional - old);
+    unsafe { self.buf.set_len(0) };
+    self.handle_capacity_increase(old);
+  }
+
+  /// Moves any elements that wrapped around the end of the old buffer of
+  /// `old` slots, so that they stay in order in the now larger buffer.
+  fn handle_capacity_increase(&mut self, old: usize) {
+    let new = self.capacity();
+    let (first, second) = self.split_for(old);
+    if second == 0 {
+      return;
+    }
+    let ptr = self.buf.as_mut_ptr();
+    unsafe {
+      if second <= new - old {
+        // move the wrapped part to just after the old end.
+        ptr::copy_nonoverlapping(ptr, ptr.add(old), second);
+      } else {
+        // move the front part to the end of the new buffer.
+        let head = new - first;
+        ptr::copy(ptr.add(self.head), ptr.add(head), first);
+        self.head = head;
+      }
+    }
+  }
+
+  /// Returns the lengths of the parts before and after the wrap-around.
+  #[inline(always)]
+  fn split(&self) -> (usize, usize) {
+    self.split_for(self.capacity())
+  }
+
+  #[inline(always)]
+  fn split_for(&self, capacity: usize) -> (usize, usize) {
+    let first = self.len.min(capacity - self.head);
+    (first, self.len - first)
+  }
+
+  /// Maps a logical offset from the front to a physical slot. The offset
+  /// must be at most the capacity, which must be nonzero.
+  ///
+  /// The capacity of a spilled deque of zero-sized elements is `usize::MAX`,
+  /// so `head + offset` is never computed directly, to avoid overflowing.
+  #[inline(always)]
+  fn physical(&self, offset: usize) -> usize {
+    let to_end = self.capacity() - self.head;
+    if offset >= to_end {
+      offset - to_end
+    } else {
+      self.head + offset
+    }
+  }
+
+  #[inline(always)]
+  fn slot_ptr(&mut self, slot: usize) -> *mut T {
+    unsafe { self.buf.as_mut_ptr().add(slot).cast() }
+  }
+}
+
+impl<T, const N: usize> Drop for SmallDeque<T, N> {
+  fn drop(&mut self) {
+    let (first, second) = self.as_mut_slices();
+    unsafe {
+      ptr::drop_in_place(first);
+      ptr::drop_in_place(second);
+    }
+  }
+}
+
+impl<T: Clone, const N: usize> Clone for SmallDeque<T, N> {
+  fn clone(&self) -> Self {
+    let mut deque = Self::with_capacity(self.len);
+    deque.extend(self.iter().cloned());
+    deque
+  }
+}
+
+impl<T, const N: usize> Default for SmallDeque<T, N> {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: Debug, const N: usize> Debug for SmallDeque<T, N> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_list().entries(self.iter()).finish()
+  }
+}
+
+impl<T: PartialEq, const N: usize, const M: usize> PartialEq<SmallDeque<T, M>>
+  for SmallDeque<T, N>
+{
+  #[inline]
+  fn eq(&self, other: &SmallDeque<T, M>) -> bool {
+    self.len == other.len && self.iter().eq(other.iter())
+  }
+}
+
+impl<T: Eq, const N: usize> Eq for SmallDeque<T, N> {}
+
+impl<T, const N: usize> Index<usize> for SmallDeque<T, N> {
+  type Output = T;
+
+  #[inline]
+  fn index(&self, index: usize) -> &T {
+    self.get(index).expect("index out of bounds")
+  }
+}
+
+impl<T, const N: usize> IndexMut<usize> for SmallDeque<T, N> {
+  #[inline]
+  fn index_mut(&mut self, index: usize) -> &mut T {
+    self.get_mut(index).expect("index out of bounds")
+  }
+}
+
+impl<T, const N: usize> Extend<T> for SmallDeque<T, N> {
+  fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+    let iter = iter.into_iter();
+    self.reserve(iter.size_hint().0);
+    iter.for_each(|value| self.push_back(value));
+  }
+}
+
+impl<T, const N: usize> FromIterator<T> for SmallDeque<T, N> {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    let mut deque = Self::new();
+    deque.extend(iter);
+    deque
+  }
+}
+
+impl<T, const N: usize, const M: usize> From<[T; M]> for SmallDeque<T, N> {
+  #[inline]
+  fn from(array: [T; M]) -> Self {
+    Self::from_iter(array)
+  }
+}
+
+/// An iterator over the elements of a [`SmallDeque`].
+#[derive(Clone)]
+pub struct Iter<'a, T> {
+  inner: Chain<slice::Iter<'a, T>, slice::Iter<'a, T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+  type Item = &'a T;
+
+  #[inline(always)]
+  fn next(&mut self) -> Option<&'a T> {
+    self.inner.next()
+  }
+
+  #[inline(always)]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+  #[inline(always)]
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.inner.next_back()
+  }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> FusedIterator for Iter<'_, T> {}
+
+/// An iterator over mutable references to the elements of a [`SmallDeque`].
+pub struct IterMut<'a, T> {
+  inner: Chain<slice::IterMut<'a, T>, slice::IterMut<'a, T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+  type Item = &'a mut T;
+
+  #[inline(always)]
+  fn next(&mut self) -> Option<&'a mut T> {
+    self.inner.next()
+  }
+
+  #[inline(always)]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+  #[inline(always)]
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.inner.next_back()
+  }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+/// An owning iterator over the elements of a [`SmallDeque`].
+pub struct IntoIter<T, const N: usize> {
+  deque: SmallDeque<T, N>,
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+  type Item = T;
+
+  #[inline(always)]
+  fn next(&mut self) -> Option<T> {
+    self.deque.pop_front()
+  }
+
+  #[inline(always)]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (self.deque.len, Some(self.deque.len))
+  }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+  #[inline(always)]
+  fn next_back(&mut self) -> Option<T> {
+    self.deque.pop_back()
+  }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+impl<T, const N: usize> FusedIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> IntoIterator for SmallDeque<T, N> {
+  type IntoIter = IntoIter<T, N>;
+  type Item = T;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    IntoIter { deque: self }
+  }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a SmallDeque<T, N> {
+  type IntoIter = Iter<'a, T>;
+  type Item = &'a T;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut SmallDeque<T, N> {
+  type IntoIter = IterMut<'a, T>;
+  type Item = &'a mut T;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter_mut()
+  }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use core::marker::PhantomData;
+
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+  use serde::de;
+
+  use super::*;
+  use crate::compact_vec::serde_impl::cautious_size_hint;
+
+  impl<T: Serialize, const N: usize> Serialize for SmallDeque<T, N> {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      serializer.collect_seq(self.iter())
+    }
+  }
+
+  struct SmallDequeVisitor<T, const N: usize>(PhantomData<T>);
+
+  impl<'de, T, const N: usize> de::Visitor<'de> for SmallDequeVisitor<T, N>
+  where
+    T: Deserialize<'de>,
+  {
+    type Value = SmallDeque<T, N>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a sequence")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+      A: de::SeqAccess<'de>,
+    {
+      let capacity = cautious_size_hint::<T>(seq.size_hint());
+      let mut deque = SmallDeque::with_capacity(capacity);
+      while let Some(value) = seq.next_element()? {
+        deque.push_back(value);
+      }
+      Ok(deque)
+    }
+  }
+
+  impl<'de, T, const N: usize> Deserialize<'de> for SmallDeque<T, N>
+  where
+    T: Deserialize<'de>,
+  {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      deserializer.deserialize_seq(SmallDequeVisitor(PhantomData))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::rc::Rc;
+  use alloc::vec::Vec;
+
+  use super::*;
+
+  #[test]
+  fn small_deque_wraps_inline() {
+    let mut deque: SmallDeque<u32, 4> = SmallDeque::new();
+    for round in 0..10 {
+      deque.push_back(round);
+      deque.push_back(round + 100);
+      assert_eq!(deque.pop_front(), Some(round));
+      assert_eq!(deque.pop_front(), Some(round + 100));
+    }
+    deque.extend([1, 2, 3]);
+    deque.push_front(0);
+    assert!(deque.is_inline());
+    assert_eq!(deque.as_slices().0.len() + deque.as_slices().1.len(), 4);
+    assert_eq!(
+      deque.iter().rev().copied().collect::<Vec<_>>(),
+      [3, 2, 1, 0]
+    );
+    assert_eq!((deque.front(), deque.back()), (Some(&0), Some(&3)));
+    assert_eq!(deque[2], 2);
+  }
+
+  #[test]
+  fn small_deque_grows_while_wrapped() {
+    let mut deque: SmallDeque<u32, 4> = SmallDeque::new();
+    deque.extend([2, 3]);
+    deque.push_front(1);
+    deque.push_front(0);
+    assert!(!deque.as_slices().1.is_empty());
+    deque.push_back(4);
+    assert!(!deque.is_inline());
+    assert_eq!(deque, SmallDeque::<u32, 8>::from([0, 1, 2, 3, 4]));
+
+    for i in 5..40 {
+      deque.push_front(i);
+      deque.push_back(i);
+    }
+    let (first, second) = deque.as_slices();
+    let joined: Vec<u32> = first.iter().chain(second).copied().collect();
+    assert_eq!(deque.make_contiguous(), joined.as_slice());
+    assert_eq!(deque.iter().copied().collect::<Vec<_>>(), joined);
+  }
+
+  #[test]
+  fn small_deque_drops_elements() {
+    let rc = Rc::new(());
+    let mut deque: SmallDeque<Rc<()>, 2> = SmallDeque::new();
+    for _ in 0..5 {
+      deque.push_front(rc.clone());
+    }
+    assert_eq!(Rc::strong_count(&rc), 6);
+    let mut iter = deque.clone().into_iter();
+    iter.next();
+    drop(iter);
+    assert_eq!(Rc::strong_count(&rc), 6);
+    deque.truncate(2);
+    assert_eq!(Rc::strong_count(&rc), 3);
+    drop(deque);
+    assert_eq!(Rc::strong_count(&rc), 1);
+  }
+
+  /// Returns a full deque of `[0, 1, 2, 3]` whose last three elements have
+  /// wrapped around to the start of the buffer.
+  fn wrapped() -> SmallDeque<u32, 4> {
+    let mut deque = SmallDeque::new();
+    deque.extend([1, 2, 3]);
+    deque.push_front(0);
+    assert_eq!(deque.as_slices(), (&[0][..], &[1, 2, 3][..]));
+    deque
+  }
+
+  /// Grows the buffer of `deque` to exactly `capacity` slots, as `grow`
+  /// does.
+  fn grow_exact(deque: &mut SmallDeque<u32, 4>, capacity: usize) {
+    let old = deque.capacity();
+    unsafe {
+      deque.buf.set_len(old);
+      deque.buf.reserve_exact(capacity - old);
+      deque.buf.set_len(0);
+    }
+    assert_eq!(deque.capacity(), capacity);
+    deque.handle_capacity_increase(old);
+  }
+
+  #[test]
+  fn small_deque_grow_moves_wrapped_back() {
+    // the three wrapped elements fit after the old end of the buffer.
+    let mut deque = wrapped();
+    grow_exact(&mut deque, 8);
+    assert_eq!(deque.as_slices(), (&[0, 1, 2, 3][..], &[][..]));
+    deque.push_back(4);
+    deque.push_front(9);
+    assert_eq!(
+      deque.iter().copied().collect::<Vec<_>>(),
+      [9, 0, 1, 2, 3, 4]
+    );
+  }
+
+  #[test]
+  fn small_deque_grow_moves_front() {
+    // only one slot is added, so the front element moves to the new end.
+    let mut deque = wrapped();
+    grow_exact(&mut deque, 5);
+    assert_eq!(deque.as_slices(), (&[0][..], &[1, 2, 3][..]));
+    assert_eq!(deque.head, 4);
+    deque.push_back(4);
+    assert_eq!(deque.iter().copied().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+    assert_eq!(deque.pop_front(), Some(0));
+    assert_eq!(deque.make_contiguous(), [1, 2, 3, 4]);
+  }
+
+  #[test]
+  fn small_deque_push_front_spilled() {
+    let mut deque: SmallDeque<u32, 2> = SmallDeque::new();
+    deque.extend([10, 11, 12]);
+    assert!(!deque.is_inline());
+    for i in (0..10).rev() {
+      deque.push_front(i);
+    }
+    assert_eq!(deque.len(), 13);
+    assert_eq!(
+      deque.iter().copied().collect::<Vec<_>>(),
+      [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]
+    );
+    assert_eq!(deque.pop_back(), Some(12));
+    assert_eq!(deque.pop_front(), Some(0));
+    assert_eq!((deque.front(), deque.back()), (Some(&1), Some(&11)));
+  }
+
+  #[test]
+  fn small_deque_zero_sized() {
+    let mut deque: SmallDeque<(), 0> = SmallDeque::new();
+    for _ in 0..3 {
+      deque.push_front(());
+    }
+    deque.push_back(());
+    assert_eq!(deque.len(), 4);
+    assert_eq!(deque.iter().count(), 4);
+    assert_eq!(deque.make_contiguous().len(), 4);
+    assert_eq!(deque.pop_back(), Some(()));
+    assert_eq!(deque.pop_front(), Some(()));
+    assert_eq!(deque.len(), 2);
+
+    let mut deque: SmallDeque<(), 2> = SmallDeque::new();
+    for _ in 0..5 {
+      deque.push_front(());
+      deque.push_back(());
+    }
+    assert_eq!(deque.len(), 10);
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn small_deque_serde() {
+    let deque: SmallDeque<u8, 2> = SmallDeque::from([1, 2, 3]);
+    let json = serde_json::to_string(&deque).unwrap();
+    assert_eq!(json, "[1,2,3]");
+    assert_eq!(
+      serde_json::from_str::<SmallDeque<u8, 2>>(&json).unwrap(),
+      deque
+    );
+
+    let deque = wrapped();
+    let json = serde_json::to_string(&deque).unwrap();
+    assert_eq!(json, "[0,1,2,3]");
+    let back: SmallDeque<u32, 4> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, deque);
+    assert!(back.is_inline());
+    assert!(back.as_slices().1.is_empty());
+  }
+}
@@ -0,0 +1,835 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::iter::FusedIterator;
+use core::mem;
+use core::ops::Index;
+use core::slice;
+
+use crate::CompactVec;
+use crate::compact_vec;
+
+/// A map that stores up to `N` key-value pairs inline, and spills over to the
+/// heap beyond that.
+///
+/// Entries are kept in a [`CompactVec`] in insertion order, and lookups are a
+/// linear scan comparing keys with [`Eq`]. For the handful of entries typical
+/// of HTTP headers or element attributes, that is faster than hashing and
+/// avoids the per-instance allocation of a `HashMap` entirely. Lookups stay
+/// linear after spilling, so it is not suited to large maps.
+///
+/// Iteration visits entries in the order they were first inserted; replacing
+/// the value of an existing key keeps its position, and removing an entry
+/// preserves the order of the others.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::SmallMap;
+///
+/// let mut headers: SmallMap<&str, &str, 4> = SmallMap::new();
+/// headers.insert("content-type", "text/plain");
+/// headers.insert("content-length", "42");
+/// assert_eq!(headers.get("content-type"), Some(&"text/plain"));
+/// assert!(headers.is_inline());
+///
+/// *headers.entry("x-retries").or_insert("0") = "1";
+/// assert_eq!(headers["x-retries"], "1");
+/// ```
+#[derive(Clone)]
+pub struct SmallMap<K, V, const N: usize> {
+  entries: CompactVec<(K, V), N>,
+}
+
+impl<K, V, const N: usize> SmallMap<K, V, N> {
+  /// Creates a new, empty `SmallMap` using inline storage.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      entries: CompactVec::new(),
+    }
+  }
+
+  /// Returns the number of entries in the map.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// Returns `true` if the map contains no entries.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Returns the number of entries the map can hold without reallocating.
+  #[inline(always)]
+  pub fn capacity(&self) -> usize {
+    self.entries.capacity()
+  }
+
+  /// Returns `true` if the entries are currently stored inline.
+  #[inline(always)]
+  pub fn is_inline(&self) -> bool {
+    self.entries.is_inline()
+  }
+
+  /// Reserves capacity for at least `additional` more entries.
+  #[inline]
+  pub fn reserve(&mut self, additional: usize) {
+    self.entries.reserve(additional);
+  }
+
+  /// Shrinks the capacity of the map as much as possible, moving the entries
+  /// back inline if they fit.
+  #[inline]
+  pub fn shrink_to_fit(&mut self) {
+    self.entries.shrink_to_fit();
+  }
+
+  /// Removes all entries, releasing any heap storage.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.entries.clear();
+  }
+
+  /// Returns the entries as a slice of key-value pairs, in iteration order.
+  #[inline(always)]
+  pub fn as_slice(&self) -> &[(K, V)] {
+    &self.entries
+  }
+
+  /// Returns an iterator over the entries, in insertion order.
+  #[inline]
+  pub fn iter(&self) -> Iter<'_, K, V> {
+    Iter {
+      inner: self.entries.iter(),
+    }
+  }
+
+  /// Returns an iterator over the entries with mutable references to the
+  /// values, in insertion order.
+  #[inline]
+  pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+    IterMut {
+      inner: self.entries.iter_mut(),
+    }
+  }
+
+  /// Returns an iterator over the keys, in insertion order.
+  #[inline]
+  pub fn keys(&self) -> Keys<'_, K, V> {
+    Keys {
+      inner: self.entries.iter(),
+    }
+  }
+
+  /// Returns an iterator over the values, in insertion order.
+  #[inline]
+  pub fn values(&self) -> Values<'_, K, V> {
+    Values {
+      inner: self.entries.iter(),
+    }
+  }
+
+  /// Returns an iterator over mutable references to the values, in insertion
+  /// order.
+  #[inline]
+  pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+    ValuesMut {
+      inner: self.entries.iter_mut(),
+    }
+  }
+
+  /// Retains only the entries for which `f` returns `true`, preserving the
+  /// order of the entries that remain.
+  pub fn retain<F>(&mut self, mut f: F)
+  where
+    F: FnMut(&K, &mut V) -> bool,
+  {
+    let mut kept = 0;
+    for i in 0..self.entries.len() {
+      let (key, value) = &mut self.entries[i];
+      if f(key, value) {
+        self.entries.swap(kept, i);
+        kept += 1;
+      }
+    }
+    self.entries.truncate(kept);
+  }
+
+  /// Returns the index of the entry for `key`, if present.
+  #[inline]
+  fn position<Q>(&self, key: &Q) -> Option<usize>
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Eq,
+  {
+    self.entries.iter().position(|(k, _)| k.borrow() == key)
+  }
+
+  /// Returns a reference to the value for `key`, if present.
+  #[inline]
+  pub fn get<Q>(&self, key: &Q) -> Option<&V>
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Eq,
+  {
+    self.get_key_value(key).map(|(_, value)| value)
+  }
+
+  /// Returns references to the stored key and the value for `key`, if
+  /// present.
+  #[inline]
+  pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Eq,
+  {
+    let (k, v) = &self.entries[self.position(key)?];
+    Some((k, v))
+  }
+
+  /// Returns a mutable reference to the value for `key`, if present.
+  #[inline]
+  pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Eq,
+  {
+    let index = self.position(key)?;
+    Some(&mut self.entries[index].1)
+  }
+
+  /// Returns `true` if the map contains an entry for `key`.
+  #[inline]
+  pub fn contains_key<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Eq,
+  {
+    self.position(key).is_some()
+  }
+
+  /// Inserts a key-value pair into the map.
+  ///
+  /// If the map already had an entry for the key, its value is replaced and
+  /// the old value returned; the entry keeps its position and original key.
+  /// Otherwise the entry is appended, and `None` is returned.
+  pub fn insert(&mut self, key: K, value: V) -> Option<V>
+  where
+    K: Eq,
+  {
+    match self.position(&key) {
+      Some(index) => Some(mem::replace(&mut self.entries[index].1, value)),
+      None => {
+        self.entries.push((key, value));
+        None
+      }
+    }
+  }
+
+  /// Removes the entry for `key`, returning its value if it was present.
+  #[inline]
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Eq,
+  {
+    self.remove_entry(key).map(|(_, value)| value)
+  }
+
+  /// Removes the entry for `key`, returning the stored key and value if it
+  /// was present.
+  #[inline]
+  pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+  where
+    K: Borrow<Q>,
+    Q: ?Sized + Eq,
+  {
+    let index = self.position(key)?;
+    Some(self.entries.remove(index))
+  }
+
+  /// Returns the entry for `key`, for in-place manipulation.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallMap;
+  ///
+  /// let mut counts: SmallMap<char, u32, 4> = SmallMap::new();
+  /// for c in "abca".chars() {
+  ///   *counts.entry(c).or_default() += 1;
+  /// }
+  /// assert_eq!(counts.get(&'a'), Some(&2));
+  /// assert_eq!(counts.len(), 3);
+  /// ```
+  pub fn entry(&mut self, key: K) -> Entry<'_, K, V, N>
+  where
+    K: Eq,
+  {
+    match self.position(&key) {
+      Some(index) => Entry::Occupied(OccupiedEntry { map: self, index }),
+      None => Entry::Vacant(VacantEntry { map: self, key }),
+    }
+  }
+}
+
+/// A view into a single entry of a [`SmallMap`], which may be vacant or
+/// occupied.
+///
+/// Created by [`SmallMap::entry`].
+pub enum Entry<'a, K, V, const N: usize> {
+  /// An entry that already exists in the map.
+  Occupied(OccupiedEntry<'a, K, V, N>),
+  /// An entry that does not exist in the map yet.
+  Vacant(VacantEntry<'a, K, V, N>),
+}
+
+impl<'a, K, V, const N: usize> Entry<'a, K, V, N> {
+  /// Returns a reference to the key of this entry.
+  #[inline]
+  pub fn key(&self) -> &K {
+    match self {
+      Entry::Occupied(entry) => entry.key(),
+      Entry::Vacant(entry) => entry.key(),
+    }
+  }
+
+  /// Inserts `default` if the entry is vacant, and returns a mutable
+  /// reference to the value.
+  #[inline]
+  pub fn or_insert(self, default: V) -> &'a mut V {
+    match self {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => entry.insert(default),
+    }
+  }
+
+  /// Inserts the result of `default` if the entry is vacant, and returns a
+  /// mutable reference to the value.
+  #[inline]
+  pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+    match self {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => entry.insert(default()),
+    }
+  }
+
+  /// Inserts `V::default()` if the entry is vacant, and returns a mutable
+  /// reference to the value.
+  #[inline]
+  pub fn or_default(self) -> &'a mut V
+  where
+    V: Default,
+  {
+    self.or_insert_with(V::default)
+  }
+
+  /// Calls `f` with the value if the entry is occupied, and returns the
+  /// entry.
+  #[inline]
+  pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+    if let Entry::Occupied(entry) = &mut self {
+      f(entry.get_mut());
+    }
+    self
+  }
+}
+
+/// A view into an occupied entry of a [`SmallMap`].
+pub struct OccupiedEntry<'a, K, V, const N: usize> {
+  map:   &'a mut SmallMap<K, V, N>,
+  index: usize,
+}
+
+impl<'a, K, V, const N: usize> OccupiedEntry<'a, K, V, N> {
+  /// Returns a reference to the key of this entry.
+  #[inline(always)]
+  pub fn key(&self) -> &K {
+    &self.map.entries[self.index].0
+  }
+
+  /// Returns a reference to the value of this entry.
+  #[inline(always)]
+  pub fn get(&self) -> &V {
+    &self.map.entries[self.index].1
+  }
+
+  /// Returns a mutable reference to the value of this entry.
+  #[inline(always)]
+  pub fn get_mut(&mut self) -> &mut V {
+    &mut self.map.entries[self.index].1
+  }
+
+  /// Converts the entry into a mutable reference to its value, bound to the
+  /// lifetime of the map.
+  #[inline(always)]
+  pub fn into_mut(self) -> &'a mut V {
+    &mut self.map.entries[self.index].1
+  }
+
+  /// Replaces the value of this entry, returning the old value.
+  #[inline]
+  pub fn insert(&mut self, value: V) -> V {
+    mem::replace(self.get_mut(), value)
+  }
+
+  /// Removes this entry from the map, returning its value.
+  #[inline]
+  pub fn remove(self) -> V {
+    self.remove_entry().1
+  }
+
+  /// Removes this entry from the map, returning its key and value.
+  #[inline]
+  pub fn remove_entry(self) -> (K, V) {
+    self.map.entries.remove(self.index)
+  }
+}
+
+/// A view into a vacant entry of a [`SmallMap`].
+pub struct VacantEntry<'a, K, V, const N: usize> {
+  map: &'a mut SmallMap<K, V, N>,
+  key: K,
+}
+
+impl<'a, K, V, const N: usize> VacantEntry<'a, K, V, N> {
+  /// Returns a reference to the key that would be used when inserting.
+  #[inline(always)]
+  pub fn key(&self) -> &K {
+    &self.key
+  }
+
+  /// Takes ownership of the key, leaving the map unchanged.
+  #[inline(always)]
+  pub fn into_key(self) -> K {
+    self.key
+  }
+
+  /// Inserts the entry with the given value, and returns a mutable reference
+  /// to it.
+  #[inline]
+  pub fn insert(self, value: V) -> &'a mut V {
+    let entries = &mut self.map.entries;
+    entries.push((self.key, value));
+    let last = entries.len() - 1;
+    &mut entries[last].1
+  }
+}
+
+impl<K, V, const N: usize> Default for SmallMap<K, V, N> {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<K: Debug, V: Debug, const N: usize> Debug for SmallMap<K, V, N> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_map().entries(self.iter()).finish()
+  }
+}
+
+impl<K, V, const N: usize, const M: usize> PartialEq<SmallMap<K, V, M>>
+  for SmallMap<K, V, N>
+where
+  K: Eq,
+  V: PartialEq,
+{
+  /// Two maps are equal if they contain the same entries, regardless of
+  /// their insertion order.
+  fn eq(&self, other: &SmallMap<K, V, M>) -> bool {
+    self.len() == other.len()
+      && self
+        .iter()
+        .all(|(key, value)| other.get(key) == Some(value))
+  }
+}
+
+impl<K: Eq, V: Eq, const N: usize> Eq for SmallMap<K, V, N> {}
+
+impl<K, Q, V, const N: usize> Index<&Q> for SmallMap<K, V, N>
+where
+  K: Borrow<Q>,
+  Q: ?Sized + Eq,
+{
+  type Output = V;
+
+  /// Returns a reference to the value for `key`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the key is not present in the map.
+  #[inline]
+  fn index(&self, key: &Q) -> &V {
+    self.get(key).expect("key not found in SmallMap")
+  }
+}
+
+impl<K: Eq, V, const N: usize> Extend<(K, V)> for SmallMap<K, V, N> {
+  fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+    for (key, value) in iter {
+      self.insert(key, value);
+    }
+  }
+}
+
+impl<K: Eq, V, const N: usize> FromIterator<(K, V)> for SmallMap<K, V, N> {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+    let mut map = Self::new();
+    map.extend(iter);
+    map
+  }
+}
+
+impl<K: Eq, V, const N: usize, const M: usize> From<[(K, V); M]>
+  for SmallMap<K, V, N>
+{
+  #[inline]
+  fn from(entries: [(K, V); M]) -> Self {
+    Self::from_iter(entries)
+  }
+}
+
+impl<K, V, const N: usize> IntoIterator for SmallMap<K, V, N> {
+  type Item = (K, V);
+  type IntoIter = compact_vec::IntoIter<(K, V), N>;
+
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    self.entries.into_iter()
+  }
+}
+
+impl<'a, K, V, const N: usize> IntoIterator for &'a SmallMap<K, V, N> {
+  type Item = (&'a K, &'a V);
+  type IntoIter = Iter<'a, K, V>;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<'a, K, V, const N: usize> IntoIterator for &'a mut SmallMap<K, V, N> {
+  type Item = (&'a K, &'a mut V);
+  type IntoIter = IterMut<'a, K, V>;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter_mut()
+  }
+}
+
+/// An iterator over the entries of a [`SmallMap`].
+///
+/// Created by [`SmallMap::iter`].
+#[derive(Clone)]
+pub struct Iter<'a, K, V> {
+  inner: slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+  type Item = (&'a K, &'a V);
+
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|(key, value)| (key, value))
+  }
+
+  #[inline(always)]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+  #[inline]
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.inner.next_back().map(|(key, value)| (key, value))
+  }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+/// A mutable iterator over the entries of a [`SmallMap`].
+///
+/// Created by [`SmallMap::iter_mut`].
+pub struct IterMut<'a, K, V> {
+  inner: slice::IterMut<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+  type Item = (&'a K, &'a mut V);
+
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> {
+    self.inner.next().map(|(key, value)| (&*key, value))
+  }
+
+  #[inline(always)]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+  #[inline]
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.inner.next_back().map(|(key, value)| (&*key, value))
+  }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+/// An iterator over the keys of a [`SmallMap`].
+///
+/// Created by [`SmallMap::keys`].
+#[derive(Clone)]
+pub struct Keys<'a, K, V> {
+  inner: slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+  type Item = &'a K;
+
+  #[inline]
+  fn next(&mut self) -> Option<&'a K> {
+    self.inner.next().map(|(key, _)| key)
+  }
+
+  #[inline(always)]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> DoubleEndedIterator for Keys<'_, K, V> {
+  #[inline]
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.inner.next_back().map(|(key, _)| key)
+  }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+/// An iterator over the values of a [`SmallMap`].
+///
+/// Created by [`SmallMap::values`].
+#[derive(Clone)]
+pub struct Values<'a, K, V> {
+  inner: slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+  type Item = &'a V;
+
+  #[inline]
+  fn next(&mut self) -> Option<&'a V> {
+    self.inner.next().map(|(_, value)| value)
+  }
+
+  #[inline(always)]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> DoubleEndedIterator for Values<'_, K, V> {
+  #[inline]
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.inner.next_back().map(|(_, value)| value)
+  }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+/// A mutable iterator over the values of a [`SmallMap`].
+///
+/// Created by [`SmallMap::values_mut`].
+pub struct ValuesMut<'a, K, V> {
+  inner: slice::IterMut<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+  type Item = &'a mut V;
+
+  #[inline]
+  fn next(&mut self) -> Option<&'a mut V> {
+    self.inner.next().map(|(_, value)| value)
+  }
+
+  #[inline(always)]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.inner.size_hint()
+  }
+}
+
+impl<K, V> DoubleEndedIterator for ValuesMut<'_, K, V> {
+  #[inline]
+  fn next_back(&mut self) -> Option<Self::Item> {
+    self.inner.next_back().map(|(_, value)| value)
+  }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use core::marker::PhantomData;
+
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+  use serde::de;
+
+  use super::*;
+
+  impl<K: Serialize, V: Serialize, const N: usize> Serialize
+    for SmallMap<K, V, N>
+  {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      serializer.collect_map(self.iter())
+    }
+  }
+
+  struct SmallMapVisitor<K, V, const N: usize>(PhantomData<(K, V)>);
+
+  impl<'de, K, V, const N: usize> de::Visitor<'de> for SmallMapVisitor<K, V, N>
+  where
+    K: Deserialize<'de> + Eq,
+    V: Deserialize<'de>,
+  {
+    type Value = SmallMap<K, V, N>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a map")
+    }
+
+    fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+    where
+      A: de::MapAccess<'de>,
+    {
+      let mut map = SmallMap::new();
+      while let Some((key, value)) = access.next_entry()? {
+        map.insert(key, value);
+      }
+      Ok(map)
+    }
+  }
+
+  impl<'de, K, V, const N: usize> Deserialize<'de> for SmallMap<K, V, N>
+  where
+    K: Deserialize<'de> + Eq,
+    V: Deserialize<'de>,
+  {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      deserializer.deserialize_map(SmallMapVisitor(PhantomData))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn small_map_insert_get_remove() {
+    let mut map: SmallMap<&str, u32, 2> = SmallMap::new();
+    assert_eq!(map.insert("a", 1), None);
+    assert_eq!(map.insert("b", 2), None);
+    assert!(map.is_inline());
+    assert_eq!(map.insert("a", 10), Some(1));
+    assert_eq!(map.len(), 2);
+
+    assert_eq!(map.insert("c", 3), None);
+    assert!(!map.is_inline());
+    assert_eq!(map.get("a"), Some(&10));
+    assert_eq!(map.get_key_value("c"), Some((&"c", &3)));
+    assert!(map.contains_key("b"));
+    assert!(!map.contains_key("z"));
+
+    *map.get_mut("b").unwrap() += 1;
+    assert_eq!(map.remove("b"), Some(3));
+    assert_eq!(map.remove("b"), None);
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), ["a", "c"]);
+  }
+
+  #[test]
+  fn small_map_borrowed_lookup() {
+    let mut map: SmallMap<String, usize, 4> = SmallMap::new();
+    map.insert("one".into(), 1);
+    map.insert("two".into(), 2);
+    assert_eq!(map.get("two"), Some(&2));
+    assert_eq!(map["one"], 1);
+    assert_eq!(map.remove_entry("one"), Some(("one".into(), 1)));
+  }
+
+  #[test]
+  fn small_map_entry() {
+    let mut map: SmallMap<u8, Vec<u8>, 4> = SmallMap::new();
+    map.entry(1).or_default().push(10);
+    map.entry(1).or_default().push(11);
+    map.entry(2).or_insert_with(|| vec![20]);
+    map.entry(2).and_modify(|v| v.push(21)).or_default();
+    assert_eq!(map[&1], [10, 11]);
+    assert_eq!(map[&2], [20, 21]);
+
+    match map.entry(1) {
+      Entry::Occupied(entry) => assert_eq!(entry.remove(), [10, 11]),
+      Entry::Vacant(_) => unreachable!(),
+    }
+    match map.entry(3) {
+      Entry::Vacant(entry) => assert_eq!(entry.into_key(), 3),
+      Entry::Occupied(_) => unreachable!(),
+    }
+    assert_eq!(map.len(), 1);
+  }
+
+  #[test]
+  fn small_map_order_and_equality() {
+    let mut map: SmallMap<char, u8, 4> =
+      [('c', 3), ('a', 1), ('b', 2)].into_iter().collect();
+    map.insert('a', 4);
+    let order: Vec<_> = map.iter().map(|(k, v)| (*k, *v)).collect();
+    assert_eq!(order, [('c', 3), ('a', 4), ('b', 2)]);
+
+    map.retain(|_, v| *v != 3);
+    for value in map.values_mut() {
+      *value *= 10;
+    }
+    let other = SmallMap::<char, u8, 1>::from([('b', 20), ('a', 40)]);
+    assert_eq!(map, other);
+    assert_eq!(format!("{map:?}"), "{'a': 40, 'b': 20}");
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn small_map_serde() {
+    let map = SmallMap::<String, u32, 2>::from([
+      ("x".to_string(), 1),
+      ("y".to_string(), 2),
+    ]);
+    let json = serde_json::to_string(&map).unwrap();
+    assert_eq!(json, r#"{"x":1,"y":2}"#);
+    let back: SmallMap<String, u32, 2> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, map);
+  }
+}
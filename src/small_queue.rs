@@ -0,0 +1,587 @@
+use alloc::collections::VecDeque;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+#[cfg(target_has_atomic = "8")]
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering;
+
+use crate::CapacityError;
+
+/// A lock-free, single-producer single-consumer queue that stores up to `N`
+/// values inline and never allocates.
+///
+/// A `SmallQueue` is [`split`](Self::split) into a [`Producer`] and a
+/// [`Consumer`], which can be moved to different threads, or used from an
+/// interrupt handler and the main loop of an embedded program. Pushing and
+/// popping only ever load and store the queue's two indices, so they never
+/// block or spin, and work on targets without compare-and-swap atomics.
+///
+/// When the queue is full, [`Producer::push`] hands the value back. To queue
+/// values past the capacity instead, wrap the producer in a
+/// [`SpillingProducer`], which holds them on the heap until there's room.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::SmallQueue;
+///
+/// let mut queue: SmallQueue<u32, 4> = SmallQueue::new();
+/// let (mut tx, mut rx) = queue.split();
+///
+/// std::thread::scope(|scope| {
+///   scope.spawn(move || {
+///     for i in 0..100 {
+///       while tx.push(i).is_err() {
+///         std::thread::yield_now();
+///       }
+///     }
+///   });
+///
+///   let mut sum = 0;
+///   let mut received = 0;
+///   while received < 100 {
+///     match rx.pop() {
+///       Some(i) => {
+///         sum += i;
+///         received += 1;
+///       }
+///       None => std::thread::yield_now(),
+///     }
+///   }
+///   assert_eq!(sum, 4950);
+/// });
+/// ```
+pub struct SmallQueue<T, const N: usize> {
+  /// The index of the next value to pop, owned by the consumer.
+  head:  AtomicUsize,
+  /// The index of the next slot to push into, owned by the producer.
+  tail:  AtomicUsize,
+  /// Whether [`split_static`](Self::split_static) has handed out the halves.
+  #[cfg(target_has_atomic = "8")]
+  split: AtomicBool,
+  buf:   [UnsafeCell<MaybeUninit<T>>; N],
+}
+
+// SAFETY: the producer and consumer only access disjoint slots, handing each
+// over with a release store of their index, so values of a `Send` type can be
+// sent through a shared queue.
+unsafe impl<T: Send, const N: usize> Sync for SmallQueue<T, N> {}
+
+impl<T, const N: usize> SmallQueue<T, N> {
+  /// Indices run from `0` to `2 * N`, so that a full queue can be told apart
+  /// from an empty one without wasting a slot.
+  const WRAP: usize = {
+    assert!(N > 0, "a SmallQueue must have room for at least one value");
+    assert!(N <= usize::MAX / 2, "SmallQueue capacity is too large");
+    2 * N
+  };
+
+  /// Creates a new, empty queue.
+  ///
+  /// This is a `const fn`, so the queue can live in a `static`.
+  #[inline]
+  pub const fn new() -> Self {
+    // rejects an unsupported `N` at compile time.
+    let _ = Self::WRAP;
+    Self {
+      head: AtomicUsize::new(0),
+      tail: AtomicUsize::new(0),
+      #[cfg(target_has_atomic = "8")]
+      split: AtomicBool::new(false),
+      buf: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+    }
+  }
+
+  /// Returns the number of values the queue can hold.
+  #[inline(always)]
+  pub const fn capacity(&self) -> usize {
+    N
+  }
+
+  /// Returns the number of values in the queue.
+  ///
+  /// While the queue is in use from another thread, this is only a snapshot
+  /// that may already be out of date.
+  #[inline]
+  pub fn len(&self) -> usize {
+    let head = self.head.load(Ordering::Acquire);
+    let tail = self.tail.load(Ordering::Acquire);
+    Self::distance(head, tail)
+  }
+
+  /// Returns `true` if the queue holds no values.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Returns `true` if the queue holds `N` values.
+  #[inline(always)]
+  pub fn is_full(&self) -> bool {
+    self.len() == N
+  }
+
+  /// Splits the queue into its producer and consumer halves.
+  ///
+  /// To split a queue in a `static`, which can't be borrowed mutably, use
+  /// [`split_static`](Self::split_static) instead.
+  #[inline]
+  pub fn split(&mut self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+    Self::halves(self)
+  }
+
+  /// Splits a queue in a `static` into its producer and consumer halves, the
+  /// first time it is called. Any later call returns `None`, since a second
+  /// pair of halves could push or pop concurrently with the first.
+  ///
+  /// The halves borrow the queue for `'static`, so they can be stored away
+  /// for an interrupt handler and the main loop of an embedded program.
+  ///
+  /// This is only available on targets with atomic swap operations, which
+  /// the once-only check needs. On other targets, the queue can be placed
+  /// in a `&'static mut` by other means, such as the `static_cell` crate,
+  /// and [`split`](Self::split) from there.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallQueue;
+  ///
+  /// static EVENTS: SmallQueue<u8, 8> = SmallQueue::new();
+  ///
+  /// let (mut tx, mut rx) = EVENTS.split_static().unwrap();
+  /// assert!(EVENTS.split_static().is_none());
+  ///
+  /// std::thread::spawn(move || tx.push(42).unwrap())
+  ///   .join()
+  ///   .unwrap();
+  /// assert_eq!(rx.pop(), Some(42));
+  /// ```
+  #[cfg(target_has_atomic = "8")]
+  #[inline]
+  pub fn split_static(
+    &'static self,
+  ) -> Option<(Producer<'static, T, N>, Consumer<'static, T, N>)> {
+    match self.split.swap(true, Ordering::AcqRel) {
+      true => None,
+      false => Some(Self::halves(self)),
+    }
+  }
+
+  /// Returns the two halves of `queue`.
+  ///
+  /// The caller must make sure that no other halves of the same queue are
+  /// in use at the same time.
+  #[inline(always)]
+  fn halves(queue: &Self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+    (
+      Producer { queue },
+      Consumer {
+        queue,
+        _not_sync: PhantomData,
+      },
+    )
+  }
+
+  /// Pushes `value` onto the back of the queue, or returns it in an error if
+  /// the queue is full.
+  #[inline]
+  pub fn push(&mut self, value: T) -> Result<(), CapacityError<T>> {
+    // SAFETY: `&mut self` rules out any other producer.
+    unsafe { self.enqueue(value) }
+  }
+
+  /// Removes the value at the front of the queue, if any.
+  #[inline]
+  pub fn pop(&mut self) -> Option<T> {
+    // SAFETY: `&mut self` rules out any other consumer.
+    unsafe { self.dequeue() }
+  }
+
+  #[inline(always)]
+  const fn distance(head: usize, tail: usize) -> usize {
+    if tail >= head {
+      tail - head
+    } else {
+      tail + Self::WRAP - head
+    }
+  }
+
+  #[inline(always)]
+  const fn next(index: usize) -> usize {
+    if index + 1 == Self::WRAP {
+      0
+    } else {
+      index + 1
+    }
+  }
+
+  /// # Safety
+  ///
+  /// Must only be called by one thread at a time.
+  #[inline]
+  unsafe fn enqueue(&self, value: T) -> Result<(), CapacityError<T>> {
+    let tail = self.tail.load(Ordering::Relaxed);
+    let head = self.head.load(Ordering::Acquire);
+    if Self::distance(head, tail) == N {
+      return Err(CapacityError(value));
+    }
+    // SAFETY: the slot is outside `head..tail`, so the consumer won't touch
+    // it until the store below publishes it.
+    unsafe { (*self.buf[tail % N].get()).write(value) };
+    self.tail.store(Self::next(tail), Ordering::Release);
+    Ok(())
+  }
+
+  /// # Safety
+  ///
+  /// Must only be called by one thread at a time.
+  #[inline]
+  unsafe fn dequeue(&self) -> Option<T> {
+    let head = self.head.load(Ordering::Relaxed);
+    let tail = self.tail.load(Ordering::Acquire);
+    if head == tail {
+      return None;
+    }
+    // SAFETY: the slot is within `head..tail`, so the producer initialized
+    // it, and won't reuse it until the store below releases it.
+    let value = unsafe { (*self.buf[head % N].get()).assume_init_read() };
+    self.head.store(Self::next(head), Ordering::Release);
+    Some(value)
+  }
+
+  /// # Safety
+  ///
+  /// Must only be called by the consumer.
+  #[inline]
+  unsafe fn front(&self) -> Option<&T> {
+    let head = self.head.load(Ordering::Relaxed);
+    let tail = self.tail.load(Ordering::Acquire);
+    if head == tail {
+      return None;
+    }
+    // SAFETY: as in `dequeue`, and the slot stays put until the consumer
+    // pops it.
+    Some(unsafe { (*self.buf[head % N].get()).assume_init_ref() })
+  }
+}
+
+impl<T, const N: usize> Default for SmallQueue<T, N> {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T, const N: usize> Drop for SmallQueue<T, N> {
+  fn drop(&mut self) {
+    while self.pop().is_some() {}
+  }
+}
+
+impl<T, const N: usize> Debug for SmallQueue<T, N> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_struct("SmallQueue")
+      .field("len", &self.len())
+      .field("capacity", &N)
+      .finish()
+  }
+}
+
+/// The pushing half of a [`SmallQueue`].
+///
+/// Created by [`SmallQueue::split`].
+pub struct Producer<'a, T, const N: usize> {
+  queue: &'a SmallQueue<T, N>,
+}
+
+impl<'a, T, const N: usize> Producer<'a, T, N> {
+  /// Pushes `value` onto the back of the queue, or returns it in an error if
+  /// the queue is full.
+  #[inline]
+  pub fn push(&mut self, value: T) -> Result<(), CapacityError<T>> {
+    // SAFETY: there is only one producer, and `&mut self` keeps it on one
+    // thread at a time.
+    unsafe { self.queue.enqueue(value) }
+  }
+
+  /// Returns the number of values in the queue.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.queue.len()
+  }
+
+  /// Returns `true` if the queue holds no values.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.queue.is_empty()
+  }
+
+  /// Returns `true` if the queue is full, so that a push would fail.
+  ///
+  /// Only the consumer can make room, so a queue that isn't full stays that
+  /// way until the next push.
+  #[inline(always)]
+  pub fn is_full(&self) -> bool {
+    self.queue.is_full()
+  }
+
+  /// Wraps the producer in a [`SpillingProducer`], whose pushes never fail.
+  #[inline]
+  pub fn into_spilling(self) -> SpillingProducer<'a, T, N> {
+    SpillingProducer {
+      producer: self,
+      backlog:  VecDeque::new(),
+    }
+  }
+}
+
+impl<T, const N: usize> Debug for Producer<'_, T, N> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Producer")
+      .field("len", &self.len())
+      .finish()
+  }
+}
+
+/// The popping half of a [`SmallQueue`].
+///
+/// Created by [`SmallQueue::split`]. A `Consumer` can be sent to another
+/// thread, but not shared between threads, since [`peek`](Self::peek) hands
+/// out references to the value at the front of the queue.
+pub struct Consumer<'a, T, const N: usize> {
+  queue:     &'a SmallQueue<T, N>,
+  /// Keeps the consumer from being `Sync`.
+  _not_sync: PhantomData<*const ()>,
+}
+
+// SAFETY: only the values themselves cross threads through the consumer.
+unsafe impl<T: Send, const N: usize> Send for Consumer<'_, T, N> {}
+
+impl<T, const N: usize> Consumer<'_, T, N> {
+  /// Removes the value at the front of the queue, if any.
+  #[inline]
+  pub fn pop(&mut self) -> Option<T> {
+    // SAFETY: there is only one consumer, and `&mut self` keeps it on one
+    // thread at a time.
+    unsafe { self.queue.dequeue() }
+  }
+
+  /// Returns a reference to the value at the front of the queue, if any,
+  /// without removing it.
+  ///
+  /// The consumer can't be shared between threads, so the reference can't
+  /// be used from several threads at once:
+  ///
+  /// ```rust,compile_fail
+  /// use std::cell::Cell;
+  ///
+  /// use moos::SmallQueue;
+  ///
+  /// let mut queue = SmallQueue::<Cell<u8>, 4>::new();
+  /// let (mut producer, consumer) = queue.split();
+  /// producer.push(Cell::new(0)).unwrap();
+  /// std::thread::scope(|s| {
+  ///   s.spawn(|| consumer.peek().unwrap().set(1));
+  ///   s.spawn(|| consumer.peek().unwrap().set(2));
+  /// });
+  /// ```
+  #[inline]
+  pub fn peek(&self) -> Option<&T> {
+    // SAFETY: the value can't be popped while the returned reference
+    // borrows the only consumer.
+    unsafe { self.queue.front() }
+  }
+
+  /// Returns the number of values in the queue.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.queue.len()
+  }
+
+  /// Returns `true` if the queue holds no values.
+  ///
+  /// Only the producer can add values, so a queue that isn't empty stays
+  /// that way until the next pop.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.queue.is_empty()
+  }
+}
+
+impl<T, const N: usize> Iterator for Consumer<'_, T, N> {
+  type Item = T;
+
+  /// Pops the value at the front of the queue, if any. Returns `None` when
+  /// the queue is empty, which may change once the producer pushes again.
+  #[inline(always)]
+  fn next(&mut self) -> Option<T> {
+    self.pop()
+  }
+}
+
+impl<T, const N: usize> Debug for Consumer<'_, T, N> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Consumer")
+      .field("len", &self.len())
+      .finish()
+  }
+}
+
+/// A [`Producer`] that holds values on the heap while the queue is full,
+/// instead of handing them back.
+///
+/// Held values stay with the producer, in order, and are moved into the
+/// queue as the consumer makes room, on the next [`push`](Self::push) or
+/// [`flush`](Self::flush). The consumer never allocates, so the queue can
+/// still be drained from an interrupt handler. Values still held when the
+/// producer is dropped are dropped with it.
+///
+/// Created by [`Producer::into_spilling`].
+///
+/// # Example
+///
+/// ```rust
+/// use moos::SmallQueue;
+///
+/// let mut queue: SmallQueue<u8, 2> = SmallQueue::new();
+/// let (tx, mut rx) = queue.split();
+/// let mut tx = tx.into_spilling();
+/// for i in 0..5 {
+///   tx.push(i);
+/// }
+/// assert_eq!(tx.pending(), 3);
+///
+/// assert_eq!(rx.by_ref().collect::<Vec<_>>(), [0, 1]);
+/// assert_eq!(tx.flush(), 1);
+/// assert_eq!(rx.collect::<Vec<_>>(), [2, 3]);
+/// ```
+pub struct SpillingProducer<'a, T, const N: usize> {
+  producer: Producer<'a, T, N>,
+  backlog:  VecDeque<T>,
+}
+
+impl<T, const N: usize> SpillingProducer<'_, T, N> {
+  /// Pushes `value` onto the back of the queue, holding it on the heap if
+  /// the queue is full.
+  #[inline]
+  pub fn push(&mut self, value: T) {
+    if self.flush() == 0 {
+      if let Err(CapacityError(value)) = self.producer.push(value) {
+        self.backlog.push_back(value);
+      }
+    } else {
+      self.backlog.push_back(value);
+    }
+  }
+
+  /// Moves as many held values into the queue as fit, and returns the
+  /// number still held.
+  pub fn flush(&mut self) -> usize {
+    while let Some(value) = self.backlog.pop_front() {
+      if let Err(CapacityError(value)) = self.producer.push(value) {
+        self.backlog.push_front(value);
+        break;
+      }
+    }
+    self.backlog.len()
+  }
+
+  /// Returns the number of values held on the heap, waiting for room in the
+  /// queue.
+  #[inline(always)]
+  pub fn pending(&self) -> usize {
+    self.backlog.len()
+  }
+}
+
+impl<T, const N: usize> Debug for SpillingProducer<'_, T, N> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_struct("SpillingProducer")
+      .field("len", &self.producer.len())
+      .field("pending", &self.pending())
+      .finish()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::rc::Rc;
+  use alloc::vec::Vec;
+
+  use super::*;
+
+  #[test]
+  fn small_queue_push_pop_wraps() {
+    let mut queue: SmallQueue<u32, 3> = SmallQueue::new();
+    for round in 0..10 {
+      assert!(queue.push(round).is_ok());
+      assert!(queue.push(round + 100).is_ok());
+      assert_eq!(queue.len(), 2);
+      assert_eq!(queue.pop(), Some(round));
+      assert_eq!(queue.pop(), Some(round + 100));
+      assert!(queue.is_empty());
+    }
+    queue.push(1).unwrap();
+    queue.push(2).unwrap();
+    queue.push(3).unwrap();
+    assert!(queue.is_full());
+    assert_eq!(queue.push(4).unwrap_err().into_inner(), 4);
+
+    let (_, rx) = queue.split();
+    assert_eq!(rx.peek(), Some(&1));
+    assert_eq!(rx.collect::<Vec<_>>(), [1, 2, 3]);
+  }
+
+  #[test]
+  fn small_queue_threads() {
+    static QUEUE_SUM: AtomicUsize = AtomicUsize::new(0);
+
+    let mut queue: SmallQueue<usize, 8> = SmallQueue::new();
+    let (tx, mut rx) = queue.split();
+    std::thread::scope(|scope| {
+      scope.spawn(move || {
+        let mut tx = tx.into_spilling();
+        for i in 1..=1000 {
+          tx.push(i);
+        }
+        while tx.flush() > 0 {
+          std::thread::yield_now();
+        }
+      });
+      scope.spawn(move || {
+        let mut expected = 1;
+        while expected <= 1000 {
+          match rx.pop() {
+            Some(i) => {
+              assert_eq!(i, expected);
+              QUEUE_SUM.fetch_add(i, Ordering::Relaxed);
+              expected += 1;
+            }
+            None => std::thread::yield_now(),
+          }
+        }
+      });
+    });
+    assert_eq!(QUEUE_SUM.load(Ordering::Relaxed), 500_500);
+    assert!(queue.is_empty());
+  }
+
+  #[test]
+  fn small_queue_drops_remaining() {
+    let count = Rc::new(());
+    let mut queue: SmallQueue<Rc<()>, 4> = SmallQueue::default();
+    for _ in 0..3 {
+      queue.push(count.clone()).unwrap();
+    }
+    drop(queue.pop());
+    assert_eq!(Rc::strong_count(&count), 3);
+    drop(queue);
+    assert_eq!(Rc::strong_count(&count), 1);
+  }
+}
@@ -0,0 +1,674 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::iter::FusedIterator;
+use core::ops::BitAnd;
+use core::ops::BitOr;
+use core::ops::BitXor;
+use core::ops::Sub;
+use core::slice;
+
+use crate::CompactVec;
+use crate::compact_vec;
+
+/// A set that stores up to `N` elements inline, and spills over to the heap
+/// beyond that.
+///
+/// This is the set counterpart of [`SmallMap`](crate::SmallMap): elements are
+/// kept in a [`CompactVec`] and found with a linear scan using [`Eq`], so no
+/// hashing or ordering is required of them, and small sets of tags or
+/// capabilities never allocate.
+///
+/// Iteration visits elements in the order they were first inserted, and
+/// removing an element preserves the order of the others. The set operations
+/// yield the elements of `self` first, each in its own set's order.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::SmallSet;
+///
+/// let mut caps: SmallSet<&str, 4> = SmallSet::new();
+/// assert!(caps.insert("read"));
+/// assert!(caps.insert("write"));
+/// assert!(!caps.insert("read"));
+/// assert!(caps.contains("write"));
+///
+/// let required = SmallSet::<&str, 4>::from(["read", "exec"]);
+/// let missing: Vec<_> = required.difference(&caps).collect();
+/// assert_eq!(missing, [&"exec"]);
+/// ```
+#[derive(Clone)]
+pub struct SmallSet<T, const N: usize> {
+  items: CompactVec<T, N>,
+}
+
+impl<T, const N: usize> SmallSet<T, N> {
+  /// Creates a new, empty `SmallSet` using inline storage.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      items: CompactVec::new(),
+    }
+  }
+
+  /// Returns the number of elements in the set.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.items.len()
+  }
+
+  /// Returns `true` if the set contains no elements.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.items.is_empty()
+  }
+
+  /// Returns the number of elements the set can hold without reallocating.
+  #[inline(always)]
+  pub fn capacity(&self) -> usize {
+    self.items.capacity()
+  }
+
+  /// Returns `true` if the elements are currently stored inline.
+  #[inline(always)]
+  pub fn is_inline(&self) -> bool {
+    self.items.is_inline()
+  }
+
+  /// Reserves capacity for at least `additional` more elements.
+  #[inline]
+  pub fn reserve(&mut self, additional: usize) {
+    self.items.reserve(additional);
+  }
+
+  /// Shrinks the capacity of the set as much as possible, moving the
+  /// elements back inline if they fit.
+  #[inline]
+  pub fn shrink_to_fit(&mut self) {
+    self.items.shrink_to_fit();
+  }
+
+  /// Removes all elements, releasing any heap storage.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.items.clear();
+  }
+
+  /// Returns the elements as a slice, in iteration order.
+  #[inline(always)]
+  pub fn as_slice(&self) -> &[T] {
+    &self.items
+  }
+
+  /// Returns an iterator over the elements, in insertion order.
+  #[inline(always)]
+  pub fn iter(&self) -> slice::Iter<'_, T> {
+    self.items.iter()
+  }
+
+  /// Retains only the elements for which `f` returns `true`, preserving the
+  /// order of the elements that remain.
+  pub fn retain<F>(&mut self, mut f: F)
+  where
+    F: FnMut(&T) -> bool,
+  {
+    let mut kept = 0;
+    for i in 0..self.items.len() {
+      if f(&self.items[i]) {
+        self.items.swap(kept, i);
+        kept += 1;
+      }
+    }
+    self.items.truncate(kept);
+  }
+
+  /// Returns the index of `value` in the set, if present.
+  #[inline]
+  fn position<Q>(&self, value: &Q) -> Option<usize>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized + Eq,
+  {
+    self.items.iter().position(|item| item.borrow() == value)
+  }
+
+  /// Returns `true` if the set contains `value`.
+  #[inline]
+  pub fn contains<Q>(&self, value: &Q) -> bool
+  where
+    T: Borrow<Q>,
+    Q: ?Sized + Eq,
+  {
+    self.position(value).is_some()
+  }
+
+  /// Returns a reference to the element in the set equal to `value`, if any.
+  #[inline]
+  pub fn get<Q>(&self, value: &Q) -> Option<&T>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized + Eq,
+  {
+    self.position(value).map(|index| &self.items[index])
+  }
+
+  /// Adds `value` to the set, returning `true` if it was not already
+  /// present. An existing equal element is left untouched.
+  #[inline]
+  pub fn insert(&mut self, value: T) -> bool
+  where
+    T: Eq,
+  {
+    if self.contains(&value) {
+      return false;
+    }
+    self.items.push(value);
+    true
+  }
+
+  /// Adds `value` to the set, replacing and returning an existing equal
+  /// element, if any. A replaced element keeps its position.
+  #[inline]
+  pub fn replace(&mut self, value: T) -> Option<T>
+  where
+    T: Eq,
+  {
+    match self.position(&value) {
+      Some(index) => Some(core::mem::replace(&mut self.items[index], value)),
+      None => {
+        self.items.push(value);
+        None
+      }
+    }
+  }
+
+  /// Removes `value` from the set, returning `true` if it was present.
+  #[inline]
+  pub fn remove<Q>(&mut self, value: &Q) -> bool
+  where
+    T: Borrow<Q>,
+    Q: ?Sized + Eq,
+  {
+    self.take(value).is_some()
+  }
+
+  /// Removes and returns the element in the set equal to `value`, if any.
+  #[inline]
+  pub fn take<Q>(&mut self, value: &Q) -> Option<T>
+  where
+    T: Borrow<Q>,
+    Q: ?Sized + Eq,
+  {
+    let index = self.position(value)?;
+    Some(self.items.remove(index))
+  }
+
+  /// Returns an iterator over the elements in `self` or `other`, without
+  /// duplicates: all of `self`, followed by the elements only in `other`.
+  #[inline]
+  pub fn union<'a, const M: usize>(
+    &'a self,
+    other: &'a SmallSet<T, M>,
+  ) -> Union<'a, T>
+  where
+    T: Eq,
+  {
+    Union {
+      first:  self.iter(),
+      second: other.iter(),
+      seen:   self.as_slice(),
+    }
+  }
+
+  /// Returns an iterator over the elements in both `self` and `other`, in
+  /// the order of `self`.
+  #[inline]
+  pub fn intersection<'a, const M: usize>(
+    &'a self,
+    other: &'a SmallSet<T, M>,
+  ) -> Intersection<'a, T>
+  where
+    T: Eq,
+  {
+    Intersection {
+      iter:  self.iter(),
+      other: other.as_slice(),
+    }
+  }
+
+  /// Returns an iterator over the elements in `self` but not in `other`, in
+  /// the order of `self`.
+  #[inline]
+  pub fn difference<'a, const M: usize>(
+    &'a self,
+    other: &'a SmallSet<T, M>,
+  ) -> Difference<'a, T>
+  where
+    T: Eq,
+  {
+    Difference {
+      iter:  self.iter(),
+      other: other.as_slice(),
+    }
+  }
+
+  /// Returns an iterator over the elements in exactly one of `self` and
+  /// `other`: those only in `self`, followed by those only in `other`.
+  #[inline]
+  pub fn symmetric_difference<'a, const M: usize>(
+    &'a self,
+    other: &'a SmallSet<T, M>,
+  ) -> SymmetricDifference<'a, T>
+  where
+    T: Eq,
+  {
+    SymmetricDifference {
+      first:  self.difference(other),
+      second: other.difference(self),
+    }
+  }
+
+  /// Returns `true` if every element of `self` is also in `other`.
+  #[inline]
+  pub fn is_subset<const M: usize>(&self, other: &SmallSet<T, M>) -> bool
+  where
+    T: Eq,
+  {
+    self.len() <= other.len() && self.iter().all(|item| other.contains(item))
+  }
+
+  /// Returns `true` if every element of `other` is also in `self`.
+  #[inline]
+  pub fn is_superset<const M: usize>(&self, other: &SmallSet<T, M>) -> bool
+  where
+    T: Eq,
+  {
+    other.is_subset(self)
+  }
+
+  /// Returns `true` if `self` and `other` have no elements in common.
+  #[inline]
+  pub fn is_disjoint<const M: usize>(&self, other: &SmallSet<T, M>) -> bool
+  where
+    T: Eq,
+  {
+    self.intersection(other).next().is_none()
+  }
+}
+
+/// An iterator over the union of two [`SmallSet`]s.
+///
+/// Created by [`SmallSet::union`].
+#[derive(Clone)]
+pub struct Union<'a, T> {
+  first:  slice::Iter<'a, T>,
+  second: slice::Iter<'a, T>,
+  seen:   &'a [T],
+}
+
+impl<'a, T: Eq> Iterator for Union<'a, T> {
+  type Item = &'a T;
+
+  #[inline]
+  fn next(&mut self) -> Option<&'a T> {
+    if let Some(item) = self.first.next() {
+      return Some(item);
+    }
+    let seen = self.seen;
+    self.second.by_ref().find(|item| !seen.contains(item))
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let first = self.first.len();
+    (first, Some(first + self.second.len()))
+  }
+}
+
+impl<T: Eq> FusedIterator for Union<'_, T> {}
+
+/// An iterator over the intersection of two [`SmallSet`]s.
+///
+/// Created by [`SmallSet::intersection`].
+#[derive(Clone)]
+pub struct Intersection<'a, T> {
+  iter:  slice::Iter<'a, T>,
+  other: &'a [T],
+}
+
+impl<'a, T: Eq> Iterator for Intersection<'a, T> {
+  type Item = &'a T;
+
+  #[inline]
+  fn next(&mut self) -> Option<&'a T> {
+    let other = self.other;
+    self.iter.by_ref().find(|item| other.contains(item))
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (0, Some(self.iter.len()))
+  }
+}
+
+impl<T: Eq> FusedIterator for Intersection<'_, T> {}
+
+/// An iterator over the difference of two [`SmallSet`]s.
+///
+/// Created by [`SmallSet::difference`].
+#[derive(Clone)]
+pub struct Difference<'a, T> {
+  iter:  slice::Iter<'a, T>,
+  other: &'a [T],
+}
+
+impl<'a, T: Eq> Iterator for Difference<'a, T> {
+  type Item = &'a T;
+
+  #[inline]
+  fn next(&mut self) -> Option<&'a T> {
+    let other = self.other;
+    self.iter.by_ref().find(|item| !other.contains(item))
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    (0, Some(self.iter.len()))
+  }
+}
+
+impl<T: Eq> FusedIterator for Difference<'_, T> {}
+
+/// An iterator over the symmetric difference of two [`SmallSet`]s.
+///
+/// Created by [`SmallSet::symmetric_difference`].
+#[derive(Clone)]
+pub struct SymmetricDifference<'a, T> {
+  first:  Difference<'a, T>,
+  second: Difference<'a, T>,
+}
+
+impl<'a, T: Eq> Iterator for SymmetricDifference<'a, T> {
+  type Item = &'a T;
+
+  #[inline]
+  fn next(&mut self) -> Option<&'a T> {
+    self.first.next().or_else(|| self.second.next())
+  }
+
+  #[inline]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let (_, first) = self.first.size_hint();
+    let (_, second) = self.second.size_hint();
+    (0, first.zip(second).map(|(a, b)| a + b))
+  }
+}
+
+impl<T: Eq> FusedIterator for SymmetricDifference<'_, T> {}
+
+impl<T, const N: usize> Default for SmallSet<T, N> {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T: Debug, const N: usize> Debug for SmallSet<T, N> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_set().entries(self.iter()).finish()
+  }
+}
+
+impl<T: Eq, const N: usize, const M: usize> PartialEq<SmallSet<T, M>>
+  for SmallSet<T, N>
+{
+  /// Two sets are equal if they contain the same elements, regardless of
+  /// their insertion order.
+  #[inline]
+  fn eq(&self, other: &SmallSet<T, M>) -> bool {
+    self.len() == other.len() && self.is_subset(other)
+  }
+}
+
+impl<T: Eq, const N: usize> Eq for SmallSet<T, N> {}
+
+impl<T: Eq, const N: usize> Extend<T> for SmallSet<T, N> {
+  fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+    for value in iter {
+      self.insert(value);
+    }
+  }
+}
+
+impl<'a, T: Eq + Copy + 'a, const N: usize> Extend<&'a T> for SmallSet<T, N> {
+  #[inline]
+  fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+    self.extend(iter.into_iter().copied());
+  }
+}
+
+impl<T: Eq, const N: usize> FromIterator<T> for SmallSet<T, N> {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+    let mut set = Self::new();
+    set.extend(iter);
+    set
+  }
+}
+
+impl<T: Eq, const N: usize, const M: usize> From<[T; M]> for SmallSet<T, N> {
+  #[inline]
+  fn from(items: [T; M]) -> Self {
+    Self::from_iter(items)
+  }
+}
+
+impl<T, const N: usize> IntoIterator for SmallSet<T, N> {
+  type Item = T;
+  type IntoIter = compact_vec::IntoIter<T, N>;
+
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    self.items.into_iter()
+  }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a SmallSet<T, N> {
+  type Item = &'a T;
+  type IntoIter = slice::Iter<'a, T>;
+
+  #[inline(always)]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<T: Eq + Clone, const N: usize, const M: usize> BitOr<&SmallSet<T, M>>
+  for &SmallSet<T, N>
+{
+  type Output = SmallSet<T, N>;
+
+  /// Returns the union of `self` and `rhs` as a new set.
+  #[inline]
+  fn bitor(self, rhs: &SmallSet<T, M>) -> SmallSet<T, N> {
+    let mut set = self.clone();
+    set.items.extend(rhs.difference(self).cloned());
+    set
+  }
+}
+
+impl<T: Eq + Clone, const N: usize, const M: usize> BitAnd<&SmallSet<T, M>>
+  for &SmallSet<T, N>
+{
+  type Output = SmallSet<T, N>;
+
+  /// Returns the intersection of `self` and `rhs` as a new set.
+  #[inline]
+  fn bitand(self, rhs: &SmallSet<T, M>) -> SmallSet<T, N> {
+    SmallSet {
+      items: self.intersection(rhs).cloned().collect(),
+    }
+  }
+}
+
+impl<T: Eq + Clone, const N: usize, const M: usize> Sub<&SmallSet<T, M>>
+  for &SmallSet<T, N>
+{
+  type Output = SmallSet<T, N>;
+
+  /// Returns the difference of `self` and `rhs` as a new set.
+  #[inline]
+  fn sub(self, rhs: &SmallSet<T, M>) -> SmallSet<T, N> {
+    SmallSet {
+      items: self.difference(rhs).cloned().collect(),
+    }
+  }
+}
+
+impl<T: Eq + Clone, const N: usize, const M: usize> BitXor<&SmallSet<T, M>>
+  for &SmallSet<T, N>
+{
+  type Output = SmallSet<T, N>;
+
+  /// Returns the symmetric difference of `self` and `rhs` as a new set.
+  #[inline]
+  fn bitxor(self, rhs: &SmallSet<T, M>) -> SmallSet<T, N> {
+    SmallSet {
+      items: self.symmetric_difference(rhs).cloned().collect(),
+    }
+  }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use core::marker::PhantomData;
+
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+  use serde::de;
+
+  use super::*;
+
+  impl<T: Serialize, const N: usize> Serialize for SmallSet<T, N> {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      serializer.collect_seq(self.iter())
+    }
+  }
+
+  struct SmallSetVisitor<T, const N: usize>(PhantomData<T>);
+
+  impl<'de, T, const N: usize> de::Visitor<'de> for SmallSetVisitor<T, N>
+  where
+    T: Deserialize<'de> + Eq,
+  {
+    type Value = SmallSet<T, N>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a sequence")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+      A: de::SeqAccess<'de>,
+    {
+      let mut set = SmallSet::new();
+      while let Some(value) = seq.next_element()? {
+        set.insert(value);
+      }
+      Ok(set)
+    }
+  }
+
+  impl<'de, T, const N: usize> Deserialize<'de> for SmallSet<T, N>
+  where
+    T: Deserialize<'de> + Eq,
+  {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      deserializer.deserialize_seq(SmallSetVisitor(PhantomData))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn small_set_insert_remove() {
+    let mut set: SmallSet<u8, 2> = SmallSet::new();
+    assert!(set.insert(3));
+    assert!(set.insert(1));
+    assert!(!set.insert(3));
+    assert!(set.is_inline());
+    assert!(set.insert(2));
+    assert!(!set.is_inline());
+    assert_eq!(set.as_slice(), [3, 1, 2]);
+
+    assert!(set.remove(&1));
+    assert!(!set.remove(&1));
+    assert_eq!(set.take(&3), Some(3));
+    assert_eq!(set.replace(2), Some(2));
+    assert_eq!(set.as_slice(), [2]);
+  }
+
+  #[test]
+  fn small_set_borrowed_lookup() {
+    let set: SmallSet<String, 4> =
+      ["a", "b"].into_iter().map(String::from).collect();
+    assert!(set.contains("a"));
+    assert_eq!(set.get("b").map(String::as_str), Some("b"));
+    assert!(!set.contains("c"));
+  }
+
+  #[test]
+  fn small_set_operations() {
+    let a = SmallSet::<u8, 4>::from([1, 2, 3, 4]);
+    let b = SmallSet::<u8, 2>::from([5, 4, 3]);
+
+    assert_eq!(a.union(&b).copied().collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+    assert_eq!(a.intersection(&b).copied().collect::<Vec<_>>(), [3, 4]);
+    assert_eq!(a.difference(&b).copied().collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(
+      a.symmetric_difference(&b).copied().collect::<Vec<_>>(),
+      [1, 2, 5]
+    );
+
+    assert_eq!((&a | &b).as_slice(), [1, 2, 3, 4, 5]);
+    assert_eq!((&a & &b).as_slice(), [3, 4]);
+    assert_eq!((&b - &a).as_slice(), [5]);
+    assert_eq!((&a ^ &b).as_slice(), [1, 2, 5]);
+
+    let small = SmallSet::<u8, 2>::from([4, 3]);
+    assert!(small.is_subset(&a));
+    assert!(a.is_superset(&small));
+    assert!(!a.is_subset(&small));
+    assert!(small.is_disjoint(&SmallSet::<u8, 1>::from([9])));
+    assert_eq!(small, SmallSet::<u8, 8>::from([3, 4]));
+  }
+
+  #[test]
+  fn small_set_retain_and_debug() {
+    let mut set: SmallSet<u8, 8> = (1..=6).collect();
+    set.retain(|n| n % 2 == 0);
+    assert_eq!(format!("{set:?}"), "{2, 4, 6}");
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn small_set_serde() {
+    let set: SmallSet<u8, 2> = serde_json::from_str("[1,2,1,3]").unwrap();
+    assert_eq!(set.as_slice(), [1, 2, 3]);
+    assert_eq!(serde_json::to_string(&set).unwrap(), "[1,2,3]");
+  }
+}
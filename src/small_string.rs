@@ -0,0 +1,2524 @@
+use alloc::boxed::Box;
+use alloc::ffi::CString;
+use alloc::ffi::NulError;
+use alloc::string::String;
+use core::borrow::Borrow;
+use core::borrow::BorrowMut;
+use core::cmp::Ordering;
+use core::convert::Infallible;
+use core::ffi::CStr;
+use core::ffi::FromBytesWithNulError;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+use core::ops::DerefMut;
+use core::ops::Index;
+use core::ops::IndexMut;
+use core::ops::RangeBounds;
+use core::slice::SliceIndex;
+use core::str;
+use core::str::Chars;
+use core::str::FromStr;
+use core::str::Utf8Error;
+
+use crate::CapacityError;
+use crate::CompactVec;
+use crate::MemoryUsage;
+use crate::compact_vec::resolve_range;
+
+/// Error returned by [`SmallString::from_utf8`] when the given bytes are not
+/// valid UTF-8.
+///
+/// The original bytes are handed back, and can be recovered with
+/// [`into_bytes`](FromUtf8Error::into_bytes).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FromUtf8Error<const N: usize> {
+  bytes: CompactVec<u8, N>,
+  error: Utf8Error,
+}
+
+impl<const N: usize> FromUtf8Error<N> {
+  /// Returns a slice of the bytes that were attempted to be converted.
+  #[inline(always)]
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.bytes
+  }
+
+  /// Returns the bytes that were attempted to be converted.
+  #[inline(always)]
+  pub fn into_bytes(self) -> CompactVec<u8, N> {
+    self.bytes
+  }
+
+  /// Returns the underlying [`Utf8Error`], with details about the failure.
+  #[inline(always)]
+  pub fn utf8_error(&self) -> Utf8Error {
+    self.error
+  }
+}
+
+impl<const N: usize> Display for FromUtf8Error<N> {
+  #[inline(always)]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Display::fmt(&self.error, f)
+  }
+}
+
+impl<const N: usize> core::error::Error for FromUtf8Error<N> {}
+
+/// Error returned by [`SmallString::from_utf16`] when the given code units
+/// are not valid UTF-16 (contain an unpaired surrogate).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FromUtf16Error(());
+
+impl Display for FromUtf16Error {
+  #[inline(always)]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.write_str("invalid utf-16: lone surrogate found")
+  }
+}
+
+impl core::error::Error for FromUtf16Error {}
+
+/// A growable UTF-8 string that stores up to `N` bytes inline, spilling over
+/// to the heap once that capacity is exceeded.
+///
+/// `SmallString` is to [`String`] what [`CompactVec`] is to `Vec`: it is
+/// built on a `CompactVec<u8, N>`, so short strings never touch the heap,
+/// while longer ones still work transparently. Unlike [`InlineStr`], its
+/// inline capacity is chosen by the caller, and it can be edited in place.
+///
+/// [`InlineStr`]: crate::InlineStr
+///
+/// # Example
+///
+/// ```rust
+/// use moos::SmallString;
+///
+/// let mut s: SmallString<8> = SmallString::from("hello");
+/// s.push('!');
+/// assert!(s.is_inline());
+///
+/// s.push_str(" world");
+/// assert!(!s.is_inline());
+/// assert_eq!(s, "hello! world");
+/// ```
+#[derive(Clone, Default)]
+pub struct SmallString<const N: usize> {
+  buf: CompactVec<u8, N>,
+}
+
+impl<const N: usize> SmallString<N> {
+  /// Creates a new, empty `SmallString` using inline storage.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      buf: CompactVec::new(),
+    }
+  }
+
+  /// Creates an inline `SmallString` holding a copy of `s`, in a `const`
+  /// context if need be.
+  ///
+  /// This is what the [`small_str!`](crate::small_str) macro uses to check
+  /// string literals at compile time.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `s` is longer than `N` bytes, which is a compile error when
+  /// called in a `const` context.
+  #[inline]
+  pub const fn from_inline_str(s: &str) -> Self {
+    Self {
+      buf: CompactVec::from_inline_slice(s.as_bytes()),
+    }
+  }
+
+  /// Creates a new, empty `SmallString` with room for at least `capacity`
+  /// bytes.
+  ///
+  /// If `capacity <= N`, the string is stored inline and no allocation is
+  /// made. Otherwise, it spills to the heap up front, with exactly enough
+  /// room for `capacity` bytes.
+  #[inline]
+  pub fn with_capacity(capacity: usize) -> Self {
+    let mut string = Self::new();
+    string.buf.reserve_exact(capacity);
+    string
+  }
+
+  /// Converts a vector of bytes into a `SmallString`, without copying, if
+  /// the bytes are valid UTF-8.
+  ///
+  /// The storage of `bytes` is kept as-is, so inline bytes stay inline and
+  /// spilled bytes keep their heap allocation.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [`FromUtf8Error`] holding the original bytes if they are not
+  /// valid UTF-8.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  /// use moos::SmallString;
+  ///
+  /// let bytes: CompactVec<u8, 8> = CompactVec::from(*b"moo");
+  /// let s = SmallString::from_utf8(bytes).unwrap();
+  /// assert_eq!(s, "moo");
+  ///
+  /// let bytes: CompactVec<u8, 8> = CompactVec::from([0xff, 0xfe]);
+  /// let err = SmallString::from_utf8(bytes).unwrap_err();
+  /// assert_eq!(err.into_bytes(), [0xff, 0xfe]);
+  /// ```
+  #[inline]
+  pub fn from_utf8(bytes: CompactVec<u8, N>) -> Result<Self, FromUtf8Error<N>> {
+    match str::from_utf8(&bytes) {
+      Ok(_) => Ok(Self { buf: bytes }),
+      Err(error) => Err(FromUtf8Error { bytes, error }),
+    }
+  }
+
+  /// Converts a slice of bytes into a `SmallString`, replacing any invalid
+  /// UTF-8 sequences with the replacement character `U+FFFD` (`�`).
+  ///
+  /// The length of the result is computed up front, so it stays inline if it
+  /// fits within `N` bytes, and is allocated at most once otherwise.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let s: SmallString<16> = SmallString::from_utf8_lossy(b"temp=\xff21C");
+  /// assert_eq!(s, "temp=\u{FFFD}21C");
+  /// assert!(s.is_inline());
+  /// ```
+  pub fn from_utf8_lossy(bytes: &[u8]) -> Self {
+    const REPLACEMENT: &str = "\u{FFFD}";
+    let len = bytes
+      .utf8_chunks()
+      .map(|chunk| {
+        let invalid = !chunk.invalid().is_empty();
+        chunk.valid().len() + usize::from(invalid) * REPLACEMENT.len()
+      })
+      .sum();
+    let mut string = Self::with_capacity(len);
+    for chunk in bytes.utf8_chunks() {
+      string.push_str(chunk.valid());
+      if !chunk.invalid().is_empty() {
+        string.push_str(REPLACEMENT);
+      }
+    }
+    string
+  }
+
+  /// Decodes a slice of UTF-16 code units into a `SmallString`, returning an
+  /// error if it contains an unpaired surrogate.
+  ///
+  /// The length of the result is computed up front, so it stays inline if it
+  /// fits within `N` bytes, and is allocated at most once otherwise.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let units = [0x55, 0x53, 0x42, 0x20, 0x2764];
+  /// let s: SmallString<8> = SmallString::from_utf16(&units).unwrap();
+  /// assert_eq!(s, "USB ❤");
+  /// assert!(s.is_inline());
+  ///
+  /// assert!(SmallString::<8>::from_utf16(&[0x61, 0xD800]).is_err());
+  /// ```
+  pub fn from_utf16(units: &[u16]) -> Result<Self, FromUtf16Error> {
+    let mut len = 0;
+    for c in char::decode_utf16(units.iter().copied()) {
+      len += c.map_err(|_| FromUtf16Error(()))?.len_utf8();
+    }
+    let mut string = Self::with_capacity(len);
+    string.extend(char::decode_utf16(units.iter().copied()).flatten());
+    Ok(string)
+  }
+
+  /// Decodes a slice of UTF-16 code units into a `SmallString`, replacing
+  /// any unpaired surrogates with the replacement character `U+FFFD` (`�`).
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let s: SmallString<8> = SmallString::from_utf16_lossy(&[0x61, 0xDC00]);
+  /// assert_eq!(s, "a\u{FFFD}");
+  /// ```
+  pub fn from_utf16_lossy(units: &[u16]) -> Self {
+    let chars = || {
+      char::decode_utf16(units.iter().copied())
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+    };
+    let mut string = Self::with_capacity(chars().map(char::len_utf8).sum());
+    string.extend(chars());
+    string
+  }
+
+  /// Converts a vector of bytes into a `SmallString` without checking that
+  /// it contains valid UTF-8.
+  ///
+  /// # Safety
+  ///
+  /// The bytes must be valid UTF-8.
+  #[inline(always)]
+  pub unsafe fn from_utf8_unchecked(bytes: CompactVec<u8, N>) -> Self {
+    Self { buf: bytes }
+  }
+
+  /// Creates a `SmallString` from pre-compiled format arguments, as
+  /// produced by [`format_args!`].
+  ///
+  /// This is what the [`format_small!`](crate::format_small) macro expands
+  /// to; the output is written directly into the string's buffer, without
+  /// an intermediate `String`.
+  #[inline]
+  pub fn from_fmt(args: fmt::Arguments<'_>) -> Self {
+    if let Some(s) = args.as_str() {
+      return Self::from(s);
+    }
+    let mut string = Self::new();
+    let _ = fmt::Write::write_fmt(&mut string, args);
+    string
+  }
+
+  /// Returns the length of the string in bytes.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.buf.len()
+  }
+
+  /// Returns `true` if the string has a length of zero.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.buf.is_empty()
+  }
+
+  /// Returns the number of bytes the string can hold without spilling to
+  /// the heap (when inline) or reallocating (when spilled).
+  #[inline(always)]
+  pub fn capacity(&self) -> usize {
+    self.buf.capacity()
+  }
+
+  /// Reserves capacity for at least `additional` more bytes, spilling to
+  /// the heap once up front if the result won't fit inline.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the new capacity overflows `usize`.
+  #[inline]
+  pub fn reserve(&mut self, additional: usize) {
+    self.buf.reserve(additional);
+  }
+
+  /// Shrinks the capacity of the string as much as possible, moving it back
+  /// inline if it fits.
+  #[inline]
+  pub fn shrink_to_fit(&mut self) {
+    self.buf.shrink_to_fit();
+  }
+
+  /// Shrinks the capacity of the string with a lower bound, moving it back
+  /// inline if both the length and `min_capacity` fit within `N` bytes.
+  #[inline]
+  pub fn shrink_to(&mut self, min_capacity: usize) {
+    self.buf.shrink_to(min_capacity);
+  }
+
+  /// Moves the string back into inline storage if it has spilled to the heap
+  /// but its contents now fit within `N` bytes, releasing the allocation.
+  ///
+  /// Returns `true` if the string is stored inline afterwards. Unlike
+  /// [`shrink_to_fit`](Self::shrink_to_fit), a string that still doesn't fit
+  /// keeps its heap buffer untouched.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let mut s: SmallString<8> = SmallString::from("a longer status line");
+  /// assert!(s.is_spilled());
+  /// assert!(!s.try_make_inline());
+  ///
+  /// s.truncate(6);
+  /// assert!(s.try_make_inline());
+  /// assert_eq!(s, "a long");
+  /// assert_eq!(s.memory_usage().heap, 0);
+  /// ```
+  #[inline]
+  pub fn try_make_inline(&mut self) -> bool {
+    if self.buf.is_spilled() && self.len() <= N {
+      self.buf.shrink_to(0);
+    }
+    self.buf.is_inline()
+  }
+
+  /// Returns `true` if the string is currently stored inline.
+  #[inline(always)]
+  pub fn is_inline(&self) -> bool {
+    self.buf.is_inline()
+  }
+
+  /// Returns `true` if the string has spilled over to the heap.
+  ///
+  /// This is the inverse of [`is_inline`](Self::is_inline).
+  #[inline(always)]
+  pub fn is_spilled(&self) -> bool {
+    self.buf.is_spilled()
+  }
+
+  /// Returns the size in bytes of the heap buffer owned by the string, or `0`
+  /// if it is stored inline.
+  #[inline(always)]
+  pub fn heap_capacity_bytes(&self) -> usize {
+    self.buf.heap_capacity_bytes()
+  }
+
+  /// Returns a breakdown of the memory footprint of the string, split into
+  /// its fixed inline size and any heap allocation it owns.
+  #[inline]
+  pub fn memory_usage(&self) -> MemoryUsage {
+    self.buf.memory_usage()
+  }
+
+  /// Returns the contents of the string as a byte slice.
+  #[inline(always)]
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.buf
+  }
+
+  /// Converts the string into its underlying vector of bytes, without
+  /// copying or changing where the bytes are stored.
+  #[inline(always)]
+  pub fn into_bytes(self) -> CompactVec<u8, N> {
+    self.buf
+  }
+
+  /// Converts the string into a `Box<str>`.
+  ///
+  /// A spilled string hands its heap buffer over to the box, shrinking it to
+  /// fit if it has excess capacity. An inline string is copied into a single,
+  /// exactly-sized allocation.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let s: SmallString<8> = SmallString::from("frozen");
+  /// let boxed: Box<str> = s.into_boxed_str();
+  /// assert_eq!(&*boxed, "frozen");
+  /// ```
+  #[inline]
+  pub fn into_boxed_str(self) -> Box<str> {
+    let bytes = self.buf.into_vec().into_boxed_slice();
+    unsafe { alloc::str::from_boxed_utf8_unchecked(bytes) }
+  }
+
+  /// Converts the string into a `SmallString` with a different inline
+  /// capacity `M`.
+  ///
+  /// The contents end up inline if they fit within `M` bytes. Otherwise a
+  /// spilled string hands its heap buffer over without copying, and an
+  /// inline string is copied into a new allocation.
+  ///
+  /// This is provided as a method because a `From<SmallString<N>>` impl for
+  /// `SmallString<M>` would overlap with the reflexive `From<T> for T` impl
+  /// when `N == M`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let long: SmallString<4> = SmallString::from("device-7");
+  /// assert!(long.is_spilled());
+  ///
+  /// let short: SmallString<16> = long.resize_capacity();
+  /// assert_eq!(short, "device-7");
+  /// assert!(short.is_inline());
+  /// ```
+  #[inline]
+  pub fn resize_capacity<const M: usize>(self) -> SmallString<M> {
+    if self.is_spilled() {
+      SmallString::from(String::from(self))
+    } else {
+      SmallString::from(self.as_str())
+    }
+  }
+
+  /// Returns a copy of the string with all matches of `from` replaced by
+  /// `to`.
+  ///
+  /// Unlike `str::replace`, which always allocates a `String`, the output
+  /// length is computed before building the result, so it stays inline if it
+  /// fits within `N` bytes and is allocated at most once otherwise.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let s: SmallString<16> = SmallString::from("a-b-c");
+  /// let replaced = s.replace("-", " / ");
+  /// assert_eq!(replaced, "a / b / c");
+  /// assert!(replaced.is_inline());
+  /// ```
+  #[inline]
+  pub fn replace(&self, from: &str, to: &str) -> Self {
+    self.replacen(from, to, usize::MAX)
+  }
+
+  /// Returns a copy of the string with the first `count` matches of `from`
+  /// replaced by `to`.
+  ///
+  /// Like [`replace`](Self::replace), the result is allocated at most once.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let s: SmallString<16> = SmallString::from("x=1;y=2;z=3");
+  /// assert_eq!(s.replacen(";", ", ", 2), "x=1, y=2, z=3");
+  /// ```
+  pub fn replacen(&self, from: &str, to: &str, count: usize) -> Self {
+    let len = if from.len() == to.len() {
+      self.len()
+    } else {
+      let matches = self.match_indices(from).take(count).count();
+      self.len() - matches * from.len() + matches * to.len()
+    };
+    let mut result = Self::with_capacity(len);
+    let mut last = 0;
+    for (start, part) in self.match_indices(from).take(count) {
+      result.push_str(&self[last..start]);
+      result.push_str(to);
+      last = start + part.len();
+    }
+    result.push_str(&self[last..]);
+    result
+  }
+
+  /// Returns the remaining spare capacity of the string as a slice of
+  /// uninitialized bytes.
+  ///
+  /// Bytes written here can be appended to the string afterwards with
+  /// [`commit_utf8`](Self::commit_utf8), avoiding an intermediate buffer.
+  #[inline(always)]
+  pub fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+    self.buf.spare_capacity_mut()
+  }
+
+  /// Appends the first `count` bytes of the spare capacity to the string,
+  /// after validating that they are UTF-8.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [`Utf8Error`] if the bytes are not valid UTF-8, in which case
+  /// the string is left unchanged.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `count` is greater than the length of
+  /// [`spare_capacity_mut`].
+  ///
+  /// # Safety
+  ///
+  /// The first `count` bytes of [`spare_capacity_mut`] must have been
+  /// initialized.
+  ///
+  /// [`spare_capacity_mut`]: Self::spare_capacity_mut
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let mut s: SmallString<16> = SmallString::from("id=");
+  /// let spare = s.spare_capacity_mut();
+  /// for (slot, byte) in spare.iter_mut().zip(b"42") {
+  ///   slot.write(*byte);
+  /// }
+  /// unsafe { s.commit_utf8(2).unwrap() };
+  /// assert_eq!(s, "id=42");
+  /// ```
+  pub unsafe fn commit_utf8(&mut self, count: usize) -> Result<(), Utf8Error> {
+    let len = self.len();
+    let spare = &self.buf.spare_capacity_mut()[..count];
+    // SAFETY: the caller guarantees these bytes are initialized.
+    let bytes = unsafe { &*(spare as *const [MaybeUninit<u8>] as *const [u8]) };
+    str::from_utf8(bytes)?;
+    unsafe { self.buf.set_len(len + count) };
+    Ok(())
+  }
+
+  /// Returns a mutable reference to the underlying vector of bytes.
+  ///
+  /// # Safety
+  ///
+  /// The caller must ensure that the bytes are valid UTF-8 by the time the
+  /// borrow ends. Leaving invalid UTF-8 in the string is undefined behavior,
+  /// as the rest of the API assumes it is always valid.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let mut s: SmallString<8> = SmallString::from("rot13");
+  /// unsafe {
+  ///   for byte in s.as_mut_vec().iter_mut() {
+  ///     if byte.is_ascii_lowercase() {
+  ///       *byte = (*byte - b'a' + 13) % 26 + b'a';
+  ///     }
+  ///   }
+  /// }
+  /// assert_eq!(s, "ebg13");
+  /// ```
+  #[inline(always)]
+  pub unsafe fn as_mut_vec(&mut self) -> &mut CompactVec<u8, N> {
+    &mut self.buf
+  }
+
+  /// Creates a `SmallString` from a C string, returning an error if it is
+  /// not valid UTF-8. The trailing nul terminator is not included.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let s: SmallString<8> = SmallString::from_cstr(c"eth0").unwrap();
+  /// assert_eq!(s, "eth0");
+  /// ```
+  #[inline]
+  pub fn from_cstr(s: &CStr) -> Result<Self, Utf8Error> {
+    s.to_str().map(Self::from)
+  }
+
+  /// Converts the string into a [`CString`], returning an error if it
+  /// contains an interior nul byte.
+  ///
+  /// A spilled string hands its heap buffer over to the `CString`; an
+  /// inline string is copied into a new allocation.
+  #[inline]
+  pub fn into_cstring(self) -> Result<CString, NulError> {
+    CString::new(self.buf.into_vec())
+  }
+
+  /// Writes a nul terminator just past the end of the string and returns the
+  /// contents as a [`CStr`], without changing the string itself.
+  ///
+  /// The terminator is placed in the string's spare capacity, so no copy is
+  /// made; the string only spills if it is exactly `N` bytes long and still
+  /// inline. Returns an error if the string contains an interior nul byte.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let mut s: SmallString<8> = SmallString::from("ttyS0");
+  /// assert_eq!(s.as_nul_terminated().unwrap(), c"ttyS0");
+  /// assert_eq!(s, "ttyS0");
+  ///
+  /// let mut bad: SmallString<8> = SmallString::from("a\0b");
+  /// assert!(bad.as_nul_terminated().is_err());
+  /// ```
+  pub fn as_nul_terminated(&mut self) -> Result<&CStr, FromBytesWithNulError> {
+    let len = self.len();
+    self.buf.reserve(1);
+    self.buf.spare_capacity_mut()[0].write(0);
+    // SAFETY: the first `len` bytes are initialized string contents, and the
+    // byte after them was just written.
+    let bytes =
+      unsafe { core::slice::from_raw_parts(self.buf.as_ptr(), len + 1) };
+    CStr::from_bytes_with_nul(bytes)
+  }
+
+  /// Returns the contents of the string as a `&str`.
+  #[inline(always)]
+  pub fn as_str(&self) -> &str {
+    unsafe { str::from_utf8_unchecked(&self.buf) }
+  }
+
+  /// Returns the contents of the string as a `&mut str`.
+  #[inline(always)]
+  pub fn as_mut_str(&mut self) -> &mut str {
+    unsafe { str::from_utf8_unchecked_mut(&mut self.buf) }
+  }
+
+  /// Returns a subslice of the string, or `None` if the index is out of
+  /// bounds or does not lie on [`char`] boundaries.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let s: SmallString<8> = SmallString::from("añb");
+  /// assert_eq!(s.get(1..3), Some("ñ"));
+  /// assert_eq!(s.get(1..2), None);
+  /// assert_eq!(s.get(..9), None);
+  /// ```
+  #[inline]
+  pub fn get<I: SliceIndex<str>>(&self, index: I) -> Option<&I::Output> {
+    self.as_str().get(index)
+  }
+
+  /// Returns a mutable subslice of the string, or `None` if the index is out
+  /// of bounds or does not lie on [`char`] boundaries.
+  #[inline]
+  pub fn get_mut<I: SliceIndex<str>>(
+    &mut self,
+    index: I,
+  ) -> Option<&mut I::Output> {
+    self.as_mut_str().get_mut(index)
+  }
+
+  /// Appends the given `char` to the end of the string.
+  #[inline]
+  pub fn push(&mut self, c: char) {
+    self.push_str(c.encode_utf8(&mut [0; 4]));
+  }
+
+  /// Appends the given string slice to the end of the string.
+  ///
+  /// Space is reserved once for the whole slice, which is then copied in a
+  /// single `memcpy`.
+  #[inline]
+  pub fn push_str(&mut self, s: &str) {
+    self.buf.extend_from_copy_slice(s.as_bytes());
+  }
+
+  /// Appends the given `char` to the end of the string if it fits within
+  /// the current capacity, without spilling to the heap or reallocating.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [`CapacityError`] containing `c` if there is no room.
+  #[inline]
+  pub fn try_push(&mut self, c: char) -> Result<(), CapacityError<char>> {
+    match self.try_push_str(c.encode_utf8(&mut [0; 4])) {
+      Ok(()) => Ok(()),
+      Err(_) => Err(CapacityError(c)),
+    }
+  }
+
+  /// Appends the given string slice to the end of the string if it fits
+  /// within the current capacity, without spilling to the heap or
+  /// reallocating. Nothing is appended on failure.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [`CapacityError`] containing `s` if there is not enough room
+  /// for all of it.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let mut s: SmallString<8> = SmallString::new();
+  /// assert!(s.try_push_str("sensor").is_ok());
+  /// assert_eq!(s.remaining_inline_capacity(), 2);
+  ///
+  /// let err = s.try_push_str("-42").unwrap_err();
+  /// assert_eq!(err.into_inner(), "-42");
+  /// assert_eq!(s, "sensor");
+  /// assert!(s.is_inline());
+  /// ```
+  #[inline]
+  pub fn try_push_str<'a>(
+    &mut self,
+    s: &'a str,
+  ) -> Result<(), CapacityError<&'a str>> {
+    if s.len() > self.capacity() - self.len() {
+      return Err(CapacityError(s));
+    }
+    self.push_str(s);
+    Ok(())
+  }
+
+  /// Returns the number of bytes that can still be appended before the
+  /// string spills to the heap, or `0` if it has already spilled.
+  #[inline]
+  pub fn remaining_inline_capacity(&self) -> usize {
+    if self.is_inline() { N - self.len() } else { 0 }
+  }
+
+  /// Appends as much of `s` as fits without the string's total length
+  /// exceeding `max_total_bytes`, never splitting a [`char`]. Returns the
+  /// number of bytes that were appended.
+  ///
+  /// Nothing is appended if the string is already at or over the budget.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let mut line: SmallString<32> = SmallString::from("WARN ");
+  /// let written = line.push_str_truncating("disk nearly full", 12);
+  /// assert_eq!(written, 7);
+  /// assert_eq!(line, "WARN disk ne");
+  /// ```
+  #[inline]
+  pub fn push_str_truncating(
+    &mut self,
+    s: &str,
+    max_total_bytes: usize,
+  ) -> usize {
+    let budget = max_total_bytes.saturating_sub(self.len());
+    let count = floor_char_boundary(s, budget);
+    self.push_str(&s[..count]);
+    count
+  }
+
+  /// Removes the last character from the string and returns it, or `None`
+  /// if the string is empty.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let mut s: SmallString<8> = SmallString::from("añ🐄");
+  /// assert_eq!(s.pop(), Some('🐄'));
+  /// assert_eq!(s.pop(), Some('ñ'));
+  /// assert_eq!(s, "a");
+  /// ```
+  #[inline]
+  pub fn pop(&mut self) -> Option<char> {
+    let c = self.chars().next_back()?;
+    let new_len = self.len() - c.len_utf8();
+    self.buf.truncate(new_len);
+    Some(c)
+  }
+
+  /// Inserts a character at byte position `idx`, shifting the rest of the
+  /// string to the right.
+  ///
+  /// The bytes are shifted within the inline buffer when the result still
+  /// fits; otherwise the string spills to the heap once.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `idx` is larger than the string's length, or if it does not
+  /// lie on a [`char`] boundary.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let mut s: SmallString<8> = SmallString::from("fo");
+  /// s.insert(2, 'o');
+  /// s.insert(0, '¡');
+  /// assert_eq!(s, "¡foo");
+  /// ```
+  #[inline]
+  pub fn insert(&mut self, idx: usize, c: char) {
+    self.insert_str(idx, c.encode_utf8(&mut [0; 4]));
+  }
+
+  /// Inserts a string slice at byte position `idx`, shifting the rest of the
+  /// string to the right.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `idx` is larger than the string's length, or if it does not
+  /// lie on a [`char`] boundary.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let mut s: SmallString<16> = SmallString::from("hello!");
+  /// s.insert_str(5, ", world");
+  /// assert_eq!(s, "hello, world!");
+  /// ```
+  pub fn insert_str(&mut self, idx: usize, string: &str) {
+    assert!(
+      self.is_char_boundary(idx),
+      "byte index {idx} is not a char boundary"
+    );
+    self.buf.insert_from_slice(idx, string.as_bytes());
+  }
+
+  /// Removes the [`char`] at byte position `idx` and returns it, shifting the
+  /// rest of the string to the left in place.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `idx` is larger than or equal to the string's length, or if
+  /// it does not lie on a [`char`] boundary.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let mut s: SmallString<8> = SmallString::from("naïve");
+  /// assert_eq!(s.remove(2), 'ï');
+  /// assert_eq!(s, "nave");
+  /// ```
+  pub fn remove(&mut self, idx: usize) -> char {
+    let c = match self[idx..].chars().next() {
+      Some(c) => c,
+      None => panic!("cannot remove a char from the end of a string"),
+    };
+    let len = self.len();
+    let next = idx + c.len_utf8();
+    self.buf.copy_within(next.., idx);
+    self.buf.truncate(len - (next - idx));
+    c
+  }
+
+  /// Removes the specified byte range from the string, and replaces it with
+  /// the given string slice, which need not be the same length.
+  ///
+  /// The bytes are edited in place, so the string stays inline as long as
+  /// the result still fits.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the start or end of the range does not lie on a [`char`]
+  /// boundary, or if the range is out of bounds.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let mut s: SmallString<16> = SmallString::from("α is alpha");
+  /// let beta_offset = s.find(' ').unwrap();
+  /// s.replace_range(..beta_offset, "β");
+  /// assert_eq!(s, "β is alpha");
+  /// assert!(s.is_inline());
+  /// ```
+  pub fn replace_range<R>(&mut self, range: R, replace_with: &str)
+  where
+    R: RangeBounds<usize>,
+  {
+    let len = self.len();
+    let (start, end) = resolve_range(&range, len);
+    assert!(self.is_char_boundary(start), "start is not a char boundary");
+    assert!(self.is_char_boundary(end), "end is not a char boundary");
+    let bytes = replace_with.as_bytes();
+    let removed = end - start;
+    if bytes.len() <= removed {
+      self.buf[start..start + bytes.len()].copy_from_slice(bytes);
+      self.buf.copy_within(end.., start + bytes.len());
+      self.buf.truncate(len - (removed - bytes.len()));
+    } else {
+      self.buf[start..end].copy_from_slice(&bytes[..removed]);
+      self.buf.insert_from_slice(end, &bytes[removed..]);
+    }
+  }
+
+  /// Removes the specified byte range from the string, returning its chars
+  /// as an iterator.
+  ///
+  /// The range is removed from the string when the iterator is dropped,
+  /// whether or not it was fully consumed, by shifting the remaining bytes
+  /// down once.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the start or end of the range does not lie on a [`char`]
+  /// boundary, or if the range is out of bounds.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let mut s: SmallString<16> = SmallString::from("α is alpha");
+  /// let drained: String = s.drain(..3).collect();
+  /// assert_eq!(drained, "α ");
+  /// assert_eq!(s, "is alpha");
+  /// ```
+  pub fn drain<R>(&mut self, range: R) -> Drain<'_, N>
+  where
+    R: RangeBounds<usize>,
+  {
+    let (start, end) = resolve_range(&range, self.len());
+    assert!(self.is_char_boundary(start), "start is not a char boundary");
+    assert!(self.is_char_boundary(end), "end is not a char boundary");
+    let string: *mut Self = self;
+    // the chars borrow from the string through a raw pointer, since the
+    // drain also needs to mutate it once they are no longer in use.
+    let iter = unsafe { (*string).get_unchecked(start..end) }.chars();
+    Drain {
+      string,
+      start,
+      end,
+      iter,
+      _marker: PhantomData,
+    }
+  }
+
+  /// Shortens the string to the specified length in bytes. Has no effect if
+  /// `new_len` is greater than the string's current length.
+  ///
+  /// Like [`CompactVec::truncate`], this never moves a spilled string back
+  /// inline; use [`clear`](Self::clear) to release the heap allocation.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `new_len` does not lie on a [`char`] boundary.
+  #[inline]
+  pub fn truncate(&mut self, new_len: usize) {
+    if new_len <= self.len() {
+      assert!(
+        self.is_char_boundary(new_len),
+        "new length {new_len} is not a char boundary"
+      );
+      self.buf.truncate(new_len);
+    }
+  }
+
+  /// Shortens the string to at most `max_bytes` bytes, without splitting a
+  /// [`char`]. Has no effect if the string already fits.
+  ///
+  /// If `max_bytes` falls inside a multi-byte character, that character is
+  /// dropped entirely, so the result may be up to 3 bytes shorter than the
+  /// budget.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let mut s: SmallString<16> = SmallString::from("naïve café");
+  /// s.truncate_to_fit(3);
+  /// assert_eq!(s, "na");
+  /// ```
+  #[inline]
+  pub fn truncate_to_fit(&mut self, max_bytes: usize) {
+    let new_len = floor_char_boundary(self.as_str(), max_bytes);
+    self.buf.truncate(new_len);
+  }
+
+  /// Removes all contents of the string, releasing any heap storage and
+  /// returning to inline mode.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.buf.clear();
+  }
+
+  /// Returns a byte-oriented writer that appends to the end of the string.
+  ///
+  /// Incoming bytes are validated as UTF-8. A multi-byte sequence that is
+  /// split across two writes is held back until it is completed, so text
+  /// produced by writers that emit arbitrary byte chunks can be collected
+  /// directly. Implements `std::io::Write` when the `std` feature is enabled,
+  /// and the `embedded-io` `Write` trait when the `embedded-io` feature is
+  /// enabled.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// # #[cfg(feature = "std")] {
+  /// use std::io::Write;
+  ///
+  /// use moos::SmallString;
+  ///
+  /// let mut s: SmallString<16> = SmallString::new();
+  /// let mut writer = s.writer();
+  /// writer.write_all(b"caf\xC3").unwrap();
+  /// writer.write_all(b"\xA9!").unwrap();
+  /// drop(writer);
+  /// assert_eq!(s, "café!");
+  /// # }
+  /// ```
+  #[inline]
+  pub fn writer(&mut self) -> Writer<'_, N> {
+    Writer {
+      string:      self,
+      pending:     [0; 4],
+      pending_len: 0,
+    }
+  }
+
+  /// Returns an iterator over consecutive pieces of the string, each holding
+  /// `width` chars, except for the last one which may be shorter.
+  ///
+  /// Pieces always end on a [`char`] boundary, so they can be collected into
+  /// smaller strings for fixed-width fields or displays.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `width` is `0`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::CompactVec;
+  /// use moos::SmallString;
+  ///
+  /// let s: SmallString<16> = SmallString::from("Grüße, Welt");
+  /// let rows: CompactVec<SmallString<8>, 3> =
+  ///   s.chunks(4).map(SmallString::from).collect();
+  /// assert_eq!(rows.as_slice(), ["Grüß", "e, W", "elt"]);
+  /// ```
+  #[inline]
+  pub fn chunks(&self, width: usize) -> Chunks<'_> {
+    assert!(width != 0, "chunk width must be non-zero");
+    Chunks {
+      rest: self.as_str(),
+      width,
+    }
+  }
+
+  /// Returns an iterator over the lines of the string word-wrapped to at
+  /// most `width` chars each.
+  ///
+  /// Lines are broken at the last whitespace that fits, and the whitespace
+  /// at a break is dropped. Words longer than `width` are split across lines,
+  /// and existing `\n` line breaks are kept.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `width` is `0`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::SmallString;
+  ///
+  /// let s: SmallString<32> = SmallString::from("Temp 21.5C\nHumidity high");
+  /// let lines: Vec<&str> = s.wrap(8).collect();
+  /// assert_eq!(lines, ["Temp", "21.5C", "Humidity", "high"]);
+  /// ```
+  #[inline]
+  pub fn wrap(&self, width: usize) -> Wrap<'_> {
+    assert!(width != 0, "wrap width must be non-zero");
+    Wrap {
+      rest: self.as_str(),
+      width,
+    }
+  }
+}
+
+/// Returns the encoded length of a UTF-8 sequence from its leading byte,
+/// which must be the start of a multi-byte sequence.
+#[inline(always)]
+fn utf8_width(lead: u8) -> usize {
+  match lead {
+    0xF0.. => 4,
+    0xE0.. => 3,
+    _ => 2,
+  }
+}
+
+/// Returns the largest char boundary in `s` that is not greater than `index`.
+#[inline]
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+  if index >= s.len() {
+    return s.len();
+  }
+  // a char is at most 4 bytes long, so a boundary is at most 3 bytes back.
+  (index.saturating_sub(3)..=index)
+    .rev()
+    .find(|&i| s.is_char_boundary(i))
+    .unwrap_or(0)
+}
+
+/// A draining iterator over a range of chars in a [`SmallString`].
+///
+/// Created by [`SmallString::drain`].
+pub struct Drain<'a, const N: usize> {
+  string:  *mut SmallString<N>,
+  start:   usize,
+  end:     usize,
+  iter:    Chars<'a>,
+  _marker: PhantomData<&'a mut SmallString<N>>,
+}
+
+impl<const N: usize> Drain<'_, N> {
+  /// Returns the remaining (not yet yielded) part of the drained range.
+  #[inline(always)]
+  pub fn as_str(&self) -> &str {
+    self.iter.as_str()
+  }
+}
+
+impl<const N: usize> Iterator for Drain<'_, N> {
+  type Item = char;
+
+  #[inline(always)]
+  fn next(&mut self) -> Option<char> {
+    self.iter.next()
+  }
+
+  #[inline(always)]
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.iter.size_hint()
+  }
+}
+
+impl<const N: usize> DoubleEndedIterator for Drain<'_, N> {
+  #[inline(always)]
+  fn next_back(&mut self) -> Option<char> {
+    self.iter.next_back()
+  }
+}
+
+impl<const N: usize> FusedIterator for Drain<'_, N> {}
+
+impl<const N: usize> Drop for Drain<'_, N> {
+  fn drop(&mut self) {
+    let string = unsafe { &mut *self.string };
+    string.replace_range(self.start..self.end, "");
+  }
+}
+
+/// A UTF-8 validating byte writer that appends to a [`SmallString`].
+///
+/// Created by [`SmallString::writer`].
+pub struct Writer<'a, const N: usize> {
+  string:      &'a mut SmallString<N>,
+  pending:     [u8; 4],
+  pending_len: u8,
+}
+
+impl<const N: usize> Writer<'_, N> {
+  /// Returns the bytes of an incomplete UTF-8 sequence that are being held
+  /// back until the rest of the sequence is written.
+  #[inline(always)]
+  pub fn pending(&self) -> &[u8] {
+    &self.pending[..self.pending_len as usize]
+  }
+
+  /// Discards the [`pending`](Self::pending) bytes, returning how many
+  /// there were.
+  #[inline]
+  pub fn discard_pending(&mut self) -> usize {
+    mem::take(&mut self.pending_len) as usize
+  }
+
+  /// Validates `bytes` as UTF-8 and appends them to the string, returning
+  /// the number of bytes consumed.
+  ///
+  /// A trailing incomplete sequence is consumed and held back until a later
+  /// write completes it. If invalid UTF-8 is encountered after some bytes
+  /// were appended, the count of bytes up to the invalid sequence is
+  /// returned; otherwise the error is returned and nothing is appended.
+  ///
+  /// If `bytes` can't complete a sequence held back by an earlier write,
+  /// the error is returned without consuming anything, and the held-back
+  /// bytes are kept: they can be inspected with [`pending`](Self::pending)
+  /// and dropped with [`discard_pending`](Self::discard_pending).
+  pub fn write_utf8(&mut self, bytes: &[u8]) -> Result<usize, Utf8Error> {
+    let mut consumed = 0;
+    if self.pending_len > 0 {
+      let have = self.pending_len as usize;
+      let width = utf8_width(self.pending[0]);
+      consumed = (width - have).min(bytes.len());
+      self.pending[have..have + consumed].copy_from_slice(&bytes[..consumed]);
+      match str::from_utf8(&self.pending[..have + consumed]) {
+        Ok(s) => {
+          self.string.push_str(s);
+          self.pending_len = 0;
+        }
+        Err(e) if e.error_len().is_none() => {
+          self.pending_len = (have + consumed) as u8;
+          return Ok(consumed);
+        }
+        // `pending_len` is left as it was, so the copied bytes are ignored.
+        Err(e) => return Err(e),
+      }
+    }
+    let rest = &bytes[consumed..];
+    match str::from_utf8(rest) {
+      Ok(s) => {
+        self.string.push_str(s);
+        Ok(bytes.len())
+      }
+      Err(e) => {
+        let valid = e.valid_up_to();
+        // SAFETY: `valid_up_to` marks the end of the valid UTF-8 prefix.
+        self
+          .string
+          .push_str(unsafe { str::from_utf8_unchecked(&rest[..valid]) });
+        if e.error_len().is_none() {
+          let tail = &rest[valid..];
+          self.pending[..tail.len()].copy_from_slice(tail);
+          self.pending_len = tail.len() as u8;
+          Ok(bytes.len())
+        } else if consumed + valid > 0 {
+          Ok(consumed + valid)
+        } else {
+          Err(e)
+        }
+      }
+    }
+  }
+}
+
+impl<const N: usize> Drop for Writer<'_, N> {
+  /// Replaces an incomplete trailing sequence, if any, with
+  /// [`char::REPLACEMENT_CHARACTER`].
+  fn drop(&mut self) {
+    if self.pending_len > 0 {
+      self.string.push(char::REPLACEMENT_CHARACTER);
+    }
+  }
+}
+
+/// An iterator over fixed-width pieces of a [`SmallString`].
+///
+/// Created by [`SmallString::chunks`].
+#[derive(Debug, Clone)]
+pub struct Chunks<'a> {
+  rest:  &'a str,
+  width: usize,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+  type Item = &'a str;
+
+  fn next(&mut self) -> Option<&'a str> {
+    if self.rest.is_empty() {
+      return None;
+    }
+    let end = self
+      .rest
+      .char_indices()
+      .nth(self.width)
+      .map_or(self.rest.len(), |(i, _)| i);
+    let (chunk, rest) = self.rest.split_at(end);
+    self.rest = rest;
+    Some(chunk)
+  }
+}
+
+impl FusedIterator for Chunks<'_> {}
+
+/// An iterator over the word-wrapped lines of a [`SmallString`].
+///
+/// Created by [`SmallString::wrap`].
+#[derive(Debug, Clone)]
+pub struct Wrap<'a> {
+  rest:  &'a str,
+  width: usize,
+}
+
+impl<'a> Iterator for Wrap<'a> {
+  type Item = &'a str;
+
+  fn next(&mut self) -> Option<&'a str> {
+    if self.rest.is_empty() {
+      return None;
+    }
+    let rest = self.rest;
+    // byte offset of the last whitespace seen within the first `width` chars.
+    let mut last_space = None;
+    let mut chars = rest.char_indices();
+    for (i, c) in chars.by_ref().take(self.width) {
+      if c == '\n' {
+        self.rest = &rest[i + 1..];
+        return Some(&rest[..i]);
+      }
+      if c.is_whitespace() {
+        last_space = Some(i);
+      }
+    }
+    let (end, at_space) = match chars.next() {
+      None => {
+        self.rest = "";
+        return Some(rest);
+      }
+      Some((i, c)) if c.is_whitespace() => (i, true),
+      Some((i, _)) => match last_space {
+        Some(space) if rest[..space].trim_end().is_empty() => (i, false),
+        Some(space) => (space, true),
+        None => (i, false),
+      },
+    };
+    let line = &rest[..end];
+    if at_space {
+      let tail = rest[end..]
+        .trim_start_matches(|c: char| c.is_whitespace() && c != '\n');
+      self.rest = tail.strip_prefix('\n').unwrap_or(tail);
+      Some(line.trim_end())
+    } else {
+      self.rest = &rest[end..];
+      Some(line)
+    }
+  }
+}
+
+impl FusedIterator for Wrap<'_> {}
+
+impl<const N: usize> Deref for SmallString<N> {
+  type Target = str;
+
+  #[inline(always)]
+  fn deref(&self) -> &str {
+    self.as_str()
+  }
+}
+
+impl<const N: usize, I: SliceIndex<str>> Index<I> for SmallString<N> {
+  type Output = I::Output;
+
+  /// Returns a subslice of the string.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the index is out of bounds or does not lie on [`char`]
+  /// boundaries, like indexing a `String`.
+  #[inline(always)]
+  fn index(&self, index: I) -> &I::Output {
+    &self.as_str()[index]
+  }
+}
+
+impl<const N: usize, I: SliceIndex<str>> IndexMut<I> for SmallString<N> {
+  #[inline(always)]
+  fn index_mut(&mut self, index: I) -> &mut I::Output {
+    &mut self.as_mut_str()[index]
+  }
+}
+
+impl<const N: usize> DerefMut for SmallString<N> {
+  #[inline(always)]
+  fn deref_mut(&mut self) -> &mut str {
+    self.as_mut_str()
+  }
+}
+
+impl<const N: usize> Borrow<str> for SmallString<N> {
+  #[inline(always)]
+  fn borrow(&self) -> &str {
+    self.as_str()
+  }
+}
+
+impl<const N: usize> BorrowMut<str> for SmallString<N> {
+  #[inline(always)]
+  fn borrow_mut(&mut self) -> &mut str {
+    self.as_mut_str()
+  }
+}
+
+impl<const N: usize> AsRef<str> for SmallString<N> {
+  #[inline(always)]
+  fn as_ref(&self) -> &str {
+    self.as_str()
+  }
+}
+
+impl<const N: usize> AsMut<str> for SmallString<N> {
+  #[inline(always)]
+  fn as_mut(&mut self) -> &mut str {
+    self.as_mut_str()
+  }
+}
+
+impl<const N: usize> AsRef<[u8]> for SmallString<N> {
+  #[inline(always)]
+  fn as_ref(&self) -> &[u8] {
+    self.as_bytes()
+  }
+}
+
+impl<const N: usize> Display for SmallString<N> {
+  #[inline(always)]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Display::fmt(self.as_str(), f)
+  }
+}
+
+impl<const N: usize> Debug for SmallString<N> {
+  #[inline(always)]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Debug::fmt(self.as_str(), f)
+  }
+}
+
+impl<const N: usize> fmt::Write for SmallString<N> {
+  #[inline(always)]
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    self.push_str(s);
+    Ok(())
+  }
+
+  #[inline(always)]
+  fn write_char(&mut self, c: char) -> fmt::Result {
+    self.push(c);
+    Ok(())
+  }
+}
+
+/// Creates a [`SmallString`] using interpolation of runtime expressions,
+/// like `format!`, but without going through a `String`.
+///
+/// The inline capacity can be given explicitly before a semicolon, or
+/// otherwise inferred from context.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::SmallString;
+/// use moos::format_small;
+///
+/// let ms = 42;
+/// let s = format_small!(16; "{ms}ms");
+/// assert_eq!(s, "42ms");
+/// assert!(s.is_inline());
+///
+/// let s: SmallString<8> = format_small!("{}-{}", 1, 2);
+/// assert_eq!(s, "1-2");
+/// ```
+#[macro_export]
+macro_rules! format_small {
+  ($n:expr; $($arg:tt)*) => {
+    $crate::SmallString::<$n>::from_fmt(::core::format_args!($($arg)*))
+  };
+  ($($arg:tt)*) => {
+    $crate::SmallString::from_fmt(::core::format_args!($($arg)*))
+  };
+}
+
+/// Creates an inline [`SmallString`] from a string literal or constant,
+/// checking its length at compile time.
+///
+/// The inline capacity can be given explicitly before a semicolon, or
+/// otherwise inferred from context. Either way, the string is built in a
+/// `const` block, so one that doesn't fit inline is a compile error.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::SmallString;
+/// use moos::small_str;
+///
+/// let s = small_str!(8; "moo");
+/// assert!(s.is_inline());
+///
+/// let mut s: SmallString<16> = small_str!("content-");
+/// s.push_str("type");
+/// assert_eq!(s, "content-type");
+/// ```
+///
+/// A string that doesn't fit inline fails to compile:
+///
+/// ```rust,compile_fail
+/// let s = moos::small_str!(4; "too long");
+/// ```
+#[macro_export]
+macro_rules! small_str {
+  ($n:expr; $s:expr $(,)?) => {
+    const { $crate::SmallString::<$n>::from_inline_str($s) }
+  };
+  ($s:expr $(,)?) => {
+    const { $crate::SmallString::from_inline_str($s) }
+  };
+}
+
+impl<const N: usize> Hash for SmallString<N> {
+  #[inline(always)]
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.as_str().hash(state);
+  }
+}
+
+impl<const N: usize, const M: usize> PartialEq<SmallString<M>>
+  for SmallString<N>
+{
+  #[inline(always)]
+  fn eq(&self, other: &SmallString<M>) -> bool {
+    self.as_str() == other.as_str()
+  }
+}
+
+impl<const N: usize> PartialEq<str> for SmallString<N> {
+  #[inline(always)]
+  fn eq(&self, other: &str) -> bool {
+    self.as_str() == other
+  }
+}
+
+impl<const N: usize> PartialEq<&str> for SmallString<N> {
+  #[inline(always)]
+  fn eq(&self, other: &&str) -> bool {
+    self.as_str() == *other
+  }
+}
+
+impl<const N: usize> PartialEq<String> for SmallString<N> {
+  #[inline(always)]
+  fn eq(&self, other: &String) -> bool {
+    self.as_str() == other
+  }
+}
+
+impl<const N: usize> PartialEq<SmallString<N>> for str {
+  #[inline(always)]
+  fn eq(&self, other: &SmallString<N>) -> bool {
+    self == other.as_str()
+  }
+}
+
+impl<const N: usize> PartialEq<SmallString<N>> for &str {
+  #[inline(always)]
+  fn eq(&self, other: &SmallString<N>) -> bool {
+    *self == other.as_str()
+  }
+}
+
+impl<const N: usize> Eq for SmallString<N> {}
+
+impl<const N: usize> PartialOrd for SmallString<N> {
+  #[inline(always)]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<const N: usize> Ord for SmallString<N> {
+  #[inline(always)]
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.as_str().cmp(other.as_str())
+  }
+}
+
+impl<const N: usize> From<&str> for SmallString<N> {
+  #[inline]
+  fn from(s: &str) -> Self {
+    let mut string = Self::new();
+    string.push_str(s);
+    string
+  }
+}
+
+impl<const N: usize> From<String> for SmallString<N> {
+  /// Converts a `String` into a `SmallString`, adopting its heap allocation
+  /// unless it fits inline.
+  #[inline]
+  fn from(s: String) -> Self {
+    Self {
+      buf: CompactVec::from(s.into_bytes()),
+    }
+  }
+}
+
+impl<const N: usize> From<char> for SmallString<N> {
+  #[inline]
+  fn from(c: char) -> Self {
+    let mut string = Self::new();
+    string.push(c);
+    string
+  }
+}
+
+impl<const N: usize> From<SmallString<N>> for String {
+  /// Converts a `SmallString` into a `String`, reusing its heap allocation
+  /// if it has spilled.
+  #[inline]
+  fn from(s: SmallString<N>) -> Self {
+    unsafe { String::from_utf8_unchecked(s.buf.into_vec()) }
+  }
+}
+
+impl<const N: usize> From<SmallString<N>> for Box<str> {
+  /// Converts a `SmallString` into a `Box<str>`; see
+  /// [`SmallString::into_boxed_str`].
+  #[inline]
+  fn from(s: SmallString<N>) -> Self {
+    s.into_boxed_str()
+  }
+}
+
+impl<const N: usize> FromStr for SmallString<N> {
+  type Err = Infallible;
+
+  #[inline(always)]
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Ok(Self::from(s))
+  }
+}
+
+impl<const N: usize> Extend<char> for SmallString<N> {
+  #[inline]
+  fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+    for c in iter {
+      self.push(c);
+    }
+  }
+}
+
+impl<'a, const N: usize> Extend<&'a str> for SmallString<N> {
+  #[inline]
+  fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+    for s in iter {
+      self.push_str(s);
+    }
+  }
+}
+
+impl<const N: usize> FromIterator<char> for SmallString<N> {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+    let mut string = Self::new();
+    string.extend(iter);
+    string
+  }
+}
+
+impl<'a, const N: usize> FromIterator<&'a str> for SmallString<N> {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+    let mut string = Self::new();
+    string.extend(iter);
+    string
+  }
+}
+
+#[cfg(feature = "std")]
+mod std_impl {
+  use std::ffi::OsStr;
+  use std::io;
+  use std::path::Path;
+
+  use super::*;
+
+  /// The most bytes [`SmallString::read_into`] reads at once.
+  pub(super) const MAX_READ_WINDOW: usize = 8 * 1024;
+
+  impl<const N: usize> SmallString<N> {
+    /// Performs a single read from `reader` directly into the string's spare
+    /// capacity, then appends the bytes read once they are validated as
+    /// UTF-8. Returns the number of bytes appended, or `0` at end of input.
+    ///
+    /// If the string has no spare capacity, it grows first. At most 8 KiB are
+    /// read per call. If the read ends in the middle of a multi-byte
+    /// character, the rest of that character is read before returning.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind [`InvalidData`](io::ErrorKind::InvalidData)
+    /// if the input is not valid UTF-8 (or ends mid-character), in which case
+    /// the string is left unchanged. Errors from `reader` are passed through,
+    /// except for [`Interrupted`](io::ErrorKind::Interrupted) reads, which
+    /// are retried.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use moos::SmallString;
+    ///
+    /// let mut port = &b"OK\r\n"[..];
+    /// let mut line: SmallString<32> = SmallString::new();
+    /// assert_eq!(line.read_into(&mut port).unwrap(), 4);
+    /// assert_eq!(line, "OK\r\n");
+    /// assert!(line.is_inline());
+    /// ```
+    pub fn read_into<R>(&mut self, reader: &mut R) -> io::Result<usize>
+    where
+      R: io::Read + ?Sized,
+    {
+      if self.len() == self.capacity() {
+        self.reserve(1);
+      }
+      // only a bounded window is zeroed, so that each read into a large,
+      // mostly empty buffer doesn't cost time proportional to its capacity.
+      let spare = self.spare_capacity_mut();
+      let window = spare.len().min(MAX_READ_WINDOW);
+      let spare = &mut spare[..window];
+      spare.fill(MaybeUninit::new(0));
+      // SAFETY: every byte of the window was just initialized.
+      let buf = unsafe { &mut *(spare as *mut [MaybeUninit<u8>] as *mut [u8]) };
+      let count = loop {
+        match reader.read(buf) {
+          Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+          result => break result?,
+        }
+      };
+      let invalid = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+      let error = match str::from_utf8(&buf[..count]) {
+        Ok(_) => {
+          unsafe { self.buf.set_len(self.len() + count) };
+          return Ok(count);
+        }
+        Err(e) if e.error_len().is_none() => e,
+        Err(e) => return Err(invalid(e)),
+      };
+      // the read stopped partway through a character: read the rest of it.
+      let valid = error.valid_up_to();
+      let mut pending = [0; 4];
+      let have = count - valid;
+      pending[..have].copy_from_slice(&buf[valid..count]);
+      let width = utf8_width(pending[0]);
+      reader.read_exact(&mut pending[have..width]).map_err(|e| {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+          invalid(error)
+        } else {
+          e
+        }
+      })?;
+      let c = str::from_utf8(&pending[..width]).map_err(invalid)?;
+      unsafe { self.buf.set_len(self.len() + valid) };
+      self.push_str(c);
+      Ok(valid + width)
+    }
+  }
+
+  impl<const N: usize> io::Write for Writer<'_, N> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      self
+        .write_utf8(buf)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> io::Result<()> {
+      Ok(())
+    }
+  }
+
+  impl<const N: usize> AsRef<OsStr> for SmallString<N> {
+    #[inline]
+    fn as_ref(&self) -> &OsStr {
+      OsStr::new(self.as_str())
+    }
+  }
+
+  impl<const N: usize> AsRef<Path> for SmallString<N> {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+      Path::new(self.as_str())
+    }
+  }
+}
+
+#[cfg(feature = "embedded-io")]
+mod embedded_io_impl {
+  use embedded_io::ErrorKind;
+  use embedded_io::ErrorType;
+  use embedded_io::Write;
+
+  use super::*;
+
+  impl<const N: usize> ErrorType for Writer<'_, N> {
+    type Error = ErrorKind;
+  }
+
+  impl<const N: usize> Write for Writer<'_, N> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+      self.write_utf8(buf).map_err(|_| ErrorKind::InvalidData)
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> Result<(), Self::Error> {
+      Ok(())
+    }
+  }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+  use serde::de;
+
+  use super::*;
+
+  impl<const N: usize> Serialize for SmallString<N> {
+    #[inline(always)]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      serializer.serialize_str(self.as_str())
+    }
+  }
+
+  struct SmallStringVisitor<const N: usize>;
+
+  impl<'de, const N: usize> de::Visitor<'de> for SmallStringVisitor<N> {
+    type Value = SmallString<N>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+      E: de::Error,
+    {
+      Ok(SmallString::from(v))
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+    where
+      E: de::Error,
+    {
+      Ok(SmallString::from(v))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+      E: de::Error,
+    {
+      // adopts the allocation of `v`, unless it fits inline.
+      Ok(SmallString::from(v))
+    }
+  }
+
+  impl<'de, const N: usize> Deserialize<'de> for SmallString<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      deserializer.deserialize_str(SmallStringVisitor)
+    }
+  }
+}
+
+#[cfg(feature = "zeroize")]
+mod zeroize_impl {
+  use zeroize::Zeroize;
+  use zeroize::ZeroizeOnDrop;
+
+  use super::*;
+
+  impl<const N: usize> Zeroize for SmallString<N> {
+    /// Wipes the string's entire capacity (inline or heap) and clears it.
+    #[inline]
+    fn zeroize(&mut self) {
+      self.buf.zeroize();
+    }
+  }
+
+  /// The underlying `CompactVec` wipes its storage when dropped with the
+  /// `zeroize` feature enabled.
+  impl<const N: usize> ZeroizeOnDrop for SmallString<N> {}
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn small_string_push_and_spill() {
+    let mut s: SmallString<4> = SmallString::new();
+    s.push_str("ab");
+    s.push('c');
+    assert!(s.is_inline());
+    assert_eq!(s, "abc");
+    s.push('é');
+    assert!(!s.is_inline());
+    assert_eq!(s.len(), 5);
+    assert_eq!(String::from(s), "abcé");
+  }
+
+  #[test]
+  fn small_string_insert() {
+    let mut s: SmallString<8> = SmallString::from("ac");
+    s.insert(1, 'b');
+    s.insert_str(3, "dé");
+    assert!(s.is_inline());
+    assert_eq!(s, "abcdé");
+    s.insert_str(0, "0123");
+    assert_eq!(s, "0123abcdé");
+    assert!(!s.is_inline());
+  }
+
+  #[test]
+  #[should_panic]
+  fn small_string_insert_not_char_boundary() {
+    let mut s: SmallString<8> = SmallString::from("é");
+    s.insert(1, 'x');
+  }
+
+  #[test]
+  fn small_string_remove() {
+    let mut s: SmallString<4> = SmallString::from("añbc");
+    assert!(!s.is_inline());
+    assert_eq!(s.remove(1), 'ñ');
+    assert_eq!(s.remove(2), 'c');
+    assert_eq!(s.remove(0), 'a');
+    assert_eq!(s, "b");
+  }
+
+  #[test]
+  #[should_panic]
+  fn small_string_remove_past_end() {
+    let mut s: SmallString<4> = SmallString::from("ab");
+    s.remove(2);
+  }
+
+  #[test]
+  fn small_string_truncate_and_clear() {
+    let mut s: SmallString<4> = SmallString::from("héllo");
+    s.truncate(10);
+    assert_eq!(s, "héllo");
+    s.truncate(3);
+    assert_eq!(s, "hé");
+    assert!(!s.is_inline());
+    s.clear();
+    assert!(s.is_empty() && s.is_inline());
+  }
+
+  #[test]
+  #[should_panic]
+  fn small_string_truncate_not_char_boundary() {
+    let mut s: SmallString<4> = SmallString::from("hé");
+    s.truncate(2);
+  }
+
+  #[test]
+  fn small_string_pop() {
+    let mut s: SmallString<2> = SmallString::from("xé");
+    assert_eq!(s.pop(), Some('é'));
+    assert_eq!(s.pop(), Some('x'));
+    assert_eq!(s.pop(), None);
+    assert!(s.is_empty());
+  }
+
+  #[test]
+  fn small_string_replace_range() {
+    let mut s: SmallString<8> = SmallString::from("abcdef");
+    s.replace_range(1..3, "X");
+    assert_eq!(s, "aXdef");
+    s.replace_range(1..=1, "ééé");
+    assert_eq!(s, "aééédef");
+    s.replace_range(.., "");
+    assert!(s.is_empty());
+    s.replace_range(0..0, "new");
+    assert_eq!(s, "new");
+  }
+
+  #[test]
+  #[should_panic]
+  fn small_string_replace_range_not_char_boundary() {
+    let mut s: SmallString<8> = SmallString::from("é");
+    s.replace_range(..1, "e");
+  }
+
+  #[test]
+  fn small_string_fmt_write() {
+    use core::fmt::Write;
+
+    let mut s: SmallString<8> = SmallString::new();
+    write!(s, "{}ms", 250).unwrap();
+    assert_eq!(s, "250ms");
+    assert!(s.is_inline());
+
+    let s = crate::format_small!(4; "{:>6}", "ab");
+    assert_eq!(s, "    ab");
+    let s: SmallString<4> = crate::format_small!("static");
+    assert_eq!(s, "static");
+  }
+
+  #[test]
+  fn small_string_macro() {
+    const HEADER: SmallString<16> = crate::small_str!("accept");
+    let mut s = HEADER;
+    s.push_str("-encoding");
+    assert_eq!(s, "accept-encoding");
+    assert!(s.is_inline());
+
+    let s = crate::small_str!(4; "🐄");
+    assert_eq!((s.len(), s.capacity()), (4, 4));
+    assert_eq!(HEADER, "accept");
+  }
+
+  #[test]
+  fn small_string_capacity() {
+    let s: SmallString<8> = SmallString::with_capacity(4);
+    assert!(s.is_inline());
+    assert_eq!(s.capacity(), 8);
+
+    let mut s: SmallString<8> = SmallString::with_capacity(20);
+    assert!(!s.is_inline());
+    assert_eq!(s.capacity(), 20);
+    s.push_str("short");
+    s.shrink_to_fit();
+    assert!(s.is_inline());
+
+    s.reserve(10);
+    assert!(!s.is_inline());
+    assert!(s.capacity() >= 15);
+    assert_eq!(s, "short");
+  }
+
+  #[test]
+  fn small_string_bytes_roundtrip() {
+    let s: SmallString<4> = SmallString::from("spilled");
+    let ptr = s.as_bytes().as_ptr();
+    let bytes = s.into_bytes();
+    assert_eq!(bytes, *b"spilled");
+    let s = SmallString::from_utf8(bytes).unwrap();
+    assert_eq!(s.as_ptr(), ptr);
+
+    let bytes: CompactVec<u8, 4> = CompactVec::from([b'a', 0xc3]);
+    let err = SmallString::from_utf8(bytes).unwrap_err();
+    assert_eq!(err.utf8_error().valid_up_to(), 1);
+    assert_eq!(err.as_bytes(), [b'a', 0xc3]);
+  }
+
+  #[test]
+  fn small_string_from_utf8_lossy() {
+    let s: SmallString<8> = SmallString::from_utf8_lossy(b"ok");
+    assert_eq!(s, "ok");
+    let s: SmallString<8> = SmallString::from_utf8_lossy(b"a\xf0\x90b\xff");
+    assert_eq!(s, "a\u{FFFD}b\u{FFFD}");
+    assert!(s.is_inline());
+    let s: SmallString<4> = SmallString::from_utf8_lossy(b"\xff\xff");
+    assert_eq!(s.capacity(), 6);
+  }
+
+  #[test]
+  fn small_string_drain() {
+    let mut s: SmallString<8> = SmallString::from("añbcdé");
+    let mut drain = s.drain(1..5);
+    assert_eq!(drain.next(), Some('ñ'));
+    assert_eq!(drain.next_back(), Some('c'));
+    assert_eq!(drain.as_str(), "b");
+    drop(drain);
+    assert_eq!(s, "adé");
+
+    assert_eq!(s.drain(..).count(), 3);
+    assert!(s.is_empty());
+  }
+
+  #[test]
+  fn small_string_conversions() {
+    let s: SmallString<4> = String::from("hello").into();
+    assert!(!s.is_inline());
+    let s: SmallString<8> = "hi".parse().unwrap();
+    assert_eq!(s.as_str(), "hi");
+    let s: SmallString<8> = ["a", "b"].into_iter().collect();
+    assert_eq!(s, SmallString::<2>::from("ab"));
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn small_string_serde_roundtrip() {
+    let s: SmallString<8> = SmallString::from("moo");
+    let json = serde_json::to_string(&s).unwrap();
+    assert_eq!(json, r#""moo""#);
+    let back: SmallString<8> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, s);
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn small_string_serde_owned_and_escaped() {
+    let s: SmallString<8> = serde_json::from_str(r#""a\"b\n""#).unwrap();
+    assert_eq!(s, "a\"b\n");
+    let value = serde_json::json!("owned string value");
+    let s: SmallString<8> = serde_json::from_value(value).unwrap();
+    assert_eq!(s, "owned string value");
+    let mut reader = &br#""from a reader""#[..];
+    let s: SmallString<8> = serde_json::from_reader(&mut reader).unwrap();
+    assert_eq!(s, "from a reader");
+  }
+
+  #[test]
+  fn small_string_borrow_str_lookup() {
+    use std::collections::BTreeMap;
+    use std::collections::HashMap;
+
+    let mut map: HashMap<SmallString<16>, u32> = HashMap::new();
+    map.insert("alpha".into(), 1);
+    map.insert("a string that spills to the heap".into(), 2);
+    assert_eq!(map.get("alpha"), Some(&1));
+    assert_eq!(map.get("a string that spills to the heap"), Some(&2));
+    assert_eq!(map.get("beta"), None);
+
+    let mut tree: BTreeMap<SmallString<16>, u32> = BTreeMap::new();
+    tree.insert("b".into(), 2);
+    tree.insert("a".into(), 1);
+    assert_eq!(tree.get("a"), Some(&1));
+    assert!(tree.contains_key("b"));
+  }
+
+  #[test]
+  fn small_string_as_ref() {
+    fn bytes_len(b: impl AsRef<[u8]>) -> usize {
+      b.as_ref().len()
+    }
+    fn upper(mut s: impl AsMut<str>) -> String {
+      s.as_mut().make_ascii_uppercase();
+      s.as_mut().to_owned()
+    }
+
+    let s: SmallString<8> = SmallString::from("moos");
+    assert_eq!(AsRef::<str>::as_ref(&s), "moos");
+    assert_eq!(bytes_len(&s), 4);
+    assert_eq!(upper(s.clone()), "MOOS");
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn small_string_as_ref_path() {
+    use std::ffi::OsStr;
+    use std::path::Path;
+
+    let s: SmallString<16> = SmallString::from("dir/file.txt");
+    let path: &Path = s.as_ref();
+    assert_eq!(path.extension(), Some(OsStr::new("txt")));
+    let os: &OsStr = s.as_ref();
+    assert_eq!(os, "dir/file.txt");
+  }
+
+  #[test]
+  fn small_string_writer_split_sequences() {
+    let mut s: SmallString<8> = SmallString::new();
+    let mut writer = s.writer();
+    let bytes = "añ€😀z".as_bytes();
+    for byte in bytes {
+      assert_eq!(writer.write_utf8(core::slice::from_ref(byte)), Ok(1));
+    }
+    assert!(writer.pending().is_empty());
+    drop(writer);
+    assert_eq!(s, "añ€😀z");
+
+    let mut writer = s.writer();
+    assert_eq!(writer.write_utf8(b"ok\xFFno"), Ok(2));
+    assert!(writer.write_utf8(b"\xFFno").is_err());
+    assert_eq!(writer.write_utf8(b"\xE2\x82"), Ok(2));
+    assert_eq!(writer.pending(), b"\xE2\x82");
+    drop(writer);
+    assert_eq!(s, "añ€😀zok\u{FFFD}");
+  }
+
+  #[test]
+  fn small_string_writer_keeps_pending_on_error() {
+    let mut s: SmallString<8> = SmallString::new();
+    let mut writer = s.writer();
+    assert_eq!(writer.write_utf8(b"a\xE2"), Ok(2));
+    assert!(writer.write_utf8(b"b").is_err());
+    assert_eq!(writer.pending(), b"\xE2");
+    assert_eq!(writer.write_utf8(b"\x82\xACb"), Ok(3));
+    assert!(writer.pending().is_empty());
+
+    assert_eq!(writer.write_utf8(b"\xC3"), Ok(1));
+    assert!(writer.write_utf8(b"c").is_err());
+    assert_eq!(writer.discard_pending(), 1);
+    assert_eq!(writer.write_utf8(b"c"), Ok(1));
+    drop(writer);
+    assert_eq!(s, "a€bc");
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn small_string_writer_std_io() {
+    use std::io::Write;
+
+    let mut s: SmallString<4> = SmallString::new();
+    let umlaut = 'ü';
+    write!(s.writer(), "{umlaut}-{}", 42).unwrap();
+    assert_eq!(s, "ü-42");
+    let err = s.writer().write_all(b"\xC0\x80").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(s, "ü-42");
+  }
+
+  #[test]
+  fn small_string_from_utf16() {
+    let text = "Grüße, 😀";
+    let units: Vec<u16> = text.encode_utf16().collect();
+    let s = SmallString::<16>::from_utf16(&units).unwrap();
+    assert_eq!(s, text);
+    assert!(s.is_inline());
+    let s = SmallString::<4>::from_utf16(&units).unwrap();
+    assert_eq!(s, text);
+    assert_eq!(s.capacity(), text.len());
+
+    let bad = [0xD83D, 0x61];
+    assert_eq!(SmallString::<8>::from_utf16(&bad), Err(FromUtf16Error(())));
+    assert_eq!(SmallString::<8>::from_utf16_lossy(&bad), "\u{FFFD}a");
+  }
+
+  #[test]
+  fn small_string_cstr_interop() {
+    let s: SmallString<4> = SmallString::from_cstr(c"abcd").unwrap();
+    assert!(s.is_inline());
+    assert!(SmallString::<4>::from_cstr(c"\xFF").is_err());
+
+    let mut s = s;
+    assert_eq!(
+      s.as_nul_terminated().unwrap().to_bytes_with_nul(),
+      b"abcd\0"
+    );
+    assert_eq!(s.len(), 4);
+    assert_eq!(s, "abcd");
+    assert_eq!(s.clone().into_cstring().unwrap().as_c_str(), c"abcd");
+
+    let s: SmallString<4> = SmallString::from("nul\0inside");
+    let err = s.into_cstring().unwrap_err();
+    assert_eq!(err.nul_position(), 3);
+  }
+
+  #[test]
+  fn small_string_chunks() {
+    let s: SmallString<16> = SmallString::from("aé😀bcdef");
+    let chunks: Vec<&str> = s.chunks(3).collect();
+    assert_eq!(chunks, ["aé😀", "bcd", "ef"]);
+    assert_eq!(s.chunks(9).collect::<Vec<_>>(), ["aé😀bcdef"]);
+    assert_eq!(SmallString::<4>::new().chunks(2).next(), None);
+  }
+
+  #[test]
+  fn small_string_wrap() {
+    let s: SmallString<64> =
+      SmallString::from("the quick brown fox\n\njumps  over extraordinarily");
+    let lines: Vec<&str> = s.wrap(10).collect();
+    assert_eq!(
+      lines,
+      [
+        "the quick",
+        "brown fox",
+        "",
+        "jumps",
+        "over",
+        "extraordin",
+        "arily"
+      ]
+    );
+    let s: SmallString<16> = SmallString::from("   indented");
+    assert_eq!(s.wrap(5).collect::<Vec<_>>(), ["   in", "dente", "d"]);
+  }
+
+  #[test]
+  fn small_string_storage_control() {
+    let mut s: SmallString<4> = SmallString::from("abcdefgh");
+    assert!(s.is_spilled());
+    assert!(s.heap_capacity_bytes() >= 8);
+    assert_eq!(s.memory_usage().heap, s.heap_capacity_bytes());
+
+    s.truncate(5);
+    assert!(!s.try_make_inline());
+    assert!(s.heap_capacity_bytes() >= 8);
+
+    s.pop();
+    assert!(s.try_make_inline());
+    assert!(!s.is_spilled());
+    assert_eq!(s.heap_capacity_bytes(), 0);
+    assert_eq!(s, "abcd");
+    assert!(s.try_make_inline());
+  }
+
+  #[test]
+  fn small_string_truncate_to_fit() {
+    let mut s: SmallString<16> = SmallString::from("a€😀b");
+    s.truncate_to_fit(64);
+    assert_eq!(s, "a€😀b");
+    s.truncate_to_fit(7);
+    assert_eq!(s, "a€");
+    s.truncate_to_fit(4);
+    assert_eq!(s, "a€");
+    s.truncate_to_fit(3);
+    assert_eq!(s, "a");
+    s.truncate_to_fit(0);
+    assert!(s.is_empty());
+  }
+
+  #[test]
+  fn small_string_push_str_truncating() {
+    let mut s: SmallString<8> = SmallString::new();
+    assert_eq!(s.push_str_truncating("ab", 8), 2);
+    assert_eq!(s.push_str_truncating("c€d", 5), 1);
+    assert_eq!(s, "abc");
+    assert_eq!(s.push_str_truncating("€d", 6), 3);
+    assert_eq!(s, "abc€");
+    assert_eq!(s.push_str_truncating("xyz", 4), 0);
+    assert_eq!(s, "abc€");
+    assert!(s.is_inline());
+  }
+
+  #[test]
+  fn small_string_try_push() {
+    let mut s: SmallString<4> = SmallString::new();
+    assert_eq!(s.remaining_inline_capacity(), 4);
+    assert_eq!(s.try_push('€'), Ok(()));
+    assert_eq!(s.try_push('é'), Err(CapacityError('é')));
+    assert_eq!(s.try_push('x'), Ok(()));
+    assert_eq!(s.remaining_inline_capacity(), 0);
+    assert_eq!(s.try_push_str(""), Ok(()));
+    assert_eq!(s.try_push_str("y"), Err(CapacityError("y")));
+    assert_eq!(s, "€x");
+    assert!(s.is_inline());
+
+    s.push_str("spill");
+    assert_eq!(s.remaining_inline_capacity(), 0);
+    let spare = s.capacity() - s.len();
+    assert!(s.try_push_str(&"z".repeat(spare + 1)).is_err());
+    assert_eq!(s.try_push_str(&"z".repeat(spare)), Ok(()));
+  }
+
+  #[test]
+  fn small_string_into_boxed_str() {
+    let inline: SmallString<8> = SmallString::from("abc");
+    assert_eq!(&*inline.into_boxed_str(), "abc");
+
+    let mut spilled: SmallString<2> = SmallString::with_capacity(32);
+    spilled.push_str("heap");
+    let boxed: Box<str> = spilled.into();
+    assert_eq!(&*boxed, "heap");
+
+    let exact: SmallString<2> = SmallString::from(String::from("exact"));
+    let ptr_exact = exact.as_ptr();
+    let boxed_exact = exact.into_boxed_str();
+    assert_eq!(boxed_exact.as_ptr(), ptr_exact);
+  }
+
+  #[test]
+  fn small_string_resize_capacity() {
+    let small: SmallString<4> = SmallString::from("abc");
+    let larger: SmallString<8> = small.resize_capacity();
+    assert!(larger.is_inline());
+    let smaller: SmallString<2> = larger.resize_capacity();
+    assert!(smaller.is_spilled());
+    assert_eq!(smaller, "abc");
+
+    let ptr = smaller.as_ptr();
+    let still_spilled: SmallString<1> = smaller.resize_capacity();
+    assert_eq!(still_spilled.as_ptr(), ptr);
+    let back: SmallString<4> = still_spilled.resize_capacity();
+    assert!(back.is_inline());
+    assert_eq!(back, "abc");
+  }
+
+  #[test]
+  fn small_string_index_ranges() {
+    fn first_two<S: Index<core::ops::Range<usize>, Output = str>>(
+      s: &S,
+    ) -> &str {
+      &s[0..2]
+    }
+
+    let mut s: SmallString<16> = SmallString::from("hello, wörld");
+    assert_eq!(&s[..5], "hello");
+    assert_eq!(&s[7..], "wörld");
+    assert_eq!(&s[7..=9], "wö");
+    assert_eq!(&s[..], "hello, wörld");
+    assert_eq!(first_two(&s), "he");
+    s[..5].make_ascii_uppercase();
+    assert_eq!(s, "HELLO, wörld");
+    assert_eq!(s.get(8..9), None);
+    assert_eq!(s.get_mut(..1).map(|h| &*h), Some("H"));
+  }
+
+  #[test]
+  #[should_panic]
+  fn small_string_index_not_char_boundary() {
+    let s: SmallString<8> = SmallString::from("ö");
+    let _ = &s[..1];
+  }
+
+  #[test]
+  fn small_string_as_mut_vec() {
+    let mut s: SmallString<4> = SmallString::from("ab");
+    unsafe {
+      let bytes = s.as_mut_vec();
+      bytes.push(b'c');
+      bytes.extend_from_slice("ü!".as_bytes());
+      bytes.swap(0, 1);
+    }
+    assert_eq!(s, "bacü!");
+    assert!(s.is_spilled());
+  }
+
+  #[test]
+  fn small_string_replace() {
+    let s: SmallString<8> = SmallString::from("aXbXc");
+    let r = s.replace("X", "");
+    assert_eq!(r, "abc");
+    assert!(r.is_inline());
+    let r = s.replace("X", "--");
+    assert_eq!(r, "a--b--c");
+    assert_eq!(r.capacity(), 8);
+    let r = s.replace("X", "___");
+    assert_eq!(r, "a___b___c");
+    assert_eq!(r.capacity(), 9);
+    assert_eq!(s.replace("", "."), "aXbXc".replace("", "."));
+    assert_eq!(s.replacen("X", "é", 1), "aébXc");
+    assert_eq!(s.replacen("X", "é", 0), s);
+    assert_eq!(s.replace("none", "x"), s);
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn small_string_read_into() {
+    use std::io;
+
+    // yields its data in fixed-size pieces, like a serial port.
+    struct Trickle<'a>(&'a [u8], usize);
+
+    impl io::Read for Trickle<'_> {
+      fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.0.len().min(buf.len()).min(self.1);
+        buf[..count].copy_from_slice(&self.0[..count]);
+        self.0 = &self.0[count..];
+        Ok(count)
+      }
+    }
+
+    let mut s: SmallString<4> = SmallString::from("ab");
+    let mut reader = Trickle("c€d".as_bytes(), 2);
+    assert_eq!(s.read_into(&mut reader).unwrap(), 4);
+    assert_eq!(s, "abc€");
+    assert_eq!(s.read_into(&mut reader).unwrap(), 1);
+    assert_eq!(s, "abc€d");
+    assert_eq!(s.read_into(&mut reader).unwrap(), 0);
+
+    let mut s: SmallString<8> = SmallString::new();
+    let err = s.read_into(&mut &b"ok\xFF"[..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let err = s.read_into(&mut &b"ok\xE2\x82"[..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(s.is_empty());
+
+    // a read into a large buffer is capped to a bounded window.
+    let mut s: SmallString<8> = SmallString::with_capacity(64 * 1024);
+    let data = [b'x'; 20 * 1024];
+    assert_eq!(
+      s.read_into(&mut &data[..]).unwrap(),
+      std_impl::MAX_READ_WINDOW
+    );
+    assert_eq!(s.len(), std_impl::MAX_READ_WINDOW);
+  }
+
+  #[test]
+  #[should_panic]
+  fn small_string_commit_utf8_past_capacity() {
+    let mut s: SmallString<4> = SmallString::new();
+    let count = s.spare_capacity_mut().len() + 1;
+    unsafe {
+      let _ = s.commit_utf8(count);
+    }
+  }
+}
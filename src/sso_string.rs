@@ -0,0 +1,348 @@
+use alloc::string::String;
+use core::fmt::Write;
+
+use crate::CapacityError;
+use crate::CompactString;
+use crate::CowStr;
+use crate::InlineStr;
+use crate::MAX_INLINE_STR_LEN;
+use crate::SmallString;
+
+/// The common interface of the crate's small-string-optimized string types:
+/// [`InlineStr`], [`SmallString`], [`CowStr`], and [`CompactString`].
+///
+/// Code written against `SsoString` lets its callers pick the storage that
+/// suits them, such as a parser that produces tokens as `InlineStr`s when
+/// they must never allocate, or as `SmallString<N>`s when an occasional long
+/// one may spill to the heap.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::InlineStr;
+/// use moos::SmallString;
+/// use moos::SsoString;
+///
+/// /// Splits `input` into words, upper-casing each into storage of the
+/// /// caller's choice.
+/// fn shout<S: SsoString>(input: &str) -> Option<Vec<S>> {
+///   input
+///     .split_whitespace()
+///     .map(|word| {
+///       let mut s = S::default();
+///       for c in word.chars().flat_map(char::to_uppercase) {
+///         s.try_push_str(c.encode_utf8(&mut [0; 4])).ok()?;
+///       }
+///       Some(s)
+///     })
+///     .collect()
+/// }
+///
+/// let words = shout::<SmallString<8>>(
+///   "moo said the supercalifragilisticexpialidocious cow",
+/// )
+/// .unwrap();
+/// assert_eq!(words[1].as_str(), "SAID");
+/// assert!(!words[3].is_inline());
+///
+/// // an `InlineStr` has nowhere to put the longest word.
+/// assert!(
+///   shout::<InlineStr>("moo said the supercalifragilisticexpialidocious cow")
+///     .is_none()
+/// );
+/// ```
+pub trait SsoString: Default {
+  /// Returns the string as a string slice.
+  fn as_str(&self) -> &str;
+
+  /// Appends `s` to the string, moving it to the heap if needed and
+  /// possible.
+  ///
+  /// Only types with a fixed capacity, such as [`InlineStr`], return an
+  /// error, leaving the string unchanged, when `s` doesn't fit. Note that
+  /// this differs from [`SmallString::try_push_str`], which also refuses to
+  /// spill to the heap.
+  fn try_push_str<'a>(
+    &mut self,
+    s: &'a str,
+  ) -> Result<(), CapacityError<&'a str>>;
+
+  /// Returns `true` if the string is stored inline, rather than on the
+  /// heap or borrowed.
+  fn is_inline(&self) -> bool;
+
+  /// Returns the number of bytes the string can hold without growing.
+  fn capacity(&self) -> usize;
+
+  /// Converts the string into a `String`.
+  fn into_string(self) -> String;
+
+  /// Returns the length of the string in bytes.
+  #[inline]
+  fn len(&self) -> usize {
+    self.as_str().len()
+  }
+
+  /// Returns `true` if the string is empty.
+  #[inline]
+  fn is_empty(&self) -> bool {
+    self.as_str().is_empty()
+  }
+}
+
+impl SsoString for InlineStr {
+  #[inline(always)]
+  fn as_str(&self) -> &str {
+    InlineStr::as_str(self)
+  }
+
+  #[inline]
+  fn try_push_str<'a>(
+    &mut self,
+    s: &'a str,
+  ) -> Result<(), CapacityError<&'a str>> {
+    self.write_str(s).map_err(|_| CapacityError(s))
+  }
+
+  #[inline(always)]
+  fn is_inline(&self) -> bool {
+    true
+  }
+
+  #[inline(always)]
+  fn capacity(&self) -> usize {
+    MAX_INLINE_STR_LEN
+  }
+
+  #[inline]
+  fn into_string(self) -> String {
+    String::from(self)
+  }
+}
+
+impl<const N: usize> SsoString for SmallString<N> {
+  #[inline(always)]
+  fn as_str(&self) -> &str {
+    SmallString::as_str(self)
+  }
+
+  #[inline]
+  fn try_push_str<'a>(
+    &mut self,
+    s: &'a str,
+  ) -> Result<(), CapacityError<&'a str>> {
+    self.push_str(s);
+    Ok(())
+  }
+
+  #[inline(always)]
+  fn is_inline(&self) -> bool {
+    SmallString::is_inline(self)
+  }
+
+  #[inline(always)]
+  fn capacity(&self) -> usize {
+    SmallString::capacity(self)
+  }
+
+  #[inline]
+  fn into_string(self) -> String {
+    String::from(self)
+  }
+}
+
+impl SsoString for CompactString {
+  #[inline(always)]
+  fn as_str(&self) -> &str {
+    CompactString::as_str(self)
+  }
+
+  #[inline]
+  fn try_push_str<'a>(
+    &mut self,
+    s: &'a str,
+  ) -> Result<(), CapacityError<&'a str>> {
+    self.push_str(s);
+    Ok(())
+  }
+
+  #[inline(always)]
+  fn is_inline(&self) -> bool {
+    CompactString::is_inline(self)
+  }
+
+  #[inline(always)]
+  fn capacity(&self) -> usize {
+    CompactString::capacity(self)
+  }
+
+  #[inline]
+  fn into_string(self) -> String {
+    CompactString::into_string(self)
+  }
+}
+
+impl SsoString for CowStr<'_> {
+  #[inline(always)]
+  fn as_str(&self) -> &str {
+    CowStr::as_str(self)
+  }
+
+  /// Appends `s`, keeping the result inline while it fits and moving it to
+  /// an owned string otherwise. This never fails.
+  fn try_push_str<'a>(
+    &mut self,
+    s: &'a str,
+  ) -> Result<(), CapacityError<&'a str>> {
+    if s.is_empty() {
+      return Ok(());
+    }
+    if let CowStr::Inlined(inline) = self {
+      if inline.write_str(s).is_ok() {
+        return Ok(());
+      }
+    } else if self.len() + s.len() <= MAX_INLINE_STR_LEN {
+      let mut inline = InlineStr::default();
+      // both fit, as checked above.
+      let _ = inline.write_str(self.as_str());
+      let _ = inline.write_str(s);
+      *self = CowStr::Inlined(inline);
+      return Ok(());
+    }
+    let mut owned = String::with_capacity(self.len() + s.len());
+    owned.push_str(self.as_str());
+    owned.push_str(s);
+    *self = CowStr::Owned(owned.into_boxed_str());
+    Ok(())
+  }
+
+  #[inline(always)]
+  fn is_inline(&self) -> bool {
+    matches!(self, CowStr::Inlined(_))
+  }
+
+  /// Returns the inline capacity for an inline string, and the length for
+  /// a borrowed or owned one, which can't grow in place.
+  #[inline]
+  fn capacity(&self) -> usize {
+    match self {
+      CowStr::Inlined(_) => MAX_INLINE_STR_LEN,
+      _ => self.len(),
+    }
+  }
+
+  #[inline]
+  fn into_string(self) -> String {
+    CowStr::into_string(self)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn build<S: SsoString>(parts: &[&str]) -> Result<S, usize> {
+    let mut s = S::default();
+    for (i, part) in parts.iter().enumerate() {
+      s.try_push_str(part).map_err(|_| i)?;
+    }
+    Ok(s)
+  }
+
+  const PARTS: [&str; 3] = ["memory-", "optimized ", "objects"];
+  const LONG: &str = "memory-optimized objects";
+
+  #[test]
+  fn sso_string_inline_str() {
+    let s: InlineStr = build(&PARTS[..2]).unwrap();
+    assert_eq!(SsoString::as_str(&s), "memory-optimized ");
+    assert!(SsoString::is_inline(&s));
+    assert_eq!(SsoString::capacity(&s), MAX_INLINE_STR_LEN);
+    assert!(SsoString::is_empty(&InlineStr::default()));
+
+    let s: Result<InlineStr, _> = build(&PARTS);
+    assert_eq!(s.unwrap_err(), 2);
+  }
+
+  #[test]
+  fn sso_string_inline_str_partial_write() {
+    let mut s: InlineStr = build(&PARTS[..2]).unwrap();
+    // only part of "objects" would fit, so none of it is written.
+    assert_eq!(s.try_push_str(PARTS[2]), Err(CapacityError(PARTS[2])));
+    assert_eq!(SsoString::as_str(&s), "memory-optimized ");
+    assert_eq!(SsoString::len(&s), 17);
+
+    // what does fit is still appended, up to the capacity exactly.
+    s.try_push_str("obje").unwrap();
+    assert_eq!(SsoString::len(&s), MAX_INLINE_STR_LEN - 1);
+    s.try_push_str("c").unwrap();
+    assert_eq!(SsoString::len(&s), MAX_INLINE_STR_LEN);
+    assert_eq!(s.try_push_str("t"), Err(CapacityError("t")));
+    assert_eq!(s.into_string(), "memory-optimized objec");
+  }
+
+  #[test]
+  fn sso_string_small_string() {
+    let s: SmallString<8> = build(&PARTS[..1]).unwrap();
+    assert!(SsoString::is_inline(&s));
+    assert_eq!(SsoString::capacity(&s), 8);
+
+    let s: SmallString<8> = build(&PARTS).unwrap();
+    assert_eq!(
+      (SsoString::as_str(&s), SsoString::is_inline(&s)),
+      (LONG, false)
+    );
+    assert!(SsoString::capacity(&s) >= LONG.len());
+    assert_eq!(s.into_string(), LONG);
+  }
+
+  #[test]
+  fn sso_string_compact_string() {
+    let s: CompactString = build(&PARTS[..1]).unwrap();
+    assert!(SsoString::is_inline(&s));
+
+    let s: CompactString = build(&PARTS).unwrap();
+    assert!(!SsoString::is_inline(&s));
+    assert_eq!(SsoString::len(&s), LONG.len());
+    assert_eq!(s.into_string(), LONG);
+  }
+
+  #[test]
+  fn sso_string_cow_str() {
+    let s: CowStr = build(&PARTS[..2]).unwrap();
+    assert!(SsoString::is_inline(&s));
+    assert_eq!(SsoString::capacity(&s), MAX_INLINE_STR_LEN);
+
+    // an inlined string that overflows moves to an owned one.
+    let s: CowStr = build(&PARTS).unwrap();
+    assert!(s.is_owned());
+    assert_eq!(SsoString::capacity(&s), LONG.len());
+    assert_eq!(s.into_string(), LONG);
+  }
+
+  #[test]
+  fn sso_string_cow_str_promotion() {
+    let mut s = CowStr::Borrowed("moo");
+    s.try_push_str("").unwrap();
+    assert!(s.is_borrowed());
+    assert_eq!(SsoString::capacity(&s), 3);
+
+    // a borrowed string that still fits once appended to is inlined.
+    s.try_push_str(", said the cow").unwrap();
+    assert!(s.is_inlined());
+    assert_eq!(SsoString::as_str(&s), "moo, said the cow");
+
+    let mut s = CowStr::Borrowed("moo");
+    s.try_push_str(" ".repeat(MAX_INLINE_STR_LEN - 3).as_str())
+      .unwrap();
+    assert!(s.is_inlined());
+    assert_eq!(SsoString::len(&s), MAX_INLINE_STR_LEN);
+
+    // one that doesn't is moved to an owned string instead.
+    let mut s = CowStr::Borrowed(LONG);
+    s.try_push_str("!").unwrap();
+    assert!(s.is_owned());
+    s.try_push_str("").unwrap();
+    assert_eq!(SsoString::as_str(&s), "memory-optimized objects!");
+  }
+}
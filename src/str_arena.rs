@@ -0,0 +1,267 @@
+use alloc::alloc::Layout;
+use alloc::alloc::alloc;
+use alloc::alloc::dealloc;
+use alloc::alloc::handle_alloc_error;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::ptr;
+use core::ptr::NonNull;
+use core::slice;
+use core::str;
+
+use crate::CowStr;
+use crate::InlineStr;
+
+/// The size of the first chunk allocated by an arena created with
+/// [`StrArena::new`].
+const FIRST_CHUNK_SIZE: usize = 1024;
+
+/// Chunks stop doubling in size once they reach this many bytes.
+const MAX_CHUNK_SIZE: usize = 1 << 20;
+
+/// A bump allocator for strings, which copies them into large chunks and
+/// frees them all at once.
+///
+/// Each allocation costs a pointer bump and a copy, with no per-string
+/// header, so a parser can allocate thousands of strings that live as long
+/// as the arena. When the current chunk is full a new one is allocated,
+/// twice the size of the last up to 1 MiB; strings larger than that get a
+/// chunk of their own. Earlier chunks are never moved, so strings handed
+/// out stay valid until the arena is [`reset`](Self::reset) or dropped.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::StrArena;
+///
+/// let arena = StrArena::new();
+/// let words: Vec<&str> = "the quick brown fox"
+///   .split(' ')
+///   .map(|w| arena.alloc(&w.to_uppercase()))
+///   .collect();
+/// assert_eq!(words, ["THE", "QUICK", "BROWN", "FOX"]);
+///
+/// // short strings don't need the arena at all.
+/// let cow = arena.alloc_cow("fox");
+/// assert!(cow.is_inlined());
+/// ```
+pub struct StrArena {
+  chunks: UnsafeCell<Vec<Chunk>>,
+  /// The next free byte in the current chunk.
+  cursor: Cell<*mut u8>,
+  /// The end of the current chunk.
+  end:    Cell<*mut u8>,
+}
+
+/// A heap block owned by a [`StrArena`].
+struct Chunk {
+  ptr: NonNull<u8>,
+  len: usize,
+}
+
+impl Chunk {
+  fn new(len: usize) -> Self {
+    let layout = Self::layout(len);
+    let ptr = unsafe { alloc(layout) };
+    match NonNull::new(ptr) {
+      Some(ptr) => Self { ptr, len },
+      None => handle_alloc_error(layout),
+    }
+  }
+
+  #[inline(always)]
+  fn layout(len: usize) -> Layout {
+    Layout::array::<u8>(len).expect("StrArena chunk too large")
+  }
+}
+
+impl Drop for Chunk {
+  fn drop(&mut self) {
+    unsafe { dealloc(self.ptr.as_ptr(), Self::layout(self.len)) }
+  }
+}
+
+// SAFETY: the arena owns its chunks, and the cells are only shared between
+// references on one thread since the arena is not `Sync`.
+unsafe impl Send for StrArena {}
+
+impl StrArena {
+  /// Creates a new, empty arena. Does not allocate until a string is
+  /// allocated.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      chunks: UnsafeCell::new(Vec::new()),
+      cursor: Cell::new(ptr::null_mut()),
+      end:    Cell::new(ptr::null_mut()),
+    }
+  }
+
+  /// Creates a new arena whose first chunk holds at least `capacity` bytes.
+  #[inline]
+  pub fn with_capacity(capacity: usize) -> Self {
+    let arena = Self::new();
+    if capacity > 0 {
+      arena.push_chunk(capacity);
+    }
+    arena
+  }
+
+  /// Copies `s` into the arena, returning a reference to the copy.
+  pub fn alloc(&self, s: &str) -> &str {
+    if s.is_empty() {
+      return "";
+    }
+    let dst = self.alloc_bytes(s.len());
+    unsafe {
+      ptr::copy_nonoverlapping(s.as_ptr(), dst, s.len());
+      str::from_utf8_unchecked(slice::from_raw_parts(dst, s.len()))
+    }
+  }
+
+  /// Returns `s` as a `CowStr` borrowing from the arena. Strings short enough
+  /// to be inlined are returned as [`CowStr::Inlined`] without using any
+  /// arena space.
+  #[inline]
+  pub fn alloc_cow(&self, s: &str) -> CowStr<'_> {
+    match InlineStr::try_from(s) {
+      Ok(inline) => CowStr::Inlined(inline),
+      Err(_) => CowStr::Borrowed(self.alloc(s)),
+    }
+  }
+
+  /// Returns the total number of bytes in the arena's chunks, used or not.
+  #[inline]
+  pub fn allocated_bytes(&self) -> usize {
+    self.chunks().iter().map(|chunk| chunk.len).sum()
+  }
+
+  /// Returns the number of bytes left in the current chunk.
+  #[inline]
+  pub fn remaining_bytes(&self) -> usize {
+    self.end.get() as usize - self.cursor.get() as usize
+  }
+
+  /// Frees every string in the arena at once.
+  ///
+  /// The largest chunk is kept for reuse, so an arena that is reset between
+  /// passes of similar size stops allocating after the first.
+  pub fn reset(&mut self) {
+    let chunks = self.chunks.get_mut();
+    if let Some(largest) = (0..chunks.len()).max_by_key(|&i| chunks[i].len) {
+      let chunk = chunks.swap_remove(largest);
+      chunks.clear();
+      chunks.push(chunk);
+    }
+    match chunks.last() {
+      Some(chunk) => {
+        self.cursor.set(chunk.ptr.as_ptr());
+        self.end.set(chunk.ptr.as_ptr().wrapping_add(chunk.len));
+      }
+      None => {
+        self.cursor.set(ptr::null_mut());
+        self.end.set(ptr::null_mut());
+      }
+    }
+  }
+
+  /// Reserves `len` bytes, moving to a new chunk if the current one is too
+  /// small.
+  #[inline]
+  fn alloc_bytes(&self, len: usize) -> *mut u8 {
+    if len > self.remaining_bytes() {
+      let last = self.chunks().last().map_or(0, |chunk| chunk.len);
+      let size = if last == 0 {
+        FIRST_CHUNK_SIZE
+      } else {
+        (last * 2).min(MAX_CHUNK_SIZE)
+      };
+      self.push_chunk(size.max(len));
+    }
+    let ptr = self.cursor.get();
+    self.cursor.set(ptr.wrapping_add(len));
+    ptr
+  }
+
+  fn push_chunk(&self, len: usize) {
+    let chunk = Chunk::new(len);
+    self.cursor.set(chunk.ptr.as_ptr());
+    self.end.set(chunk.ptr.as_ptr().wrapping_add(len));
+    // SAFETY: no reference to the chunk list outlives a method call, and
+    // pushing never moves the chunks' memory that strings point into.
+    unsafe { (*self.chunks.get()).push(chunk) };
+  }
+
+  #[inline(always)]
+  fn chunks(&self) -> &[Chunk] {
+    unsafe { &*self.chunks.get() }
+  }
+}
+
+impl Default for StrArena {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Debug for StrArena {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_struct("StrArena")
+      .field("chunks", &self.chunks().len())
+      .field("allocated_bytes", &self.allocated_bytes())
+      .field("remaining_bytes", &self.remaining_bytes())
+      .finish()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::string::String;
+
+  use super::*;
+
+  #[test]
+  fn str_arena_alloc() {
+    let arena = StrArena::new();
+    assert_eq!(arena.allocated_bytes(), 0);
+    assert_eq!(arena.alloc(""), "");
+    assert_eq!(arena.allocated_bytes(), 0);
+
+    let owned = String::from("hello, wörld");
+    let a = arena.alloc(&owned);
+    drop(owned);
+    let b = arena.alloc("again");
+    assert_eq!(a, "hello, wörld");
+    assert_eq!(b, "again");
+    assert_eq!(arena.allocated_bytes(), FIRST_CHUNK_SIZE);
+    assert_eq!(arena.remaining_bytes(), FIRST_CHUNK_SIZE - 18);
+
+    let long = "a string that is too long to be stored inline";
+    let cow = arena.alloc_cow(long);
+    assert!(cow.is_borrowed());
+    assert_eq!(cow, long);
+  }
+
+  #[test]
+  fn str_arena_grows_and_resets() {
+    let mut arena = StrArena::with_capacity(16);
+    let line = "0123456789";
+    let strings: Vec<&str> = (0..300).map(|_| arena.alloc(line)).collect();
+    assert!(strings.iter().all(|s| *s == line));
+    let huge = "x".repeat(MAX_CHUNK_SIZE + 1);
+    assert_eq!(arena.alloc(&huge).len(), huge.len());
+
+    let allocated = arena.allocated_bytes();
+    assert!(allocated > MAX_CHUNK_SIZE + 3000);
+    arena.reset();
+    assert_eq!(arena.allocated_bytes(), MAX_CHUNK_SIZE + 1);
+    assert_eq!(arena.remaining_bytes(), MAX_CHUNK_SIZE + 1);
+    assert_eq!(arena.alloc("reused"), "reused");
+    assert_eq!(arena.allocated_bytes(), MAX_CHUNK_SIZE + 1);
+  }
+}
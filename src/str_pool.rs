@@ -0,0 +1,479 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use core::hash::Hasher;
+use core::num::NonZeroU32;
+use core::ops::Index;
+
+use crate::CowStr;
+use crate::InlineStr;
+#[cfg(doc)]
+use crate::MAX_INLINE_STR_LEN;
+use crate::pre_hashed::FxHasher;
+
+/// A handle to a string interned in a [`StrPool`].
+///
+/// Symbols are 4 bytes, `Copy`, and compare and hash as integers, so they
+/// make cheap keys for identifiers that would otherwise be compared as
+/// strings. A symbol is only meaningful for the pool that created it, and
+/// `Option<Symbol>` is the same size as `Symbol`.
+///
+/// Symbols are ordered by when their strings were first interned, not
+/// lexicographically.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(NonZeroU32);
+
+impl Symbol {
+  /// Returns the zero-based index of the symbol in its pool, which is the
+  /// number of distinct strings interned before it.
+  #[inline(always)]
+  pub const fn index(self) -> usize {
+    self.0.get() as usize - 1
+  }
+
+  #[inline(always)]
+  fn from_index(index: usize) -> Self {
+    let id = u32::try_from(index + 1).expect("too many strings in StrPool");
+    Self(NonZeroU32::new(id).unwrap())
+  }
+}
+
+impl Debug for Symbol {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    write!(f, "Symbol({})", self.index())
+  }
+}
+
+/// A string interner, which stores each distinct string once and hands out
+/// [`Symbol`]s that can be resolved back to it.
+///
+/// Strings are stored as [`CowStr`]s: those of up to [`MAX_INLINE_STR_LEN`]
+/// bytes are kept inline without a heap allocation of their own, longer ones
+/// are boxed, and `&'static str`s interned with
+/// [`intern_static`](Self::intern_static) are not copied at all. Lookups use
+/// an open-addressing hash table of symbol indices, so no `std` is needed.
+///
+/// For a pool that can be shared between threads, see `SyncStrPool` (with
+/// the `std` feature enabled).
+///
+/// # Example
+///
+/// ```rust
+/// use moos::StrPool;
+///
+/// let mut pool = StrPool::new();
+/// let a = pool.intern("width");
+/// let b = pool.intern("height");
+/// assert_eq!(pool.intern("width"), a);
+/// assert_ne!(a, b);
+///
+/// assert_eq!(pool.resolve(b), Some("height"));
+/// assert_eq!(&pool[a], "width");
+/// assert_eq!(pool.get("depth"), None);
+/// ```
+#[derive(Clone, Default)]
+pub struct StrPool {
+  strings: Vec<CowStr<'static>>,
+  /// Open-addressing table of `index + 1` into `strings`, where `0` marks an
+  /// empty slot. Its length is zero or a power of two.
+  table:   Vec<u32>,
+}
+
+impl StrPool {
+  /// Creates a new, empty pool. Does not allocate until a string is interned.
+  #[inline]
+  pub const fn new() -> Self {
+    Self {
+      strings: Vec::new(),
+      table:   Vec::new(),
+    }
+  }
+
+  /// Creates a new, empty pool with room for at least `capacity` distinct
+  /// strings before it needs to grow.
+  #[inline]
+  pub fn with_capacity(capacity: usize) -> Self {
+    let mut pool = Self::new();
+    pool.strings.reserve(capacity);
+    pool.rehash(table_size(capacity));
+    pool
+  }
+
+  /// Returns the number of distinct strings in the pool.
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.strings.len()
+  }
+
+  /// Returns `true` if no strings have been interned.
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.strings.is_empty()
+  }
+
+  /// Interns `s`, returning its symbol. The string is copied into the pool
+  /// only if it is not already present.
+  pub fn intern(&mut self, s: &str) -> Symbol {
+    self.intern_with(s, |s| match InlineStr::try_from(s) {
+      Ok(inline) => CowStr::Inlined(inline),
+      Err(_) => CowStr::Owned(s.into()),
+    })
+  }
+
+  /// Interns a static string, returning its symbol. If the string is not
+  /// already present, the pool borrows it rather than making a copy.
+  #[inline]
+  pub fn intern_static(&mut self, s: &'static str) -> Symbol {
+    self.intern_with(s, CowStr::Borrowed)
+  }
+
+  /// Interns a `CowStr`, returning its symbol. If the string is not already
+  /// present, it is stored as-is, reusing any allocation it owns.
+  #[inline]
+  pub fn intern_cow(&mut self, s: CowStr<'static>) -> Symbol {
+    let hash = hash_str(&s);
+    match self.find(&s, hash) {
+      Ok(symbol) => symbol,
+      Err(slot) => self.insert_at(slot, s),
+    }
+  }
+
+  #[inline]
+  fn intern_with<'a>(
+    &mut self,
+    s: &'a str,
+    store: impl FnOnce(&'a str) -> CowStr<'static>,
+  ) -> Symbol {
+    let hash = hash_str(s);
+    match self.find(s, hash) {
+      Ok(symbol) => symbol,
+      Err(slot) => self.insert_at(slot, store(s)),
+    }
+  }
+
+  /// Returns the symbol of `s` if it has been interned, without interning
+  /// it.
+  #[inline]
+  pub fn get(&self, s: &str) -> Option<Symbol> {
+    self.find(s, hash_str(s)).ok()
+  }
+
+  /// Returns `true` if `s` has been interned.
+  #[inline]
+  pub fn contains(&self, s: &str) -> bool {
+    self.get(s).is_some()
+  }
+
+  /// Returns the string for `symbol`, or `None` if it does not belong to
+  /// this pool.
+  #[inline]
+  pub fn resolve(&self, symbol: Symbol) -> Option<&str> {
+    self.strings.get(symbol.index()).map(CowStr::as_str)
+  }
+
+  /// Returns the stored `CowStr` for `symbol`, or `None` if it does not
+  /// belong to this pool.
+  #[inline]
+  pub fn resolve_cow(&self, symbol: Symbol) -> Option<&CowStr<'static>> {
+    self.strings.get(symbol.index())
+  }
+
+  /// Returns an iterator over the symbols and strings in the pool, in the
+  /// order they were interned.
+  #[inline]
+  pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> + '_ {
+    self
+      .strings
+      .iter()
+      .enumerate()
+      .map(|(index, s)| (Symbol::from_index(index), s.as_str()))
+  }
+
+  /// Looks up `s` in the table, returning its symbol if found, or otherwise
+  /// the index of the empty slot where it belongs.
+  fn find(&self, s: &str, hash: u64) -> Result<Symbol, usize> {
+    if self.table.is_empty() {
+      return Err(0);
+    }
+    let mask = self.table.len() - 1;
+    let mut slot = hash as usize & mask;
+    loop {
+      match self.table[slot] {
+        0 => return Err(slot),
+        id => {
+          let symbol = Symbol(NonZeroU32::new(id).unwrap());
+          if self.strings[symbol.index()] == *s {
+            return Ok(symbol);
+          }
+        }
+      }
+      slot = (slot + 1) & mask;
+    }
+  }
+
+  fn insert_at(&mut self, slot: usize, s: CowStr<'static>) -> Symbol {
+    let symbol = Symbol::from_index(self.strings.len());
+    self.strings.push(s);
+    // keep the load factor at or below one half.
+    if self.strings.len() * 2 > self.table.len() {
+      self.rehash(table_size(self.strings.len()));
+    } else {
+      self.table[slot] = symbol.0.get();
+    }
+    symbol
+  }
+
+  /// Rebuilds the table with `size` slots from the stored strings.
+  fn rehash(&mut self, size: usize) {
+    self.table = vec![0; size];
+    let mask = size - 1;
+    for (index, s) in self.strings.iter().enumerate() {
+      let mut slot = hash_str(s) as usize & mask;
+      while self.table[slot] != 0 {
+        slot = (slot + 1) & mask;
+      }
+      self.table[slot] = Symbol::from_index(index).0.get();
+    }
+  }
+}
+
+/// Returns the table size needed to hold `count` strings at a load factor
+/// of at most one half.
+#[inline]
+fn table_size(count: usize) -> usize {
+  (count * 2).next_power_of_two().max(8)
+}
+
+/// Hashes a string with the Fx hash function, which is fast for the short
+/// keys typical of interners.
+fn hash_str(s: &str) -> u64 {
+  let mut hasher = FxHasher::default();
+  hasher.write(s.as_bytes());
+  hasher.write_usize(s.len());
+  hasher.finish()
+}
+
+impl Index<Symbol> for StrPool {
+  type Output = str;
+
+  /// Returns the string for `symbol`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the symbol does not belong to this pool.
+  #[inline]
+  fn index(&self, symbol: Symbol) -> &str {
+    self
+      .resolve(symbol)
+      .expect("symbol does not belong to this StrPool")
+  }
+}
+
+impl Debug for StrPool {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_map().entries(self.iter()).finish()
+  }
+}
+
+impl<'a> Extend<&'a str> for StrPool {
+  fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+    for s in iter {
+      self.intern(s);
+    }
+  }
+}
+
+impl<'a> FromIterator<&'a str> for StrPool {
+  #[inline]
+  fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+    let mut pool = Self::new();
+    pool.extend(iter);
+    pool
+  }
+}
+
+#[cfg(feature = "std")]
+mod sync_impl {
+  use std::sync::PoisonError;
+  use std::sync::RwLock;
+
+  use super::*;
+
+  /// A thread-safe [`StrPool`], for interning strings from several threads.
+  ///
+  /// Interning a string that is already present only takes a read lock.
+  /// Since the pool may grow while other threads hold symbols, strings are
+  /// resolved through a callback rather than by reference.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use moos::str_pool::SyncStrPool;
+  ///
+  /// let pool = SyncStrPool::new();
+  /// let symbols: Vec<_> = std::thread::scope(|scope| {
+  ///   let handles: Vec<_> = (0..4)
+  ///     .map(|_| scope.spawn(|| pool.intern("shared")))
+  ///     .collect();
+  ///   handles.into_iter().map(|h| h.join().unwrap()).collect()
+  /// });
+  /// assert!(symbols.iter().all(|&s| s == symbols[0]));
+  /// assert_eq!(pool.with_str(symbols[0], str::len), Some(6));
+  /// ```
+  #[derive(Debug, Default)]
+  pub struct SyncStrPool {
+    pool: RwLock<StrPool>,
+  }
+
+  impl SyncStrPool {
+    /// Creates a new, empty pool.
+    #[inline]
+    pub const fn new() -> Self {
+      Self {
+        pool: RwLock::new(StrPool::new()),
+      }
+    }
+
+    /// Returns the number of distinct strings in the pool.
+    #[inline]
+    pub fn len(&self) -> usize {
+      self.read(StrPool::len)
+    }
+
+    /// Returns `true` if no strings have been interned.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+      self.read(StrPool::is_empty)
+    }
+
+    /// Interns `s`, returning its symbol.
+    pub fn intern(&self, s: &str) -> Symbol {
+      if let Some(symbol) = self.get(s) {
+        return symbol;
+      }
+      self.write(|pool| pool.intern(s))
+    }
+
+    /// Interns a static string without copying it, returning its symbol.
+    pub fn intern_static(&self, s: &'static str) -> Symbol {
+      if let Some(symbol) = self.get(s) {
+        return symbol;
+      }
+      self.write(|pool| pool.intern_static(s))
+    }
+
+    /// Returns the symbol of `s` if it has been interned.
+    #[inline]
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+      self.read(|pool| pool.get(s))
+    }
+
+    /// Calls `f` with the string for `symbol`, returning its result, or
+    /// `None` if the symbol does not belong to this pool.
+    #[inline]
+    pub fn with_str<R>(
+      &self,
+      symbol: Symbol,
+      f: impl FnOnce(&str) -> R,
+    ) -> Option<R> {
+      self.read(|pool| pool.resolve(symbol).map(f))
+    }
+
+    /// Returns a clone of the stored `CowStr` for `symbol`, which does not
+    /// allocate for inline or static strings.
+    #[inline]
+    pub fn resolve_cow(&self, symbol: Symbol) -> Option<CowStr<'static>> {
+      self.read(|pool| pool.resolve_cow(symbol).cloned())
+    }
+
+    /// Consumes the lock, returning the underlying pool.
+    #[inline]
+    pub fn into_inner(self) -> StrPool {
+      self
+        .pool
+        .into_inner()
+        .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // a panic while holding the lock can't leave the pool inconsistent, so
+    // poisoning is ignored.
+    #[inline]
+    fn read<R>(&self, f: impl FnOnce(&StrPool) -> R) -> R {
+      f(&self.pool.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    #[inline]
+    fn write<R>(&self, f: impl FnOnce(&mut StrPool) -> R) -> R {
+      f(&mut self.pool.write().unwrap_or_else(PoisonError::into_inner))
+    }
+  }
+
+  impl From<StrPool> for SyncStrPool {
+    #[inline]
+    fn from(pool: StrPool) -> Self {
+      Self {
+        pool: RwLock::new(pool),
+      }
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+pub use sync_impl::SyncStrPool;
+
+#[cfg(test)]
+mod tests {
+  use alloc::string::String;
+
+  use super::*;
+
+  #[test]
+  fn str_pool_dedup_and_resolve() {
+    let mut pool = StrPool::new();
+    let long = "a string that is too long to be stored inline";
+    let a = pool.intern("a");
+    let b = pool.intern(long);
+    let c = pool.intern_static("static");
+    assert_eq!(pool.intern("a"), a);
+    assert_eq!(pool.intern(&String::from(long)), b);
+    assert_eq!(pool.intern("static"), c);
+    assert_eq!(pool.len(), 3);
+
+    assert!(pool.resolve_cow(a).unwrap().is_inlined());
+    assert!(pool.resolve_cow(b).unwrap().is_owned());
+    assert!(pool.resolve_cow(c).unwrap().is_borrowed());
+    assert_eq!(&pool[b], long);
+    assert_eq!(
+      pool.iter().map(|(s, _)| s.index()).collect::<Vec<_>>(),
+      [0, 1, 2]
+    );
+    assert_eq!(size_of::<Option<Symbol>>(), 4);
+  }
+
+  #[test]
+  fn str_pool_grows() {
+    let mut pool = StrPool::with_capacity(2);
+    let names: Vec<String> = (0..500).map(|i| format!("name{i}")).collect();
+    let symbols: Vec<Symbol> = names.iter().map(|n| pool.intern(n)).collect();
+    for (name, symbol) in names.iter().zip(&symbols) {
+      assert_eq!(pool.get(name), Some(*symbol));
+      assert_eq!(pool.resolve(*symbol), Some(name.as_str()));
+    }
+    assert!(!pool.contains("name500"));
+    assert_eq!(
+      pool.intern_cow(CowStr::from(String::from("name7"))),
+      symbols[7]
+    );
+  }
+
+  #[test]
+  fn str_pool_foreign_symbol() {
+    let mut big = StrPool::from_iter(["x", "y"]);
+    let y = big.intern("y");
+    let small = StrPool::from_iter(["x"]);
+    assert_eq!(small.resolve(y), None);
+    assert_eq!(format!("{small:?}"), r#"{Symbol(0): "x"}"#);
+  }
+}
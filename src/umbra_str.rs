@@ -0,0 +1,433 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::ops::Deref;
+use core::ptr;
+use core::slice;
+use core::str;
+use core::str::FromStr;
+
+use crate::CowStr;
+
+/// The maximum length in bytes of a string stored inline in an [`UmbraStr`].
+pub const MAX_INLINE_UMBRA_STR_LEN: usize = 12;
+
+/// The number of leading bytes an [`UmbraStr`] keeps next to its length.
+const PREFIX_LEN: usize = 4;
+
+/// An immutable string in the "German string" layout of the Umbra database:
+/// 16 bytes holding the length, the first four bytes of the string, and
+/// either the rest of a short string or a pointer to a long one.
+///
+/// Because the length and prefix live in the struct itself, most equality
+/// checks and comparisons between strings with different lengths or prefixes
+/// finish without following the pointer, which keeps sorts and joins keyed
+/// on strings from thrashing the cache. Strings of up to
+/// [`MAX_INLINE_UMBRA_STR_LEN`] bytes are stored entirely inline.
+///
+/// Lengths are stored as a `u32`, so strings are limited to 4 GiB.
+///
+/// # Example
+///
+/// ```rust
+/// use moos::UmbraStr;
+///
+/// let mut keys: Vec<UmbraStr> =
+///   ["zeta-long-key-01", "alpha", "alpha-long-key-02"]
+///     .into_iter()
+///     .map(UmbraStr::from)
+///     .collect();
+/// keys.sort();
+/// assert_eq!(keys, ["alpha", "alpha-long-key-02", "zeta-long-key-01"]);
+///
+/// assert!(keys[0].is_inline());
+/// assert_eq!(keys[2].prefix(), b"zeta");
+/// assert_eq!(size_of::<UmbraStr>(), 16);
+/// ```
+#[repr(C)]
+pub struct UmbraStr {
+  len:    u32,
+  prefix: [u8; PREFIX_LEN],
+  rest:   Rest,
+}
+
+/// The second half of an [`UmbraStr`]: bytes 4 to 12 of an inline string,
+/// zero-padded, or a pointer to the whole of a heap string.
+#[repr(C)]
+union Rest {
+  inline: [u8; 8],
+  heap:   *const u8,
+}
+
+// SAFETY: an `UmbraStr` uniquely owns its immutable heap allocation, if any.
+unsafe impl Send for UmbraStr {}
+unsafe impl Sync for UmbraStr {}
+
+impl UmbraStr {
+  /// Creates an `UmbraStr` holding a copy of `s`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `s` is longer than `u32::MAX` bytes.
+  pub fn new(s: &str) -> Self {
+    let len = u32::try_from(s.len()).expect("string too long for UmbraStr");
+    let bytes = s.as_bytes();
+    let mut prefix = [0; PREFIX_LEN];
+    let head = bytes.len().min(PREFIX_LEN);
+    prefix[..head].copy_from_slice(&bytes[..head]);
+    let rest = if bytes.len() <= MAX_INLINE_UMBRA_STR_LEN {
+      let mut inline = [0; 8];
+      inline[..bytes.len() - head].copy_from_slice(&bytes[head..]);
+      Rest { inline }
+    } else {
+      let heap = Box::<[u8]>::from(bytes);
+      Rest {
+        heap: Box::into_raw(heap).cast::<u8>(),
+      }
+    };
+    Self { len, prefix, rest }
+  }
+
+  /// Returns the length of the string in bytes.
+  #[inline(always)]
+  pub const fn len(&self) -> usize {
+    self.len as usize
+  }
+
+  /// Returns `true` if the string is empty.
+  #[inline(always)]
+  pub const fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Returns `true` if the string is stored inline.
+  #[inline(always)]
+  pub const fn is_inline(&self) -> bool {
+    self.len() <= MAX_INLINE_UMBRA_STR_LEN
+  }
+
+  /// Returns the first four bytes of the string, or all of it if shorter.
+  /// This never touches the heap.
+  #[inline(always)]
+  pub fn prefix(&self) -> &[u8] {
+    &self.prefix[..self.len().min(PREFIX_LEN)]
+  }
+
+  /// Returns the string as a string slice.
+  #[inline]
+  pub fn as_str(&self) -> &str {
+    unsafe { str::from_utf8_unchecked(self.as_bytes()) }
+  }
+
+  /// Returns the UTF-8 bytes of the string.
+  #[inline]
+  pub fn as_bytes(&self) -> &[u8] {
+    let ptr = if self.is_inline() {
+      // the prefix and the inline bytes are adjacent.
+      ptr::from_ref(self).cast::<u8>().wrapping_add(4)
+    } else {
+      unsafe { self.rest.heap }
+    };
+    unsafe { slice::from_raw_parts(ptr, self.len()) }
+  }
+
+  /// Returns the length and prefix as one integer, so both can be compared
+  /// at once.
+  #[inline(always)]
+  fn head(&self) -> u64 {
+    (self.len as u64) << 32 | u32::from_ne_bytes(self.prefix) as u64
+  }
+}
+
+impl Drop for UmbraStr {
+  fn drop(&mut self) {
+    if !self.is_inline() {
+      unsafe {
+        let ptr =
+          ptr::slice_from_raw_parts_mut(self.rest.heap.cast_mut(), self.len());
+        drop(Box::from_raw(ptr));
+      }
+    }
+  }
+}
+
+impl Clone for UmbraStr {
+  #[inline]
+  fn clone(&self) -> Self {
+    if self.is_inline() {
+      Self {
+        len:    self.len,
+        prefix: self.prefix,
+        rest:   Rest {
+          inline: unsafe { self.rest.inline },
+        },
+      }
+    } else {
+      Self::new(self.as_str())
+    }
+  }
+}
+
+impl Default for UmbraStr {
+  #[inline(always)]
+  fn default() -> Self {
+    Self::new("")
+  }
+}
+
+impl Deref for UmbraStr {
+  type Target = str;
+
+  #[inline(always)]
+  fn deref(&self) -> &str {
+    self.as_str()
+  }
+}
+
+impl AsRef<str> for UmbraStr {
+  #[inline(always)]
+  fn as_ref(&self) -> &str {
+    self
+  }
+}
+
+impl Borrow<str> for UmbraStr {
+  #[inline(always)]
+  fn borrow(&self) -> &str {
+    self
+  }
+}
+
+impl Debug for UmbraStr {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Debug::fmt(self.as_str(), f)
+  }
+}
+
+impl Display for UmbraStr {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    Display::fmt(self.as_str(), f)
+  }
+}
+
+impl Hash for UmbraStr {
+  #[inline]
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.as_str().hash(state);
+  }
+}
+
+impl PartialEq for UmbraStr {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    if self.head() != other.head() {
+      return false;
+    }
+    if self.is_inline() {
+      // the inline bytes are zero-padded, so all of them can be compared.
+      unsafe { self.rest.inline == other.rest.inline }
+    } else {
+      self.as_bytes()[PREFIX_LEN..] == other.as_bytes()[PREFIX_LEN..]
+    }
+  }
+}
+
+impl Eq for UmbraStr {}
+
+impl PartialOrd for UmbraStr {
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for UmbraStr {
+  #[inline]
+  fn cmp(&self, other: &Self) -> Ordering {
+    // byte-wise order of the prefixes is numeric order when read big-endian.
+    let a = u32::from_be_bytes(self.prefix);
+    let b = u32::from_be_bytes(other.prefix);
+    match a.cmp(&b) {
+      Ordering::Equal => self.as_bytes().cmp(other.as_bytes()),
+      ordering => ordering,
+    }
+  }
+}
+
+impl PartialEq<str> for UmbraStr {
+  #[inline]
+  fn eq(&self, other: &str) -> bool {
+    self.as_str() == other
+  }
+}
+
+impl PartialEq<&str> for UmbraStr {
+  #[inline]
+  fn eq(&self, other: &&str) -> bool {
+    self.as_str() == *other
+  }
+}
+
+impl PartialEq<UmbraStr> for str {
+  #[inline]
+  fn eq(&self, other: &UmbraStr) -> bool {
+    self == other.as_str()
+  }
+}
+
+impl PartialEq<UmbraStr> for &str {
+  #[inline]
+  fn eq(&self, other: &UmbraStr) -> bool {
+    *self == other.as_str()
+  }
+}
+
+impl From<&str> for UmbraStr {
+  #[inline(always)]
+  fn from(s: &str) -> Self {
+    Self::new(s)
+  }
+}
+
+impl From<String> for UmbraStr {
+  #[inline(always)]
+  fn from(s: String) -> Self {
+    Self::new(&s)
+  }
+}
+
+impl<'i> From<CowStr<'i>> for UmbraStr {
+  #[inline(always)]
+  fn from(s: CowStr<'i>) -> Self {
+    Self::new(&s)
+  }
+}
+
+impl From<UmbraStr> for String {
+  #[inline(always)]
+  fn from(s: UmbraStr) -> Self {
+    String::from(s.as_str())
+  }
+}
+
+impl FromStr for UmbraStr {
+  type Err = core::convert::Infallible;
+
+  #[inline(always)]
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Ok(Self::new(s))
+  }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use serde::Deserialize;
+  use serde::Deserializer;
+  use serde::Serialize;
+  use serde::Serializer;
+  use serde::de;
+
+  use super::*;
+
+  impl Serialize for UmbraStr {
+    #[inline(always)]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+      S: Serializer,
+    {
+      serializer.serialize_str(self.as_str())
+    }
+  }
+
+  struct UmbraStrVisitor;
+
+  impl de::Visitor<'_> for UmbraStrVisitor {
+    type Value = UmbraStr;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+      formatter.write_str("a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+      E: de::Error,
+    {
+      u32::try_from(v.len())
+        .map(|_| UmbraStr::new(v))
+        .map_err(|_| E::invalid_length(v.len(), &self))
+    }
+  }
+
+  impl<'de> Deserialize<'de> for UmbraStr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      deserializer.deserialize_str(UmbraStrVisitor)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::vec::Vec;
+
+  use super::*;
+
+  #[test]
+  fn umbra_str_layout() {
+    let inline = UmbraStr::from("twelve bytes");
+    assert!(inline.is_inline());
+    assert_eq!(inline, "twelve bytes");
+    assert_eq!(inline.prefix(), b"twel");
+
+    let heap = UmbraStr::from("thirteen byte");
+    assert!(!heap.is_inline());
+    assert_eq!(heap.clone(), heap);
+    assert_eq!(UmbraStr::from("ab").prefix(), b"ab");
+    assert!(UmbraStr::default().is_empty());
+  }
+
+  #[test]
+  fn umbra_str_eq_and_ord() {
+    let words = [
+      "",
+      "a",
+      "ab",
+      "abc",
+      "abcd",
+      "abcde",
+      "abcdefghijklmnop",
+      "abcdefghijklmnoq",
+      "abd",
+      "b",
+      "ÿ",
+      "ÿÿÿÿÿÿÿ",
+    ];
+    let umbra: Vec<UmbraStr> =
+      words.iter().map(|w| UmbraStr::from(*w)).collect();
+    for (i, a) in umbra.iter().enumerate() {
+      for (j, b) in umbra.iter().enumerate() {
+        assert_eq!(a.cmp(b), words[i].cmp(words[j]), "{a:?} vs {b:?}");
+        assert_eq!(a == b, i == j);
+      }
+    }
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn umbra_str_serde() {
+    let s = UmbraStr::from("serialized umbra string");
+    let json = serde_json::to_string(&s).unwrap();
+    assert_eq!(serde_json::from_str::<UmbraStr>(&json).unwrap(), s);
+  }
+}
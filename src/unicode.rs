@@ -0,0 +1,300 @@
+//! Unicode case folding, for comparing text without regard to case.
+//!
+//! Case folding maps every character to a canonical case, so that two
+//! strings that differ only in case fold to the same string. It is the
+//! right tool for matching identifiers, keywords, and user names across
+//! scripts, where lowercasing falls short: `"Straße"` and `"STRASSE"` fold
+//! to the same string, as do `"ΣΊΣΥΦΟΣ"` and `"σίσυφος"`.
+//!
+//! The mappings are the full (`C` and `F`) case foldings of the Unicode
+//! Character Database, without the Turkic (`T`) special cases. Folding
+//! doesn't normalize, so canonically equivalent strings in different
+//! normalization forms still compare unequal.
+//!
+//! Everything here works in `no_std`, and neither comparing nor iterating
+//! over folded characters allocates.
+//!
+//! # Example
+//!
+//! ```rust
+//! use moos::unicode;
+//!
+//! assert!(unicode::caseless_eq("Straße", "STRASSE"));
+//! assert!(unicode::caseless_eq("ﬁle", "FILE"));
+//! assert!(!unicode::caseless_eq("file", "files"));
+//!
+//! assert!(unicode::fold_case("already folded").is_borrowed());
+//! assert_eq!(unicode::fold_case("Grüße, ΚΌΣΜΕ"), "grüsse, κόσμε");
+//! ```
+
+use core::char::ToLowercase;
+use core::iter::FlatMap;
+use core::iter::FusedIterator;
+use core::str::Chars;
+
+use crate::CowStr;
+use crate::MAX_INLINE_STR_LEN;
+use crate::SmallString;
+
+/// Returns the case folding of `s`.
+///
+/// Returns `s` itself if it is already folded; otherwise the result is
+/// inline if it is short enough.
+pub fn fold_case<'a>(s: impl Into<CowStr<'a>>) -> CowStr<'a> {
+  let s = s.into();
+  let Some(first) = s.find(|c| !is_folded(c)) else {
+    return s;
+  };
+  let mut out = SmallString::<MAX_INLINE_STR_LEN>::with_capacity(s.len());
+  out.push_str(&s[..first]);
+  out.extend(fold_chars(&s[first..]));
+  CowStr::from_small(out)
+}
+
+/// Returns `true` if `a` and `b` are equal after case folding.
+pub fn caseless_eq(a: &str, b: &str) -> bool {
+  if a == b {
+    return true;
+  }
+  if a.is_ascii() && b.is_ascii() {
+    return a.eq_ignore_ascii_case(b);
+  }
+  fold_chars(a).eq(fold_chars(b))
+}
+
+/// Returns an iterator over the case-folded characters of `s`.
+#[inline]
+pub fn fold_chars(s: &str) -> FoldChars<'_> {
+  FoldChars {
+    inner: s.chars().flat_map(fold_char),
+  }
+}
+
+/// An iterator over the case-folded characters of a string, returned by
+/// [`fold_chars`].
+#[derive(Clone)]
+pub struct FoldChars<'a> {
+  inner: FlatMap<Chars<'a>, FoldChar, fn(char) -> FoldChar>,
+}
+
+impl Iterator for FoldChars<'_> {
+  type Item = char;
+
+  #[inline]
+  fn next(&mut self) -> Option<char> {
+    self.inner.next()
+  }
+}
+
+impl FusedIterator for FoldChars<'_> {}
+
+/// The case folding of a single character, which is one to three
+/// characters long.
+#[derive(Clone)]
+enum FoldChar {
+  One(Option<char>),
+  Lower(ToLowercase),
+  Table(Chars<'static>),
+}
+
+impl Iterator for FoldChar {
+  type Item = char;
+
+  #[inline]
+  fn next(&mut self) -> Option<char> {
+    match self {
+      Self::One(c) => c.take(),
+      Self::Lower(lower) => lower.next(),
+      Self::Table(chars) => chars.next(),
+    }
+  }
+}
+
+/// Returns `true` if `c` folds to itself.
+#[inline]
+fn is_folded(c: char) -> bool {
+  if c.is_ascii() {
+    return !c.is_ascii_uppercase();
+  }
+  let mut folded = fold_char(c);
+  folded.next() == Some(c) && folded.next().is_none()
+}
+
+fn fold_char(c: char) -> FoldChar {
+  if c.is_ascii() {
+    return FoldChar::One(Some(c.to_ascii_lowercase()));
+  }
+  // Cherokee folds to its upper case, which came first in Unicode.
+  let cherokee = match c as u32 {
+    0x13a0..=0x13f5 => Some(c as u32),
+    0x13f8..=0x13fd => Some(c as u32 - 8),
+    0xab70..=0xabbf => Some(c as u32 - 0xab70 + 0x13a0),
+    _ => None,
+  };
+  if let Some(upper) = cherokee {
+    return FoldChar::One(char::from_u32(upper));
+  }
+  match FOLDINGS.binary_search_by_key(&c, |&(from, _)| from) {
+    Ok(i) => FoldChar::Table(FOLDINGS[i].1.chars()),
+    Err(_) => FoldChar::Lower(c.to_lowercase()),
+  }
+}
+
+/// The characters whose case folding differs from their lowercase mapping,
+/// other than Cherokee, sorted by character.
+#[rustfmt::skip]
+const FOLDINGS: &[(char, &str)] = &[
+  ('\u{b5}', "\u{3bc}"),
+  ('\u{df}', "ss"),
+  ('\u{149}', "\u{2bc}n"),
+  ('\u{17f}', "s"),
+  ('\u{1f0}', "j\u{30c}"),
+  ('\u{345}', "\u{3b9}"),
+  ('\u{390}', "\u{3b9}\u{308}\u{301}"),
+  ('\u{3b0}', "\u{3c5}\u{308}\u{301}"),
+  ('\u{3c2}', "\u{3c3}"),
+  ('\u{3d0}', "\u{3b2}"),
+  ('\u{3d1}', "\u{3b8}"),
+  ('\u{3d5}', "\u{3c6}"),
+  ('\u{3d6}', "\u{3c0}"),
+  ('\u{3f0}', "\u{3ba}"),
+  ('\u{3f1}', "\u{3c1}"),
+  ('\u{3f5}', "\u{3b5}"),
+  ('\u{587}', "\u{565}\u{582}"),
+  ('\u{1c80}', "\u{432}"),
+  ('\u{1c81}', "\u{434}"),
+  ('\u{1c82}', "\u{43e}"),
+  ('\u{1c83}', "\u{441}"),
+  ('\u{1c84}', "\u{442}"),
+  ('\u{1c85}', "\u{442}"),
+  ('\u{1c86}', "\u{44a}"),
+  ('\u{1c87}', "\u{463}"),
+  ('\u{1c88}', "\u{a64b}"),
+  ('\u{1e96}', "h\u{331}"),
+  ('\u{1e97}', "t\u{308}"),
+  ('\u{1e98}', "w\u{30a}"),
+  ('\u{1e99}', "y\u{30a}"),
+  ('\u{1e9a}', "a\u{2be}"),
+  ('\u{1e9b}', "\u{1e61}"),
+  ('\u{1e9e}', "ss"),
+  ('\u{1f50}', "\u{3c5}\u{313}"),
+  ('\u{1f52}', "\u{3c5}\u{313}\u{300}"),
+  ('\u{1f54}', "\u{3c5}\u{313}\u{301}"),
+  ('\u{1f56}', "\u{3c5}\u{313}\u{342}"),
+  ('\u{1f80}', "\u{1f00}\u{3b9}"),
+  ('\u{1f81}', "\u{1f01}\u{3b9}"),
+  ('\u{1f82}', "\u{1f02}\u{3b9}"),
+  ('\u{1f83}', "\u{1f03}\u{3b9}"),
+  ('\u{1f84}', "\u{1f04}\u{3b9}"),
+  ('\u{1f85}', "\u{1f05}\u{3b9}"),
+  ('\u{1f86}', "\u{1f06}\u{3b9}"),
+  ('\u{1f87}', "\u{1f07}\u{3b9}"),
+  ('\u{1f88}', "\u{1f00}\u{3b9}"),
+  ('\u{1f89}', "\u{1f01}\u{3b9}"),
+  ('\u{1f8a}', "\u{1f02}\u{3b9}"),
+  ('\u{1f8b}', "\u{1f03}\u{3b9}"),
+  ('\u{1f8c}', "\u{1f04}\u{3b9}"),
+  ('\u{1f8d}', "\u{1f05}\u{3b9}"),
+  ('\u{1f8e}', "\u{1f06}\u{3b9}"),
+  ('\u{1f8f}', "\u{1f07}\u{3b9}"),
+  ('\u{1f90}', "\u{1f20}\u{3b9}"),
+  ('\u{1f91}', "\u{1f21}\u{3b9}"),
+  ('\u{1f92}', "\u{1f22}\u{3b9}"),
+  ('\u{1f93}', "\u{1f23}\u{3b9}"),
+  ('\u{1f94}', "\u{1f24}\u{3b9}"),
+  ('\u{1f95}', "\u{1f25}\u{3b9}"),
+  ('\u{1f96}', "\u{1f26}\u{3b9}"),
+  ('\u{1f97}', "\u{1f27}\u{3b9}"),
+  ('\u{1f98}', "\u{1f20}\u{3b9}"),
+  ('\u{1f99}', "\u{1f21}\u{3b9}"),
+  ('\u{1f9a}', "\u{1f22}\u{3b9}"),
+  ('\u{1f9b}', "\u{1f23}\u{3b9}"),
+  ('\u{1f9c}', "\u{1f24}\u{3b9}"),
+  ('\u{1f9d}', "\u{1f25}\u{3b9}"),
+  ('\u{1f9e}', "\u{1f26}\u{3b9}"),
+  ('\u{1f9f}', "\u{1f27}\u{3b9}"),
+  ('\u{1fa0}', "\u{1f60}\u{3b9}"),
+  ('\u{1fa1}', "\u{1f61}\u{3b9}"),
+  ('\u{1fa2}', "\u{1f62}\u{3b9}"),
+  ('\u{1fa3}', "\u{1f63}\u{3b9}"),
+  ('\u{1fa4}', "\u{1f64}\u{3b9}"),
+  ('\u{1fa5}', "\u{1f65}\u{3b9}"),
+  ('\u{1fa6}', "\u{1f66}\u{3b9}"),
+  ('\u{1fa7}', "\u{1f67}\u{3b9}"),
+  ('\u{1fa8}', "\u{1f60}\u{3b9}"),
+  ('\u{1fa9}', "\u{1f61}\u{3b9}"),
+  ('\u{1faa}', "\u{1f62}\u{3b9}"),
+  ('\u{1fab}', "\u{1f63}\u{3b9}"),
+  ('\u{1fac}', "\u{1f64}\u{3b9}"),
+  ('\u{1fad}', "\u{1f65}\u{3b9}"),
+  ('\u{1fae}', "\u{1f66}\u{3b9}"),
+  ('\u{1faf}', "\u{1f67}\u{3b9}"),
+  ('\u{1fb2}', "\u{1f70}\u{3b9}"),
+  ('\u{1fb3}', "\u{3b1}\u{3b9}"),
+  ('\u{1fb4}', "\u{3ac}\u{3b9}"),
+  ('\u{1fb6}', "\u{3b1}\u{342}"),
+  ('\u{1fb7}', "\u{3b1}\u{342}\u{3b9}"),
+  ('\u{1fbc}', "\u{3b1}\u{3b9}"),
+  ('\u{1fbe}', "\u{3b9}"),
+  ('\u{1fc2}', "\u{1f74}\u{3b9}"),
+  ('\u{1fc3}', "\u{3b7}\u{3b9}"),
+  ('\u{1fc4}', "\u{3ae}\u{3b9}"),
+  ('\u{1fc6}', "\u{3b7}\u{342}"),
+  ('\u{1fc7}', "\u{3b7}\u{342}\u{3b9}"),
+  ('\u{1fcc}', "\u{3b7}\u{3b9}"),
+  ('\u{1fd2}', "\u{3b9}\u{308}\u{300}"),
+  ('\u{1fd3}', "\u{3b9}\u{308}\u{301}"),
+  ('\u{1fd6}', "\u{3b9}\u{342}"),
+  ('\u{1fd7}', "\u{3b9}\u{308}\u{342}"),
+  ('\u{1fe2}', "\u{3c5}\u{308}\u{300}"),
+  ('\u{1fe3}', "\u{3c5}\u{308}\u{301}"),
+  ('\u{1fe4}', "\u{3c1}\u{313}"),
+  ('\u{1fe6}', "\u{3c5}\u{342}"),
+  ('\u{1fe7}', "\u{3c5}\u{308}\u{342}"),
+  ('\u{1ff2}', "\u{1f7c}\u{3b9}"),
+  ('\u{1ff3}', "\u{3c9}\u{3b9}"),
+  ('\u{1ff4}', "\u{3ce}\u{3b9}"),
+  ('\u{1ff6}', "\u{3c9}\u{342}"),
+  ('\u{1ff7}', "\u{3c9}\u{342}\u{3b9}"),
+  ('\u{1ffc}', "\u{3c9}\u{3b9}"),
+  ('\u{fb00}', "ff"),
+  ('\u{fb01}', "fi"),
+  ('\u{fb02}', "fl"),
+  ('\u{fb03}', "ffi"),
+  ('\u{fb04}', "ffl"),
+  ('\u{fb05}', "st"),
+  ('\u{fb06}', "st"),
+  ('\u{fb13}', "\u{574}\u{576}"),
+  ('\u{fb14}', "\u{574}\u{565}"),
+  ('\u{fb15}', "\u{574}\u{56b}"),
+  ('\u{fb16}', "\u{57e}\u{576}"),
+  ('\u{fb17}', "\u{574}\u{56d}"),
+];
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fold_case_mappings() {
+    assert!(FOLDINGS.windows(2).all(|w| w[0].0 < w[1].0));
+    assert_eq!(fold_case("MOOSE"), "moose");
+    assert_eq!(fold_case("ǰ ŉ ﬀ"), "j\u{30c} ʼn ff");
+    assert_eq!(fold_case("ΣΑΣ ς µ ſ"), "σασ σ μ s");
+    assert_eq!(fold_case("ᏣᎳᎩ ꮳꮃꭹ"), "ᏣᎳᎩ ᏣᎳᎩ");
+    assert_eq!(fold_case("ᾼ"), "αι");
+    assert!(fold_case("ᏣᎳᎩ").is_borrowed());
+    assert!(fold_case("Ab").is_inlined());
+  }
+
+  #[test]
+  fn caseless_eq_compares_folded() {
+    assert!(caseless_eq("ASCII Only", "ascii only"));
+    assert!(caseless_eq("MASSE", "Maße"));
+    assert!(caseless_eq("ꮳꮃꭹ", "ᏣᎳᎩ"));
+    assert!(caseless_eq("ΌΣΟΣ", "όσος"));
+    assert!(!caseless_eq("ß", "s"));
+    assert!(!caseless_eq("abc", "abd"));
+    assert_eq!(fold_chars("ẞ").count(), 2);
+  }
+}
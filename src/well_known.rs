@@ -0,0 +1,185 @@
+//! Static copies of extremely common small strings.
+//!
+//! Code that turns flags, characters, and small numbers into strings tends
+//! to produce the same handful of values over and over. The functions here
+//! return those values as `&'static str`s, borrowed from tables built at
+//! compile time, so they can go straight into a [`CowStr::Borrowed`] with no
+//! allocation and no copy.
+//!
+//! The tables cover the empty string, `"true"` and `"false"`, every ASCII
+//! character, and the decimal integers from 0 to 255.
+//!
+//! # Example
+//!
+//! ```rust
+//! use moos::CowStr;
+//! use moos::well_known;
+//!
+//! assert_eq!(well_known::bool_str(true), "true");
+//! assert_eq!(well_known::u8_str(200), "200");
+//! assert_eq!(well_known::char_str('x'), Some("x"));
+//!
+//! // a count that is usually small only needs formatting when it isn't.
+//! assert!(matches!(well_known::u64_cow(42), CowStr::Borrowed("42")));
+//! assert!(well_known::u64_cow(1_000_000).is_inlined());
+//!
+//! // swap a runtime copy of a common value for the static one.
+//! let parsed = String::from("false");
+//! assert!(well_known::get(&parsed).is_some_and(|s| s == "false"));
+//! ```
+
+use core::str;
+
+use crate::CowStr;
+use crate::InlineStr;
+use crate::fmt_compact::NumBuffer;
+
+/// The empty string.
+pub const EMPTY: &str = "";
+
+/// Every ASCII character, in order, each one byte long.
+static ASCII: [u8; 128] = ascii_table();
+
+/// The decimal integers from 0 to 255 with no separators; `int_span` gives
+/// the position of each one.
+static INTS: [u8; INTS_LEN] = int_table();
+
+/// 10 one-digit, 90 two-digit, and 156 three-digit numbers.
+const INTS_LEN: usize = 10 + 90 * 2 + 156 * 3;
+
+const fn ascii_table() -> [u8; 128] {
+  let mut table = [0; 128];
+  let mut i = 0;
+  while i < 128 {
+    table[i] = i as u8;
+    i += 1;
+  }
+  table
+}
+
+const fn int_table() -> [u8; INTS_LEN] {
+  let mut table = [0; INTS_LEN];
+  let mut n = 0;
+  while n < 256 {
+    let (start, len) = int_span(n as u8);
+    let mut value = n;
+    let mut i = start + len;
+    while i > start {
+      i -= 1;
+      table[i] = b'0' + (value % 10) as u8;
+      value /= 10;
+    }
+    n += 1;
+  }
+  table
+}
+
+/// Returns the offset and length of `n` in the integer table.
+#[inline(always)]
+const fn int_span(n: u8) -> (usize, usize) {
+  let n = n as usize;
+  match n {
+    0..=9 => (n, 1),
+    10..=99 => (10 + (n - 10) * 2, 2),
+    _ => (190 + (n - 100) * 3, 3),
+  }
+}
+
+/// Returns `"true"` or `"false"`.
+#[inline(always)]
+pub const fn bool_str(value: bool) -> &'static str {
+  if value { "true" } else { "false" }
+}
+
+/// Returns `c` as a one-character string if it is ASCII.
+#[inline]
+pub fn char_str(c: char) -> Option<&'static str> {
+  let i = c as usize;
+  // SAFETY: the table holds ASCII bytes, each of which is valid UTF-8.
+  ASCII
+    .get(i..=i)
+    .map(|bytes| unsafe { str::from_utf8_unchecked(bytes) })
+}
+
+/// Returns the decimal representation of `n`.
+#[inline]
+pub fn u8_str(n: u8) -> &'static str {
+  let (start, len) = int_span(n);
+  // SAFETY: the table holds ASCII digits.
+  unsafe { str::from_utf8_unchecked(&INTS[start..start + len]) }
+}
+
+/// Returns the decimal representation of `n` if it is between 0 and 255.
+#[inline]
+pub fn int_str(n: i64) -> Option<&'static str> {
+  u8::try_from(n).ok().map(u8_str)
+}
+
+/// Returns the decimal representation of `n`: borrowed from the static
+/// table if it is at most 255, and formatted inline otherwise.
+///
+/// On targets where the largest values don't fit inline, they are stored
+/// on the heap.
+#[inline]
+pub fn u64_cow(n: u64) -> CowStr<'static> {
+  if let Ok(n) = u8::try_from(n) {
+    return CowStr::Borrowed(u8_str(n));
+  }
+  let mut buf = NumBuffer::new();
+  let digits = buf.format_u64(n);
+  match InlineStr::try_from(digits) {
+    Ok(inline) => CowStr::Inlined(inline),
+    Err(_) => CowStr::Owned(digits.into()),
+  }
+}
+
+/// Returns the static copy of `s`, if it is one of the well-known values.
+///
+/// Integers match only in their canonical form, so `"7"` is found but
+/// `"07"` and `"+7"` are not.
+pub fn get(s: &str) -> Option<&'static str> {
+  match s {
+    "" => Some(EMPTY),
+    "true" => Some("true"),
+    "false" => Some("false"),
+    _ if s.len() == 1 => s.chars().next().and_then(char_str),
+    _ if s.len() <= 3 && !s.starts_with('0') => {
+      s.parse::<u8>().ok().map(u8_str).filter(|n| *n == s)
+    }
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn well_known_tables() {
+    for n in 0..=255u8 {
+      assert_eq!(u8_str(n), format!("{n}"));
+      assert_eq!(get(&format!("{n}")), Some(u8_str(n)));
+    }
+    for c in (0..128u8).map(char::from) {
+      assert_eq!(char_str(c), Some(c.encode_utf8(&mut [0; 4]) as &str));
+    }
+    assert_eq!(char_str('é'), None);
+    assert_eq!(int_str(-1), None);
+    assert_eq!(int_str(256), None);
+    assert_eq!(int_str(255), Some("255"));
+  }
+
+  #[test]
+  fn well_known_lookups() {
+    assert_eq!(get(""), Some(""));
+    assert_eq!(get("true"), Some(bool_str(true)));
+    assert_eq!(get("07"), None);
+    assert_eq!(get("+7"), None);
+    assert_eq!(get("256"), None);
+    assert_eq!(get("é"), None);
+
+    assert!(u64_cow(255).is_borrowed());
+    assert_eq!(u64_cow(256), "256");
+    assert_eq!(u64_cow(u64::MAX), "18446744073709551615");
+  }
+}
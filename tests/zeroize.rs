@@ -0,0 +1,102 @@
+//! Checks that the `zeroize` feature wipes secrets when they are dropped.
+//!
+//! Freed memory can't be read back safely, so this test binary installs an
+//! allocator that inspects each block as it is freed. It lives here, rather
+//! than with the unit tests, so that allocator doesn't slow down the rest.
+
+#![cfg(feature = "zeroize")]
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::cell::Cell;
+use std::mem::size_of;
+use std::slice;
+
+use moos::CompactVec;
+use moos::CowStr;
+use moos::SmallString;
+
+const SECRET: &[u8] = b"hunter2";
+
+/// Passes allocations through to the system allocator, and, while a thread
+/// is inside [`leaks`], records the blocks it frees.
+struct Inspector;
+
+thread_local! {
+  /// The number of blocks freed so far, and whether any of them still held
+  /// [`SECRET`], or `None` if the thread isn't inside `leaks`.
+  static FREED: Cell<Option<(usize, bool)>> = const { Cell::new(None) };
+}
+
+unsafe impl GlobalAlloc for Inspector {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    unsafe { System.alloc(layout) }
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    let _ = FREED.try_with(|freed| {
+      if let Some((count, leaked)) = freed.get() {
+        // every block freed by the values under test is fully initialized.
+        let block = unsafe { slice::from_raw_parts(ptr, layout.size()) };
+        let found = block.windows(SECRET.len()).any(|w| w == SECRET);
+        freed.set(Some((count + 1, leaked || found)));
+      }
+    });
+    unsafe { System.dealloc(ptr, layout) }
+  }
+}
+
+#[global_allocator]
+static ALLOCATOR: Inspector = Inspector;
+
+/// Drops `value`, returning whether any heap block it freed still held
+/// [`SECRET`]. Panics if it didn't free any.
+fn leaks<T>(value: T) -> bool {
+  FREED.with(|freed| freed.set(Some((0, false))));
+  drop(value);
+  let (count, leaked) = FREED.with(|freed| freed.take()).unwrap();
+  assert!(count > 0, "nothing was freed");
+  leaked
+}
+
+#[test]
+fn zeroize_inspector_sees_secrets() {
+  let secret: Box<[u8]> = SECRET.into();
+  assert!(leaks(secret));
+}
+
+#[test]
+fn zeroize_compact_vec_on_drop() {
+  // an inline vector is boxed so its buffer is freed, and checked, too. With
+  // 24 bytes inline, it has no padding that the check would have to read.
+  let v: Box<CompactVec<u8, 24>> = Box::new(SECRET.into());
+  assert!(v.is_inline());
+  assert_eq!(size_of::<CompactVec<u8, 24>>(), 32);
+  assert!(!leaks(v));
+
+  let v: CompactVec<u8, 4> = SECRET.into();
+  assert!(!v.is_inline());
+  assert!(!leaks(v));
+}
+
+#[test]
+fn zeroize_small_string_on_drop() {
+  let s: Box<SmallString<24>> = Box::new(SmallString::from("hunter2"));
+  assert!(s.is_inline());
+  assert!(!leaks(s));
+
+  let s: SmallString<4> = SmallString::from("hunter2");
+  assert!(!s.is_inline());
+  assert!(!leaks(s));
+}
+
+#[test]
+fn zeroize_cow_str_on_drop() {
+  let s: Box<CowStr> = Box::new("hunter2".parse().unwrap());
+  assert!(s.is_inlined());
+  assert!(!leaks(s));
+
+  let s = CowStr::Owned("hunter2".into());
+  assert!(!leaks(s));
+}